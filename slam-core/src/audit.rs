@@ -0,0 +1,163 @@
+// src/audit.rs
+
+//! Read-only compliance checks: which repos have files that deviate from an expected content.
+//! Nothing is branched, committed or pushed.
+
+use eyre::{eyre, Result};
+use log::debug;
+use rayon::prelude::*;
+use regex::Regex;
+
+use crate::pool;
+use crate::remote;
+use crate::set::RepoSet;
+use crate::utils;
+
+/// What every matched file is expected to hold.
+#[derive(Debug, Clone)]
+pub enum Expectation {
+    /// The content matches the regex somewhere.
+    Pattern(Regex),
+    /// The content hashes to this SHA-256 (lowercase hex).
+    Sha256(String),
+}
+
+impl Expectation {
+    /// `sha256:<hex>`, or 64 bare hex digits, expects exactly that content; anything else is a
+    /// regex the content must match.
+    pub fn parse(s: &str) -> Result<Self> {
+        let hex = s.strip_prefix("sha256:").unwrap_or(s);
+        let is_hash = hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit());
+        if is_hash {
+            return Ok(Expectation::Sha256(hex.to_lowercase()));
+        }
+        if s.starts_with("sha256:") {
+            return Err(eyre!("'{}' isn't a SHA-256: expected 64 hex digits", s));
+        }
+        Regex::new(s)
+            .map(Expectation::Pattern)
+            .map_err(|e| eyre!("Invalid regex '{}': {}", s, e))
+    }
+
+    pub fn met_by(&self, content: &str) -> bool {
+        match self {
+            Expectation::Pattern(regex) => regex.is_match(content),
+            Expectation::Sha256(sum) => utils::sha256_hex(content.as_bytes()) == *sum,
+        }
+    }
+}
+
+/// How one repo fares against an `Expectation`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Finding {
+    Compliant,
+    /// The matched files that don't meet the expectation (or aren't UTF-8 text).
+    Deviating(Vec<String>),
+    /// No file matches the file patterns.
+    Missing,
+    /// The files couldn't be read, e.g. from a remote repo.
+    Unreadable(String),
+}
+
+impl Finding {
+    pub fn is_compliant(&self) -> bool {
+        *self == Finding::Compliant
+    }
+}
+
+/// Checks the matched files of every repo in `repos` against `expectation`, reading them from
+/// the clones or, for a remote set, from the default branches. Returns each reposlug with its
+/// finding, in the set's order.
+pub fn audit(repos: &RepoSet, expectation: &Expectation) -> Vec<(String, Finding)> {
+    let check = |repo: &crate::Repo| -> Finding {
+        if repo.files.is_empty() {
+            return Finding::Missing;
+        }
+        let contents = if repos.is_remote() {
+            match remote::read_files(&repo.reposlug, &repo.files) {
+                Ok(contents) => contents,
+                Err(e) => return Finding::Unreadable(e.to_string()),
+            }
+        } else {
+            let repo_path = repo.path(repos.root());
+            repo.files
+                .iter()
+                .map(|file| std::fs::read_to_string(repo_path.join(file)).ok())
+                .collect()
+        };
+        let deviating: Vec<String> = repo
+            .files
+            .iter()
+            .zip(contents)
+            .filter(|(_, content)| !content.as_deref().is_some_and(|content| expectation.met_by(content)))
+            .map(|(file, _)| file.clone())
+            .collect();
+        debug!("{} file(s) of '{}' deviate", deviating.len(), repo.reposlug);
+        if deviating.is_empty() {
+            Finding::Compliant
+        } else {
+            Finding::Deviating(deviating)
+        }
+    };
+    let findings: Vec<Finding> = if repos.is_remote() {
+        pool::gh(|| repos.repos().par_iter().map(check).collect())
+    } else {
+        repos.repos().par_iter().map(check).collect()
+    };
+    repos
+        .repos()
+        .iter()
+        .map(|repo| repo.reposlug.clone())
+        .zip(findings)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_expectation_parse() {
+        let sum = utils::sha256_hex(b"* @org/platform\n");
+        assert!(matches!(Expectation::parse(&sum).unwrap(), Expectation::Sha256(s) if s == sum));
+        let upper = format!("sha256:{}", sum.to_uppercase());
+        assert!(Expectation::parse(&upper).unwrap().met_by("* @org/platform\n"));
+        assert!(Expectation::parse("sha256:abc").is_err());
+        assert!(Expectation::parse("(").is_err());
+
+        let pattern = Expectation::parse(r"(?m)^\* @org/platform$").unwrap();
+        assert!(pattern.met_by("# owners\n* @org/platform\n"));
+        assert!(!pattern.met_by("* @org/other\n"));
+    }
+
+    #[test]
+    fn test_audit_local_clones() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        for (repo, content) in [
+            ("org/good", Some("node: 20\n")),
+            ("org/bad", Some("node: 18\n")),
+            ("org/none", None),
+        ] {
+            let path = root.join(repo);
+            fs::create_dir_all(path.join(".git")).unwrap();
+            if let Some(content) = content {
+                fs::write(path.join("ci.yml"), content).unwrap();
+            }
+        }
+        let repos = RepoSet::discover(root, &None, &["ci.yml".to_string()], "audit", false).unwrap();
+
+        let mut findings = audit(&repos, &Expectation::parse("node: 20").unwrap());
+        findings.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            findings,
+            [
+                ("org/bad".to_string(), Finding::Deviating(vec!["ci.yml".to_string()])),
+                ("org/good".to_string(), Finding::Compliant),
+                ("org/none".to_string(), Finding::Missing),
+            ]
+        );
+    }
+}
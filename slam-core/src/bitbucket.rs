@@ -0,0 +1,76 @@
+// src/bitbucket.rs
+
+use crate::forge::{Forge, ForgeKind, Protocol};
+
+/// Host of Bitbucket Cloud; self-hosted instances set `bitbucket.host`.
+pub const DEFAULT_HOST: &str = "bitbucket.org";
+
+/// Push-only Bitbucket support: `create` pushes the branch and prints the page on which
+/// the pull request can be opened.
+pub struct Bitbucket {
+    host: String,
+}
+
+impl Bitbucket {
+    pub fn new(host: Option<String>) -> Self {
+        Self {
+            host: host.unwrap_or_else(|| DEFAULT_HOST.to_string()),
+        }
+    }
+}
+
+impl Forge for Bitbucket {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::Bitbucket
+    }
+
+    fn clone_url(&self, reposlug: &str, protocol: Protocol) -> String {
+        match protocol {
+            Protocol::Ssh => format!("git@{}:{}.git", self.host, reposlug),
+            Protocol::Https => format!("https://{}/{}.git", self.host, reposlug),
+        }
+    }
+
+    fn automates_prs(&self) -> bool {
+        false
+    }
+
+    fn review_url(&self, reposlug: &str, branch: &str, base: &str) -> String {
+        format!(
+            "https://{}/{}/pull-requests/new?source={}&dest={}",
+            self.host, reposlug, branch, base
+        )
+    }
+
+    fn pr_url(&self, reposlug: &str, number: u64) -> String {
+        format!("https://{}/{}/pull-requests/{}", self.host, reposlug, number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls() {
+        let bitbucket = Bitbucket::new(None);
+        assert_eq!(
+            bitbucket.clone_url("team/svc", Protocol::Ssh),
+            "git@bitbucket.org:team/svc.git"
+        );
+        assert_eq!(
+            bitbucket.clone_url("team/svc", Protocol::Https),
+            "https://bitbucket.org/team/svc.git"
+        );
+        assert_eq!(
+            bitbucket.review_url("team/svc", "SLAM-x", "main"),
+            "https://bitbucket.org/team/svc/pull-requests/new?source=SLAM-x&dest=main"
+        );
+        assert!(bitbucket.push_refspec("SLAM-x", "main").is_none());
+        assert!(bitbucket
+            .open_prs("team/svc")
+            .unwrap_err()
+            .to_string()
+            .contains("not supported"));
+    }
+}
@@ -0,0 +1,103 @@
+// src/cache.rs
+
+use log::{debug, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+/// How long cached GitHub listings stay valid unless configured otherwise.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+struct Settings {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Enables the cache under `dir`. Entries older than `ttl` are ignored; a zero `ttl`
+/// bypasses reads while still refreshing the stored entries. Until this is called
+/// (e.g. in tests) nothing is read or written.
+pub fn configure(dir: PathBuf, ttl: Duration) {
+    if SETTINGS.set(Settings { dir, ttl }).is_err() {
+        warn!("Cache already configured; ignoring");
+    }
+}
+
+fn read_fresh(path: &Path, ttl: Duration) -> Option<String> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let age = SystemTime::now().duration_since(modified).unwrap_or_default();
+    if age >= ttl {
+        debug!("Cache entry '{}' is stale ({:?} old)", path.display(), age);
+        return None;
+    }
+    fs::read_to_string(path).ok()
+}
+
+fn write_entry(path: &Path, content: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)
+}
+
+/// Returns the cached content for `key` if it exists and is younger than the TTL.
+pub fn read(key: &str) -> Option<String> {
+    let settings = SETTINGS.get()?;
+    let content = read_fresh(&settings.dir.join(key), settings.ttl)?;
+    debug!("Cache hit for '{}'", key);
+    Some(content)
+}
+
+/// Stores `content` under `key`; failures are logged and otherwise ignored.
+pub fn write(key: &str, content: &str) {
+    if let Some(settings) = SETTINGS.get() {
+        let path = settings.dir.join(key);
+        if let Err(e) = write_entry(&path, content) {
+            warn!("Failed to write cache entry '{}': {}", path.display(), e);
+        }
+    }
+}
+
+/// Drops the entry for `key`, so the next read goes to GitHub.
+pub fn remove(key: &str) {
+    if let Some(settings) = SETTINGS.get() {
+        let path = settings.dir.join(key);
+        if let Err(e) = fs::remove_file(&path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove cache entry '{}': {}", path.display(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_read_fresh_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested/repos-org.json");
+        write_entry(&path, "[]").unwrap();
+
+        assert_eq!(read_fresh(&path, DEFAULT_TTL), Some("[]".to_string()));
+    }
+
+    #[test]
+    fn test_read_fresh_zero_ttl_is_stale() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("repos-org.json");
+        write_entry(&path, "[]").unwrap();
+
+        assert_eq!(read_fresh(&path, Duration::ZERO), None);
+    }
+
+    #[test]
+    fn test_read_fresh_missing_entry() {
+        let temp_dir = TempDir::new().unwrap();
+        assert_eq!(read_fresh(&temp_dir.path().join("missing.json"), DEFAULT_TTL), None);
+    }
+}
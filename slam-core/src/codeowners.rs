@@ -0,0 +1,255 @@
+// src/codeowners.rs
+
+use eyre::Result;
+use log::{debug, warn};
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+/// Where GitHub and GitLab look for the file, in order of precedence.
+pub(crate) const LOCATIONS: [&str; 5] = [
+    ".github/CODEOWNERS",
+    "CODEOWNERS",
+    "docs/CODEOWNERS",
+    ".gitlab/CODEOWNERS",
+    "docs/.gitlab/CODEOWNERS",
+];
+
+/// An owner as written in CODEOWNERS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Owner {
+    /// `@login`
+    User(String),
+    /// `@org/team-slug`
+    Team { org: String, slug: String },
+    /// `someone@example.com`; cannot be requested as a reviewer by name.
+    Email(String),
+}
+
+impl Owner {
+    fn parse(token: &str) -> Option<Self> {
+        match token.strip_prefix('@') {
+            Some(name) => match name.split_once('/') {
+                Some((org, slug)) => Some(Owner::Team {
+                    org: org.to_string(),
+                    slug: slug.to_string(),
+                }),
+                None => Some(Owner::User(name.to_string())),
+            },
+            None if token.contains('@') => Some(Owner::Email(token.to_string())),
+            None => None,
+        }
+    }
+
+    /// The reviewer as `gh pr edit --add-reviewer` takes it (`login` or `org/team`).
+    pub fn reviewer(&self) -> Option<String> {
+        match self {
+            Owner::User(login) => Some(login.clone()),
+            Owner::Team { org, slug } => Some(format!("{}/{}", org, slug)),
+            Owner::Email(_) => None,
+        }
+    }
+}
+
+struct Rule {
+    pattern: Regex,
+    owners: Vec<Owner>,
+}
+
+/// A parsed CODEOWNERS file. As on GitHub, the last rule matching a path decides its owners.
+pub struct CodeOwners {
+    rules: Vec<Rule>,
+}
+
+/// Translates a gitignore-style CODEOWNERS pattern into a regex over repo-relative paths.
+fn pattern_regex(pattern: &str) -> Option<Regex> {
+    let dir_only = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+    // A slash anywhere but the end anchors the pattern to the repo root.
+    let anchored = trimmed.contains('/');
+    let trimmed = trimmed.trim_start_matches('/');
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    let mut re = String::from(if anchored { "^" } else { "^(?:.*/)?" });
+    let mut rest = trimmed;
+    while !rest.is_empty() {
+        if let Some(tail) = rest.strip_prefix("**/") {
+            re.push_str("(?:.*/)?");
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("**") {
+            re.push_str(".*");
+            rest = tail;
+        } else {
+            let c = rest.chars().next().unwrap();
+            match c {
+                '*' => re.push_str("[^/]*"),
+                '?' => re.push_str("[^/]"),
+                _ => re.push_str(&regex::escape(&c.to_string())),
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    // A pattern naming a directory owns everything below it; one ending in a wildcard
+    // (`docs/*`) only matches at its own level, as on GitHub.
+    let last = trimmed.rsplit('/').next().unwrap_or(trimmed);
+    re.push_str(if dir_only {
+        "/.*$"
+    } else if last != "**" && last.contains(['*', '?']) {
+        "$"
+    } else {
+        "(?:/.*)?$"
+    });
+    Regex::new(&re).ok()
+}
+
+impl CodeOwners {
+    pub fn parse(text: &str) -> Self {
+        let rules = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            // GitLab section headers, e.g. `[Docs]` or `^[Optional][2] @owner`.
+            .filter(|line| !line.starts_with('[') && !line.starts_with("^["))
+            .filter_map(|line| {
+                let mut tokens = line.split_whitespace();
+                let pattern = tokens.next()?;
+                let owners = tokens
+                    .take_while(|token| !token.starts_with('#'))
+                    .filter_map(Owner::parse)
+                    .collect();
+                match pattern_regex(pattern) {
+                    Some(pattern) => Some(Rule { pattern, owners }),
+                    None => {
+                        warn!("Ignoring unsupported CODEOWNERS pattern '{}'", pattern);
+                        None
+                    }
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Reads the repo's CODEOWNERS from the first location that has one.
+    pub fn load(repo_path: &Path) -> Result<Option<Self>> {
+        for location in LOCATIONS {
+            let path = repo_path.join(location);
+            if path.is_file() {
+                debug!("Using CODEOWNERS at '{}'", path.display());
+                return Ok(Some(Self::parse(&fs::read_to_string(&path)?)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Owners of `path`; empty when no rule matches or the last match names nobody.
+    pub fn owners_of(&self, path: &str) -> &[Owner] {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.pattern.is_match(path))
+            .map_or(&[], |rule| rule.owners.as_slice())
+    }
+
+    /// The distinct owners of all `paths`, in first-seen order.
+    pub fn owners_for<S: AsRef<str>>(&self, paths: &[S]) -> Vec<Owner> {
+        let mut owners: Vec<Owner> = Vec::new();
+        for owner in paths.iter().flat_map(|path| self.owners_of(path.as_ref())) {
+            if !owners.contains(owner) {
+                owners.push(owner.clone());
+            }
+        }
+        owners
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const CODEOWNERS: &str = "\
+# Default owners
+*                 @org/platform
+*.js              @web-dev   # inline comment
+/build/logs/      @ops
+docs/*            docs@example.com
+apps/             @octocat
+**/deploy.yml     @org/sre @ops
+/scripts/
+";
+
+    fn names(owners: &[Owner]) -> Vec<String> {
+        owners
+            .iter()
+            .map(|o| o.reviewer().unwrap_or_else(|| format!("{:?}", o)))
+            .collect()
+    }
+
+    #[test]
+    fn test_last_matching_rule_wins() {
+        let codeowners = CodeOwners::parse(CODEOWNERS);
+        assert_eq!(names(codeowners.owners_of("README.md")), vec!["org/platform"]);
+        assert_eq!(names(codeowners.owners_of("src/app/index.js")), vec!["web-dev"]);
+        assert_eq!(names(codeowners.owners_of("build/logs/today.log")), vec!["ops"]);
+        assert_eq!(
+            names(codeowners.owners_of("x/build/logs/today.log")),
+            vec!["org/platform"]
+        );
+        assert_eq!(
+            codeowners.owners_of("docs/intro.md"),
+            &[Owner::Email("docs@example.com".to_string())]
+        );
+        assert_eq!(names(codeowners.owners_of("docs/api/intro.md")), vec!["org/platform"]);
+        assert_eq!(names(codeowners.owners_of("nested/apps/web/main.rs")), vec!["octocat"]);
+        assert_eq!(
+            names(codeowners.owners_of("ci/prod/deploy.yml")),
+            vec!["org/sre", "ops"]
+        );
+        assert!(codeowners.owners_of("scripts/run.sh").is_empty());
+    }
+
+    #[test]
+    fn test_owners_for_dedupes() {
+        let codeowners = CodeOwners::parse(CODEOWNERS);
+        let owners = codeowners.owners_for(&["a.js", "b.js", "deploy.yml", "README.md"]);
+        assert_eq!(names(&owners), vec!["web-dev", "org/sre", "ops", "org/platform"]);
+    }
+
+    #[test]
+    fn test_pattern_regex() {
+        let re = pattern_regex("*.yml").unwrap();
+        assert!(re.is_match("a.yml") && re.is_match("ci/a.yml"));
+        assert!(!re.is_match("a.yaml"));
+
+        let re = pattern_regex("/config/*.yml").unwrap();
+        assert!(re.is_match("config/a.yml"));
+        assert!(!re.is_match("x/config/a.yml") && !re.is_match("config/sub/a.yml"));
+
+        let re = pattern_regex("docs").unwrap();
+        assert!(re.is_match("docs") && re.is_match("docs/a/b.md") && re.is_match("x/docs/a.md"));
+
+        let re = pattern_regex("src/**/mod.rs").unwrap();
+        assert!(re.is_match("src/mod.rs") && re.is_match("src/a/b/mod.rs"));
+        assert!(pattern_regex("/").is_none());
+    }
+
+    #[test]
+    fn test_gitlab_sections_are_skipped() {
+        let codeowners = CodeOwners::parse("[Docs]\n*.md @writer\n^[Optional] @nobody\n");
+        assert_eq!(names(codeowners.owners_of("README.md")), vec!["writer"]);
+    }
+
+    #[test]
+    fn test_load_prefers_github_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(CodeOwners::load(temp_dir.path()).unwrap().is_none());
+
+        fs::write(temp_dir.path().join("CODEOWNERS"), "* @root\n").unwrap();
+        fs::create_dir(temp_dir.path().join(".github")).unwrap();
+        fs::write(temp_dir.path().join(".github/CODEOWNERS"), "* @github\n").unwrap();
+        let codeowners = CodeOwners::load(temp_dir.path()).unwrap().unwrap();
+        assert_eq!(names(codeowners.owners_of("x")), vec!["github"]);
+    }
+}
@@ -0,0 +1,945 @@
+use clap::ValueEnum;
+use colored::*;
+use regex::Regex;
+use similar::{ChangeTag, DiffOp, DiffTag, InlineChange, TextDiff};
+use std::fmt;
+use std::ops::AddAssign;
+use std::sync::OnceLock;
+
+/// How changed lines are rendered.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DiffStyle {
+    /// One column, whole changed lines colored.
+    #[default]
+    Unified,
+    /// One column, with the changed words within a line highlighted.
+    Word,
+    /// Old and new text in two columns.
+    SideBySide,
+}
+
+/// Widest the left column of a side-by-side diff gets; longer lines are truncated.
+const SIDE_BY_SIDE_MAX_WIDTH: usize = 80;
+
+/// Context (`--buffer full`) that shows every line of a changed file. Half of `usize::MAX`, as
+/// the hunk grouping doubles it.
+pub const FULL_CONTEXT: usize = usize::MAX / 2;
+
+/// Lines of context around each hunk of a PR's patch, and so the most a review diff can show
+/// when the files' base contents can't be read.
+pub const PATCH_CONTEXT: usize = 3;
+
+static STYLE: OnceLock<DiffStyle> = OnceLock::new();
+static MAX_LINES: OnceLock<Option<usize>> = OnceLock::new();
+
+/// Selects the style used by `generate_diff`; defaults to `DiffStyle::Unified`.
+pub fn configure(style: DiffStyle) {
+    let _ = STYLE.set(style);
+}
+
+/// Caps the lines `generate_diff` renders per file; the rest is replaced by a truncation notice.
+pub fn configure_max_lines(max_lines: Option<usize>) {
+    let _ = MAX_LINES.set(max_lines);
+}
+
+/// Files changed, lines inserted and lines deleted, as in `git diff --stat`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffStat {
+    pub files: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl AddAssign for DiffStat {
+    fn add_assign(&mut self, other: Self) {
+        self.files += other.files;
+        self.insertions += other.insertions;
+        self.deletions += other.deletions;
+    }
+}
+
+fn plural(count: usize, singular: &str, plural: &str) -> String {
+    format!("{} {}", count, if count == 1 { singular } else { plural })
+}
+
+impl fmt::Display for DiffStat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} changed, {}(+), {}(-)",
+            plural(self.files, "file", "files"),
+            plural(self.insertions, "insertion", "insertions"),
+            plural(self.deletions, "deletion", "deletions")
+        )
+    }
+}
+
+/// Counts the lines inserted and deleted going from `original` to `updated`; a file
+/// with any change counts as one changed file.
+pub fn diffstat(original: &str, updated: &str) -> DiffStat {
+    let mut stat = DiffStat::default();
+    for change in TextDiff::from_lines(original, updated).iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Insert => stat.insertions += 1,
+            ChangeTag::Delete => stat.deletions += 1,
+            ChangeTag::Equal => {}
+        }
+    }
+    if stat.insertions + stat.deletions > 0 {
+        stat.files = 1;
+    }
+    stat
+}
+
+/// One line per repo with its diffstat, followed by the total across all repos.
+pub fn format_diffstat_summary(stats: &[(String, DiffStat)]) -> String {
+    let width = stats.iter().map(|(reposlug, _)| reposlug.len()).max().unwrap_or(0);
+    let mut total = DiffStat::default();
+    let mut out = format!("{}\n", "Diffstat:".bold());
+    for (reposlug, stat) in stats {
+        out.push_str(&format!("  {:<width$}  {}\n", reposlug, stat));
+        total += *stat;
+    }
+    out.push_str(&format!(
+        "  {} {}: {}\n",
+        "total".bold(),
+        plural(stats.len(), "repo", "repos"),
+        total
+    ));
+    out
+}
+
+pub fn reconstruct_files_from_unified_diff(diff_text: &str) -> Vec<(String, String, String)> {
+    let mut results = Vec::new();
+    let mut current_filename = String::new();
+    let mut orig_lines: Vec<String> = Vec::new();
+    let mut upd_lines: Vec<String> = Vec::new();
+    let mut next_orig_line = 1;
+    let mut next_upd_line = 1;
+
+    let hunk_header_re = Regex::new(r"^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@").unwrap();
+
+    for line in diff_text.lines() {
+        if line.starts_with("diff --git ") {
+            if !current_filename.is_empty() {
+                results.push((current_filename.clone(), orig_lines.join("\n"), upd_lines.join("\n")));
+            }
+            current_filename.clear();
+            orig_lines.clear();
+            upd_lines.clear();
+            next_orig_line = 1;
+            next_upd_line = 1;
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() >= 4 {
+                current_filename = parts[2].trim_start_matches("a/").to_string();
+            }
+        } else if line.starts_with("+++ ") {
+            if line.trim() != "+++ /dev/null" {
+                current_filename = line.trim_start_matches("+++ b/").to_string();
+            }
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            current_filename = path.to_string();
+        } else if let Some(caps) = hunk_header_re.captures(line) {
+            let hunk_orig_start: usize = caps.get(1).unwrap().as_str().parse().unwrap();
+            let hunk_upd_start: usize = caps.get(3).unwrap().as_str().parse().unwrap();
+
+            if hunk_orig_start > next_orig_line {
+                let gap = hunk_orig_start - next_orig_line;
+                for _ in 0..gap {
+                    orig_lines.push(String::new());
+                }
+                next_orig_line = hunk_orig_start;
+            }
+            if hunk_upd_start > next_upd_line {
+                let gap = hunk_upd_start - next_upd_line;
+                for _ in 0..gap {
+                    upd_lines.push(String::new());
+                }
+                next_upd_line = hunk_upd_start;
+            }
+        } else if let Some(content) = line.strip_prefix(" ") {
+            let content = content.to_string();
+            orig_lines.push(content.clone());
+            upd_lines.push(content);
+            next_orig_line += 1;
+            next_upd_line += 1;
+        } else if line.starts_with("-") && !line.starts_with("---") {
+            let content = line[1..].to_string();
+            orig_lines.push(content);
+            next_orig_line += 1;
+        } else if line.starts_with("+") && !line.starts_with("+++") {
+            let content = line[1..].to_string();
+            upd_lines.push(content);
+            next_upd_line += 1;
+        }
+    }
+    if !current_filename.is_empty() {
+        results.push((current_filename, orig_lines.join("\n"), upd_lines.join("\n")));
+    }
+    results
+}
+
+/// Each file's part of a unified diff, as `(file name, patch)`, named as by
+/// `reconstruct_files_from_unified_diff`.
+pub fn split_file_patches(diff_text: &str) -> Vec<(String, String)> {
+    let mut patches: Vec<String> = Vec::new();
+    for line in diff_text.lines() {
+        if line.starts_with("diff --git ") || patches.is_empty() {
+            patches.push(String::new());
+        }
+        if let Some(patch) = patches.last_mut() {
+            patch.push_str(line);
+            patch.push('\n');
+        }
+    }
+    patches
+        .into_iter()
+        .filter_map(|patch| {
+            let (name, _, _) = reconstruct_files_from_unified_diff(&patch).into_iter().next()?;
+            Some((name, patch))
+        })
+        .collect()
+}
+
+/// Applies one file's `patch` (see `split_file_patches`) to `original`, giving the file's lines
+/// before and after joined as `reconstruct_files_from_unified_diff` joins them. `None` when a
+/// hunk doesn't match `original`, e.g. because it is not the patch's base.
+pub fn apply_patch(original: &str, patch: &str) -> Option<(String, String)> {
+    let hunk_header_re = Regex::new(r"^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@").unwrap();
+    let base: Vec<&str> = original.lines().collect();
+    let mut updated: Vec<&str> = Vec::new();
+    let mut next = 0;
+    let mut in_hunk = false;
+    for line in patch.lines() {
+        if let Some(caps) = hunk_header_re.captures(line) {
+            let start: usize = caps[1].parse().ok()?;
+            let len: usize = caps.get(2).map_or(Some(1), |len| len.as_str().parse().ok())?;
+            // An empty range names the line after which the hunk goes.
+            let start = if len == 0 { start } else { start.checked_sub(1)? };
+            if start < next || start > base.len() {
+                return None;
+            }
+            updated.extend_from_slice(&base[next..start]);
+            next = start;
+            in_hunk = true;
+        } else if !in_hunk || line.starts_with('\\') {
+            continue;
+        } else if let Some(content) = line.strip_prefix(' ') {
+            if base.get(next) != Some(&content) {
+                return None;
+            }
+            updated.push(content);
+            next += 1;
+        } else if let Some(content) = line.strip_prefix('-') {
+            if base.get(next) != Some(&content) {
+                return None;
+            }
+            next += 1;
+        } else if let Some(content) = line.strip_prefix('+') {
+            updated.push(content);
+        } else if line.is_empty() {
+            // Some tools drop the space of an empty context line.
+            if base.get(next) != Some(&"") {
+                return None;
+            }
+            updated.push("");
+            next += 1;
+        } else {
+            in_hunk = false;
+        }
+    }
+    updated.extend_from_slice(&base[next..]);
+    Some((base.join("\n"), updated.join("\n")))
+}
+
+/// What one file's patch (see `split_file_patches`) says about the file besides its lines.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatchHeader {
+    /// The file's old path when the patch renames it.
+    pub renamed_from: Option<String>,
+    /// The file's `(old, new)` modes when the patch changes them.
+    pub mode_change: Option<(String, String)>,
+    pub new_file: bool,
+    pub deleted: bool,
+    /// The file is binary, so the patch carries no lines.
+    pub binary: bool,
+}
+
+/// Reads the lines ahead of a file patch's first hunk.
+pub fn patch_header(patch: &str) -> PatchHeader {
+    let mut header = PatchHeader::default();
+    let mut old_mode = None;
+    let (mut old_path, mut new_path) = (None, None);
+    for line in patch.lines() {
+        if line.starts_with("@@ ") {
+            break;
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            header.renamed_from = Some(path.to_string());
+        } else if let Some(mode) = line.strip_prefix("old mode ") {
+            old_mode = Some(mode.trim().to_string());
+        } else if let Some(mode) = line.strip_prefix("new mode ") {
+            header.mode_change = old_mode.take().map(|old| (old, mode.trim().to_string()));
+        } else if line.starts_with("new file mode ") || line == "--- /dev/null" {
+            header.new_file = true;
+        } else if line.starts_with("deleted file mode ") || line == "+++ /dev/null" {
+            header.deleted = true;
+        } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+            header.binary = true;
+        } else if let Some(path) = line.strip_prefix("--- a/") {
+            old_path = Some(path.to_string());
+        } else if let Some(path) = line.strip_prefix("+++ b/") {
+            new_path = Some(path.to_string());
+        }
+    }
+    // Patches without git's extended headers, e.g. GitLab's, only show a rename by the paths.
+    if header.renamed_from.is_none() {
+        if let (Some(old), Some(new)) = (old_path, new_path) {
+            if old != new {
+                header.renamed_from = Some(old);
+            }
+        }
+    }
+    header
+}
+
+pub fn generate_diff(original: &str, updated: &str, buffer: usize) -> String {
+    let diff = render_diff(original, updated, buffer, STYLE.get().copied().unwrap_or_default());
+    match MAX_LINES.get().copied().flatten() {
+        Some(max_lines) => truncate_lines(diff, max_lines),
+        None => diff,
+    }
+}
+
+/// Keeps the first `max_lines` lines of a rendered diff and says how many were dropped.
+fn truncate_lines(diff: String, max_lines: usize) -> String {
+    let total = diff.lines().count();
+    if total <= max_lines {
+        return diff;
+    }
+    let mut result: String = diff.lines().take(max_lines).flat_map(|line| [line, "\n"]).collect();
+    let notice = format!(
+        "… {} truncated (--max-lines-per-file {}) …",
+        plural(total - max_lines, "more line", "more lines"),
+        max_lines
+    );
+    result.push_str(&format!("{}\n", notice.yellow()));
+    result
+}
+
+/// Unchanged lines skipped between each group of hunks and the one before it; 0 for the first.
+fn gaps(groups: &[Vec<DiffOp>]) -> Vec<usize> {
+    let mut previous_end = None;
+    groups
+        .iter()
+        .map(|group| {
+            let start = group.first().map_or(0, |op| op.old_range().start);
+            let gap = previous_end.map_or(0, |end: usize| start.saturating_sub(end));
+            previous_end = group.last().map(|op| op.old_range().end);
+            gap
+        })
+        .collect()
+}
+
+/// The line standing in for `count` unchanged lines collapsed between two hunks.
+fn collapsed(count: usize) -> String {
+    format!(
+        "{}\n",
+        format!("      … {} …", plural(count, "unchanged line", "unchanged lines")).dimmed()
+    )
+}
+
+fn render_diff(original: &str, updated: &str, buffer: usize, style: DiffStyle) -> String {
+    if updated.is_empty() {
+        let mut result = String::new();
+        for (i, line) in original.lines().enumerate() {
+            result.push_str(&format!("{} | {}\n", format!("-{:4}", i + 1).red(), line.red()));
+        }
+        return result;
+    }
+    let diff = TextDiff::from_lines(original, updated);
+    match style {
+        DiffStyle::Unified => render_unified(&diff, buffer),
+        DiffStyle::Word => render_word(&diff, buffer),
+        DiffStyle::SideBySide => render_side_by_side(&diff, buffer),
+    }
+}
+
+fn render_unified<'a>(diff: &TextDiff<'a, 'a, 'a, str>, buffer: usize) -> String {
+    let mut result = String::new();
+
+    let groups = diff.grouped_ops(buffer);
+    for (group, gap) in groups.iter().zip(gaps(&groups)) {
+        if gap > 0 {
+            result.push_str(&collapsed(gap));
+        }
+        for op in group {
+            for change in diff.iter_changes(op) {
+                match change.tag() {
+                    ChangeTag::Delete => {
+                        result.push_str(&format!(
+                            "{} | {}\n",
+                            format!("-{:4}", change.old_index().unwrap() + 1).red(),
+                            change.to_string().trim_end().red()
+                        ));
+                    }
+                    ChangeTag::Insert => {
+                        result.push_str(&format!(
+                            "{} | {}\n",
+                            format!("+{:4}", change.new_index().unwrap() + 1).green(),
+                            change.to_string().trim_end().green()
+                        ));
+                    }
+                    ChangeTag::Equal => {
+                        result.push_str(&format!(
+                            "{} | {}\n",
+                            format!(" {:4}", change.old_index().unwrap() + 1).dimmed(),
+                            change.to_string().trim_end().dimmed()
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Splits a changed line into (emphasized, text) segments, dropping the line ending.
+fn inline_segments(change: &InlineChange<str>) -> Vec<(bool, String)> {
+    change
+        .iter_strings_lossy()
+        .map(|(emphasized, value)| (emphasized, value.trim_end_matches(['\n', '\r']).to_string()))
+        .filter(|(_, value)| !value.is_empty())
+        .collect()
+}
+
+/// Like the unified style, but only the words that changed within a line are emphasized.
+fn render_word<'a>(diff: &'a TextDiff<'a, 'a, 'a, str>, buffer: usize) -> String {
+    let mut result = String::new();
+
+    let groups = diff.grouped_ops(buffer);
+    for (group, gap) in groups.iter().zip(gaps(&groups)) {
+        if gap > 0 {
+            result.push_str(&collapsed(gap));
+        }
+        for op in group {
+            for change in diff.iter_inline_changes(op) {
+                let (gutter, text) = match change.tag() {
+                    ChangeTag::Equal => {
+                        let line = change
+                            .iter_strings_lossy()
+                            .map(|(_, s)| s.into_owned())
+                            .collect::<String>();
+                        result.push_str(&format!(
+                            "{} | {}\n",
+                            format!(" {:4}", change.old_index().unwrap() + 1).dimmed(),
+                            line.trim_end().dimmed()
+                        ));
+                        continue;
+                    }
+                    ChangeTag::Delete => (format!("-{:4}", change.old_index().unwrap() + 1).red(), Color::Red),
+                    ChangeTag::Insert => (format!("+{:4}", change.new_index().unwrap() + 1).green(), Color::Green),
+                };
+                let mut line = String::new();
+                for (emphasized, value) in inline_segments(&change) {
+                    let segment = value.color(text);
+                    if emphasized {
+                        line.push_str(&segment.bold().reversed().to_string());
+                    } else {
+                        line.push_str(&segment.to_string());
+                    }
+                }
+                result.push_str(&format!("{} | {}\n", gutter, line));
+            }
+        }
+    }
+    result
+}
+
+fn truncate(line: &str, width: usize) -> String {
+    if line.chars().count() <= width {
+        line.to_string()
+    } else {
+        let mut out: String = line.chars().take(width.saturating_sub(1)).collect();
+        out.push('…');
+        out
+    }
+}
+
+/// Old lines on the left, new lines on the right; replaced lines are paired up row by row.
+fn render_side_by_side<'a>(diff: &TextDiff<'a, 'a, 'a, str>, buffer: usize) -> String {
+    let old_lines = diff.old_slices();
+    let new_lines = diff.new_slices();
+    let groups = diff.grouped_ops(buffer);
+    let width = groups
+        .iter()
+        .flatten()
+        .flat_map(|op| op.old_range())
+        .map(|i| old_lines[i].trim_end().chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(SIDE_BY_SIDE_MAX_WIDTH);
+
+    let mut result = String::new();
+    let ops = groups.iter().zip(gaps(&groups)).flat_map(|(group, gap)| {
+        group
+            .iter()
+            .enumerate()
+            .map(move |(i, op)| (op, if i == 0 { gap } else { 0 }))
+    });
+    for (op, gap) in ops {
+        if gap > 0 {
+            result.push_str(&collapsed(gap));
+        }
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        let rows = old_range.len().max(new_range.len());
+        for row in 0..rows {
+            let old = (row < old_range.len()).then(|| old_range.start + row);
+            let new = (row < new_range.len()).then(|| new_range.start + row);
+            let left = match old {
+                Some(i) => format!("{:4} {:<width$}", i + 1, truncate(old_lines[i].trim_end(), width)),
+                None => " ".repeat(width + 5),
+            };
+            let right = match new {
+                Some(j) => format!("{:4} {}", j + 1, new_lines[j].trim_end()),
+                None => String::new(),
+            };
+            let (left, right) = match tag {
+                DiffTag::Equal => (left.dimmed(), right.dimmed()),
+                _ => (left.red(), right.green()),
+            };
+            let marker = side_by_side_marker(op, old.is_some(), new.is_some());
+            result.push_str(format!("{} {} {}", left, marker, right).trim_end());
+            result.push('\n');
+        }
+    }
+    result
+}
+
+fn side_by_side_marker(op: &DiffOp, has_old: bool, has_new: bool) -> &'static str {
+    match (op.tag(), has_old, has_new) {
+        (DiffTag::Equal, _, _) => "|",
+        (_, true, true) => "~",
+        (_, true, false) => "<",
+        _ => ">",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_diff_empty_updated() {
+        let original = "line1\nline2\nline3";
+        let updated = "";
+        let result = generate_diff(original, updated, 1);
+
+        // Should show all original lines as deletions (ignoring color codes)
+        assert!(result.contains("-   1"));
+        assert!(result.contains("-   2"));
+        assert!(result.contains("-   3"));
+        assert!(result.contains("line1"));
+        assert!(result.contains("line2"));
+        assert!(result.contains("line3"));
+    }
+
+    #[test]
+    fn test_generate_diff_no_changes() {
+        let original = "line1\nline2\nline3";
+        let updated = "line1\nline2\nline3";
+        let result = generate_diff(original, updated, 1);
+
+        // When there are no changes, the diff should be empty
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_generate_diff_with_changes() {
+        let original = "line1\nline2\nline3";
+        let updated = "line1\nmodified_line2\nline3";
+        let result = generate_diff(original, updated, 1);
+
+        // Should show deletion and insertion (ignoring color codes)
+        assert!(result.contains("-   2"));
+        assert!(result.contains("+   2"));
+        assert!(result.contains("line2"));
+        assert!(result.contains("modified_line2"));
+    }
+
+    #[test]
+    fn test_generate_diff_empty_original() {
+        let original = "";
+        let updated = "new_line1\nnew_line2";
+        let result = generate_diff(original, updated, 1);
+
+        // Should show all lines as insertions (ignoring color codes)
+        assert!(result.contains("+   1"));
+        assert!(result.contains("+   2"));
+        assert!(result.contains("new_line1"));
+        assert!(result.contains("new_line2"));
+    }
+
+    #[test]
+    fn test_diffstat_counts_lines() {
+        assert_eq!(
+            diffstat("a\nb\nc\n", "a\nB\nc\nd\n"),
+            DiffStat {
+                files: 1,
+                insertions: 2,
+                deletions: 1
+            }
+        );
+        assert_eq!(diffstat("same\n", "same\n"), DiffStat::default());
+        assert_eq!(diffstat("x\ny\n", "").deletions, 2);
+    }
+
+    #[test]
+    fn test_format_diffstat_summary() {
+        colored::control::set_override(false);
+        let one = DiffStat {
+            files: 1,
+            insertions: 1,
+            deletions: 1,
+        };
+        let two = DiffStat {
+            files: 2,
+            insertions: 5,
+            deletions: 0,
+        };
+        let out = format_diffstat_summary(&[("org/a".to_string(), one), ("org/long".to_string(), two)]);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "Diffstat:",
+                "  org/a     1 file changed, 1 insertion(+), 1 deletion(-)",
+                "  org/long  2 files changed, 5 insertions(+), 0 deletions(-)",
+                "  total 2 repos: 3 files changed, 6 insertions(+), 1 deletion(-)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_diff_word_highlights_changed_token() {
+        let original = "key: value\nimage: app:1.2.3\n";
+        let updated = "key: value\nimage: app:1.2.4\n";
+        let result = render_diff(original, updated, 1, DiffStyle::Word);
+        assert!(result.contains("-   2"));
+        assert!(result.contains("+   2"));
+        assert!(result.contains("1.2.4"));
+
+        let diff = TextDiff::from_lines(original, updated);
+        let emphasized: Vec<(ChangeTag, String)> = diff
+            .ops()
+            .iter()
+            .flat_map(|op| diff.iter_inline_changes(op))
+            .flat_map(|change| {
+                let tag = change.tag();
+                inline_segments(&change)
+                    .into_iter()
+                    .filter(|(emphasized, _)| *emphasized)
+                    .map(move |(_, value)| (tag, value))
+            })
+            .collect();
+        assert_eq!(
+            emphasized,
+            vec![
+                (ChangeTag::Delete, "app:1.2.3".to_string()),
+                (ChangeTag::Insert, "app:1.2.4".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_diff_side_by_side() {
+        colored::control::set_override(false);
+        let original = "a\nold\nc\ngone\n";
+        let updated = "a\nnew\nc\n";
+        let result = render_diff(original, updated, 1, DiffStyle::SideBySide);
+        let lines: Vec<&str> = result.lines().collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "   1 a    |    1 a",
+                "   2 old  ~    2 new",
+                "   3 c    |    3 c",
+                "   4 gone <",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_diff_side_by_side_insert_and_truncate() {
+        colored::control::set_override(false);
+        let long = "x".repeat(SIDE_BY_SIDE_MAX_WIDTH + 10);
+        let original = format!("{}\n", long);
+        let updated = format!("{}\nadded\n", long);
+        let result = render_diff(&original, &updated, 1, DiffStyle::SideBySide);
+        let lines: Vec<&str> = result.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains('…'));
+        assert!(lines[1].ends_with(">    2 added"));
+    }
+
+    #[test]
+    fn test_reconstruct_files_from_unified_diff_simple() {
+        let diff_text = r#"diff --git a/file1.txt b/file1.txt
+index 1234567..abcdefg 100644
+--- a/file1.txt
++++ b/file1.txt
+@@ -1,3 +1,3 @@
+ line1
+-old_line2
++new_line2
+ line3"#;
+
+        let result = reconstruct_files_from_unified_diff(diff_text);
+        assert_eq!(result.len(), 1);
+
+        let (filename, orig, upd) = &result[0];
+        assert_eq!(filename, "file1.txt");
+        assert_eq!(orig, "line1\nold_line2\nline3");
+        assert_eq!(upd, "line1\nnew_line2\nline3");
+    }
+
+    #[test]
+    fn test_reconstruct_files_from_unified_diff_multiple_files() {
+        let diff_text = r#"diff --git a/file1.txt b/file1.txt
+index 1234567..abcdefg 100644
+--- a/file1.txt
++++ b/file1.txt
+@@ -1,2 +1,2 @@
+ line1
+-old_line2
++new_line2
+diff --git a/file2.txt b/file2.txt
+index 2345678..bcdefgh 100644
+--- a/file2.txt
++++ b/file2.txt
+@@ -1,2 +1,2 @@
+ another_line1
+-another_old_line2
++another_new_line2"#;
+
+        let result = reconstruct_files_from_unified_diff(diff_text);
+        assert_eq!(result.len(), 2);
+
+        let (filename1, orig1, upd1) = &result[0];
+        assert_eq!(filename1, "file1.txt");
+        assert_eq!(orig1, "line1\nold_line2");
+        assert_eq!(upd1, "line1\nnew_line2");
+
+        let (filename2, orig2, upd2) = &result[1];
+        assert_eq!(filename2, "file2.txt");
+        assert_eq!(orig2, "another_line1\nanother_old_line2");
+        assert_eq!(upd2, "another_line1\nanother_new_line2");
+    }
+
+    #[test]
+    fn test_reconstruct_files_from_unified_diff_empty() {
+        let diff_text = "";
+        let result = reconstruct_files_from_unified_diff(diff_text);
+        assert_eq!(result.len(), 0);
+    }
+
+    #[test]
+    fn test_reconstruct_files_from_unified_diff_new_file() {
+        let diff_text = r#"diff --git a/newfile.txt b/newfile.txt
+new file mode 100644
+index 0000000..1234567
+--- /dev/null
++++ b/newfile.txt
+@@ -0,0 +1,2 @@
++new_line1
++new_line2"#;
+
+        let result = reconstruct_files_from_unified_diff(diff_text);
+        assert_eq!(result.len(), 1);
+
+        let (filename, orig, upd) = &result[0];
+        assert_eq!(filename, "newfile.txt");
+        assert_eq!(orig, "");
+        assert_eq!(upd, "new_line1\nnew_line2");
+    }
+
+    #[test]
+    fn test_reconstruct_files_from_unified_diff_deleted_file() {
+        let diff_text = r#"diff --git a/deletedfile.txt b/deletedfile.txt
+deleted file mode 100644
+index 1234567..0000000
+--- a/deletedfile.txt
++++ /dev/null
+@@ -1,2 +0,0 @@
+-deleted_line1
+-deleted_line2"#;
+
+        let result = reconstruct_files_from_unified_diff(diff_text);
+        assert_eq!(result.len(), 1);
+
+        let (filename, orig, upd) = &result[0];
+        assert_eq!(filename, "deletedfile.txt");
+        assert_eq!(orig, "deleted_line1\ndeleted_line2");
+        assert_eq!(upd, "");
+    }
+
+    #[test]
+    fn test_render_diff_full_context() {
+        let original: String = (1..=20).map(|i| format!("line{}\n", i)).collect();
+        let updated = original.replace("line10\n", "changed\n");
+        let lines = |buffer| {
+            render_diff(&original, &updated, buffer, DiffStyle::Unified)
+                .lines()
+                .count()
+        };
+        assert_eq!(lines(1), 4);
+        assert_eq!(lines(5), 12);
+        assert_eq!(lines(FULL_CONTEXT), 21);
+        assert_eq!(
+            render_diff(&original, &updated, FULL_CONTEXT, DiffStyle::SideBySide)
+                .lines()
+                .count(),
+            20
+        );
+    }
+
+    #[test]
+    fn test_render_diff_collapses_unchanged_lines() {
+        let original: String = (1..=30).map(|i| format!("line{}\n", i)).collect();
+        let updated = original
+            .replace("line5\n", "five\n")
+            .replace("line25\n", "twenty-five\n");
+        for style in [DiffStyle::Unified, DiffStyle::Word, DiffStyle::SideBySide] {
+            let diff = render_diff(&original, &updated, 1, style);
+            assert_eq!(diff.matches("… 17 unchanged lines …").count(), 1, "{:?}", style);
+        }
+        let single = render_diff(&original, &original.replace("line5\n", "five\n"), 1, DiffStyle::Unified);
+        assert!(!single.contains("unchanged"));
+    }
+
+    #[test]
+    fn test_truncate_lines() {
+        let diff: String = (1..=10).map(|i| format!("line{}\n", i)).collect();
+        assert_eq!(truncate_lines(diff.clone(), 10), diff);
+        let truncated = truncate_lines(diff, 4);
+        assert_eq!(truncated.lines().count(), 5);
+        assert!(truncated.contains("line4\n"));
+        assert!(truncated.contains("6 more lines truncated (--max-lines-per-file 4)"));
+    }
+
+    #[test]
+    fn test_apply_patch() {
+        let original: String = (1..=12).map(|i| format!("line{}\n", i)).collect();
+        let diff_text = r#"diff --git a/file.txt b/file.txt
+index 1234567..abcdefg 100644
+--- a/file.txt
++++ b/file.txt
+@@ -2,3 +2,3 @@
+ line2
+-line3
++three
+ line4
+@@ -10,2 +10,3 @@
+ line10
++ten and a half
+ line11
+diff --git a/new.txt b/new.txt
+new file mode 100644
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1,2 @@
++a
++b"#;
+
+        let patches = split_file_patches(diff_text);
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[0].0, "file.txt");
+        assert_eq!(patches[1].0, "new.txt");
+
+        let (before, after) = apply_patch(&original, &patches[0].1).unwrap();
+        assert_eq!(before.lines().count(), 12);
+        assert_eq!(after.lines().count(), 13);
+        assert!(after.starts_with("line1\nline2\nthree\nline4\nline5"));
+        assert!(after.ends_with("line10\nten and a half\nline11\nline12"));
+        assert_eq!(
+            apply_patch("", &patches[1].1),
+            Some((String::new(), "a\nb".to_string()))
+        );
+
+        assert_eq!(apply_patch("something else\n", &patches[0].1), None);
+    }
+
+    #[test]
+    fn test_patch_header() {
+        let diff_text = r#"diff --git a/run.sh b/run.sh
+old mode 100644
+new mode 100755
+diff --git a/old.yml b/new.yml
+similarity index 90%
+rename from old.yml
+rename to new.yml
+--- a/old.yml
++++ b/new.yml
+@@ -1 +1 @@
+-old
++new
+diff --git a/logo.png b/logo.png
+new file mode 100644
+index 0000000..1234567
+Binary files /dev/null and b/logo.png differ
+diff --git a/a.txt b/b.txt
+--- a/a.txt
++++ b/b.txt
+diff --git a/gone.txt b/gone.txt
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye"#;
+
+        let patches = split_file_patches(diff_text);
+        let names: Vec<&str> = patches.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["run.sh", "new.yml", "logo.png", "b.txt", "gone.txt"]);
+        let headers: Vec<PatchHeader> = patches.iter().map(|(_, patch)| patch_header(patch)).collect();
+        assert_eq!(
+            headers[0].mode_change,
+            Some(("100644".to_string(), "100755".to_string()))
+        );
+        assert_eq!(headers[1].renamed_from.as_deref(), Some("old.yml"));
+        assert!(headers[2].binary && headers[2].new_file);
+        assert_eq!(headers[3].renamed_from.as_deref(), Some("a.txt"));
+        assert!(headers[4].deleted && headers[4].renamed_from.is_none());
+
+        let renamed = "diff --git a/x.md b/docs/x.md\nsimilarity index 100%\nrename from x.md\nrename to docs/x.md\n";
+        assert_eq!(reconstruct_files_from_unified_diff(renamed)[0].0, "docs/x.md");
+    }
+
+    #[test]
+    fn test_reconstruct_files_from_unified_diff_context_lines() {
+        let diff_text = r#"diff --git a/file.txt b/file.txt
+index 1234567..abcdefg 100644
+--- a/file.txt
++++ b/file.txt
+@@ -2,5 +2,5 @@
+ context_line1
+ context_line2
+-old_line
++new_line
+ context_line3
+ context_line4"#;
+
+        let result = reconstruct_files_from_unified_diff(diff_text);
+        assert_eq!(result.len(), 1);
+
+        let (filename, orig, upd) = &result[0];
+        assert_eq!(filename, "file.txt");
+        assert_eq!(
+            orig,
+            "\ncontext_line1\ncontext_line2\nold_line\ncontext_line3\ncontext_line4"
+        );
+        assert_eq!(
+            upd,
+            "\ncontext_line1\ncontext_line2\nnew_line\ncontext_line3\ncontext_line4"
+        );
+    }
+}
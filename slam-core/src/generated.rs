@@ -0,0 +1,151 @@
+// src/generated.rs
+
+use log::debug;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Component, Path, PathBuf};
+
+use crate::git;
+
+/// Lockfiles produced by package managers; editing them by hand only creates churn.
+const LOCKFILES: &[&str] = &[
+    "Cargo.lock",
+    "Gemfile.lock",
+    "Pipfile.lock",
+    "Podfile.lock",
+    "bun.lockb",
+    "composer.lock",
+    "flake.lock",
+    "go.sum",
+    "mix.lock",
+    "package-lock.json",
+    "packages.lock.json",
+    "pnpm-lock.yaml",
+    "poetry.lock",
+    "pubspec.lock",
+    "uv.lock",
+    "yarn.lock",
+];
+
+/// Directories that hold build output or vendored third-party code.
+const GENERATED_DIRS: &[&str] = &["dist", "vendor", "node_modules"];
+
+/// How many leading lines are searched for a generated-code marker.
+const MARKER_LINES: usize = 10;
+
+fn is_lockfile(relative: &Path) -> bool {
+    relative
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| LOCKFILES.contains(&name))
+}
+
+fn in_generated_dir(relative: &Path) -> bool {
+    relative.parent().is_some_and(|parent| {
+        parent
+            .components()
+            .any(|c| matches!(c, Component::Normal(name) if GENERATED_DIRS.iter().any(|d| name == *d)))
+    })
+}
+
+/// True for lockfiles and files under `dist/`, `vendor/` or `node_modules/`: what can be told
+/// from the path alone, without reading the file.
+pub fn is_generated_path(relative: &Path) -> bool {
+    is_lockfile(relative) || in_generated_dir(relative)
+}
+
+/// True if one of the first lines carries `@generated` or Go's "Code generated ... DO NOT EDIT." header.
+fn has_generated_marker(full_path: &Path) -> bool {
+    let Ok(file) = File::open(full_path) else {
+        return false;
+    };
+    BufReader::new(file)
+        .lines()
+        .take(MARKER_LINES)
+        .map_while(Result::ok)
+        .any(|line| line.contains("@generated") || (line.contains("Code generated") && line.contains("DO NOT EDIT")))
+}
+
+/// Removes generated files (lockfiles, `dist/`, `vendor/`, `@generated` headers and paths
+/// marked `linguist-generated` in `.gitattributes`) from `files`, which are relative to `repo`.
+pub fn exclude_generated(repo: &Path, files: Vec<PathBuf>) -> Vec<PathBuf> {
+    let linguist_generated = git::linguist_generated(repo, &files).unwrap_or_else(|e| {
+        debug!("Skipping linguist-generated check in '{}': {}", repo.display(), e);
+        Vec::new()
+    });
+    files
+        .into_iter()
+        .filter(|relative| {
+            let generated = is_generated_path(relative)
+                || linguist_generated.contains(relative)
+                || has_generated_marker(&repo.join(relative));
+            if generated {
+                debug!(
+                    "Excluding generated file '{}' in '{}' (pass --include-generated to keep it)",
+                    relative.display(),
+                    repo.display()
+                );
+            }
+            !generated
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_is_lockfile() {
+        assert!(is_lockfile(Path::new("Cargo.lock")));
+        assert!(is_lockfile(Path::new("web/package-lock.json")));
+        assert!(!is_lockfile(Path::new("Cargo.toml")));
+        assert!(!is_lockfile(Path::new("docs/yarn.lock.md")));
+    }
+
+    #[test]
+    fn test_in_generated_dir() {
+        assert!(in_generated_dir(Path::new("dist/app.js")));
+        assert!(in_generated_dir(Path::new("web/node_modules/x/index.js")));
+        assert!(in_generated_dir(Path::new("vendor/github.com/pkg/errors/errors.go")));
+        assert!(!in_generated_dir(Path::new("src/dist.rs")));
+        assert!(!in_generated_dir(Path::new("vendor")));
+    }
+
+    #[test]
+    fn test_has_generated_marker() {
+        let temp_dir = TempDir::new().unwrap();
+        let marked = temp_dir.path().join("schema.rs");
+        let go = temp_dir.path().join("api.pb.go");
+        let plain = temp_dir.path().join("main.rs");
+        fs::write(&marked, "// @generated by diesel\npub mod schema {}\n").unwrap();
+        fs::write(&go, "// Code generated by protoc-gen-go. DO NOT EDIT.\npackage api\n").unwrap();
+        fs::write(&plain, "fn main() {}\n").unwrap();
+
+        assert!(has_generated_marker(&marked));
+        assert!(has_generated_marker(&go));
+        assert!(!has_generated_marker(&plain));
+        assert!(!has_generated_marker(&temp_dir.path().join("missing.rs")));
+    }
+
+    #[test]
+    fn test_exclude_generated() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path();
+        fs::create_dir_all(repo.join("dist")).unwrap();
+        fs::write(repo.join("README.md"), "docs\n").unwrap();
+        fs::write(repo.join("Cargo.lock"), "# lock\n").unwrap();
+        fs::write(repo.join("dist/bundle.js"), "x\n").unwrap();
+        fs::write(repo.join("gen.py"), "# @generated\n").unwrap();
+
+        let files = vec![
+            PathBuf::from("README.md"),
+            PathBuf::from("Cargo.lock"),
+            PathBuf::from("dist/bundle.js"),
+            PathBuf::from("gen.py"),
+        ];
+        assert_eq!(exclude_generated(repo, files), vec![PathBuf::from("README.md")]);
+    }
+}
@@ -0,0 +1,86 @@
+// src/gerrit.rs
+
+use crate::forge::{Forge, ForgeKind, Protocol};
+
+/// Port Gerrit serves git over SSH on.
+pub const DEFAULT_SSH_PORT: u16 = 29418;
+
+/// Push-only Gerrit support: `create` pushes the commit to `refs/for/<base>` with the
+/// change id as topic, which opens the review, and prints the topic's search page.
+pub struct Gerrit {
+    host: String,
+    ssh_port: u16,
+    web_url: String,
+}
+
+impl Gerrit {
+    /// `web_url` defaults to `https://<host>`.
+    pub fn new(host: String, ssh_port: Option<u16>, web_url: Option<String>) -> Self {
+        let web_url = web_url.unwrap_or_else(|| format!("https://{}", host));
+        Self {
+            ssh_port: ssh_port.unwrap_or(DEFAULT_SSH_PORT),
+            web_url: web_url.trim_end_matches('/').to_string(),
+            host,
+        }
+    }
+}
+
+impl Forge for Gerrit {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::Gerrit
+    }
+
+    /// Over HTTPS, the `/a/` prefix makes Gerrit authenticate the request.
+    fn clone_url(&self, reposlug: &str, protocol: Protocol) -> String {
+        match protocol {
+            Protocol::Ssh => format!("ssh://{}:{}/{}", self.host, self.ssh_port, reposlug),
+            Protocol::Https => format!("{}/a/{}", self.web_url, reposlug),
+        }
+    }
+
+    fn automates_prs(&self) -> bool {
+        false
+    }
+
+    fn push_refspec(&self, branch: &str, base: &str) -> Option<String> {
+        Some(format!("HEAD:refs/for/{}%topic={}", base, branch))
+    }
+
+    fn review_url(&self, _reposlug: &str, branch: &str, _base: &str) -> String {
+        format!("{}/q/topic:{}", self.web_url, branch)
+    }
+
+    fn pr_url(&self, reposlug: &str, number: u64) -> String {
+        format!("{}/c/{}/+/{}", self.web_url, reposlug, number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls() {
+        let gerrit = Gerrit::new("review.example.com".to_string(), None, None);
+        assert_eq!(
+            gerrit.clone_url("platform/svc", Protocol::Ssh),
+            "ssh://review.example.com:29418/platform/svc"
+        );
+        assert_eq!(
+            gerrit.clone_url("platform/svc", Protocol::Https),
+            "https://review.example.com/a/platform/svc"
+        );
+        assert_eq!(
+            gerrit.push_refspec("SLAM-x", "main").as_deref(),
+            Some("HEAD:refs/for/main%topic=SLAM-x")
+        );
+        assert_eq!(
+            gerrit.review_url("platform/svc", "SLAM-x", "main"),
+            "https://review.example.com/q/topic:SLAM-x"
+        );
+        assert_eq!(
+            gerrit.pr_url("platform/svc", 42),
+            "https://review.example.com/c/platform/svc/+/42"
+        );
+    }
+}
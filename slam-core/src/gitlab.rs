@@ -0,0 +1,595 @@
+// src/gitlab.rs
+
+use crate::codeowners::Owner;
+use crate::forge::{encode_path_segment, Forge, ForgeKind, Protocol};
+use crate::github::next_page;
+use eyre::{eyre, Result};
+use log::{debug, info};
+use serde_json::{json, Value};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Host of gitlab.com; self-managed instances set `gitlab.host`.
+pub const DEFAULT_HOST: &str = "gitlab.com";
+
+/// How requests reach GitLab: through the `glab` CLI (which brings its own auth), or
+/// straight to the REST API with an optional token.
+enum Transport {
+    Glab,
+    Api {
+        api_url: String,
+        token: Option<String>,
+        agent: ureq::Agent,
+    },
+}
+
+/// Merge request operations against one GitLab instance.
+pub struct Gitlab {
+    host: String,
+    transport: Transport,
+}
+
+fn glab_installed() -> bool {
+    Command::new("glab")
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+fn project(reposlug: &str) -> String {
+    format!("projects/{}", encode_path_segment(reposlug))
+}
+
+/// Parses `glab api --paginate` output, which prints one JSON array per page back to back.
+fn parse_pages(text: &str) -> Result<Vec<Value>> {
+    let mut items = Vec::new();
+    for page in serde_json::Deserializer::from_str(text).into_iter::<Value>() {
+        match page? {
+            Value::Array(page) => items.extend(page),
+            other => return Err(eyre!("Expected a JSON array from GitLab, got: {}", other)),
+        }
+    }
+    Ok(items)
+}
+
+/// A GitLab project trimmed to the fields of `git::OrgRepo`; GitLab has no primary language.
+fn project_to_repo(project: &Value) -> Value {
+    json!({
+        "name": project["path"],
+        "archived": project["archived"].as_bool().unwrap_or(false),
+        "visibility": project["visibility"],
+        "topics": project["topics"].as_array().cloned().unwrap_or_default(),
+    })
+}
+
+/// A merge request shaped like a `gh pr list --json headRefName,number,author` entry.
+fn mr_to_pr(mr: &Value) -> Value {
+    json!({
+        "headRefName": mr["source_branch"],
+        "number": mr["iid"],
+        "author": { "login": mr["author"]["username"] },
+    })
+}
+
+/// Rebuilds a unified diff from `merge_requests/:iid/diffs`, which only carries the hunks.
+fn unified_diff(files: &[Value]) -> String {
+    let mut out = String::new();
+    for file in files {
+        let old_path = file["old_path"].as_str().unwrap_or_default();
+        let new_path = file["new_path"].as_str().unwrap_or_default();
+        out.push_str(&format!("diff --git a/{} b/{}\n", old_path, new_path));
+        if file["new_file"].as_bool() == Some(true) {
+            out.push_str(&format!(
+                "new file mode {}\n--- /dev/null\n",
+                file["b_mode"].as_str().unwrap_or("100644")
+            ));
+        } else {
+            out.push_str(&format!("--- a/{}\n", old_path));
+        }
+        if file["deleted_file"].as_bool() == Some(true) {
+            out.push_str("+++ /dev/null\n");
+        } else {
+            out.push_str(&format!("+++ b/{}\n", new_path));
+        }
+        let hunks = file["diff"].as_str().unwrap_or_default();
+        out.push_str(hunks);
+        if !hunks.is_empty() && !hunks.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// The merge request as `gh pr view --json headRefName,isDraft,mergeable,reviewDecision,statusCheckRollup` prints it.
+fn mr_status(mr: &Value, approvals: &Value) -> Value {
+    let mergeable = if mr["has_conflicts"].as_bool() == Some(true) {
+        "CONFLICTING"
+    } else {
+        "MERGEABLE"
+    };
+    let review_decision = if approvals["approved"].as_bool() == Some(true) {
+        "APPROVED"
+    } else {
+        "REVIEW_REQUIRED"
+    };
+    let pipeline = &mr["head_pipeline"];
+    let conclusion = match pipeline["status"].as_str() {
+        None => None,
+        Some("success") => Some("SUCCESS"),
+        Some("skipped") => Some("SKIPPED"),
+        Some("failed") | Some("canceled") => Some("FAILURE"),
+        Some(_) => Some("PENDING"),
+    };
+    let checks = match conclusion {
+        Some(conclusion) => {
+            json!([{ "name": "pipeline", "conclusion": conclusion, "detailsUrl": pipeline["web_url"] }])
+        }
+        None => json!([]),
+    };
+    let state = match mr["state"].as_str() {
+        Some("merged") => "MERGED",
+        Some("closed") | Some("locked") => "CLOSED",
+        _ => "OPEN",
+    };
+    json!({
+        "headRefName": mr["source_branch"],
+        "state": state,
+        "isDraft": mr["draft"].as_bool().unwrap_or(false),
+        "mergeable": mergeable,
+        "reviewDecision": review_decision,
+        "statusCheckRollup": checks,
+        "author": { "login": mr["author"]["username"] },
+        "createdAt": mr["created_at"],
+        "milestone": mr["milestone"],
+    })
+}
+
+impl Gitlab {
+    /// Talks to `host` through `glab` when it is installed, else through the API at
+    /// `api_url` (default: `https://<host>/api/v4`) with `token`.
+    pub fn new(host: Option<String>, api_url: Option<String>, token: Option<String>) -> Self {
+        let host = host.unwrap_or_else(|| DEFAULT_HOST.to_string());
+        let transport = if glab_installed() {
+            debug!("Using glab for GitLab host {}", host);
+            Transport::Glab
+        } else {
+            let api_url = api_url
+                .unwrap_or_else(|| format!("https://{}/api/v4", host))
+                .trim_end_matches('/')
+                .to_string();
+            info!(
+                "glab not found; using the GitLab API at {} ({})",
+                api_url,
+                if token.is_some() {
+                    "with token"
+                } else {
+                    "unauthenticated"
+                }
+            );
+            Transport::Api {
+                api_url,
+                token,
+                agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(60)).build(),
+            }
+        };
+        Self { host, transport }
+    }
+
+    fn glab(&self, method: &str, path: &str, body: Option<&Value>, paginate: bool) -> Result<String> {
+        debug!("glab api -X {} {}", method, path);
+        let mut cmd = Command::new("glab");
+        cmd.args(["api", "--hostname", &self.host, "-X", method, path]);
+        if paginate {
+            cmd.arg("--paginate");
+        }
+        if body.is_some() {
+            cmd.args(["-H", "Content-Type: application/json", "--input", "-"]);
+        }
+        let mut child = cmd
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| eyre!("Failed to execute glab api: {}", e))?;
+        if let (Some(mut stdin), Some(body)) = (child.stdin.take(), body) {
+            stdin.write_all(body.to_string().as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(eyre!(
+                "GitLab API {} {} failed: {}",
+                method,
+                path,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Sends one API request; errors carry the HTTP status (e.g. "HTTP 404") and GitLab's message.
+    fn send(&self, method: &str, path: &str, body: Option<&Value>) -> Result<ureq::Response> {
+        let Transport::Api { api_url, token, agent } = &self.transport else {
+            return Err(eyre!("GitLab host {} is served by glab, not the API", self.host));
+        };
+        let url = if path.starts_with("http") {
+            path.to_string()
+        } else {
+            format!("{}/{}", api_url, path)
+        };
+        debug!("GitLab API {} {}", method, path);
+        let mut request = agent.request(method, &url).set("User-Agent", "slam");
+        if let Some(token) = token {
+            request = request.set("PRIVATE-TOKEN", token);
+        }
+        let result = match body {
+            Some(body) => request.send_json(body),
+            None => request.call(),
+        };
+        match result {
+            Ok(response) => Ok(response),
+            Err(ureq::Error::Status(code, response)) => {
+                let text = response.into_string().unwrap_or_default();
+                let message = serde_json::from_str::<Value>(&text)
+                    .ok()
+                    .and_then(|v| {
+                        let message = if v["message"].is_null() {
+                            &v["error"]
+                        } else {
+                            &v["message"]
+                        };
+                        match message {
+                            Value::Null => None,
+                            Value::String(s) => Some(s.clone()),
+                            other => Some(other.to_string()),
+                        }
+                    })
+                    .unwrap_or(text);
+                Err(eyre!(
+                    "GitLab API {} {} failed: HTTP {}: {}",
+                    method,
+                    path,
+                    code,
+                    message
+                ))
+            }
+            Err(e) => Err(eyre!("GitLab API {} {} failed: {}", method, path, e)),
+        }
+    }
+
+    /// Sends a JSON request and returns the parsed body (`Null` for empty responses).
+    pub fn call(&self, method: &str, path: &str, body: Option<&Value>) -> Result<Value> {
+        let text = match &self.transport {
+            Transport::Glab => self.glab(method, path, body, false)?,
+            Transport::Api { .. } => self.send(method, path, body)?.into_string()?,
+        };
+        if text.trim().is_empty() {
+            Ok(Value::Null)
+        } else {
+            Ok(serde_json::from_str(&text)?)
+        }
+    }
+
+    /// GETs every page of a list endpoint.
+    pub fn get_all(&self, path: &str) -> Result<Vec<Value>> {
+        if let Transport::Glab = self.transport {
+            return parse_pages(&self.glab("GET", path, None, true)?);
+        }
+        let mut items = Vec::new();
+        let mut next = Some(path.to_string());
+        while let Some(url) = next {
+            let response = self.send("GET", &url, None)?;
+            next = next_page(response.header("Link"));
+            match response.into_json::<Value>()? {
+                Value::Array(page) => items.extend(page),
+                other => return Err(eyre!("Expected a JSON array from '{}', got: {}", url, other)),
+            }
+        }
+        Ok(items)
+    }
+
+    fn merge_request(&self, reposlug: &str, iid: u64) -> Result<Value> {
+        self.call("GET", &format!("{}/merge_requests/{}", project(reposlug), iid), None)
+    }
+}
+
+impl Forge for Gitlab {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::Gitlab
+    }
+
+    fn clone_url(&self, reposlug: &str, protocol: Protocol) -> String {
+        match protocol {
+            Protocol::Ssh => format!("git@{}:{}.git", self.host, reposlug),
+            Protocol::Https => format!("https://{}/{}.git", self.host, reposlug),
+        }
+    }
+
+    fn review_url(&self, reposlug: &str, branch: &str, base: &str) -> String {
+        format!(
+            "https://{}/{}/-/merge_requests/new?merge_request[source_branch]={}&merge_request[target_branch]={}",
+            self.host, reposlug, branch, base
+        )
+    }
+
+    fn pr_url(&self, reposlug: &str, number: u64) -> String {
+        format!("https://{}/{}/-/merge_requests/{}", self.host, reposlug, number)
+    }
+
+    /// Projects directly in the group (or user namespace as fallback); subgroups are skipped
+    /// because slam addresses repos as `owner/name`.
+    fn owner_repos(&self, owner: &str) -> Result<Vec<Value>> {
+        let owner = encode_path_segment(owner);
+        let projects = self
+            .get_all(&format!("groups/{}/projects?per_page=100", owner))
+            .or_else(|_| self.get_all(&format!("users/{}/projects?per_page=100", owner)))?;
+        Ok(projects.iter().map(project_to_repo).collect())
+    }
+
+    fn lookup_repo(&self, reposlug: &str) -> Result<Option<String>> {
+        match self.call("GET", &project(reposlug), None) {
+            Ok(project) => Ok(project["path_with_namespace"].as_str().map(str::to_string)),
+            Err(e) if e.to_string().contains("404") => Ok(None),
+            Err(e) => Err(eyre!("Failed to look up repo '{}': {}", reposlug, e)),
+        }
+    }
+
+    fn open_prs(&self, reposlug: &str) -> Result<Value> {
+        let mrs = self.get_all(&format!(
+            "{}/merge_requests?state=opened&per_page=100",
+            project(reposlug)
+        ))?;
+        Ok(Value::Array(mrs.iter().map(mr_to_pr).collect()))
+    }
+
+    fn all_prs(&self, reposlug: &str) -> Result<Value> {
+        let mrs = self.get_all(&format!("{}/merge_requests?state=all&per_page=100", project(reposlug)))?;
+        Ok(Value::Array(
+            mrs.iter()
+                .map(|mr| {
+                    let state = match mr["state"].as_str() {
+                        Some("opened") => "OPEN",
+                        Some("merged") => "MERGED",
+                        _ => "CLOSED",
+                    };
+                    json!({
+                        "headRefName": mr["source_branch"],
+                        "number": mr["iid"],
+                        "state": state,
+                        "createdAt": mr["created_at"],
+                        "mergedAt": mr["merged_at"],
+                    })
+                })
+                .collect(),
+        ))
+    }
+
+    fn pr_diff(&self, reposlug: &str, number: u64) -> Result<String> {
+        let files = self.get_all(&format!(
+            "{}/merge_requests/{}/diffs?per_page=100",
+            project(reposlug),
+            number
+        ))?;
+        Ok(unified_diff(&files))
+    }
+
+    fn pr_status(&self, reposlug: &str, number: u64) -> Result<Value> {
+        let mr = self.merge_request(reposlug, number)?;
+        let approvals = self.call(
+            "GET",
+            &format!("{}/merge_requests/{}/approvals", project(reposlug), number),
+            None,
+        )?;
+        Ok(mr_status(&mr, &approvals))
+    }
+
+    fn create_pr(&self, reposlug: &str, head: &str, base: &str, title: &str, body: &str) -> Result<String> {
+        let request = json!({
+            "source_branch": head,
+            "target_branch": base,
+            "title": title,
+            "description": body,
+            "remove_source_branch": true,
+        });
+        let mr = self.call("POST", &format!("{}/merge_requests", project(reposlug)), Some(&request))?;
+        mr["web_url"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| eyre!("GitLab returned no URL for the new merge request in '{}'", reposlug))
+    }
+
+    /// Adds a user to the merge request's reviewers; GitLab cannot assign groups as reviewers.
+    fn request_review(&self, reposlug: &str, number: u64, owner: &Owner) -> Result<()> {
+        let Owner::User(username) = owner else {
+            return Err(eyre!("GitLab can only request reviews from users, not {:?}", owner));
+        };
+        let users = self.call(
+            "GET",
+            &format!("users?username={}", encode_path_segment(username)),
+            None,
+        )?;
+        let user_id = users[0]["id"]
+            .as_u64()
+            .ok_or_else(|| eyre!("No GitLab user '{}'", username))?;
+        let mr = self.merge_request(reposlug, number)?;
+        let mut reviewer_ids: Vec<u64> = mr["reviewers"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|reviewer| reviewer["id"].as_u64())
+            .collect();
+        if !reviewer_ids.contains(&user_id) {
+            reviewer_ids.push(user_id);
+        }
+        let path = format!("{}/merge_requests/{}", project(reposlug), number);
+        self.call("PUT", &path, Some(&json!({ "reviewer_ids": reviewer_ids })))?;
+        Ok(())
+    }
+
+    fn approve_pr(&self, reposlug: &str, number: u64) -> Result<()> {
+        self.call(
+            "POST",
+            &format!("{}/merge_requests/{}/approve", project(reposlug), number),
+            None,
+        )?;
+        Ok(())
+    }
+
+    fn merge_pr(&self, reposlug: &str, number: u64) -> Result<()> {
+        let path = format!("{}/merge_requests/{}/merge", project(reposlug), number);
+        let request = json!({ "squash": true, "should_remove_source_branch": true });
+        // GitLab answers 405 (or 401 for missing approvals) when the MR may not be merged yet.
+        let merged = self.call("PUT", &path, Some(&request)).map_err(|e| {
+            let message = e.to_string();
+            if message.contains("405") || message.contains("401") || message.to_lowercase().contains("approv") {
+                eyre!("Merge blocked: approval required (GitLab rules not satisfied)")
+            } else {
+                e
+            }
+        })?;
+        if merged["state"].as_str() != Some("merged") {
+            return Err(eyre!(
+                "Merge request merge not confirmed; merge blocked by approval requirements"
+            ));
+        }
+        Ok(())
+    }
+
+    fn update_pr_body(&self, reposlug: &str, number: u64, body: &str) -> Result<()> {
+        let path = format!("{}/merge_requests/{}", project(reposlug), number);
+        self.call("PUT", &path, Some(&json!({ "description": body })))?;
+        Ok(())
+    }
+
+    fn enable_auto_merge(&self, reposlug: &str, number: u64) -> Result<()> {
+        let path = format!("{}/merge_requests/{}/merge", project(reposlug), number);
+        // `auto_merge` replaces `merge_when_pipeline_succeeds` on GitLab 17.11+; older ones ignore it.
+        let request = json!({
+            "merge_when_pipeline_succeeds": true,
+            "auto_merge": true,
+            "squash": true,
+            "should_remove_source_branch": true,
+        });
+        self.call("PUT", &path, Some(&request))?;
+        Ok(())
+    }
+
+    fn close_pr(&self, reposlug: &str, number: u64, comment: &str) -> Result<()> {
+        let mr_path = format!("{}/merge_requests/{}", project(reposlug), number);
+        self.call("POST", &format!("{}/notes", mr_path), Some(&json!({ "body": comment })))?;
+        let mr = self.call("PUT", &mr_path, Some(&json!({ "state_event": "close" })))?;
+        if let Some(source_branch) = mr["source_branch"].as_str() {
+            self.delete_branch(reposlug, source_branch)?;
+        }
+        Ok(())
+    }
+
+    fn branches(&self, reposlug: &str) -> Result<Vec<String>> {
+        Ok(self
+            .get_all(&format!("{}/repository/branches?per_page=100", project(reposlug)))?
+            .iter()
+            .filter_map(|branch| branch["name"].as_str().map(str::to_string))
+            .collect())
+    }
+
+    fn delete_branch(&self, reposlug: &str, branch: &str) -> Result<()> {
+        let path = format!(
+            "{}/repository/branches/{}",
+            project(reposlug),
+            encode_path_segment(branch)
+        );
+        self.call("DELETE", &path, None)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_path_is_encoded() {
+        assert_eq!(project("group/repo"), "projects/group%2Frepo");
+    }
+
+    #[test]
+    fn test_parse_pages() {
+        let items = parse_pages("[{\"iid\":1}]\n[{\"iid\":2},{\"iid\":3}]").unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[2]["iid"], 3);
+        assert!(parse_pages("{\"message\":\"nope\"}").is_err());
+    }
+
+    #[test]
+    fn test_project_to_repo_matches_org_repo() {
+        let project = json!({
+            "path": "svc",
+            "name": "Service",
+            "archived": true,
+            "visibility": "internal",
+            "topics": ["rust"],
+        });
+        let repo: crate::git::OrgRepo = serde_json::from_value(project_to_repo(&project)).unwrap();
+        assert_eq!(repo.name, "svc");
+        assert!(repo.archived);
+        assert_eq!(repo.visibility, Some(crate::git::Visibility::Internal));
+        assert_eq!(repo.topics, vec!["rust"]);
+        assert!(repo.language.is_none());
+    }
+
+    #[test]
+    fn test_mr_to_pr() {
+        let mr = json!({ "iid": 12, "source_branch": "SLAM-x", "author": { "username": "alice" } });
+        assert_eq!(
+            mr_to_pr(&mr),
+            json!({ "headRefName": "SLAM-x", "number": 12, "author": { "login": "alice" } })
+        );
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let files = vec![
+            json!({
+                "old_path": "a.txt", "new_path": "a.txt",
+                "new_file": false, "deleted_file": false,
+                "diff": "@@ -1 +1 @@\n-old\n+new\n",
+            }),
+            json!({
+                "old_path": "b.txt", "new_path": "b.txt", "b_mode": "100644",
+                "new_file": true, "deleted_file": false,
+                "diff": "@@ -0,0 +1 @@\n+hi",
+            }),
+        ];
+        assert_eq!(
+            unified_diff(&files),
+            "diff --git a/a.txt b/a.txt\n--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-old\n+new\n\
+             diff --git a/b.txt b/b.txt\nnew file mode 100644\n--- /dev/null\n+++ b/b.txt\n@@ -0,0 +1 @@\n+hi\n"
+        );
+    }
+
+    #[test]
+    fn test_mr_status() {
+        let mr = json!({
+            "source_branch": "SLAM-x",
+            "draft": false,
+            "has_conflicts": false,
+            "head_pipeline": { "status": "success" },
+        });
+        let status = mr_status(&mr, &json!({ "approved": true }));
+        assert_eq!(status["headRefName"], "SLAM-x");
+        assert_eq!(status["state"], "OPEN");
+        assert_eq!(status["mergeable"], "MERGEABLE");
+        assert_eq!(status["reviewDecision"], "APPROVED");
+        assert_eq!(status["statusCheckRollup"][0]["conclusion"], "SUCCESS");
+
+        let blocked = json!({ "source_branch": "SLAM-x", "draft": true, "has_conflicts": true, "head_pipeline": null });
+        let status = mr_status(&blocked, &json!({ "approved": false }));
+        assert_eq!(status["isDraft"], true);
+        assert_eq!(status["mergeable"], "CONFLICTING");
+        assert_eq!(status["reviewDecision"], "REVIEW_REQUIRED");
+        assert_eq!(status["statusCheckRollup"], json!([]));
+
+        let merged = json!({ "source_branch": "SLAM-x", "state": "merged" });
+        assert_eq!(mr_status(&merged, &json!({}))["state"], "MERGED");
+    }
+}
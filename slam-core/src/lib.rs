@@ -0,0 +1,35 @@
+// src/lib.rs
+
+//! The multi-repo engine behind the `slam` CLI.
+//!
+//! A [`RepoSet`] is the set of local clones a change applies to, a [`ChangeRun`] applies one
+//! [`Change`] across it (branch, commit, push, PR), and a [`ReviewSet`] is the set of open PRs
+//! for one or more change ids, ready to be listed, cloned, approved or deleted. A set built with
+//! [`RepoSet::remote`] works on GitHub directly instead of local clones (see [`remote`]).
+//! [`audit`] checks a set's files against an expected content without changing anything.
+//!
+//! Process-wide settings (`pool::configure`, `cache::configure`, `git::configure_network_retry`,
+//! `diff::configure`, `github::configure`, `forge::configure`, `transaction::configure_journal`)
+//! should be applied once before any work starts; sensible defaults are used otherwise.
+
+pub mod audit;
+pub mod bitbucket;
+pub mod cache;
+pub mod codeowners;
+pub mod diff;
+pub mod forge;
+pub mod generated;
+pub mod gerrit;
+pub mod git;
+pub mod github;
+pub mod gitlab;
+pub mod pool;
+pub mod remote;
+pub mod repo;
+pub mod search;
+pub mod set;
+pub mod transaction;
+pub mod utils;
+
+pub use repo::{Change, CreateOptions, CreateResult, Repo, ReviewOp};
+pub use set::{ChangeRun, RepoSet, ReviewSet};
@@ -0,0 +1,111 @@
+// src/pool.rs
+
+use eyre::{eyre, Result};
+use log::debug;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::sync::OnceLock;
+
+/// Default number of concurrent `gh` calls; kept low to stay friendly with API rate limits.
+pub const DEFAULT_GH_JOBS: usize = 8;
+
+static GH_POOL: OnceLock<ThreadPool> = OnceLock::new();
+static GIT_POOL: OnceLock<ThreadPool> = OnceLock::new();
+
+/// Network-bound GitHub API work (`gh pr ...`, `gh api ...`) and disk-bound git work
+/// (checkout, commit, push, clone) run on separate bounded pools so that a large
+/// create or clone run cannot starve API polling, and vice versa.
+fn build_pool(name: &'static str, jobs: usize) -> ThreadPool {
+    debug!("Building '{}' pool with {} threads", name, jobs);
+    ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .thread_name(move |i| format!("slam-{}-{}", name, i))
+        .build()
+        .expect("Failed to build thread pool")
+}
+
+fn default_git_jobs() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4)
+}
+
+/// Sizes both pools. Must be called before any work is scheduled; `None` keeps the default.
+pub fn configure(gh_jobs: Option<usize>, git_jobs: Option<usize>) -> Result<()> {
+    let gh_jobs = gh_jobs.unwrap_or(DEFAULT_GH_JOBS);
+    let git_jobs = git_jobs.unwrap_or_else(default_git_jobs);
+    if gh_jobs == 0 || git_jobs == 0 {
+        return Err(eyre!(
+            "Pool sizes must be at least 1 (gh: {}, git: {})",
+            gh_jobs,
+            git_jobs
+        ));
+    }
+    GH_POOL
+        .set(build_pool("gh", gh_jobs))
+        .map_err(|_| eyre!("gh pool already initialized"))?;
+    GIT_POOL
+        .set(build_pool("git", git_jobs))
+        .map_err(|_| eyre!("git pool already initialized"))?;
+    Ok(())
+}
+
+/// Runs `op` on the gh (network) pool, blocking until it completes.
+/// Parallel iterators inside `op` are bounded by the gh pool size.
+pub fn gh<OP, R>(op: OP) -> R
+where
+    OP: FnOnce() -> R + Send,
+    R: Send,
+{
+    // Keep the caller's span (e.g. the repo being worked on) for what runs on the pool.
+    let span = tracing::Span::current();
+    GH_POOL
+        .get_or_init(|| build_pool("gh", DEFAULT_GH_JOBS))
+        .install(move || span.in_scope(op))
+}
+
+/// Runs `op` on the git (disk) pool, blocking until it completes.
+/// Parallel iterators inside `op` are bounded by the git pool size.
+pub fn git<OP, R>(op: OP) -> R
+where
+    OP: FnOnce() -> R + Send,
+    R: Send,
+{
+    let span = tracing::Span::current();
+    GIT_POOL
+        .get_or_init(|| build_pool("git", default_git_jobs()))
+        .install(move || span.in_scope(op))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rayon::prelude::*;
+
+    fn thread_name() -> String {
+        std::thread::current().name().unwrap_or_default().to_string()
+    }
+
+    #[test]
+    fn test_build_pool_size() {
+        let pool = build_pool("test", 3);
+        assert_eq!(pool.current_num_threads(), 3);
+    }
+
+    #[test]
+    fn test_work_runs_on_named_pools() {
+        assert!(gh(thread_name).starts_with("slam-gh-"));
+        assert!(git(thread_name).starts_with("slam-git-"));
+    }
+
+    #[test]
+    fn test_git_work_can_hand_off_to_gh_pool() {
+        let names: Vec<(String, String)> = git(|| {
+            (0..4)
+                .into_par_iter()
+                .map(|_| (thread_name(), gh(thread_name)))
+                .collect()
+        });
+        for (outer, inner) in names {
+            assert!(outer.starts_with("slam-git-"));
+            assert!(inner.starts_with("slam-gh-"));
+        }
+    }
+}
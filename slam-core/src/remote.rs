@@ -0,0 +1,784 @@
+// src/remote.rs
+
+//! Creating changes through the GitHub API instead of local clones: files are read from the
+//! default branch's tree, and the commit, branch and PR are created remotely, so no sandbox is
+//! needed. Pre-commit hooks don't run and commits can't be signed locally in this mode.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use eyre::{eyre, Result};
+use glob::{MatchOptions, Pattern};
+use log::{debug, info, warn};
+use rayon::prelude::*;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::codeowners::{self, CodeOwners};
+use crate::diff::{self, DiffStat};
+use crate::forge;
+use crate::generated;
+use crate::git;
+use crate::github;
+use crate::pool;
+use crate::repo::{self, Change, CreateOptions, CreateResult, IfExists, Repo};
+use crate::search::Substitution;
+use crate::transaction::{Step, Transaction};
+
+/// Calls the GitHub API through the configured client, or `gh api` without one.
+fn api(method: &str, path: &str, body: Option<&Value>) -> Result<Value> {
+    if let Some(client) = github::client() {
+        return client.call(method, path, body);
+    }
+    debug!("gh api -X {} {}", method, path);
+    let mut cmd = Command::new("gh");
+    cmd.args(["api", "-X", method, path]);
+    if body.is_some() {
+        cmd.args(["--input", "-"]);
+    }
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| eyre!("Failed to execute gh api: {}", e))?;
+    if let (Some(mut stdin), Some(body)) = (child.stdin.take(), body) {
+        stdin.write_all(body.to_string().as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "GitHub API {} {} failed: {}",
+            method,
+            path,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    if text.trim().is_empty() {
+        Ok(Value::Null)
+    } else {
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+/// A file in the tree of a repo's default branch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TreeEntry {
+    path: String,
+    mode: String,
+    sha: String,
+}
+
+/// The tip of a repo's default branch and the files in it.
+#[derive(Debug)]
+struct Head {
+    branch: String,
+    commit: String,
+    tree: String,
+    files: Vec<TreeEntry>,
+}
+
+impl Head {
+    fn file(&self, path: &str) -> Option<&TreeEntry> {
+        self.files.iter().find(|entry| entry.path == path)
+    }
+
+    /// True for a file at `path` or a directory holding files.
+    fn exists(&self, path: &str) -> bool {
+        let dir = format!("{}/", path.trim_end_matches('/'));
+        self.files
+            .iter()
+            .any(|entry| entry.path == path || entry.path.starts_with(&dir))
+    }
+}
+
+fn parse_tree(tree: &Value) -> Vec<TreeEntry> {
+    tree["tree"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry["type"].as_str() == Some("blob"))
+        .filter_map(|entry| {
+            Some(TreeEntry {
+                path: entry["path"].as_str()?.to_string(),
+                mode: entry["mode"].as_str()?.to_string(),
+                sha: entry["sha"].as_str()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn fetch_head(reposlug: &str) -> Result<Head> {
+    let repo = api("GET", &format!("repos/{}", reposlug), None)?;
+    let branch = repo["default_branch"]
+        .as_str()
+        .ok_or_else(|| eyre!("GitHub returned no default branch for '{}'", reposlug))?;
+    let tip = api("GET", &format!("repos/{}/branches/{}", reposlug, branch), None)?;
+    let (Some(commit), Some(tree)) = (
+        tip["commit"]["sha"].as_str(),
+        tip["commit"]["commit"]["tree"]["sha"].as_str(),
+    ) else {
+        return Err(eyre!("GitHub returned no commit for '{}' in '{}'", branch, reposlug));
+    };
+    let listing = api(
+        "GET",
+        &format!("repos/{}/git/trees/{}?recursive=1", reposlug, tree),
+        None,
+    )?;
+    let files = if listing["truncated"].as_bool() == Some(true) {
+        debug!("The tree of '{}' is too large to list at once; walking it", reposlug);
+        let mut files = Vec::new();
+        walk_tree(reposlug, tree, "", &mut files)?;
+        files
+    } else {
+        parse_tree(&listing)
+    };
+    Ok(Head {
+        branch: branch.to_string(),
+        commit: commit.to_string(),
+        tree: tree.to_string(),
+        files,
+    })
+}
+
+/// Lists the blobs of tree `sha` one directory at a time, for trees too large for one
+/// recursive listing; paths are prefixed with `prefix`.
+fn walk_tree(reposlug: &str, sha: &str, prefix: &str, files: &mut Vec<TreeEntry>) -> Result<()> {
+    let listing = api("GET", &format!("repos/{}/git/trees/{}", reposlug, sha), None)?;
+    if listing["truncated"].as_bool() == Some(true) {
+        return Err(eyre!(
+            "The tree of '{}' has too many entries in '/{}' to list",
+            reposlug,
+            prefix
+        ));
+    }
+    for entry in listing["tree"].as_array().into_iter().flatten() {
+        let (Some(path), Some(sha)) = (entry["path"].as_str(), entry["sha"].as_str()) else {
+            continue;
+        };
+        let path = format!("{}{}", prefix, path);
+        match entry["type"].as_str() {
+            Some("tree") => walk_tree(reposlug, sha, &format!("{}/", path), files)?,
+            Some("blob") => {
+                if let Some(mode) = entry["mode"].as_str() {
+                    files.push(TreeEntry {
+                        path,
+                        mode: mode.to_string(),
+                        sha: sha.to_string(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The contents of blob `sha`, or `None` when it is not UTF-8 text.
+fn read_blob(reposlug: &str, sha: &str) -> Result<Option<String>> {
+    let blob = api("GET", &format!("repos/{}/git/blobs/{}", reposlug, sha), None)?;
+    let encoded: String = blob["content"]
+        .as_str()
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect();
+    let bytes = STANDARD
+        .decode(encoded)
+        .map_err(|e| eyre!("Failed to decode blob {} of '{}': {}", sha, reposlug, e))?;
+    Ok(String::from_utf8(bytes).ok())
+}
+
+/// Paths of `files` matching any of the glob `ptns` as they would in a clone (`*` stops at
+/// `/`), sorted. Generated files are left out unless `include_generated`; only what the path
+/// tells counts, as the contents are not read.
+fn matching_files(files: &[TreeEntry], ptns: &[String], include_generated: bool) -> Result<Vec<String>> {
+    let options = MatchOptions {
+        require_literal_separator: true,
+        ..MatchOptions::new()
+    };
+    let patterns = ptns
+        .iter()
+        .map(|ptn| Pattern::new(ptn).map_err(|e| eyre!("Invalid file pattern '{}': {}", ptn, e)))
+        .collect::<Result<Vec<_>>>()?;
+    let mut matched: Vec<String> = files
+        .iter()
+        .filter(|entry| {
+            patterns
+                .iter()
+                .any(|pattern| pattern.matches_with(&entry.path, options))
+        })
+        .filter(|entry| include_generated || !generated::is_generated_path(Path::new(&entry.path)))
+        .map(|entry| entry.path.clone())
+        .collect();
+    matched.sort();
+    matched.dedup();
+    Ok(matched)
+}
+
+/// Whether the default branch of `reposlug` has, for every glob in `ptns`, a file or a
+/// directory matching it.
+pub fn has_paths(reposlug: &str, ptns: &[String]) -> Result<bool> {
+    let options = MatchOptions {
+        require_literal_separator: true,
+        ..MatchOptions::new()
+    };
+    let patterns = ptns
+        .iter()
+        .map(|ptn| Pattern::new(ptn.trim_end_matches('/')).map_err(|e| eyre!("Invalid file pattern '{}': {}", ptn, e)))
+        .collect::<Result<Vec<_>>>()?;
+    let head = fetch_head(reposlug)?;
+    // Directories only show up as the leading components of the files in them.
+    let paths: Vec<&str> = head
+        .files
+        .iter()
+        .flat_map(|entry| {
+            entry
+                .path
+                .match_indices('/')
+                .map(|(i, _)| &entry.path[..i])
+                .chain([entry.path.as_str()])
+        })
+        .collect();
+    Ok(patterns
+        .iter()
+        .all(|pattern| paths.iter().any(|path| pattern.matches_with(path, options))))
+}
+
+/// The contents of `paths` on the default branch of `reposlug`, `None` for those missing or
+/// not UTF-8 text.
+pub fn read_files(reposlug: &str, paths: &[String]) -> Result<Vec<Option<String>>> {
+    let head = fetch_head(reposlug)?;
+    paths
+        .iter()
+        .map(|path| match head.file(path) {
+            Some(entry) => read_blob(reposlug, &entry.sha),
+            None => Ok(None),
+        })
+        .collect()
+}
+
+/// `reposlug` prepared for `change`, recording the files on its default branch that match
+/// `file_ptns`.
+pub fn repo_with_files(
+    reposlug: &str,
+    change: &Option<Change>,
+    file_ptns: &[String],
+    change_id: &str,
+    include_generated: bool,
+) -> Result<Repo> {
+    let files = if file_ptns.is_empty() {
+        Vec::new()
+    } else {
+        matching_files(&fetch_head(reposlug)?.files, file_ptns, include_generated)?
+    };
+    Ok(Repo {
+        reposlug: reposlug.to_string(),
+        dir: None,
+        change_id: change_id.to_string(),
+        change: change.clone(),
+        files,
+        pr_number: 0,
+    })
+}
+
+/// `repo_with_files` for each of `reposlugs` in parallel, and the repos that could not be
+/// listed, each with the reason.
+pub fn discover(
+    reposlugs: &[String],
+    change: &Option<Change>,
+    file_ptns: &[String],
+    change_id: &str,
+    include_generated: bool,
+) -> (Vec<Repo>, Vec<(String, String)>) {
+    let results: Vec<Result<Repo>> = pool::gh(|| {
+        reposlugs
+            .par_iter()
+            .map(|reposlug| repo_with_files(reposlug, change, file_ptns, change_id, include_generated))
+            .collect()
+    });
+    let mut repos = Vec::new();
+    let mut skipped = Vec::new();
+    for (reposlug, result) in reposlugs.iter().zip(results) {
+        match result {
+            Ok(repo) => repos.push(repo),
+            Err(e) => {
+                warn!("Skipping '{}': {}", reposlug, e);
+                skipped.push((reposlug.clone(), e.to_string()));
+            }
+        }
+    }
+    (repos, skipped)
+}
+
+/// Whether `branch` exists in `reposlug` on GitHub.
+pub fn branch_exists(reposlug: &str, branch: &str) -> Result<bool> {
+    match api("GET", &format!("repos/{}/git/ref/heads/{}", reposlug, branch), None) {
+        Ok(_) => Ok(true),
+        Err(e) if e.to_string().contains("404") => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Points `branch` of `reposlug` at commit `sha`, creating the branch or force-moving it.
+pub(crate) fn point_branch(reposlug: &str, branch: &str, sha: &str) -> Result<()> {
+    info!("Pointing branch '{}' of '{}' at {}", branch, reposlug, sha);
+    if branch_exists(reposlug, branch)? {
+        api(
+            "PATCH",
+            &format!("repos/{}/git/refs/heads/{}", reposlug, branch),
+            Some(&json!({ "sha": sha, "force": true })),
+        )?;
+    } else {
+        api(
+            "POST",
+            &format!("repos/{}/git/refs", reposlug),
+            Some(&json!({ "ref": format!("refs/heads/{}", branch), "sha": sha })),
+        )?;
+    }
+    Ok(())
+}
+
+/// What a change does to one repo: the diff listing, its stats and the tree entries to commit.
+#[derive(Debug, Default)]
+struct Plan {
+    diff: String,
+    stat: DiffStat,
+    numstat: Vec<(String, usize, usize)>,
+    tree: Vec<Value>,
+}
+
+impl Plan {
+    fn write(&mut self, header: &str, entry: Option<&TreeEntry>, path: &str, before: &str, after: &str, buffer: usize) {
+        let stat = diff::diffstat(before, after);
+        self.diff
+            .push_str(&repo::diff_block(header, &diff::generate_diff(before, after, buffer)));
+        self.stat += stat;
+        self.numstat.push((path.to_string(), stat.insertions, stat.deletions));
+        let mode = entry.map_or("100644", |entry| entry.mode.as_str());
+        self.tree
+            .push(json!({ "path": path, "mode": mode, "type": "blob", "content": after }));
+    }
+
+    /// Writes `contents` to `path` as `Change::Add` does, unless it already holds them.
+    fn add(
+        &mut self,
+        head: &Head,
+        path: &str,
+        contents: &str,
+        if_exists: IfExists,
+        buffer: usize,
+        read: &impl Fn(&TreeEntry) -> Result<Option<String>>,
+    ) -> Result<()> {
+        let entry = head.file(path);
+        let existing = match entry {
+            Some(entry) => Some(read(entry)?.unwrap_or_default()),
+            None => None,
+        };
+        if let Some(updated) = repo::added_contents(path, existing.as_deref(), contents, if_exists)? {
+            let indicator = if entry.is_some() { "M" } else { "A" };
+            let before = existing.as_deref().unwrap_or("");
+            self.write(
+                &format!("{} {}", indicator, path),
+                entry,
+                path,
+                before,
+                &updated,
+                buffer,
+            );
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, entry: &TreeEntry) {
+        self.tree
+            .push(json!({ "path": entry.path, "mode": entry.mode, "type": "blob", "sha": null }));
+    }
+}
+
+/// Works out what `repo`'s change does to the files of `head`, reading them with `read`.
+fn plan(
+    repo: &Repo,
+    head: &Head,
+    buffer: usize,
+    simplified: bool,
+    read: impl Fn(&TreeEntry) -> Result<Option<String>>,
+) -> Result<Plan> {
+    let mut plan = Plan::default();
+    let Some(change) = repo.change.as_ref() else {
+        return Ok(plan);
+    };
+    match change {
+        Change::Delete => {
+            for file in &repo.files {
+                let Some(entry) = head.file(file) else {
+                    continue;
+                };
+                let content = read(entry)?.unwrap_or_default();
+                plan.write(&format!("D {}", file), Some(entry), file, &content, "", buffer);
+                plan.tree.pop();
+                plan.delete(entry);
+            }
+        }
+        Change::DeleteDir(dir, max_files) => {
+            let prefix = format!("{}/", dir.trim_end_matches('/'));
+            let entries: Vec<&TreeEntry> = head
+                .files
+                .iter()
+                .filter(|entry| entry.path.starts_with(&prefix))
+                .collect();
+            if entries.len() > *max_files {
+                return Err(eyre!(
+                    "'{}' in '{}' holds {} tracked files, more than --max-files {}; refusing to delete",
+                    dir,
+                    repo.reposlug,
+                    entries.len(),
+                    max_files
+                ));
+            }
+            let mut contents = HashMap::new();
+            for entry in &entries {
+                if let Some(content) = read(entry)? {
+                    plan.stat.deletions += content.lines().count();
+                    contents.insert(entry.path.clone(), content);
+                }
+                plan.numstat.push((entry.path.clone(), 0, 0));
+                plan.delete(entry);
+            }
+            plan.stat.files += entries.len();
+            let tracked: Vec<String> = entries.iter().map(|entry| entry.path.clone()).collect();
+            plan.diff
+                .push_str(&repo::delete_dir_summary(&tracked, simplified, |file| {
+                    contents.get(file).cloned()
+                }));
+        }
+        Change::Add(path, contents, if_exists) => plan.add(head, path, contents, *if_exists, buffer, &read)?,
+        Change::Copy(files, if_exists) => {
+            for (path, contents) in files {
+                plan.add(head, path, contents, *if_exists, buffer, &read)?;
+            }
+        }
+        Change::Chmod(mode) => {
+            for file in &repo.files {
+                let Some(entry) = head.file(file) else {
+                    continue;
+                };
+                if entry.mode == mode.git_mode() {
+                    continue;
+                }
+                plan.diff
+                    .push_str(&repo::diff_block(&repo::chmod_header(file, &entry.mode, *mode), ""));
+                plan.stat.files += 1;
+                plan.numstat.push((file.clone(), 0, 0));
+                plan.tree
+                    .push(json!({ "path": entry.path, "mode": mode.git_mode(), "type": "blob", "sha": entry.sha }));
+            }
+        }
+        Change::Sub(_, _) | Change::Regex(_, _) | Change::MultiSub(_) => {
+            let Some(substitution) = Substitution::new(change)? else {
+                return Ok(plan);
+            };
+            for file in &repo.files {
+                let Some(entry) = head.file(file) else {
+                    continue;
+                };
+                let Some(content) = read(entry)? else {
+                    continue;
+                };
+                if let Some(updated) = substitution.apply(&content) {
+                    let prefix = if simplified { "><" } else { "M" };
+                    plan.write(
+                        &format!("{} {}", prefix, file),
+                        Some(entry),
+                        file,
+                        &content,
+                        &updated,
+                        buffer,
+                    );
+                }
+            }
+        }
+    }
+    Ok(plan)
+}
+
+/// `{ name, email }` for the Git data API.
+fn person(identity: &git::Identity) -> Value {
+    json!({ "name": identity.name, "email": identity.email })
+}
+
+/// The CODEOWNERS on `head`, read from the first location that has one.
+fn load_codeowners(reposlug: &str, head: &Head) -> Result<Option<CodeOwners>> {
+    for location in codeowners::LOCATIONS {
+        if let Some(entry) = head.file(location) {
+            return Ok(read_blob(reposlug, &entry.sha)?.map(|text| CodeOwners::parse(&text)));
+        }
+    }
+    Ok(None)
+}
+
+/// As `Repo::create`, through the GitHub API: the change is applied to the files of the
+/// default branch and committed there on the change's branch, without a local clone.
+pub fn create(repo: &Repo, opts: &CreateOptions) -> CreateResult {
+    let _span = repo.span().entered();
+    try_create(repo, opts).unwrap_or_else(|e| CreateResult::Failed { reason: e.to_string() })
+}
+
+fn try_create(repo: &Repo, opts: &CreateOptions) -> Result<CreateResult> {
+    let reposlug = repo.reposlug.as_str();
+    if forge::for_repo(reposlug).is_some() {
+        return Err(eyre!(
+            "'{}' is not on GitHub; --remote only works for GitHub repos",
+            reposlug
+        ));
+    }
+    let head = fetch_head(reposlug)?;
+    let repo = &repo.resolved(|| Ok(head.branch.clone()))?;
+    let plan = plan(repo, &head, opts.buffer, opts.simplified, |entry| {
+        read_blob(reposlug, &entry.sha)
+    })?;
+    if plan.tree.is_empty() {
+        let applied = repo.already_applied_with(
+            |path| {
+                head.file(path)
+                    .and_then(|entry| read_blob(reposlug, &entry.sha).ok().flatten())
+            },
+            |path| head.exists(path),
+        );
+        if applied {
+            info!("'{}' is already compliant; skipping.", reposlug);
+            return Ok(CreateResult::AlreadyCompliant);
+        }
+        info!("No changes detected in '{}'; skipping.", reposlug);
+        return Ok(CreateResult::NoChanges);
+    }
+    let diff = format!("{}\n{}", reposlug, plan.diff);
+    let stat = plan.stat;
+
+    let Some(message) = opts.commit_msg.as_deref() else {
+        info!("Dry run for '{}'; returning diff.", reposlug);
+        return Ok(CreateResult::DryRun { diff, stat });
+    };
+    debug!("Committing remotely as '{}'; pre-commit hooks are not run", message);
+
+    let branch = repo::normalize_change_id(&repo.change_id, &opts.branch_prefix);
+    let reused_pr = if opts.update_existing_pr {
+        pool::gh(|| git::get_pr_number_for_repo(reposlug, &branch))?
+    } else {
+        0
+    };
+    let changed_files: Vec<String> = plan.numstat.iter().map(|(path, _, _)| path.clone()).collect();
+    let commit_msg = repo.render_commit_msg(opts, &branch, &changed_files);
+
+    let tree = api(
+        "POST",
+        &format!("repos/{}/git/trees", reposlug),
+        Some(&json!({ "base_tree": head.tree, "tree": plan.tree })),
+    )?;
+    let tree_sha = tree["sha"]
+        .as_str()
+        .ok_or_else(|| eyre!("GitHub returned no tree for '{}'", reposlug))?;
+    let mut request = json!({ "message": commit_msg, "tree": tree_sha, "parents": [head.commit] });
+    if let Some(author) = &opts.author {
+        request["author"] = person(author);
+    }
+    if let Some(committer) = opts.committer.as_ref().or(opts.author.as_ref()) {
+        request["committer"] = person(committer);
+    }
+    let commit = api("POST", &format!("repos/{}/git/commits", reposlug), Some(&request))?;
+    let commit_sha = commit["sha"]
+        .as_str()
+        .ok_or_else(|| eyre!("GitHub returned no commit for '{}'", reposlug))?;
+
+    let update_ref = Step::UpdateRef {
+        reposlug: reposlug.to_string(),
+        branch: branch.clone(),
+        sha: commit_sha.to_string(),
+    };
+    let mut transaction = Transaction::journaled(&branch, reposlug);
+    if reused_pr != 0 {
+        // Moving the open PR's branch can't be undone, so it is no transaction step.
+        update_ref.apply()?;
+    } else {
+        transaction.apply(update_ref)?;
+    }
+
+    let hook_fixed_files: Vec<String> = Vec::new();
+    let body = repo.render_pr_body(opts, &branch, &commit_msg, &plan.numstat, &hook_fixed_files);
+    if reused_pr != 0 {
+        let pr_url = forge::pr_url(reposlug, reused_pr);
+        match pool::gh(|| git::update_pr_body(reposlug, reused_pr, &body)) {
+            Ok(()) => info!("Updated PR #{} for '{}'.", reused_pr, reposlug),
+            Err(e) => warn!("Pushed to {} but failed to update its body: {}", pr_url, e),
+        }
+        repo.track_pr(opts, &pr_url);
+        return Ok(CreateResult::PrUpdated { diff, stat, pr_url });
+    }
+
+    let existing_pr = pool::gh(|| git::get_pr_number_for_repo(reposlug, &branch))?;
+    if existing_pr != 0 {
+        info!("Existing PR #{} found for '{}'; closing it.", existing_pr, reposlug);
+        pool::gh(|| git::close_pr(reposlug, existing_pr))?;
+    }
+    let pr_url = pool::gh(|| git::create_pr(Path::new("."), reposlug, &branch, None, &body))
+        .ok_or_else(|| eyre!("Failed to create PR for repo '{}'", reposlug))?;
+    repo.track_pr(opts, &pr_url);
+    if opts.request_reviews {
+        repo.request_codeowner_reviews(load_codeowners(reposlug, &head), &pr_url, &plan.numstat);
+    }
+
+    transaction.commit();
+    info!("Repository '{}' processed successfully.", reposlug);
+    Ok(CreateResult::PrCreated { diff, stat, pr_url })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::FileMode;
+
+    fn entry(path: &str) -> TreeEntry {
+        TreeEntry {
+            path: path.to_string(),
+            mode: "100644".to_string(),
+            sha: format!("sha-{}", path),
+        }
+    }
+
+    fn head(paths: &[&str]) -> Head {
+        Head {
+            branch: "main".to_string(),
+            commit: "c0".to_string(),
+            tree: "t0".to_string(),
+            files: paths.iter().map(|path| entry(path)).collect(),
+        }
+    }
+
+    fn repo(change: Change, files: &[&str]) -> Repo {
+        Repo {
+            reposlug: "org/repo".to_string(),
+            dir: None,
+            change_id: "SLAM-test".to_string(),
+            change: Some(change),
+            files: files.iter().map(|file| file.to_string()).collect(),
+            pr_number: 0,
+        }
+    }
+
+    fn blobs(files: &[(&str, &str)]) -> impl Fn(&TreeEntry) -> Result<Option<String>> {
+        let files: HashMap<String, String> = files
+            .iter()
+            .map(|(path, content)| (format!("sha-{}", path), content.to_string()))
+            .collect();
+        move |entry| Ok(files.get(&entry.sha).cloned())
+    }
+
+    #[test]
+    fn test_parse_tree_keeps_blobs() {
+        let tree = json!({ "tree": [
+            { "path": "src", "mode": "040000", "type": "tree", "sha": "a" },
+            { "path": "src/main.rs", "mode": "100644", "type": "blob", "sha": "b" },
+            { "path": "run.sh", "mode": "100755", "type": "blob", "sha": "c" },
+        ]});
+        let entries = parse_tree(&tree);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].mode, "100755");
+    }
+
+    #[test]
+    fn test_matching_files() {
+        let files = head(&["config.yml", "app/config.yml", "yarn.lock", "vendor/x/config.yml"]).files;
+        let ptns = |ptns: &[&str]| -> Vec<String> { ptns.iter().map(|p| p.to_string()).collect() };
+        assert_eq!(
+            matching_files(&files, &ptns(&["*.yml"]), false).unwrap(),
+            ["config.yml"]
+        );
+        assert_eq!(
+            matching_files(&files, &ptns(&["**/config.yml"]), false).unwrap(),
+            ["app/config.yml", "config.yml"]
+        );
+        assert_eq!(
+            matching_files(&files, &ptns(&["**/config.yml", "*.lock"]), true).unwrap(),
+            ["app/config.yml", "config.yml", "vendor/x/config.yml", "yarn.lock"]
+        );
+        assert!(matching_files(&files, &ptns(&["["]), false).is_err());
+    }
+
+    #[test]
+    fn test_plan_sub_and_add() {
+        let head = head(&["a.yml", "b.yml", "run.sh"]);
+        let read = blobs(&[("a.yml", "version: 1\n"), ("b.yml", "other\n"), ("run.sh", "echo\n")]);
+
+        let sub = repo(
+            Change::Sub("version: 1".into(), "version: 2".into()),
+            &["a.yml", "b.yml"],
+        );
+        let plan = plan(&sub, &head, 1, false, &read).unwrap();
+        assert_eq!(
+            plan.tree,
+            [json!({ "path": "a.yml", "mode": "100644", "type": "blob", "content": "version: 2\n" })]
+        );
+        assert_eq!(plan.numstat, [("a.yml".to_string(), 1, 1)]);
+        assert!(plan.diff.starts_with("  M a.yml\n"));
+
+        let add = |path: &str, if_exists| repo(Change::Add(path.into(), "new".into(), if_exists), &[]);
+        let plan_add = |repo: &Repo| super::plan(repo, &head, 1, false, &read);
+        assert!(plan_add(&add("run.sh", IfExists::Fail)).is_err());
+        assert!(plan_add(&add("run.sh", IfExists::Skip)).unwrap().tree.is_empty());
+        let created = plan_add(&add("docs/new.md", IfExists::Fail)).unwrap();
+        assert!(created.diff.starts_with("  A docs/new.md\n"));
+        assert_eq!(created.tree[0]["content"], "new\n");
+
+        let copy = repo(
+            Change::Copy(
+                vec![("run.sh".into(), "echo\n".into()), ("docs/a.md".into(), "a".into())],
+                IfExists::Fail,
+            ),
+            &[],
+        );
+        let copied = plan_add(&copy).unwrap();
+        assert_eq!(copied.tree.len(), 1, "run.sh already holds the contents");
+        assert!(copied.diff.starts_with("  A docs/a.md\n"));
+    }
+
+    #[test]
+    fn test_plan_deletes() {
+        let head = head(&["old/a", "old/b", "keep"]);
+        let read = blobs(&[("old/a", "1\n2\n"), ("old/b", "3\n"), ("keep", "x\n")]);
+
+        let plan = plan(&repo(Change::DeleteDir("old".into(), 10), &[]), &head, 1, false, &read).unwrap();
+        assert_eq!(plan.tree.len(), 2);
+        assert!(plan.tree.iter().all(|entry| entry["sha"].is_null()));
+        assert_eq!(plan.stat.files, 2);
+        assert_eq!(plan.stat.deletions, 3);
+        assert!(plan.diff.contains("D old/a (-2 lines)"));
+        assert!(super::plan(&repo(Change::DeleteDir("old".into(), 1), &[]), &head, 1, false, &read).is_err());
+
+        let plan = super::plan(&repo(Change::Delete, &["keep", "gone"]), &head, 1, false, &read).unwrap();
+        assert_eq!(
+            plan.tree,
+            [json!({ "path": "keep", "mode": "100644", "type": "blob", "sha": null })]
+        );
+        assert!(head.exists("old") && !head.exists("ol"));
+    }
+
+    #[test]
+    fn test_plan_chmod() {
+        let head = head(&["run.sh", "README.md"]);
+        let read = blobs(&[]);
+        let plan = plan(
+            &repo(Change::Chmod(FileMode::Executable), &["run.sh", "README.md", "gone.sh"]),
+            &head,
+            1,
+            false,
+            &read,
+        )
+        .unwrap();
+        assert_eq!(plan.stat.files, 2);
+        assert!(plan.diff.contains("M run.sh (mode 100644 => 100755)"));
+        assert_eq!(plan.tree[0]["mode"], "100755");
+        assert_eq!(plan.tree[0]["sha"], head.files[0].sha);
+    }
+}
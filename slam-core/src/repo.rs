@@ -0,0 +1,2913 @@
+use eyre::{eyre, Result};
+use log::{debug, error, info, warn};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::codeowners::CodeOwners;
+use crate::diff::{self, DiffStat};
+use crate::forge;
+use crate::generated;
+use crate::git;
+use crate::pool;
+use crate::search::{Prefilter, Substitution};
+use crate::transaction;
+use crate::utils;
+
+/// What `Change::Add` does when the target file already exists with different contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum IfExists {
+    /// Abort the repo with an error (default).
+    #[default]
+    Fail,
+    /// Leave the existing file alone.
+    Skip,
+    /// Replace the existing file.
+    Overwrite,
+    /// Keep both versions separated by git-style conflict markers.
+    MergeMarkers,
+}
+
+/// The mode a `Change::Chmod` gives files; git only tracks whether a file is executable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileMode {
+    /// `100755`
+    Executable,
+    /// `100644`
+    Regular,
+}
+
+impl FileMode {
+    /// The mode as git records it in a tree.
+    pub fn git_mode(self) -> &'static str {
+        match self {
+            FileMode::Executable => "100755",
+            FileMode::Regular => "100644",
+        }
+    }
+}
+
+impl std::str::FromStr for FileMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "+x" | "755" | "0755" | "100755" => Ok(FileMode::Executable),
+            "-x" | "644" | "0644" | "100644" => Ok(FileMode::Regular),
+            _ => Err(format!("`{}` isn't a mode git tracks; use +x/755 or -x/644", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Change {
+    Delete,
+    /// Remove a directory (relative to the repo root) and every file git tracks in it,
+    /// refusing when it holds more than the given number of files.
+    DeleteDir(String, usize),
+    Add(String, String, IfExists),
+    Sub(String, String),
+    Regex(String, String),
+    /// Give every matched file the mode.
+    Chmod(FileMode),
+    /// Add several files at once, as `(path, contents)`, e.g. a directory tree copied in.
+    Copy(Vec<(String, String)>, IfExists),
+    /// Replace each of many literals with its replacement, as `(pattern, replacement)`, in one
+    /// pass per file (see `search::Substitution`), e.g. a table of symbols to rename.
+    MultiSub(Vec<(String, String)>),
+}
+
+impl Change {
+    /// The same change with `replacement` as the new text of a `Sub`/`Regex` or the content of an `Add`.
+    pub fn with_replacement(&self, replacement: &str) -> Result<Change> {
+        match self {
+            Change::Add(path, _, if_exists) => Ok(Change::Add(path.clone(), replacement.to_string(), *if_exists)),
+            Change::Sub(ptn, _) => Ok(Change::Sub(ptn.clone(), replacement.to_string())),
+            Change::Regex(ptn, _) => Ok(Change::Regex(ptn.clone(), replacement.to_string())),
+            Change::Delete | Change::DeleteDir(..) | Change::Chmod(_) | Change::Copy(..) | Change::MultiSub(_) => {
+                Err(eyre!("A replacement does not apply to {:?}", self))
+            }
+        }
+    }
+
+    /// The same change with the per-repo placeholders of a `Sub`/`Regex` replacement, or of the
+    /// files of a `Copy` (e.g. a blueprint), rendered for `reposlug`: `{{repo}}`, `{{org}}`,
+    /// `{{reposlug}}` and `{{default_branch}}`, which is only looked up (with `default_branch`)
+    /// when used.
+    pub fn interpolated(&self, reposlug: &str, default_branch: impl FnOnce() -> Result<String>) -> Result<Change> {
+        let texts: Vec<&str> = match self {
+            Change::Sub(_, replacement) | Change::Regex(_, replacement) => vec![replacement],
+            Change::Copy(files, _) => files.iter().map(|(_, contents)| contents.as_str()).collect(),
+            _ => return Ok(self.clone()),
+        };
+        if !texts.iter().any(|text| text.contains("{{")) {
+            return Ok(self.clone());
+        }
+        let (org, repo) = reposlug.rsplit_once('/').unwrap_or(("", reposlug));
+        let mut vars = vec![
+            ("repo", repo.to_string()),
+            ("org", org.to_string()),
+            ("reposlug", reposlug.to_string()),
+        ];
+        if texts.iter().any(|text| text.contains("default_branch")) {
+            let branch = default_branch().map_err(|e| eyre!("Failed to look up the default branch: {}", e))?;
+            vars.push(("default_branch", branch));
+        }
+        match self {
+            Change::Copy(files, if_exists) => Ok(Change::Copy(
+                files
+                    .iter()
+                    .map(|(path, contents)| (path.clone(), utils::render_template(contents, &vars)))
+                    .collect(),
+                *if_exists,
+            )),
+            _ => self.with_replacement(&utils::render_template(texts[0], &vars)),
+        }
+    }
+}
+
+/// Per-repo adjustments to a change, as listed in `slam-overrides.yaml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RepoOverride {
+    /// Leave the repo out of the run.
+    pub skip: bool,
+    /// Why the repo is skipped, shown in the output.
+    pub reason: Option<String>,
+    /// File patterns used instead of `--files`.
+    pub files: Option<Vec<String>>,
+    /// Used instead of the change's replacement text (or, for `add`, its content).
+    pub replacement: Option<String>,
+}
+
+/// Maximum number of files listed in the diff summary of a `Change::DeleteDir`.
+pub const DELETE_DIR_SUMMARY_LIMIT: usize = 20;
+
+/// PR body used when neither `--body-template` nor the config default is set.
+pub const DEFAULT_PR_BODY_TEMPLATE: &str = "{{commit_msg}}
+
+{{description}}
+
+{{diffstat}}
+{{files}}
+
+{{hook_fixes}}
+
+docs: https://github.com/scottidler/slam/blob/main/README.md";
+
+/// Commit message template used when no `--commit-template` is given.
+pub const DEFAULT_COMMIT_TEMPLATE: &str = "{{message}}";
+
+/// Conventional Commits header: `type(optional scope)!: description`.
+const CONVENTIONAL_COMMIT_RE: &str =
+    r"^(build|chore|ci|docs|feat|fix|perf|refactor|revert|style|test)(\([\w\-./ ]+\))?!?: \S.*";
+
+/// Checks that the first line of `msg` is a valid Conventional Commits header.
+pub fn validate_conventional_commit(msg: &str) -> Result<()> {
+    let header = msg.lines().next().unwrap_or_default();
+    let re = regex::Regex::new(CONVENTIONAL_COMMIT_RE).unwrap();
+    if re.is_match(header) {
+        Ok(())
+    } else {
+        Err(eyre!(
+            "Commit message '{}' is not a Conventional Commit (expected e.g. 'chore(scope): description')",
+            header
+        ))
+    }
+}
+
+/// Prefixes `change_id` with `branch_prefix` unless it already starts with it, so that
+/// every branch and PR title slam manages can be found again by purge and refresh.
+pub fn normalize_change_id(change_id: &str, branch_prefix: &str) -> String {
+    if change_id.starts_with(branch_prefix) {
+        change_id.to_string()
+    } else {
+        format!("{}-{}", branch_prefix, change_id)
+    }
+}
+
+/// Options controlling how `Repo::create` applies, commits and publishes a change.
+#[derive(Debug, Clone, Default)]
+pub struct CreateOptions {
+    pub buffer: usize,
+    pub branch_prefix: String,
+    pub commit_msg: Option<String>,
+    /// Template for the commit message; see `Repo::render_commit_msg` for the available variables.
+    pub commit_template: Option<String>,
+    pub simplified: bool,
+    /// Template for the PR body; see `Repo::render_pr_body` for the available variables.
+    pub body_template: Option<String>,
+    /// Free-form description exposed to the body template as `{{description}}`.
+    pub description: Option<String>,
+    pub hooks: HookSelection,
+    /// Sign commits (and optionally pushes); `None` leaves signing to the git config.
+    pub signing: Option<git::Signing>,
+    /// Author of the commits instead of the local git user, e.g. a bot.
+    pub author: Option<git::Identity>,
+    /// Committer of the commits; defaults to `author` when only that is set.
+    pub committer: Option<git::Identity>,
+    /// Request reviews from the CODEOWNERS of the changed files on each new PR.
+    pub request_reviews: bool,
+    /// Ticket the change is for, referenced in every commit message and PR body.
+    pub ticket: Option<Ticket>,
+    /// Title of the milestone to put each PR on.
+    pub milestone: Option<String>,
+    /// Title of the project (board) to add each PR to.
+    pub project: Option<String>,
+    /// Force-push to the change's branch and keep its open PR, with a refreshed body, instead
+    /// of closing it and opening a new one.
+    pub update_existing_pr: bool,
+    /// Push to a fork of each repo (created if needed) and open cross-fork PRs, for repos
+    /// the user can't push to.
+    pub fork: bool,
+    /// Commit to a local branch named after the change id in each clone and stop there;
+    /// `Repo::publish` pushes it and opens the PR later.
+    pub stage: bool,
+    /// Copy the matched files git doesn't track from the clone into the fresh checkout, so they
+    /// are changed and committed like the tracked ones.
+    pub include_untracked: bool,
+}
+
+/// Issue tracker ticket (e.g. a Jira issue) a change is made for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ticket {
+    /// Issue key, e.g. `OPS-123`.
+    pub key: String,
+    /// Web page of the issue, when the tracker is configured.
+    pub url: Option<String>,
+}
+
+impl Ticket {
+    /// A markdown link to the issue, or just its key.
+    pub fn link(&self) -> String {
+        match &self.url {
+            Some(url) => format!("[{}]({})", self.key, url),
+            None => self.key.clone(),
+        }
+    }
+}
+
+/// Which pre-commit hooks `Repo::create` runs before committing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum HookSelection {
+    /// Every hook configured in the repo.
+    #[default]
+    All,
+    /// Only the hooks with these ids.
+    Only(Vec<String>),
+    /// No hooks at all, including the git hooks on commit.
+    Skip,
+}
+
+impl HookSelection {
+    pub fn from_args(no_verify: bool, hook_ids: Vec<String>) -> Self {
+        if no_verify {
+            HookSelection::Skip
+        } else if hook_ids.is_empty() {
+            HookSelection::All
+        } else {
+            HookSelection::Only(hook_ids)
+        }
+    }
+
+    fn run(&self, repo_path: &Path) -> Result<()> {
+        match self {
+            HookSelection::All => git::run_pre_commit_with_retry(repo_path, 2, None),
+            HookSelection::Only(hook_ids) => hook_ids
+                .iter()
+                .try_for_each(|id| git::run_pre_commit_with_retry(repo_path, 2, Some(id))),
+            HookSelection::Skip => {
+                info!("Skipping pre-commit hooks in '{}'", repo_path.display());
+                Ok(())
+            }
+        }
+    }
+}
+
+/// What `Repo::review` does with a repo's PR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReviewOp {
+    /// Render the PR's diff with `buffer` lines of context.
+    Show { buffer: usize },
+    /// Clone the repo (or update the clone) and check out the PR branch.
+    Clone,
+    /// Approve and merge the PR once it is mergeable and checks have passed; with
+    /// `admin_override`, merge past failing checks and branch protection.
+    Approve { admin_override: bool },
+    /// Turn on the forge's auto-merge, so the PR merges once its checks and reviews pass.
+    AutoMerge,
+    /// Close the PR and delete its remote branch; with `local`, also the branch in the sandbox
+    /// clone, checking out the default branch first.
+    Delete { local: bool },
+    /// Re-run the failed workflow runs on the PR branch, only those named in `checks` when it is
+    /// not empty.
+    Rerun { checks: Vec<String> },
+    /// Delete every branch carrying the branch prefix, closing their PRs; with `dry_run`,
+    /// only report what would go.
+    Purge { dry_run: bool },
+}
+
+/// Where a PR stands for `Repo::merge_when_ready`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeCheck {
+    /// Not mergeable yet (checks running, draft, review missing); worth checking again.
+    Waiting {
+        reason: String,
+    },
+    Merged,
+    /// Will not merge without someone stepping in: checks failed, or the PR was closed.
+    Blocked {
+        reason: String,
+    },
+}
+
+/// Decides from `status` whether a PR is done with or still waiting; `None` when it can be merged now.
+fn merge_check(status: &git::PrStatus) -> Option<MergeCheck> {
+    let waiting = |reason: &str| {
+        Some(MergeCheck::Waiting {
+            reason: reason.to_string(),
+        })
+    };
+    if status.merged {
+        Some(MergeCheck::Merged)
+    } else if !status.open {
+        Some(MergeCheck::Blocked {
+            reason: "closed without merging".to_string(),
+        })
+    } else if status.checks_failed {
+        Some(MergeCheck::Blocked {
+            reason: "checks failed".to_string(),
+        })
+    } else if status.draft {
+        waiting("draft")
+    } else if !status.checked {
+        waiting("checks running")
+    } else if status.mergeable == git::Mergeable::Conflicting {
+        Some(MergeCheck::Blocked {
+            reason: "merge conflicts".to_string(),
+        })
+    } else if status.mergeable == git::Mergeable::Unknown {
+        waiting("mergeability not known yet")
+    } else {
+        None
+    }
+}
+
+/// Error from `ReviewOp::Approve` for a PR that conflicts with its base, so callers can tell
+/// the repos that need a rebase apart from other failures.
+#[derive(Debug)]
+pub struct MergeConflict {
+    pub reposlug: String,
+    pub pr_number: u64,
+}
+
+impl std::fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "PR {} in repo '{}' has merge conflicts; a rebase is required.",
+            self.pr_number, self.reposlug
+        )
+    }
+}
+
+impl std::error::Error for MergeConflict {}
+
+/// Outcome of `Repo::create` for one repo.
+#[derive(Debug)]
+pub enum CreateResult {
+    /// Left alone on purpose; the reason says why.
+    Skipped {
+        reason: String,
+    },
+    /// The change does not modify anything in this repo.
+    NoChanges,
+    /// Nothing to change because the repo is already in the change's target state, e.g. the
+    /// replacement is present or the added file has the same contents.
+    AlreadyCompliant,
+    /// No commit message given: the change was applied, diffed and rolled back.
+    DryRun {
+        diff: String,
+        stat: DiffStat,
+    },
+    /// Committed, pushed and a PR opened.
+    PrCreated {
+        diff: String,
+        stat: DiffStat,
+        pr_url: String,
+    },
+    /// Committed and force-pushed to the branch of an open PR, whose body was refreshed.
+    PrUpdated {
+        diff: String,
+        stat: DiffStat,
+        pr_url: String,
+    },
+    /// Committed to the change's local branch in the clone; nothing was pushed.
+    Staged {
+        diff: String,
+        stat: DiffStat,
+    },
+    /// Committed and pushed to a forge slam cannot open PRs on; the review is opened at `review_url`.
+    Pushed {
+        diff: String,
+        stat: DiffStat,
+        review_url: String,
+    },
+    Failed {
+        reason: String,
+    },
+}
+
+/// What `Repo::prepare` found in a clone, before anything was fetched, committed or pushed.
+#[derive(Debug)]
+pub enum Prepared {
+    /// Nothing more to do; this is the repo's result.
+    Done(CreateResult),
+    /// The change alters the repo as `diff` shows, against the clone's checkout (which may lag
+    /// behind origin); `Repo::apply` carries it out.
+    Ready { diff: String, stat: DiffStat },
+}
+
+#[derive(Debug, Clone)]
+pub struct Repo {
+    /// The `org/repo` on the forge, which PRs, branches and patterns refer to.
+    pub reposlug: String,
+    /// Where the clone is under the sandbox root, when that isn't `reposlug` (a renamed or
+    /// nested directory); see `path`.
+    pub dir: Option<String>,
+    pub change_id: String,
+    pub change: Option<Change>,
+    pub files: Vec<String>,
+    pub pr_number: u64,
+}
+
+impl Repo {
+    /// The clone at `repo` under `root` prepared for `change`, recording the files matching
+    /// `file_ptns`. Fails, with the reason to report the repo as skipped, when `repo` isn't
+    /// under `root` or a pattern can't be searched.
+    pub fn create_repo_from_local(
+        repo: &Path,
+        root: &Path,
+        change: &Option<Change>,
+        file_ptns: &[String],
+        change_id: &str,
+        include_generated: bool,
+    ) -> Result<Self> {
+        debug!("Creating repo entry for '{}'", repo.display());
+
+        let dir = repo
+            .strip_prefix(root)
+            .map_err(|_| eyre!("not under the workdir '{}'", root.display()))?
+            .display()
+            .to_string();
+        let reposlug = git::local_reposlug(repo, root);
+
+        let mut files = Vec::new();
+
+        // If one or more file patterns were provided, find matches for each.
+        if !file_ptns.is_empty() {
+            for pattern in file_ptns {
+                match find_files_in_repo(repo, pattern, include_generated) {
+                    Ok(matched_files) => {
+                        files.append(&mut matched_files.into_iter().map(|f| f.display().to_string()).collect());
+                    }
+                    Err(e) => return Err(eyre!("failed to search for '{}': {}", pattern, e)),
+                }
+            }
+            files.sort();
+            files.dedup();
+        }
+
+        Ok(Self {
+            dir: (dir != reposlug).then_some(dir),
+            reposlug,
+            change_id: change_id.to_string(),
+            change: change.clone(),
+            files,
+            pr_number: 0,
+        })
+    }
+
+    /// The clone of this repo under `root`.
+    pub fn path(&self, root: &Path) -> PathBuf {
+        root.join(self.dir.as_ref().unwrap_or(&self.reposlug))
+    }
+
+    /// This repo with its change rendered for it (see `Change::interpolated`).
+    pub fn resolved(&self, default_branch: impl FnOnce() -> Result<String>) -> Result<Repo> {
+        let change = self
+            .change
+            .as_ref()
+            .map(|change| change.interpolated(&self.reposlug, default_branch))
+            .transpose()?;
+        Ok(Repo { change, ..self.clone() })
+    }
+
+    pub fn create_repo_from_remote_with_pr(reposlug: &str, change_id: &str, pr_number: u64) -> Self {
+        Self {
+            reposlug: reposlug.to_owned(),
+            dir: None,
+            change_id: change_id.to_owned(),
+            change: None,
+            files: Vec::new(),
+            pr_number,
+        }
+    }
+
+    /// Generate a diff and its diffstat for this repo+change. If `commit` is true, file edits are applied.
+    ///
+    /// Fails when an Add conflicts with an existing file under `IfExists::Fail`.
+    pub fn create_diff(
+        &self,
+        root: &Path,
+        buffer: usize,
+        commit: bool,
+        simplified: bool,
+    ) -> Result<(String, DiffStat)> {
+        self.diff_in(&self.path(root), buffer, commit, simplified)
+    }
+
+    /// As `create_diff`, for a checkout of this repo at `repo_path`, e.g. a worktree.
+    fn diff_in(&self, repo_path: &Path, buffer: usize, commit: bool, simplified: bool) -> Result<(String, DiffStat)> {
+        let mut file_diffs = String::new();
+        let mut stat = DiffStat::default();
+
+        if let Some(change) = self.change.as_ref() {
+            match change {
+                Change::Delete => {
+                    for file in &self.files {
+                        let full_path = repo_path.join(file);
+                        // A pattern matching a directory deletes every file git tracks in it.
+                        if full_path.is_dir() {
+                            let tracked = git::ls_files(repo_path, file)?;
+                            if tracked.is_empty() {
+                                continue;
+                            }
+                            stat.files += tracked.len();
+                            stat.deletions += tracked
+                                .iter()
+                                .filter_map(|file| fs::read_to_string(repo_path.join(file)).ok())
+                                .map(|content| content.lines().count())
+                                .sum::<usize>();
+                            file_diffs.push_str(&delete_dir_summary(&tracked, simplified, |file| {
+                                fs::read_to_string(repo_path.join(file)).ok()
+                            }));
+                            if commit {
+                                git::rm_recursive(repo_path, file)?;
+                                remove_empty_parents(repo_path, &full_path);
+                            }
+                            continue;
+                        }
+                        let mut file_diff = format!("{}\n", utils::indent(&format!("D {}", file), 2));
+                        match fs::read_to_string(&full_path) {
+                            Ok(content) => {
+                                stat += diff::diffstat(&content, "");
+                                let diff = diff::generate_diff(&content, "", buffer);
+                                for line in diff.lines() {
+                                    file_diff.push_str(&format!("{}\n", utils::indent(line, 4)));
+                                }
+                            }
+                            Err(err) => {
+                                file_diff.push_str(&format!(
+                                    "{}\n",
+                                    utils::indent(&format!("(Could not read file for diff: {})", err), 2)
+                                ));
+                            }
+                        }
+                        if !file_diff.trim().is_empty() {
+                            file_diffs.push_str(&file_diff);
+                        }
+                        if commit && full_path.exists() {
+                            fs::remove_file(&full_path)
+                                .map_err(|e| eyre!("Failed to delete '{}': {}", full_path.display(), e))?;
+                            remove_empty_parents(repo_path, &full_path);
+                        }
+                    }
+                }
+
+                Change::DeleteDir(dir, max_files) => {
+                    let tracked = git::ls_files(repo_path, dir)?;
+                    if tracked.len() > *max_files {
+                        return Err(eyre!(
+                            "'{}' in '{}' holds {} tracked files, more than --max-files {}; refusing to delete",
+                            dir,
+                            self.reposlug,
+                            tracked.len(),
+                            max_files
+                        ));
+                    }
+                    if !tracked.is_empty() {
+                        stat.files += tracked.len();
+                        stat.deletions += tracked
+                            .iter()
+                            .filter_map(|file| fs::read_to_string(repo_path.join(file)).ok())
+                            .map(|content| content.lines().count())
+                            .sum::<usize>();
+                        file_diffs.push_str(&delete_dir_summary(&tracked, simplified, |file| {
+                            fs::read_to_string(repo_path.join(file)).ok()
+                        }));
+                        if commit {
+                            git::rm_recursive(repo_path, dir)?;
+                            remove_empty_parents(repo_path, &repo_path.join(dir));
+                        }
+                    }
+                }
+
+                Change::Add(path, contents, if_exists) => {
+                    let full_path = repo_path.join(path);
+                    let indicator = if full_path.exists() { "M" } else { "A" };
+                    if let Some((diff, file_stat)) = add_file(&full_path, contents, *if_exists, buffer, commit)? {
+                        stat += file_stat;
+                        file_diffs.push_str(&diff_block(&format!("{} {}", indicator, path), &diff));
+                    }
+                }
+
+                Change::Copy(files, if_exists) => {
+                    for (path, contents) in files {
+                        let full_path = repo_path.join(path);
+                        let indicator = if full_path.exists() { "M" } else { "A" };
+                        if let Some((diff, file_stat)) = add_file(&full_path, contents, *if_exists, buffer, commit)? {
+                            stat += file_stat;
+                            file_diffs.push_str(&diff_block(&format!("{} {}", indicator, path), &diff));
+                        }
+                    }
+                }
+
+                Change::Chmod(mode) => {
+                    for file in &self.files {
+                        let Some(current) = git::file_mode(repo_path, file)? else {
+                            continue;
+                        };
+                        if current == mode.git_mode() {
+                            continue;
+                        }
+                        stat.files += 1;
+                        file_diffs.push_str(&diff_block(&chmod_header(file, &current, *mode), ""));
+                        if commit {
+                            git::chmod(repo_path, file, *mode == FileMode::Executable)?;
+                        }
+                    }
+                }
+
+                Change::Sub(_, _) | Change::Regex(_, _) | Change::MultiSub(_) => {
+                    // Only files the pre-pass finds a hit in are read in full and diffed.
+                    let prefilter = Prefilter::new(change);
+                    let Some(substitution) = Substitution::new(change)? else {
+                        unreachable!("{:?} substitutes text", change);
+                    };
+                    for file in &self.files {
+                        let full_path = repo_path.join(file);
+                        if !prefilter.may_match(&full_path) {
+                            continue;
+                        }
+                        if let Some((d, file_stat)) = substitute_file(&full_path, &substitution, buffer, commit) {
+                            stat += file_stat;
+                            let prefix = if simplified { "><" } else { "M" };
+                            file_diffs.push_str(&diff_block(&format!("{} {}", prefix, file), &d));
+                        }
+                    }
+                }
+            }
+        } else {
+            // no-change dry-run: list matched files
+            for file in &self.files {
+                file_diffs.push_str(&format!("{}\n", utils::indent(&format!(">< {}", file), 2)));
+            }
+        }
+
+        if file_diffs.trim().is_empty() {
+            Ok((String::new(), stat))
+        } else {
+            Ok((format!("{}\n{}", self.reposlug, file_diffs), stat))
+        }
+    }
+
+    /// Whether the repo already holds what the change would produce. Only meaningful when the
+    /// change's diff is empty: then a substitution's pattern is gone, so finding its replacement
+    /// means it was applied before. Regex replacements with capture groups can't be checked.
+    fn already_applied(&self, repo_path: &Path) -> bool {
+        self.already_applied_with(
+            |path| fs::read_to_string(repo_path.join(path)).ok(),
+            |path| repo_path.join(path).exists(),
+        )
+    }
+
+    /// As `already_applied`, reading files through `read` and checking for files or
+    /// directories with `exists`, e.g. in a remote repo.
+    pub(crate) fn already_applied_with(
+        &self,
+        read: impl Fn(&str) -> Option<String>,
+        exists: impl Fn(&str) -> bool,
+    ) -> bool {
+        match self.change.as_ref() {
+            None => false,
+            Some(Change::Delete) => self.files.iter().all(|file| !exists(file)),
+            Some(Change::DeleteDir(dir, _)) => !exists(dir),
+            // With an empty diff every matched file already has the mode.
+            Some(Change::Chmod(_)) => !self.files.is_empty() && self.files.iter().all(|file| exists(file)),
+            Some(Change::Add(path, contents, _)) => has_contents(path, contents, &read),
+            Some(Change::Copy(files, _)) => {
+                !files.is_empty() && files.iter().all(|(path, contents)| has_contents(path, contents, &read))
+            }
+            // Some replacement is in place and no pattern is left.
+            Some(Change::MultiSub(pairs)) => self.files.iter().filter_map(|file| read(file)).any(|content| {
+                pairs
+                    .iter()
+                    .any(|(_, repl)| !repl.is_empty() && content.contains(repl.as_str()))
+            }),
+            Some(Change::Sub(_, repl)) | Some(Change::Regex(_, repl)) => {
+                let literal = matches!(self.change, Some(Change::Sub(..))) || !repl.contains('$');
+                literal
+                    && !repl.is_empty()
+                    && self
+                        .files
+                        .iter()
+                        .filter_map(|file| read(file))
+                        .any(|content| content.contains(repl.as_str()))
+            }
+        }
+    }
+
+    /// Span the work on this repo is logged in, so its lines can be told apart from the
+    /// other repos' in a parallel run.
+    pub(crate) fn span(&self) -> tracing::Span {
+        tracing::info_span!("repo", repo = %self.reposlug)
+    }
+
+    /// Applies the change, commits, pushes and opens (or updates) the PR.
+    ///
+    /// The work happens in a temporary worktree at the tip of origin's HEAD branch, so the
+    /// clone's own checkout, branch and uncommitted changes are never touched. Only a pushed
+    /// branch needs undoing when a later step fails. When no commit message is provided the
+    /// change is applied and diffed but nothing is committed (a dry run).
+    ///
+    /// Runs both phases in one go: `prepare`, then `apply` when there is something to do.
+    pub fn create(&self, root: &Path, opts: &CreateOptions) -> CreateResult {
+        match self.prepare(root, opts) {
+            Prepared::Done(result) => result,
+            Prepared::Ready { .. } => self.apply(root, opts),
+        }
+    }
+
+    /// The first phase of `create`: diffs the change against the clone as it is, without
+    /// fetching, committing or pushing, to tell whether there is anything to do.
+    pub fn prepare(&self, root: &Path, opts: &CreateOptions) -> Prepared {
+        let _span = self.span().entered();
+        let repo_path = self.path(root);
+        let repo = match self.resolved(|| git::get_head_branch(&repo_path)) {
+            Ok(repo) => repo,
+            Err(e) => return Prepared::Done(CreateResult::Failed { reason: e.to_string() }),
+        };
+        match repo.create_diff(root, opts.buffer, false, opts.simplified) {
+            Ok((diff, stat)) if !diff.trim().is_empty() => Prepared::Ready { diff, stat },
+            Ok(_) if repo.already_applied(&repo_path) => {
+                info!("'{}' is already compliant; skipping.", self.reposlug);
+                Prepared::Done(CreateResult::AlreadyCompliant)
+            }
+            Ok(_) => {
+                info!("No changes detected in '{}'; skipping.", self.reposlug);
+                Prepared::Done(CreateResult::NoChanges)
+            }
+            Err(e) => Prepared::Done(CreateResult::Failed { reason: e.to_string() }),
+        }
+    }
+
+    /// The second phase of `create`, for a repo `prepare` found ready: applies the change to a
+    /// fresh checkout of origin's HEAD branch, then commits, pushes and opens the PR.
+    pub fn apply(&self, root: &Path, opts: &CreateOptions) -> CreateResult {
+        let _span = self.span().entered();
+        self.try_apply(root, opts)
+            .unwrap_or_else(|e| CreateResult::Failed { reason: e.to_string() })
+    }
+
+    fn try_apply(&self, root: &Path, opts: &CreateOptions) -> Result<CreateResult> {
+        let buffer = opts.buffer;
+        let simplified = opts.simplified;
+        let commit_msg = opts.commit_msg.as_deref();
+        let repo_path = self.path(root);
+        let normalized_change_id = normalize_change_id(&self.change_id, &opts.branch_prefix);
+        let mut transaction = transaction::Transaction::journaled(&normalized_change_id, &self.reposlug);
+
+        let head_branch = git::get_head_branch(&repo_path)?;
+        info!("Fetching '{}' in '{}'", head_branch, repo_path.display());
+        git::fetch_branch(&repo_path, "origin", &head_branch)?;
+        let worktree = git::Worktree::add(&repo_path, &format!("origin/{}", head_branch))?;
+        let work_path = worktree.path();
+        if opts.include_untracked {
+            copy_untracked(&repo_path, work_path, &git::untracked_paths(&repo_path, &self.files)?)?;
+        }
+
+        let forge = forge::for_repo(&self.reposlug);
+        let reused_pr = if opts.update_existing_pr && !opts.stage && forge.is_none_or(|forge| forge.automates_prs()) {
+            pool::gh(|| git::get_pr_number_for_repo(&self.reposlug, &normalized_change_id))?
+        } else {
+            0
+        };
+
+        info!(
+            "Applying file modifications for change '{}' in '{}'",
+            normalized_change_id, self.reposlug
+        );
+        let resolved = self.resolved(|| Ok(head_branch.clone()))?;
+        let (applied_diff, stat) = resolved.diff_in(work_path, buffer, true, simplified)?;
+
+        // Run pre-commit hooks; whatever the formatters rewrite goes into the same commit.
+        let before_hooks = snapshot_changed_files(work_path)?;
+        opts.hooks.run(work_path)?;
+        let hook_fixes = hook_fixes(work_path, &before_hooks, buffer)?;
+        let (applied_diff, stat) = fold_hook_fixes(applied_diff, stat, &hook_fixes);
+
+        // Dry run: without a commit message the worktree is simply discarded.
+        if commit_msg.is_none() {
+            info!("Dry run detected for '{}'; returning diff.", self.reposlug);
+            return Ok(CreateResult::DryRun {
+                diff: applied_diff,
+                stat,
+            });
+        }
+
+        let fork_owner = if opts.fork && !opts.stage {
+            Some(pool::gh(|| git::ensure_fork_remote(&repo_path, &self.reposlug))?)
+        } else {
+            None
+        };
+        let remote = if fork_owner.is_some() {
+            git::FORK_REMOTE
+        } else {
+            "origin"
+        };
+
+        if opts.stage {
+            debug!("Staging only; leaving the remote of '{}' alone", self.reposlug);
+        } else if reused_pr != 0 {
+            info!(
+                "Keeping remote branch '{}' of open PR #{} in '{}'.",
+                normalized_change_id,
+                reused_pr,
+                repo_path.display()
+            );
+        } else if git::remote_branch_exists(&repo_path, remote, &normalized_change_id)? {
+            info!(
+                "Remote branch '{}' exists in '{}'; deleting it.",
+                normalized_change_id,
+                repo_path.display()
+            );
+            git::delete_remote_branch(&repo_path, remote, &normalized_change_id)?;
+        }
+
+        let changed_files = git::changed_files(work_path)?;
+        let commit_msg = self.render_commit_msg(opts, &normalized_change_id, &changed_files);
+        info!(
+            "Committing all changes for '{}' with message '{}'",
+            self.reposlug, commit_msg
+        );
+        // The git pre-commit hook would run every hook again; only let it when all were requested.
+        let commit_opts = git::CommitOptions {
+            no_verify: opts.hooks != HookSelection::All,
+            signing: opts.signing.as_ref(),
+            author: opts.author.as_ref(),
+            committer: opts.committer.as_ref().or(opts.author.as_ref()),
+        };
+        git::commit_all(work_path, &commit_msg, &commit_opts)?;
+        if opts.stage {
+            // A local branch of that name may hold the user's own work rather than an earlier
+            // staging; keep whatever of it no remote has before pointing it at the new commit.
+            let unpushed = git::unpushed_commits(&repo_path, &normalized_change_id)?;
+            if unpushed > 0 {
+                let backup = git::backup_branch(&repo_path, &normalized_change_id)?;
+                warn!(
+                    "Local branch '{}' of '{}' had {} commit(s) on no remote; kept them at '{}'",
+                    normalized_change_id, self.reposlug, unpushed, backup
+                );
+            }
+            git::set_branch(work_path, &normalized_change_id)?;
+            info!(
+                "Staged '{}' on local branch '{}'; publish it to push and open the PR",
+                self.reposlug, normalized_change_id
+            );
+            return Ok(CreateResult::Staged {
+                diff: applied_diff,
+                stat,
+            });
+        }
+
+        let signed_push = opts.signing.as_ref().is_some_and(|signing| signing.push);
+        if reused_pr != 0 {
+            // A force-push over the open PR's branch can't be undone, so it is no transaction step.
+            info!(
+                "Force-pushing branch '{}' for '{}' to remote",
+                normalized_change_id, self.reposlug
+            );
+            if fork_owner.is_some() {
+                // --force-with-lease compares against the fork's tracking ref, so it must be current.
+                git::fetch_branch(&repo_path, remote, &normalized_change_id)?;
+            }
+            git::push_branch(work_path, remote, &normalized_change_id, signed_push, true)?;
+        } else {
+            transaction.apply(transaction::Step::Push {
+                repo_path: repo_path.clone(),
+                work_path: work_path.to_path_buf(),
+                branch: normalized_change_id.clone(),
+                refspec: forge.and_then(|forge| forge.push_refspec(&normalized_change_id, &head_branch)),
+                signed: signed_push,
+                remote: fork_owner.as_ref().map(|_| remote.to_string()),
+            })?;
+        }
+        let numstat = git::diff_numstat(work_path, "HEAD~1").unwrap_or_else(|e| {
+            warn!("Failed to compute diffstat for '{}': {}", self.reposlug, e);
+            Vec::new()
+        });
+        let hook_fixed_files: Vec<String> = hook_fixes.iter().map(|fix| fix.path.clone()).collect();
+        if reused_pr != 0 {
+            // The PR now shows the new commit; only its body is left to refresh.
+            let pr_url = forge::pr_url(&self.reposlug, reused_pr);
+            let body = self.render_pr_body(opts, &normalized_change_id, &commit_msg, &numstat, &hook_fixed_files);
+            match pool::gh(|| git::update_pr_body(&self.reposlug, reused_pr, &body)) {
+                Ok(()) => info!("Updated PR #{} for '{}'.", reused_pr, self.reposlug),
+                Err(e) => warn!("Pushed to {} but failed to update its body: {}", pr_url, e),
+            }
+            self.track_pr(opts, &pr_url);
+            return Ok(CreateResult::PrUpdated {
+                diff: applied_diff,
+                stat,
+                pr_url,
+            });
+        }
+
+        if let Some(forge) = forge.filter(|forge| !forge.automates_prs()) {
+            let review_url = forge.review_url(&self.reposlug, &normalized_change_id, &head_branch);
+            transaction.commit();
+            info!("Pushed '{}'; open the review at {}", self.reposlug, review_url);
+            return Ok(CreateResult::Pushed {
+                diff: applied_diff,
+                stat,
+                review_url,
+            });
+        }
+
+        let existing_pr = pool::gh(|| git::get_pr_number_for_repo(&self.reposlug, &normalized_change_id))?;
+        if existing_pr != 0 {
+            info!(
+                "Existing PR #{} found for '{}'; closing it.",
+                existing_pr, self.reposlug
+            );
+            pool::gh(|| git::close_pr(&self.reposlug, existing_pr))?;
+        }
+
+        info!(
+            "Creating a new PR for branch '{}' in '{}'",
+            normalized_change_id, self.reposlug
+        );
+        let body = self.render_pr_body(opts, &normalized_change_id, &commit_msg, &numstat, &hook_fixed_files);
+        let pr_url = pool::gh(|| {
+            git::create_pr(
+                work_path,
+                &self.reposlug,
+                &normalized_change_id,
+                fork_owner.as_deref(),
+                &body,
+            )
+        })
+        .ok_or_else(|| eyre!("Failed to create PR for repo '{}'", self.reposlug))?;
+        self.track_pr(opts, &pr_url);
+        if opts.request_reviews {
+            self.request_codeowner_reviews(CodeOwners::load(work_path), &pr_url, &numstat);
+        }
+
+        transaction.commit();
+        info!("Repository '{}' processed successfully.", self.reposlug);
+        Ok(CreateResult::PrCreated {
+            diff: applied_diff,
+            stat,
+            pr_url,
+        })
+    }
+
+    /// Pushes the branch `create` staged in the clone and opens its PR, as `create` would have
+    /// (the commit message and changed files come from the staged commit). The local branch is
+    /// deleted once the PR is open.
+    pub fn publish(&self, root: &Path, opts: &CreateOptions) -> CreateResult {
+        let _span = self.span().entered();
+        self.try_publish(root, opts)
+            .unwrap_or_else(|e| CreateResult::Failed { reason: e.to_string() })
+    }
+
+    fn try_publish(&self, root: &Path, opts: &CreateOptions) -> Result<CreateResult> {
+        let repo_path = self.path(root);
+        let branch = normalize_change_id(&self.change_id, &opts.branch_prefix);
+        let staged = format!("refs/heads/{}", branch);
+        if !git::branch_exists(&repo_path, &staged)? {
+            return Ok(CreateResult::Skipped {
+                reason: format!("nothing staged on '{}'", branch),
+            });
+        }
+        let commit_msg = git::commit_message(&repo_path, &staged)?;
+        let numstat = git::diff_numstat_between(&repo_path, &format!("{}~1", staged), &staged)?;
+        let stat = DiffStat {
+            files: numstat.len(),
+            insertions: numstat.iter().map(|(_, insertions, _)| insertions).sum(),
+            deletions: numstat.iter().map(|(_, _, deletions)| deletions).sum(),
+        };
+        let mut diff = format!("{}\n", self.reposlug);
+        for (path, insertions, deletions) in &numstat {
+            diff.push_str(&format!(
+                "{}\n",
+                utils::indent(&format!("M {} (+{} -{})", path, insertions, deletions), 2)
+            ));
+        }
+
+        let forge = forge::for_repo(&self.reposlug);
+        let head_branch = git::get_head_branch(&repo_path)?;
+        let fork_owner = if opts.fork {
+            Some(pool::gh(|| git::ensure_fork_remote(&repo_path, &self.reposlug))?)
+        } else {
+            None
+        };
+        let remote = if fork_owner.is_some() {
+            git::FORK_REMOTE
+        } else {
+            "origin"
+        };
+        if git::remote_branch_exists(&repo_path, remote, &branch)? {
+            info!(
+                "Remote branch '{}' exists in '{}'; deleting it.",
+                branch,
+                repo_path.display()
+            );
+            git::delete_remote_branch(&repo_path, remote, &branch)?;
+        }
+        let mut transaction = transaction::Transaction::journaled(&branch, &self.reposlug);
+        let refspec = forge
+            .and_then(|forge| forge.push_refspec(&branch, &head_branch))
+            .map(|refspec| refspec.replacen("HEAD:", &format!("{}:", staged), 1))
+            .unwrap_or_else(|| format!("{}:{}", staged, staged));
+        transaction.apply(transaction::Step::Push {
+            repo_path: repo_path.clone(),
+            work_path: repo_path.clone(),
+            branch: branch.clone(),
+            refspec: Some(refspec),
+            signed: opts.signing.as_ref().is_some_and(|signing| signing.push),
+            remote: fork_owner.as_ref().map(|_| remote.to_string()),
+        })?;
+
+        let result = if let Some(forge) = forge.filter(|forge| !forge.automates_prs()) {
+            let review_url = forge.review_url(&self.reposlug, &branch, &head_branch);
+            info!("Pushed '{}'; open the review at {}", self.reposlug, review_url);
+            CreateResult::Pushed { diff, stat, review_url }
+        } else {
+            let existing_pr = pool::gh(|| git::get_pr_number_for_repo(&self.reposlug, &branch))?;
+            if existing_pr != 0 {
+                info!(
+                    "Existing PR #{} found for '{}'; closing it.",
+                    existing_pr, self.reposlug
+                );
+                pool::gh(|| git::close_pr(&self.reposlug, existing_pr))?;
+            }
+            let body = self.render_pr_body(opts, &branch, &commit_msg, &numstat, &[]);
+            let pr_url = pool::gh(|| git::create_pr(&repo_path, &self.reposlug, &branch, fork_owner.as_deref(), &body))
+                .ok_or_else(|| eyre!("Failed to create PR for repo '{}'", self.reposlug))?;
+            self.track_pr(opts, &pr_url);
+            if opts.request_reviews {
+                self.request_codeowner_reviews(CodeOwners::load(&repo_path), &pr_url, &numstat);
+            }
+            CreateResult::PrCreated { diff, stat, pr_url }
+        };
+        transaction.commit();
+        if let Err(e) = git::delete_local_branch(&repo_path, &branch) {
+            warn!("Published '{}' but kept its local branch: {}", self.reposlug, e);
+        }
+        Ok(result)
+    }
+
+    /// Puts the PR on the run's milestone and project. Best effort, like the review requests:
+    /// the PR is open either way, so failures are only logged.
+    pub(crate) fn track_pr(&self, opts: &CreateOptions, pr_url: &str) {
+        let Some(pr_number) = git::pr_number_from_url(pr_url) else {
+            warn!("No PR number in '{}'; not adding it to a milestone or project", pr_url);
+            return;
+        };
+        if let Some(milestone) = &opts.milestone {
+            match pool::gh(|| git::set_milestone(&self.reposlug, pr_number, milestone)) {
+                Ok(()) => info!("Put {} on milestone '{}'", pr_url, milestone),
+                Err(e) => warn!("Failed to put {} on milestone '{}': {}", pr_url, milestone, e),
+            }
+        }
+        if let Some(project) = &opts.project {
+            match pool::gh(|| git::add_to_project(&self.reposlug, pr_number, project)) {
+                Ok(()) => info!("Added {} to project '{}'", pr_url, project),
+                Err(e) => warn!("Failed to add {} to project '{}': {}", pr_url, project, e),
+            }
+        }
+    }
+
+    /// Asks the CODEOWNERS of the changed files to review the new PR. Best effort: the PR
+    /// is already open, so failures are only logged.
+    pub(crate) fn request_codeowner_reviews(
+        &self,
+        codeowners: Result<Option<CodeOwners>>,
+        pr_url: &str,
+        numstat: &[(String, usize, usize)],
+    ) {
+        let codeowners = match codeowners {
+            Ok(Some(codeowners)) => codeowners,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Failed to read CODEOWNERS in '{}': {}", self.reposlug, e);
+                return;
+            }
+        };
+        let paths: Vec<&str> = numstat.iter().map(|(path, _, _)| path.as_str()).collect();
+        let owners = codeowners.owners_for(&paths);
+        if owners.is_empty() {
+            debug!("No CODEOWNERS match the files changed in '{}'", self.reposlug);
+            return;
+        }
+        match pool::gh(|| git::request_reviews(&self.reposlug, pr_url, &owners)) {
+            Ok(requested) => info!("Requested reviews on {} from {:?}", pr_url, requested),
+            Err(e) => warn!("Failed to request reviews on {}: {}", pr_url, e),
+        }
+    }
+
+    /// Renders the commit message from the configured template (or `{{message}}`).
+    ///
+    /// Available variables: `{{message}}` (the `--commit` value), `{{change_id}}`,
+    /// `{{reposlug}}`, `{{files_changed}}`, `{{files}}` (comma separated) and `{{ticket}}`.
+    /// A ticket the message doesn't mention is added as a `Refs:` trailer.
+    pub fn render_commit_msg(&self, opts: &CreateOptions, change_id: &str, files: &[String]) -> String {
+        let ticket = opts.ticket.as_ref().map(|ticket| ticket.key.clone());
+        let vars = [
+            ("message", opts.commit_msg.clone().unwrap_or_default()),
+            ("change_id", change_id.to_string()),
+            ("reposlug", self.reposlug.clone()),
+            ("files_changed", files.len().to_string()),
+            ("files", files.join(", ")),
+            ("ticket", ticket.clone().unwrap_or_default()),
+        ];
+        let template = opts.commit_template.as_deref().unwrap_or(DEFAULT_COMMIT_TEMPLATE);
+        let msg = utils::render_template(template, &vars);
+        match ticket {
+            Some(ticket) if !msg.contains(&ticket) => format!("{}\n\nRefs: {}", msg, ticket),
+            _ => msg,
+        }
+    }
+
+    /// Renders the PR body from the configured template (or the default one).
+    ///
+    /// Available variables: `{{change_id}}`, `{{reposlug}}`, `{{commit_msg}}`,
+    /// `{{description}}`, `{{files}}` (markdown list), `{{files_changed}}`,
+    /// `{{insertions}}`, `{{deletions}}`, `{{diffstat}}` (one-line summary), `{{hook_fixes}}`
+    /// (a note listing files the pre-commit hooks rewrote, empty when there were none),
+    /// `{{ticket}}` and `{{ticket_link}}`. A body without the ticket's link gets a `Ticket:` line.
+    pub fn render_pr_body(
+        &self,
+        opts: &CreateOptions,
+        change_id: &str,
+        commit_msg: &str,
+        numstat: &[(String, usize, usize)],
+        hook_fixed_files: &[String],
+    ) -> String {
+        let insertions: usize = numstat.iter().map(|(_, ins, _)| ins).sum();
+        let deletions: usize = numstat.iter().map(|(_, _, del)| del).sum();
+        let files = numstat
+            .iter()
+            .map(|(path, ins, del)| format!("- `{}` (+{} -{})", path, ins, del))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let diffstat = format!(
+            "{} file(s) changed, {} insertion(s)(+), {} deletion(s)(-)",
+            numstat.len(),
+            insertions,
+            deletions
+        );
+        let vars = [
+            ("change_id", change_id.to_string()),
+            ("reposlug", self.reposlug.clone()),
+            ("commit_msg", commit_msg.to_string()),
+            ("description", opts.description.clone().unwrap_or_default()),
+            ("files", files),
+            ("files_changed", numstat.len().to_string()),
+            ("insertions", insertions.to_string()),
+            ("deletions", deletions.to_string()),
+            ("diffstat", diffstat),
+            ("hook_fixes", hook_fixes_note(hook_fixed_files)),
+            (
+                "ticket",
+                opts.ticket.as_ref().map(|t| t.key.clone()).unwrap_or_default(),
+            ),
+            (
+                "ticket_link",
+                opts.ticket.as_ref().map(Ticket::link).unwrap_or_default(),
+            ),
+        ];
+        let template = opts.body_template.as_deref().unwrap_or(DEFAULT_PR_BODY_TEMPLATE);
+        let mut rendered = utils::render_template(template, &vars);
+        if let Some(ticket) = &opts.ticket {
+            if !rendered.contains(&ticket.link()) {
+                rendered = format!("Ticket: {}\n\n{}", ticket.link(), rendered);
+            }
+        }
+        // Empty variables (e.g. no description) would otherwise leave runs of blank lines.
+        let blank_lines_re = regex::Regex::new(r"\n{3,}").unwrap();
+        blank_lines_re.replace_all(rendered.trim(), "\n\n").to_string()
+    }
+
+    pub fn review(&self, root: &Path, op: &ReviewOp, summary: bool) -> Result<String> {
+        let _span = self.span().entered();
+        match op {
+            ReviewOp::Show { buffer } => {
+                if summary {
+                    Ok(format!("{} (# {})", self.reposlug, self.pr_number))
+                } else {
+                    Ok(self.get_review_diff(*buffer).0)
+                }
+            }
+            ReviewOp::Clone => {
+                let target = self.path(root);
+                git::clone_or_update_repo(&self.reposlug, &target, &self.change_id)?;
+                let rel_path = target.strip_prefix(root).unwrap_or(&target);
+                Ok(format!(
+                    "ensure clone {} -> {} and checkout to {}",
+                    self.reposlug,
+                    rel_path.display(),
+                    self.change_id
+                ))
+            }
+            ReviewOp::Approve { admin_override } => {
+                let status = git::get_settled_pr_status(&self.reposlug, self.pr_number)?;
+                self.ensure_pr_is_ours(&status, git::fork_owner())?;
+                if status.draft {
+                    return Err(eyre!(
+                        "PR {} in repo '{}' is a draft and cannot be approved.",
+                        self.pr_number,
+                        self.reposlug
+                    ));
+                }
+                match status.mergeable {
+                    git::Mergeable::Mergeable => {}
+                    git::Mergeable::Conflicting => return Err(self.merge_conflict()),
+                    git::Mergeable::Unknown => {
+                        return Err(eyre!(
+                            "PR {} in repo '{}' is still being checked for conflicts; try again shortly.",
+                            self.pr_number,
+                            self.reposlug
+                        ))
+                    }
+                }
+                if !status.checked && !admin_override {
+                    return Err(eyre!(
+                        "PR {} in repo '{}' has not passed all status checks.",
+                        self.pr_number,
+                        self.reposlug
+                    ));
+                }
+                if status.reviewed {
+                    warn!("PR {} is already reviewed; skipping re-approval.", self.pr_number);
+                } else {
+                    git::approve_pr(&self.reposlug, self.pr_number)?;
+                    info!("PR {} approved for repo '{}'.", self.pr_number, self.reposlug);
+                }
+                match git::merge_pr(&self.reposlug, self.pr_number, *admin_override) {
+                    Ok(()) => {
+                        info!(
+                            "Successfully merged PR {} for repo '{}'.",
+                            self.pr_number, self.reposlug
+                        );
+                    }
+                    Err(merge_err) => {
+                        if merge_err.to_string().contains("Merge conflict") {
+                            warn!(
+                                "Merge conflict detected for repo {}. A rebase is required.",
+                                self.reposlug
+                            );
+                            return Err(self.merge_conflict());
+                        } else {
+                            error!("Merge failed for repo {}: {}", self.reposlug, merge_err);
+                            return Err(merge_err);
+                        }
+                    }
+                }
+                Ok(format!(
+                    "Repo: {} -> Approved and merged PR: {} (# {})",
+                    self.reposlug, self.change_id, self.pr_number
+                ))
+            }
+            ReviewOp::AutoMerge => {
+                let status = git::get_pr_status(&self.reposlug, self.pr_number)?;
+                self.ensure_pr_is_ours(&status, git::fork_owner())?;
+                git::enable_auto_merge(&self.reposlug, self.pr_number)?;
+                info!(
+                    "Auto-merge enabled for PR {} in repo '{}'.",
+                    self.pr_number, self.reposlug
+                );
+                Ok(format!(
+                    "Repo: {} -> Auto-merge enabled for PR: {} (# {})",
+                    self.reposlug, self.change_id, self.pr_number
+                ))
+            }
+            ReviewOp::Delete { local } => {
+                let mut messages = Vec::new();
+                if self.pr_number != 0 {
+                    let status = git::get_pr_status(&self.reposlug, self.pr_number)?;
+                    self.ensure_pr_is_ours(&status, git::fork_owner())?;
+                    git::close_pr(&self.reposlug, self.pr_number)?;
+                    messages.push(format!("Closed PR #{} for repo '{}'", self.pr_number, self.reposlug));
+                } else {
+                    messages.push(format!("No open PR found for repo '{}'", self.reposlug));
+                }
+                git::delete_remote_branch_gh(&self.reposlug, &self.change_id)?;
+                messages.push(format!(
+                    "Deleted remote branch '{}' for repo '{}'",
+                    self.change_id, self.reposlug
+                ));
+                if *local {
+                    messages.extend(self.delete_local_branch(root)?);
+                }
+                Ok(messages.join("\n"))
+            }
+            ReviewOp::Rerun { checks } => {
+                let rerun = git::rerun_failed_checks(&self.reposlug, &self.change_id, checks)?;
+                if rerun.is_empty() {
+                    return Ok(format!(
+                        "Repo: {} -> No failed checks to re-run for PR: {} (# {})",
+                        self.reposlug, self.change_id, self.pr_number
+                    ));
+                }
+                info!(
+                    "Re-ran {} for PR {} in repo '{}'.",
+                    rerun.join(", "),
+                    self.pr_number,
+                    self.reposlug
+                );
+                Ok(format!(
+                    "Repo: {} -> Re-ran {} for PR: {} (# {})",
+                    self.reposlug,
+                    rerun.join(", "),
+                    self.change_id,
+                    self.pr_number
+                ))
+            }
+            ReviewOp::Purge { dry_run } => {
+                // Purge repos carry the branch prefix in place of a change id.
+                let messages = git::purge_repo(&self.reposlug, &self.change_id, *dry_run)?;
+                Ok(messages.join("\n"))
+            }
+        }
+    }
+
+    /// Merges the PR if it is ready, else reports what it is waiting on; polled by
+    /// `review automerge --watch` where the forge's own auto-merge is not available.
+    pub fn merge_when_ready(&self) -> Result<MergeCheck> {
+        let _span = self.span().entered();
+        let status = git::get_pr_status(&self.reposlug, self.pr_number)?;
+        self.ensure_pr_is_ours(&status, git::fork_owner())?;
+        if let Some(check) = merge_check(&status) {
+            return Ok(check);
+        }
+        match git::merge_pr(&self.reposlug, self.pr_number, false) {
+            Ok(()) => {
+                info!("Merged PR {} for repo '{}'.", self.pr_number, self.reposlug);
+                Ok(MergeCheck::Merged)
+            }
+            Err(e) if e.to_string().contains("review") => Ok(MergeCheck::Waiting {
+                reason: "review required".to_string(),
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn merge_conflict(&self) -> eyre::Report {
+        MergeConflict {
+            reposlug: self.reposlug.clone(),
+            pr_number: self.pr_number,
+        }
+        .into()
+    }
+
+    /// Deletes the change's branch from the sandbox clone, if there is one that has it, after
+    /// moving the clone onto its default branch. Commits no remote has are kept under
+    /// `refs/slam-backup/`; uncommitted changes on the branch leave it in place.
+    fn delete_local_branch(&self, root: &Path) -> Result<Option<String>> {
+        let repo_path = self.path(root);
+        if !repo_path.join(".git").exists() || !git::branch_exists(&repo_path, &self.change_id)? {
+            return Ok(None);
+        }
+        if git::current_branch(&repo_path)?.as_deref() == Some(self.change_id.as_str()) {
+            if git::has_modified_files(&repo_path)? {
+                return Err(eyre!(
+                    "'{}' has uncommitted changes on '{}'; left the local branch in place",
+                    repo_path.display(),
+                    self.change_id
+                ));
+            }
+            git::checkout(&repo_path, &git::get_head_branch(&repo_path)?)?;
+        }
+        let mut message = format!("Deleted local branch '{}' in '{}'", self.change_id, repo_path.display());
+        let unpushed = git::unpushed_commits(&repo_path, &self.change_id)?;
+        if unpushed > 0 {
+            let backup = git::backup_branch(&repo_path, &self.change_id)?;
+            message.push_str(&format!(" (kept {} unpushed commit(s) at '{}')", unpushed, backup));
+        }
+        git::delete_local_branch(&repo_path, &self.change_id)?;
+        Ok(Some(message))
+    }
+
+    /// Refuses to touch a PR that was not opened from this change's branch, in the repo itself
+    /// or `fork_owner`'s fork, so that approve/merge/delete can never act on an unrelated PR.
+    fn ensure_pr_is_ours(&self, status: &git::PrStatus, fork_owner: Option<&str>) -> Result<()> {
+        if status.head_ref != self.change_id {
+            return Err(eyre!(
+                "PR {} in repo '{}' is from branch '{}', not '{}'; refusing to modify it.",
+                self.pr_number,
+                self.reposlug,
+                status.head_ref,
+                self.change_id
+            ));
+        }
+        let head_owner = status.head_owner.as_deref().unwrap_or("another repo");
+        if status.cross_repository && !fork_owner.is_some_and(|owner| head_owner.eq_ignore_ascii_case(owner)) {
+            return Err(eyre!(
+                "PR {} in repo '{}' is from a fork of '{}'; refusing to modify it.",
+                self.pr_number,
+                self.reposlug,
+                head_owner
+            ));
+        }
+        Ok(())
+    }
+
+    /// The whole of `filename` before and after the PR, from its contents at the PR's base
+    /// commit (looked up once per PR into `base_sha`) with `patch` applied. `None` when the patch
+    /// changes no lines or the base can't be read or doesn't match the patch.
+    fn apply_to_base(
+        &self,
+        filename: &str,
+        patch: &str,
+        base_sha: &std::cell::OnceCell<Option<String>>,
+    ) -> Option<(String, String)> {
+        if !patch.contains("\n@@ ") {
+            return None;
+        }
+        let base_sha = base_sha
+            .get_or_init(|| {
+                git::get_pr_base_sha(&self.reposlug, self.pr_number)
+                    .map_err(|e| debug!("No base commit for {}#{}: {}", self.reposlug, self.pr_number, e))
+                    .ok()
+            })
+            .as_deref()?;
+        let base = git::get_file_at(&self.reposlug, filename, base_sha)
+            .map_err(|e| {
+                debug!(
+                    "Failed to read '{}' of '{}' at {}: {}",
+                    filename, self.reposlug, base_sha, e
+                )
+            })
+            .ok()?;
+        let applied = diff::apply_patch(base.as_deref().unwrap_or(""), patch);
+        if applied.is_none() {
+            debug!(
+                "The patch of '{}' in {}#{} doesn't apply to its base",
+                filename, self.reposlug, self.pr_number
+            );
+        }
+        applied
+    }
+
+    /// Renders the PR's diff with its diffstat.
+    pub fn get_review_diff(&self, buffer: usize) -> (String, DiffStat) {
+        self.get_review_diff_matching(buffer, &[])
+    }
+
+    /// As `get_review_diff`, for only the files (old or new path) matching one of `paths`;
+    /// every file when it is empty.
+    pub fn get_review_diff_matching(&self, buffer: usize, paths: &[glob::Pattern]) -> (String, DiffStat) {
+        let mut output = String::new();
+        let mut stat = DiffStat::default();
+        output.push_str(&format!("{} (# {})\n", self.reposlug, self.pr_number));
+        match git::get_pr_diff(&self.reposlug, self.pr_number) {
+            Ok(diff_text) => {
+                let file_patches = diff::split_file_patches(&diff_text);
+                let base_sha = std::cell::OnceCell::new();
+                let options = glob::MatchOptions {
+                    require_literal_separator: true,
+                    ..glob::MatchOptions::new()
+                };
+                for (filename, patch) in &file_patches {
+                    let header = diff::patch_header(patch);
+                    let base_path = header.renamed_from.as_deref().unwrap_or(filename);
+                    if !paths.is_empty()
+                        && !paths
+                            .iter()
+                            .any(|path| path.matches_with(filename, options) || path.matches_with(base_path, options))
+                    {
+                        continue;
+                    }
+                    let (orig_text, upd_text, buffer) = match self.apply_to_base(base_path, patch, &base_sha) {
+                        Some((orig_text, upd_text)) => (orig_text, upd_text, buffer),
+                        None => {
+                            // Without the base file only the patch's own lines are known, and
+                            // the gaps between its hunks are filled with blank lines.
+                            let (_, orig_text, upd_text) = diff::reconstruct_files_from_unified_diff(patch)
+                                .into_iter()
+                                .next()
+                                .unwrap_or_default();
+                            (orig_text, upd_text, buffer.min(diff::PATCH_CONTEXT))
+                        }
+                    };
+                    // Every patched file counts, even a rename or mode change with no line changed.
+                    stat += DiffStat {
+                        files: 1,
+                        ..diff::diffstat(&orig_text, &upd_text)
+                    };
+                    output.push_str(&format!(
+                        "{}\n",
+                        utils::indent(&review_file_title(filename, &header), 2)
+                    ));
+                    // Binary files carry no lines; pure renames and mode changes change none.
+                    if header.binary || orig_text == upd_text {
+                        continue;
+                    }
+                    let colored_diff = if upd_text.trim().is_empty() {
+                        diff::generate_diff(&orig_text, "", buffer)
+                    } else {
+                        diff::generate_diff(&orig_text, &upd_text, buffer)
+                    };
+                    for line in colored_diff.lines() {
+                        output.push_str(&format!("{}\n", utils::indent(line, 4)));
+                    }
+                }
+                if !file_patches.is_empty() {
+                    output.push('\n');
+                }
+            }
+            Err(e) => {
+                output.push_str(&format!("  (Could not fetch PR diff: {})\n", e));
+            }
+        }
+        (output, stat)
+    }
+}
+
+/// The line naming a file in a review diff: `A`dded, `D`eleted, `R`enamed (`old -> new`) or
+/// `M`odified, with any mode change and a note for binary files.
+fn review_file_title(filename: &str, header: &diff::PatchHeader) -> String {
+    let mut title = match (&header.renamed_from, header.new_file, header.deleted) {
+        (Some(old), _, _) => format!("R {} -> {}", old, filename),
+        (None, true, _) => format!("A {}", filename),
+        (None, _, true) => format!("D {}", filename),
+        (None, false, false) => format!("M {}", filename),
+    };
+    if let Some((old, new)) = &header.mode_change {
+        title.push_str(&format!(" (mode {} => {})", old, new));
+    }
+    if header.binary {
+        title.push_str(" (binary)");
+    }
+    title
+}
+
+/// Copies the untracked `files` (relative paths) of the clone at `from` into the checkout at
+/// `to`; directories are left alone.
+fn copy_untracked(from: &Path, to: &Path, files: &[String]) -> Result<()> {
+    for file in files {
+        let source = from.join(file);
+        if !source.is_file() {
+            debug!("Not copying untracked '{}': not a file", source.display());
+            continue;
+        }
+        let target = to.join(file);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| eyre!("Failed to create '{}': {}", parent.display(), e))?;
+        }
+        fs::copy(&source, &target).map_err(|e| eyre!("Failed to copy untracked '{}': {}", file, e))?;
+        info!("Copied untracked '{}' into the checkout", file);
+    }
+    Ok(())
+}
+
+/// Finds files matching `pattern` in `repo`, skipping generated files unless `include_generated` is set.
+fn find_files_in_repo(repo: &Path, pattern: &str, include_generated: bool) -> Result<Vec<PathBuf>> {
+    let search_pattern = repo.join(pattern).to_string_lossy().to_string();
+    let mut matches = Vec::new();
+    for path in glob::glob(&search_pattern)?.flatten() {
+        let relative_path = path.strip_prefix(repo)?.to_path_buf();
+        matches.push(relative_path);
+    }
+    if include_generated {
+        Ok(matches)
+    } else {
+        Ok(generated::exclude_generated(repo, matches))
+    }
+}
+
+/// Writes `contents` to `full_path` (when `commit` is true) honoring the `if_exists` strategy,
+/// and returns the diff (with its diffstat) against whatever was there before.
+///
+/// Files whose SHA-256 already matches the new contents are left untouched and yield `None`.
+fn add_file(
+    full_path: &Path,
+    contents: &str,
+    if_exists: IfExists,
+    buffer: usize,
+    commit: bool,
+) -> Result<Option<(String, DiffStat)>> {
+    let existing = if full_path.exists() {
+        Some(fs::read_to_string(full_path).map_err(|e| eyre!("Failed to read '{}': {}", full_path.display(), e))?)
+    } else {
+        None
+    };
+    let Some(updated) = added_contents(
+        &full_path.display().to_string(),
+        existing.as_deref(),
+        contents,
+        if_exists,
+    )?
+    else {
+        return Ok(None);
+    };
+
+    let original = existing.as_deref().unwrap_or("");
+    let diff = diff::generate_diff(original, &updated, buffer);
+    let stat = diff::diffstat(original, &updated);
+
+    if commit {
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| eyre!("Failed to create directories for '{}': {}", full_path.display(), e))?;
+        }
+        fs::write(full_path, &updated).map_err(|e| eyre!("Failed to write '{}': {}", full_path.display(), e))?;
+    }
+
+    Ok(Some((diff, stat)))
+}
+
+/// Whether `read` finds `path` holding what `Change::Add` would write there for `contents`.
+fn has_contents(path: &str, contents: &str, read: impl Fn(&str) -> Option<String>) -> bool {
+    let mut expected = contents.to_string();
+    if !expected.ends_with('\n') {
+        expected.push('\n');
+    }
+    read(path).is_some_and(|actual| actual == expected)
+}
+
+/// What `Change::Add` writes to `name` over its `existing` contents (`None` when absent), or
+/// `None` when nothing needs writing: the contents already match, or `if_exists` says skip.
+pub(crate) fn added_contents(
+    name: &str,
+    existing: Option<&str>,
+    contents: &str,
+    if_exists: IfExists,
+) -> Result<Option<String>> {
+    // ensure there's exactly one trailing newline
+    let mut file_contents = contents.to_string();
+    if !file_contents.ends_with('\n') {
+        file_contents.push('\n');
+    }
+    let Some(current) = existing else {
+        return Ok(Some(file_contents));
+    };
+    let current_sum = utils::sha256_hex(current.as_bytes());
+    let new_sum = utils::sha256_hex(file_contents.as_bytes());
+    if current_sum == new_sum {
+        debug!("'{}' already has the expected contents (sha256 {})", name, new_sum);
+        return Ok(None);
+    }
+    match if_exists {
+        IfExists::Fail => Err(eyre!(
+            "'{}' already exists with different contents (sha256 {} != {}); pass --if-exists skip|overwrite|merge-markers",
+            name,
+            &current_sum[..12],
+            &new_sum[..12]
+        )),
+        IfExists::Skip => {
+            info!("'{}' already exists; skipping per --if-exists skip", name);
+            Ok(None)
+        }
+        IfExists::Overwrite => Ok(Some(file_contents)),
+        IfExists::MergeMarkers => {
+            let mut current = current.to_string();
+            if !current.ends_with('\n') {
+                current.push('\n');
+            }
+            Ok(Some(format!(
+                "<<<<<<< existing\n{}=======\n{}>>>>>>> SLAM\n",
+                current, file_contents
+            )))
+        }
+    }
+}
+
+/// A file's entry in a diff listing: the `header` line (e.g. "M path") and the indented diff.
+pub(crate) fn diff_block(header: &str, diff: &str) -> String {
+    let mut block = format!("{}\n", utils::indent(header, 2));
+    for line in diff.lines() {
+        block.push_str(&format!("{}\n", utils::indent(line, 4)));
+    }
+    block
+}
+
+/// The diff entry of a `Change::Chmod` moving `file` from git mode `from` to `mode`.
+pub(crate) fn chmod_header(file: &str, from: &str, mode: FileMode) -> String {
+    format!("M {} (mode {} => {})", file, from, mode.git_mode())
+}
+
+/// One "D <file> (-N lines)" entry per deleted file, capped at `DELETE_DIR_SUMMARY_LIMIT`;
+/// `read` gives a file's contents, `None` for binary files.
+pub(crate) fn delete_dir_summary(
+    tracked: &[String],
+    simplified: bool,
+    read: impl Fn(&str) -> Option<String>,
+) -> String {
+    let mut summary = String::new();
+    for file in tracked.iter().take(DELETE_DIR_SUMMARY_LIMIT) {
+        let entry = if simplified {
+            format!("D {}", file)
+        } else {
+            match read(file) {
+                Some(content) => format!("D {} (-{} lines)", file, content.lines().count()),
+                None => format!("D {} (binary)", file),
+            }
+        };
+        summary.push_str(&format!("{}\n", utils::indent(&entry, 2)));
+    }
+    if tracked.len() > DELETE_DIR_SUMMARY_LIMIT {
+        let more = format!("... and {} more files", tracked.len() - DELETE_DIR_SUMMARY_LIMIT);
+        summary.push_str(&format!("{}\n", utils::indent(&more, 2)));
+    }
+    summary
+}
+
+/// A file the pre-commit hooks rewrote, diffed against what slam left behind.
+#[derive(Debug)]
+struct HookFix {
+    path: String,
+    diff: String,
+    /// The file's stat against HEAD once the hooks ran.
+    stat: DiffStat,
+    /// What the change's own stat already counted for the file, which `stat` replaces.
+    replaces: DiffStat,
+}
+
+/// Contents of every changed file in the working tree (`None` when deleted), keyed by path.
+fn snapshot_changed_files(repo_path: &Path) -> Result<HashMap<String, Option<String>>> {
+    Ok(git::changed_files(repo_path)?
+        .into_iter()
+        .map(|path| {
+            let content = fs::read_to_string(repo_path.join(&path)).ok();
+            (path, content)
+        })
+        .collect())
+}
+
+/// Files whose content changed while the hooks ran. Files slam had not touched are compared
+/// against HEAD.
+fn hook_fixes(repo_path: &Path, before: &HashMap<String, Option<String>>, buffer: usize) -> Result<Vec<HookFix>> {
+    let mut paths = git::changed_files(repo_path)?;
+    paths.extend(before.keys().cloned());
+    paths.sort();
+    paths.dedup();
+
+    let mut fixes = Vec::new();
+    for path in paths {
+        let original = match before.get(&path) {
+            Some(content) => content.clone(),
+            None => git::show_head_file(repo_path, &path)?,
+        };
+        let updated = fs::read_to_string(repo_path.join(&path)).ok();
+        if original == updated {
+            continue;
+        }
+        let original = original.unwrap_or_default();
+        let updated = updated.unwrap_or_default();
+        debug!("pre-commit modified '{}' in '{}'", path, repo_path.display());
+        let (stat, replaces) = if before.contains_key(&path) {
+            let head = git::show_head_file(repo_path, &path)?.unwrap_or_default();
+            (diff::diffstat(&head, &updated), diff::diffstat(&head, &original))
+        } else {
+            (diff::diffstat(&original, &updated), DiffStat::default())
+        };
+        fixes.push(HookFix {
+            diff: diff::generate_diff(&original, &updated, buffer),
+            stat,
+            replaces,
+            path,
+        });
+    }
+    Ok(fixes)
+}
+
+/// Appends the hook fixes to the change's diff and stat. Files the change already touched
+/// are counted once, by their net diff against HEAD.
+fn fold_hook_fixes(mut applied_diff: String, mut stat: DiffStat, fixes: &[HookFix]) -> (String, DiffStat) {
+    for fix in fixes {
+        applied_diff.push_str(&format!(
+            "{}\n",
+            utils::indent(&format!("M {} (pre-commit)", fix.path), 2)
+        ));
+        for line in fix.diff.lines() {
+            applied_diff.push_str(&format!("{}\n", utils::indent(line, 4)));
+        }
+        stat.files = stat.files.saturating_sub(fix.replaces.files);
+        stat.insertions = stat.insertions.saturating_sub(fix.replaces.insertions);
+        stat.deletions = stat.deletions.saturating_sub(fix.replaces.deletions);
+        stat += fix.stat;
+    }
+    (applied_diff, stat)
+}
+
+/// Removes the directories above the deleted `path` that are left empty, up to (not including)
+/// `repo_path`, so no husks stay behind in the checkout.
+fn remove_empty_parents(repo_path: &Path, path: &Path) {
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        // `remove_dir` only succeeds on empty directories, which ends the walk at the first full one.
+        if current == repo_path || !current.starts_with(repo_path) || fs::remove_dir(current).is_err() {
+            break;
+        }
+        debug!("Removed empty directory '{}'", current.display());
+        dir = current.parent();
+    }
+}
+
+/// PR body note listing the files the pre-commit hooks rewrote.
+fn hook_fixes_note(files: &[String]) -> String {
+    if files.is_empty() {
+        return String::new();
+    }
+    let files = files.iter().map(|f| format!("`{}`", f)).collect::<Vec<_>>().join(", ");
+    format!("pre-commit hooks also reformatted: {}", files)
+}
+
+/// One change applied to one file; `diff_in` compiles a substitution once and calls
+/// `substitute_file` instead, so this is left to the tests.
+#[cfg(test)]
+fn process_file(full_path: &Path, change: &Change, buffer: usize, commit: bool) -> Option<(String, DiffStat)> {
+    match change {
+        Change::DeleteDir(_, _) | Change::Chmod(_) | Change::Copy(..) => None,
+
+        Change::Delete => {
+            if commit {
+                let _ = fs::remove_file(full_path);
+            }
+            None
+        }
+
+        Change::Add(_path, contents, if_exists) => match add_file(full_path, contents, *if_exists, buffer, commit) {
+            Ok(diff) => diff,
+            Err(e) => {
+                warn!("{}", e);
+                None
+            }
+        },
+
+        Change::Sub(_, _) | Change::Regex(_, _) | Change::MultiSub(_) => {
+            substitute_file(full_path, &Substitution::new(change).ok()??, buffer, commit)
+        }
+    }
+}
+
+/// Applies `substitution` to the file at `full_path` (when `commit` is true) and returns the
+/// diff with its diffstat, or `None` when it changes nothing.
+fn substitute_file(
+    full_path: &Path,
+    substitution: &Substitution,
+    buffer: usize,
+    commit: bool,
+) -> Option<(String, DiffStat)> {
+    let content = fs::read_to_string(full_path).ok()?;
+    let updated = substitution.apply(&content)?;
+    let diff = diff::generate_diff(&content, &updated, buffer);
+    let stat = diff::diffstat(&content, &updated);
+    if commit {
+        let _ = fs::write(full_path, &updated);
+    }
+    Some((diff, stat))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_change_interpolated() {
+        let badge = Change::Sub(
+            "badge.svg".to_string(),
+            "https://ci.example.com/{{org}}/{{repo}}/badge.svg?branch={{ default_branch }}".to_string(),
+        );
+        let change = badge.interpolated("acme/api", || Ok("trunk".to_string())).unwrap();
+        assert!(
+            matches!(change, Change::Sub(_, repl) if repl == "https://ci.example.com/acme/api/badge.svg?branch=trunk")
+        );
+
+        // The default branch is only looked up when used, and unknown placeholders are kept.
+        let regex = Change::Regex(
+            "name: .*".to_string(),
+            "name: {{reposlug}} ${{ matrix.os }}".to_string(),
+        );
+        let change = regex
+            .interpolated("acme/api", || panic!("default branch looked up"))
+            .unwrap();
+        assert!(matches!(change, Change::Regex(_, repl) if repl == "name: acme/api ${{ matrix.os }}"));
+        assert!(badge.interpolated("acme/api", || Err(eyre!("no origin"))).is_err());
+
+        let blueprint = Change::Copy(
+            vec![
+                ("README.md".to_string(), "# {{repo}}\n".to_string()),
+                ("CODEOWNERS".to_string(), "* @{{org}}/platform\n".to_string()),
+            ],
+            IfExists::Overwrite,
+        );
+        let change = blueprint
+            .interpolated("acme/api", || panic!("default branch looked up"))
+            .unwrap();
+        let Change::Copy(files, IfExists::Overwrite) = change else {
+            panic!("expected a copy");
+        };
+        assert_eq!(files[0].1, "# api\n");
+        assert_eq!(files[1].1, "* @acme/platform\n");
+    }
+
+    #[test]
+    fn test_change_debug() {
+        let delete = Change::Delete;
+        let add = Change::Add("test.txt".to_string(), "content".to_string(), IfExists::Fail);
+        let sub = Change::Sub("old".to_string(), "new".to_string());
+        let regex = Change::Regex(r"\d+".to_string(), "X".to_string());
+
+        // Ensure Debug is implemented
+        assert!(!format!("{:?}", delete).is_empty());
+        assert!(!format!("{:?}", add).is_empty());
+        assert!(!format!("{:?}", sub).is_empty());
+        assert!(!format!("{:?}", regex).is_empty());
+    }
+
+    #[test]
+    fn test_change_clone() {
+        let original = Change::Add("test.txt".to_string(), "content".to_string(), IfExists::Fail);
+        let cloned = original.clone();
+
+        match (&original, &cloned) {
+            (Change::Add(path1, content1, _), Change::Add(path2, content2, _)) => {
+                assert_eq!(path1, path2);
+                assert_eq!(content1, content2);
+            }
+            _ => panic!("Clone failed"),
+        }
+    }
+
+    #[test]
+    fn test_repo_create_repo_from_remote_with_pr() {
+        let repo = Repo::create_repo_from_remote_with_pr("test-org/test-repo", "SLAM-test", 123);
+
+        assert_eq!(repo.reposlug, "test-org/test-repo");
+        assert_eq!(repo.change_id, "SLAM-test");
+        assert_eq!(repo.pr_number, 123);
+        assert!(repo.change.is_none());
+        assert!(repo.files.is_empty());
+    }
+
+    #[test]
+    fn test_repo_create_repo_from_local_basic() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let repo_path = root.join("test-repo");
+        fs::create_dir_all(&repo_path).unwrap();
+
+        let change = Some(Change::Delete);
+        let file_ptns: Vec<String> = vec![];
+        let change_id = "test-change";
+
+        let result = Repo::create_repo_from_local(&repo_path, root, &change, &file_ptns, change_id, false);
+
+        assert!(result.is_ok());
+        let repo = result.unwrap();
+        assert_eq!(repo.reposlug, "test-repo");
+        assert_eq!(repo.dir, None);
+        assert_eq!(repo.path(root), repo_path);
+        assert_eq!(repo.change_id, "test-change");
+        assert!(matches!(repo.change, Some(Change::Delete)));
+        assert!(repo.files.is_empty());
+        assert_eq!(repo.pr_number, 0);
+    }
+
+    #[test]
+    fn test_repo_create_repo_from_local_with_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let repo_path = root.join("test-repo");
+        fs::create_dir_all(&repo_path).unwrap();
+
+        // Create some test files
+        fs::write(repo_path.join("test1.txt"), "content1").unwrap();
+        fs::write(repo_path.join("test2.txt"), "content2").unwrap();
+        fs::write(repo_path.join("other.md"), "markdown").unwrap();
+
+        let change = None;
+        let file_ptns = vec!["*.txt".to_string()];
+        let change_id = "test-change";
+
+        let result = Repo::create_repo_from_local(&repo_path, root, &change, &file_ptns, change_id, false);
+
+        assert!(result.is_ok());
+        let repo = result.unwrap();
+        assert_eq!(repo.files.len(), 2);
+        assert!(repo.files.contains(&"test1.txt".to_string()));
+        assert!(repo.files.contains(&"test2.txt".to_string()));
+        assert!(!repo.files.contains(&"other.md".to_string()));
+    }
+
+    #[test]
+    fn test_repo_create_repo_from_local_names_by_remote() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let repo_path = root.join("misc").join("renamed");
+        git_init_with_files(&repo_path, &["README.md"]);
+        let status = std::process::Command::new("git")
+            .current_dir(&repo_path)
+            .args(["remote", "add", "origin", "git@github.com:org/api.git"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let repo = Repo::create_repo_from_local(&repo_path, root, &None, &[], "test-change", false).unwrap();
+        assert_eq!(repo.reposlug, "org/api");
+        assert_eq!(repo.dir.as_deref(), Some("misc/renamed"));
+        assert_eq!(repo.path(root), repo_path);
+    }
+
+    #[test]
+    fn test_repo_create_repo_from_local_invalid_prefix() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let repo_path = PathBuf::from("/completely/different/path");
+
+        let change = None;
+        let file_ptns: Vec<String> = vec![];
+        let change_id = "test-change";
+
+        let result = Repo::create_repo_from_local(&repo_path, root, &change, &file_ptns, change_id, false);
+
+        assert!(result.unwrap_err().to_string().starts_with("not under the workdir"));
+    }
+
+    #[test]
+    fn test_find_files_in_repo() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+
+        // Create test files
+        fs::write(repo_path.join("file1.txt"), "content1").unwrap();
+        fs::write(repo_path.join("file2.txt"), "content2").unwrap();
+        fs::write(repo_path.join("file3.md"), "markdown").unwrap();
+
+        let result = find_files_in_repo(repo_path, "*.txt", false);
+        assert!(result.is_ok());
+
+        let files = result.unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().any(|f| f.to_string_lossy() == "file1.txt"));
+        assert!(files.iter().any(|f| f.to_string_lossy() == "file2.txt"));
+    }
+
+    #[test]
+    fn test_find_files_in_repo_generated() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        fs::write(repo_path.join("Cargo.toml"), "[package]").unwrap();
+        fs::write(repo_path.join("Cargo.lock"), "# lock").unwrap();
+
+        let files = find_files_in_repo(repo_path, "Cargo.*", false).unwrap();
+        assert_eq!(files, vec![PathBuf::from("Cargo.toml")]);
+
+        let files = find_files_in_repo(repo_path, "Cargo.*", true).unwrap();
+        assert_eq!(files.len(), 2);
+    }
+
+    #[test]
+    fn test_process_file_delete_no_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        fs::write(&file_path, "test content").unwrap();
+
+        let change = Change::Delete;
+        let result = process_file(&file_path, &change, 1, false);
+
+        assert!(result.is_none());
+        assert!(file_path.exists()); // File should still exist
+    }
+
+    #[test]
+    fn test_process_file_delete_with_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        fs::write(&file_path, "test content").unwrap();
+
+        let change = Change::Delete;
+        let result = process_file(&file_path, &change, 1, true);
+
+        assert!(result.is_none());
+        assert!(!file_path.exists()); // File should be deleted
+    }
+
+    #[test]
+    fn test_process_file_add_no_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("new.txt");
+
+        let change = Change::Add("new.txt".to_string(), "new content".to_string(), IfExists::Fail);
+        let result = process_file(&file_path, &change, 1, false);
+
+        assert!(result.is_some());
+        let (diff, _) = result.unwrap();
+        assert!(diff.contains("new content"));
+        assert!(!file_path.exists()); // File should not be created
+    }
+
+    #[test]
+    fn test_process_file_add_with_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("new.txt");
+
+        let change = Change::Add("new.txt".to_string(), "new content".to_string(), IfExists::Fail);
+        let result = process_file(&file_path, &change, 1, true);
+
+        assert!(result.is_some());
+        assert!(file_path.exists()); // File should be created
+        let content = fs::read_to_string(&file_path).unwrap();
+        assert_eq!(content, "new content\n"); // Should have trailing newline
+    }
+
+    #[test]
+    fn test_add_file_identical_contents_is_noop() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("same.txt");
+        fs::write(&file_path, "same\n").unwrap();
+
+        let result = add_file(&file_path, "same", IfExists::Fail, 1, true).unwrap();
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_add_file_conflict_fail() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("custom.txt");
+        fs::write(&file_path, "customized\n").unwrap();
+
+        let err = add_file(&file_path, "standard", IfExists::Fail, 1, true).unwrap_err();
+        assert!(err.to_string().contains("already exists with different contents"));
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "customized\n");
+    }
+
+    #[test]
+    fn test_add_file_conflict_skip() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("custom.txt");
+        fs::write(&file_path, "customized\n").unwrap();
+
+        let result = add_file(&file_path, "standard", IfExists::Skip, 1, true).unwrap();
+        assert!(result.is_none());
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "customized\n");
+    }
+
+    #[test]
+    fn test_add_file_conflict_overwrite() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("custom.txt");
+        fs::write(&file_path, "customized\n").unwrap();
+
+        let result = add_file(&file_path, "standard", IfExists::Overwrite, 1, true).unwrap();
+        assert!(result.unwrap().0.contains("standard"));
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "standard\n");
+    }
+
+    #[test]
+    fn test_add_file_conflict_merge_markers() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("custom.txt");
+        fs::write(&file_path, "customized").unwrap();
+
+        add_file(&file_path, "standard", IfExists::MergeMarkers, 1, true).unwrap();
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "<<<<<<< existing\ncustomized\n=======\nstandard\n>>>>>>> SLAM\n"
+        );
+    }
+
+    #[test]
+    fn test_repo_create_diff_add_conflict_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("test-repo")).unwrap();
+        fs::write(root.join("test-repo").join("new.txt"), "other").unwrap();
+
+        let repo = Repo {
+            reposlug: "test-repo".to_string(),
+            dir: None,
+            change_id: "test-change".to_string(),
+            change: Some(Change::Add(
+                "new.txt".to_string(),
+                "content".to_string(),
+                IfExists::Fail,
+            )),
+            files: vec![],
+            pr_number: 0,
+        };
+
+        assert!(repo.create_diff(root, 1, false, false).is_err());
+    }
+
+    #[test]
+    fn test_process_file_sub_no_match() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        fs::write(&file_path, "original content").unwrap();
+
+        let change = Change::Sub("nonexistent".to_string(), "replacement".to_string());
+        let result = process_file(&file_path, &change, 1, false);
+
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_process_file_sub_with_match() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        fs::write(&file_path, "original content").unwrap();
+
+        let change = Change::Sub("original".to_string(), "modified".to_string());
+        let result = process_file(&file_path, &change, 1, false);
+
+        assert!(result.is_some());
+        let (diff, stat) = result.unwrap();
+        assert!(diff.contains("original"));
+        assert!(diff.contains("modified"));
+        assert_eq!(
+            stat,
+            DiffStat {
+                files: 1,
+                insertions: 1,
+                deletions: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_process_file_regex_valid() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        fs::write(&file_path, "version 123").unwrap();
+
+        let change = Change::Regex(r"\d+".to_string(), "456".to_string());
+        let result = process_file(&file_path, &change, 1, false);
+
+        assert!(result.is_some());
+        let (diff, _) = result.unwrap();
+        assert!(diff.contains("123"));
+        assert!(diff.contains("456"));
+    }
+
+    #[test]
+    fn test_process_file_regex_invalid() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        fs::write(&file_path, "test content").unwrap();
+
+        let change = Change::Regex("[invalid".to_string(), "replacement".to_string());
+        let result = process_file(&file_path, &change, 1, false);
+
+        assert!(result.is_none()); // Invalid regex should return None
+    }
+
+    #[test]
+    fn test_repo_create_diff_no_change() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+
+        let repo = Repo {
+            reposlug: "test-repo".to_string(),
+            dir: None,
+            change_id: "test-change".to_string(),
+            change: None,
+            files: vec!["file1.txt".to_string(), "file2.txt".to_string()],
+            pr_number: 0,
+        };
+
+        let (diff, _) = repo.create_diff(root, 1, false, false).unwrap();
+
+        assert!(diff.contains("test-repo"));
+        assert!(diff.contains(">< file1.txt"));
+        assert!(diff.contains(">< file2.txt"));
+    }
+
+    #[test]
+    fn test_create_reports_already_compliant() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        git_init_with_files(&root.join("org/repo"), &["README.md"]);
+        fs::write(root.join("org/repo/config.yml"), "version: 2\n").unwrap();
+        let repo = |change: Change| Repo {
+            reposlug: "org/repo".to_string(),
+            dir: None,
+            change_id: "SLAM-test".to_string(),
+            change: Some(change),
+            files: vec!["config.yml".to_string()],
+            pr_number: 0,
+        };
+        let create = |change: Change| repo(change).create(root, &CreateOptions::default());
+
+        let sub = |ptn: &str, repl: &str| Change::Sub(ptn.to_string(), repl.to_string());
+        assert!(matches!(
+            create(sub("version: 1", "version: 2")),
+            CreateResult::AlreadyCompliant
+        ));
+        assert!(matches!(
+            create(sub("version: 0", "version: 1")),
+            CreateResult::NoChanges
+        ));
+
+        let regex = |ptn: &str, repl: &str| Change::Regex(ptn.to_string(), repl.to_string());
+        assert!(matches!(
+            create(regex("v1", "version: 2")),
+            CreateResult::AlreadyCompliant
+        ));
+        assert!(matches!(create(regex("(v)1", "${1}2")), CreateResult::NoChanges));
+
+        let add = |contents: &str| Change::Add("config.yml".to_string(), contents.to_string(), IfExists::Skip);
+        assert!(matches!(create(add("version: 2")), CreateResult::AlreadyCompliant));
+        assert!(matches!(create(add("version: 3")), CreateResult::NoChanges));
+        let copy =
+            |contents: &str| Change::Copy(vec![("config.yml".to_string(), contents.to_string())], IfExists::Skip);
+        assert!(matches!(create(copy("version: 2")), CreateResult::AlreadyCompliant));
+        assert!(matches!(create(copy("version: 3")), CreateResult::NoChanges));
+
+        let delete_dir = Change::DeleteDir("old".to_string(), 10);
+        assert!(matches!(
+            repo(delete_dir).create(root, &CreateOptions::default()),
+            CreateResult::AlreadyCompliant
+        ));
+    }
+
+    #[test]
+    fn test_repo_create_diff_add_change() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+
+        let repo = Repo {
+            reposlug: "test-repo".to_string(),
+            dir: None,
+            change_id: "test-change".to_string(),
+            change: Some(Change::Add(
+                "new.txt".to_string(),
+                "content".to_string(),
+                IfExists::Fail,
+            )),
+            files: vec![],
+            pr_number: 0,
+        };
+
+        let (diff, stat) = repo.create_diff(root, 1, false, false).unwrap();
+
+        assert!(diff.contains("test-repo"));
+        assert!(diff.contains("A new.txt"));
+        assert!(diff.contains("content"));
+        assert_eq!(stat.files, 1);
+        assert_eq!(stat.insertions, 1);
+    }
+
+    #[test]
+    fn test_repo_get_review_diff_basic_format() {
+        let repo = Repo {
+            reposlug: "test-org/test-repo".to_string(),
+            dir: None,
+            change_id: "SLAM-test".to_string(),
+            change: None,
+            files: vec![],
+            pr_number: 123,
+        };
+
+        // This test checks the basic format without mocking git::get_pr_diff
+        // The actual diff fetching would be tested in integration tests
+        let (diff, _) = repo.get_review_diff(1);
+        assert!(diff.contains("test-org/test-repo (# 123)"));
+    }
+
+    #[test]
+    fn test_render_pr_body_default_template() {
+        let repo = Repo::create_repo_from_remote_with_pr("org/repo", "SLAM-test", 0);
+        let opts = CreateOptions {
+            commit_msg: Some("Bump base image".to_string()),
+            ..Default::default()
+        };
+        let numstat = vec![("Dockerfile".to_string(), 1, 1)];
+
+        let body = repo.render_pr_body(&opts, "SLAM-test", "Bump base image", &numstat, &[]);
+        assert!(body.starts_with("Bump base image\n\n1 file(s) changed"));
+        assert!(body.contains("- `Dockerfile` (+1 -1)"));
+        assert!(body.contains("docs: https://github.com/scottidler/slam"));
+        assert!(!body.contains("\n\n\n"));
+    }
+
+    #[test]
+    fn test_hook_selection_from_args() {
+        assert_eq!(HookSelection::from_args(false, vec![]), HookSelection::All);
+        assert_eq!(HookSelection::from_args(true, vec![]), HookSelection::Skip);
+        assert_eq!(
+            HookSelection::from_args(false, vec!["black".to_string()]),
+            HookSelection::Only(vec!["black".to_string()])
+        );
+        // Skipping never runs anything, even outside a repo.
+        assert!(HookSelection::Skip.run(Path::new("/nonexistent")).is_ok());
+    }
+
+    #[test]
+    fn test_fold_hook_fixes() {
+        // The change turned a.py's `x=0` into `x=1`, which the hooks reformatted to `x = 1`.
+        let fixes = vec![
+            HookFix {
+                path: "a.py".to_string(),
+                diff: diff::generate_diff("x=1\n", "x = 1\n", 1),
+                stat: diff::diffstat("x=0\n", "x = 1\n"),
+                replaces: diff::diffstat("x=0\n", "x=1\n"),
+            },
+            HookFix {
+                path: "b.py".to_string(),
+                diff: diff::generate_diff("y=2\n", "y = 2\n", 1),
+                stat: diff::diffstat("y=2\n", "y = 2\n"),
+                replaces: DiffStat::default(),
+            },
+        ];
+        let stat = DiffStat {
+            files: 1,
+            insertions: 1,
+            deletions: 1,
+        };
+
+        let (diff, stat) = fold_hook_fixes("org/repo\n  M a.py\n".to_string(), stat, &fixes);
+        assert!(diff.contains("  M a.py (pre-commit)\n"));
+        assert!(diff.contains("  M b.py (pre-commit)\n"));
+        // a.py was already part of the change, so it counts once, by its net diff from HEAD.
+        assert_eq!(
+            stat,
+            DiffStat {
+                files: 2,
+                insertions: 2,
+                deletions: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_hook_fixes_note() {
+        assert_eq!(hook_fixes_note(&[]), "");
+        assert_eq!(
+            hook_fixes_note(&["a.py".to_string(), "b.py".to_string()]),
+            "pre-commit hooks also reformatted: `a.py`, `b.py`"
+        );
+    }
+
+    #[test]
+    fn test_render_pr_body_custom_template() {
+        let repo = Repo::create_repo_from_remote_with_pr("org/repo", "SLAM-test", 0);
+        let opts = CreateOptions {
+            commit_msg: Some("msg".to_string()),
+            body_template: Some(
+                "{{change_id}} in {{reposlug}}: {{description}} ({{insertions}}+/{{deletions}}-)".to_string(),
+            ),
+            description: Some("Rotate credentials".to_string()),
+            ..Default::default()
+        };
+        let numstat = vec![("a.txt".to_string(), 2, 1), ("b.txt".to_string(), 3, 0)];
+
+        let body = repo.render_pr_body(&opts, "SLAM-test", "msg", &numstat, &[]);
+        assert_eq!(body, "SLAM-test in org/repo: Rotate credentials (5+/1-)");
+    }
+
+    #[test]
+    fn test_render_commit_msg_default_template() {
+        let repo = Repo::create_repo_from_remote_with_pr("org/repo", "SLAM-test", 0);
+        let opts = CreateOptions {
+            commit_msg: Some("Automated update generated by SLAM".to_string()),
+            ..Default::default()
+        };
+        let msg = repo.render_commit_msg(&opts, "SLAM-test", &["a.txt".to_string()]);
+        assert_eq!(msg, "Automated update generated by SLAM");
+    }
+
+    #[test]
+    fn test_ticket_in_commit_msg_and_pr_body() {
+        let repo = Repo::create_repo_from_remote_with_pr("org/repo", "SLAM-OPS-12", 0);
+        let mut opts = CreateOptions {
+            commit_msg: Some("Bump base image".to_string()),
+            ticket: Some(Ticket {
+                key: "OPS-12".to_string(),
+                url: Some("https://corp.atlassian.net/browse/OPS-12".to_string()),
+            }),
+            ..Default::default()
+        };
+        let msg = repo.render_commit_msg(&opts, "SLAM-OPS-12", &[]);
+        assert_eq!(msg, "Bump base image\n\nRefs: OPS-12");
+        let body = repo.render_pr_body(&opts, "SLAM-OPS-12", &msg, &[], &[]);
+        assert!(body.starts_with("Ticket: [OPS-12](https://corp.atlassian.net/browse/OPS-12)\n\nBump base image"));
+
+        opts.commit_template = Some("{{ticket}}: {{message}}".to_string());
+        opts.body_template = Some("Fixes {{ticket_link}}".to_string());
+        let msg = repo.render_commit_msg(&opts, "SLAM-OPS-12", &[]);
+        assert_eq!(msg, "OPS-12: Bump base image");
+        let body = repo.render_pr_body(&opts, "SLAM-OPS-12", &msg, &[], &[]);
+        assert_eq!(body, "Fixes [OPS-12](https://corp.atlassian.net/browse/OPS-12)");
+    }
+
+    #[test]
+    fn test_render_commit_msg_custom_template() {
+        let repo = Repo::create_repo_from_remote_with_pr("org/repo", "SLAM-test", 0);
+        let opts = CreateOptions {
+            commit_msg: Some("bump base image".to_string()),
+            commit_template: Some("chore({{change_id}}): {{message}} [{{files_changed}} files: {{files}}]".to_string()),
+            ..Default::default()
+        };
+        let files = vec!["Dockerfile".to_string(), "ci.yml".to_string()];
+        let msg = repo.render_commit_msg(&opts, "SLAM-test", &files);
+        assert_eq!(msg, "chore(SLAM-test): bump base image [2 files: Dockerfile, ci.yml]");
+    }
+
+    #[test]
+    fn test_validate_conventional_commit() {
+        assert!(validate_conventional_commit("chore: bump base image").is_ok());
+        assert!(validate_conventional_commit("fix(ci): pin runner").is_ok());
+        assert!(validate_conventional_commit("feat(api)!: drop v1\n\nBREAKING CHANGE: gone").is_ok());
+        assert!(validate_conventional_commit("Automated update generated by SLAM").is_err());
+        assert!(validate_conventional_commit("chore:missing space").is_err());
+        assert!(validate_conventional_commit("wip: not a type").is_err());
+    }
+
+    #[test]
+    fn test_delete_local_branch() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("sandbox");
+        let origin = dir.path().join("origin");
+        let clone = root.join("org/api");
+        let setup = format!(
+            "git init -q -b main {0} && cd {0} && git -c user.name=t -c user.email=t@t commit -q --allow-empty -m init && \
+             git clone -q {0} {1} && cd {1} && git checkout -q -b SLAM-x && \
+             git -c user.name=t -c user.email=t@t commit -q --allow-empty -m mine",
+            origin.display(),
+            clone.display()
+        );
+        let status = std::process::Command::new("sh").args(["-c", &setup]).status().unwrap();
+        assert!(status.success());
+
+        let repo = Repo::create_repo_from_remote_with_pr("org/api", "SLAM-x", 7);
+        let message = repo.delete_local_branch(&root).unwrap().unwrap();
+        assert!(message.contains("kept 1 unpushed commit(s) at 'refs/slam-backup/SLAM-x/"));
+        assert_eq!(git::current_branch(&clone).unwrap().as_deref(), Some("main"));
+        assert!(!git::branch_exists(&clone, "SLAM-x").unwrap());
+        assert_eq!(repo.delete_local_branch(&root).unwrap(), None);
+    }
+
+    fn git_init_with_files(repo_path: &Path, files: &[&str]) {
+        let run = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .current_dir(repo_path)
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?} failed", args);
+        };
+        fs::create_dir_all(repo_path).unwrap();
+        run(&["init", "--quiet"]);
+        for file in files {
+            let path = repo_path.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "line1\nline2\n").unwrap();
+        }
+        run(&["add", "--all"]);
+        run(&[
+            "-c",
+            "user.name=t",
+            "-c",
+            "user.email=t@t",
+            "commit",
+            "--quiet",
+            "-m",
+            "init",
+        ]);
+    }
+
+    #[test]
+    fn test_untracked_paths_and_copy_untracked() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path().join("org/repo");
+        git_init_with_files(&repo_path, &["ci/build.yml", "config.yml"]);
+        fs::write(repo_path.join("extra.yml"), "new\n").unwrap();
+        fs::create_dir_all(repo_path.join("tmp")).unwrap();
+        fs::write(repo_path.join("tmp/scratch.yml"), "scratch\n").unwrap();
+
+        let files: Vec<String> = ["ci", "config.yml", "extra.yml", "tmp"].map(String::from).to_vec();
+        let untracked = git::untracked_paths(&repo_path, &files).unwrap();
+        assert_eq!(untracked, vec!["extra.yml".to_string(), "tmp".to_string()]);
+
+        let checkout = temp_dir.path().join("checkout");
+        fs::create_dir_all(&checkout).unwrap();
+        copy_untracked(&repo_path, &checkout, &untracked).unwrap();
+        assert_eq!(fs::read_to_string(checkout.join("extra.yml")).unwrap(), "new\n");
+        assert!(!checkout.join("tmp").exists());
+    }
+
+    #[test]
+    fn test_repo_create_diff_delete_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path().join("org/repo");
+        git_init_with_files(
+            &repo_path,
+            &["jenkins/Jenkinsfile", "jenkins/lib/util.groovy", "README.md"],
+        );
+
+        let repo = Repo {
+            reposlug: "org/repo".to_string(),
+            dir: None,
+            change_id: "test".to_string(),
+            change: Some(Change::DeleteDir("jenkins".to_string(), 10)),
+            files: vec![],
+            pr_number: 0,
+        };
+
+        let (diff, _) = repo.create_diff(temp_dir.path(), 1, false, false).unwrap();
+        assert!(diff.contains("D jenkins/Jenkinsfile (-2 lines)"));
+        assert!(diff.contains("D jenkins/lib/util.groovy (-2 lines)"));
+        assert!(!diff.contains("README.md"));
+        assert!(repo_path.join("jenkins").exists());
+
+        repo.create_diff(temp_dir.path(), 1, true, false).unwrap();
+        assert!(!repo_path.join("jenkins").exists());
+        assert!(repo_path.join("README.md").exists());
+        assert!(git::ls_files(&repo_path, "jenkins").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_repo_create_diff_delete_files_and_dirs() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path().join("org/repo");
+        git_init_with_files(
+            &repo_path,
+            &[
+                "ci/old/build.sh",
+                "legacy/a/b.txt",
+                "legacy/c.txt",
+                "docs/keep.md",
+                "README.md",
+            ],
+        );
+
+        let repo = Repo {
+            reposlug: "org/repo".to_string(),
+            dir: None,
+            change_id: "test".to_string(),
+            change: Some(Change::Delete),
+            files: vec!["ci/old/build.sh".to_string(), "legacy".to_string()],
+            pr_number: 0,
+        };
+
+        let (diff, stat) = repo.create_diff(temp_dir.path(), 1, false, false).unwrap();
+        assert!(diff.contains("D ci/old/build.sh"));
+        assert!(diff.contains("D legacy/a/b.txt (-2 lines)"));
+        assert!(diff.contains("D legacy/c.txt (-2 lines)"));
+        assert_eq!(stat.deletions, 6);
+        assert!(repo_path.join("legacy").exists());
+
+        repo.create_diff(temp_dir.path(), 1, true, false).unwrap();
+        assert!(!repo_path.join("ci").exists(), "empty parents are removed");
+        assert!(!repo_path.join("legacy").exists());
+        assert!(repo_path.join("docs/keep.md").exists());
+        assert!(repo_path.join("README.md").exists());
+    }
+
+    #[test]
+    fn test_review_file_title() {
+        let header = |patch: &str| diff::patch_header(patch);
+        assert_eq!(
+            review_file_title("new.yml", &header("rename from old.yml\nrename to new.yml\n")),
+            "R old.yml -> new.yml"
+        );
+        assert_eq!(
+            review_file_title(
+                "logo.png",
+                &header("new file mode 100644\nBinary files /dev/null and b/logo.png differ\n")
+            ),
+            "A logo.png (binary)"
+        );
+        assert_eq!(
+            review_file_title("run.sh", &header("old mode 100644\nnew mode 100755\n")),
+            "M run.sh (mode 100644 => 100755)"
+        );
+        assert_eq!(
+            review_file_title("gone", &header("--- a/gone\n+++ /dev/null\n")),
+            "D gone"
+        );
+    }
+
+    #[test]
+    fn test_repo_create_diff_chmod() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path().join("org/repo");
+        git_init_with_files(&repo_path, &["bin/run.sh", "README.md"]);
+
+        let repo = Repo {
+            reposlug: "org/repo".to_string(),
+            dir: None,
+            change_id: "test".to_string(),
+            change: Some(Change::Chmod(FileMode::Executable)),
+            files: vec!["bin/run.sh".to_string()],
+            pr_number: 0,
+        };
+
+        let (diff, stat) = repo.create_diff(temp_dir.path(), 1, false, false).unwrap();
+        assert!(diff.contains("M bin/run.sh (mode 100644 => 100755)"));
+        assert_eq!(stat.files, 1);
+
+        repo.create_diff(temp_dir.path(), 1, true, false).unwrap();
+        assert_eq!(
+            git::file_mode(&repo_path, "bin/run.sh").unwrap().as_deref(),
+            Some("100755")
+        );
+        let (diff, _) = repo.create_diff(temp_dir.path(), 1, false, false).unwrap();
+        assert!(diff.is_empty());
+        assert!(repo.already_applied(&repo_path));
+        assert_eq!("+x".parse::<FileMode>(), Ok(FileMode::Executable));
+        assert!("700".parse::<FileMode>().is_err());
+    }
+
+    #[test]
+    fn test_create_stage_commits_to_local_branch() {
+        let temp_dir = TempDir::new().unwrap();
+        let origin = temp_dir.path().join("origin.git");
+        git_init_with_files(&temp_dir.path().join("seed"), &["config.yml"]);
+        let clone = |args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .current_dir(temp_dir.path())
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {:?} failed", args);
+        };
+        clone(&["clone", "--quiet", "--bare", "seed", origin.to_str().unwrap()]);
+        clone(&["clone", "--quiet", origin.to_str().unwrap(), "org/repo"]);
+        let repo_path = temp_dir.path().join("org/repo");
+
+        let repo = Repo {
+            reposlug: "org/repo".to_string(),
+            dir: None,
+            change_id: "SLAM-x".to_string(),
+            change: Some(Change::Sub("line2".to_string(), "line3".to_string())),
+            files: vec!["config.yml".to_string()],
+            pr_number: 0,
+        };
+        let opts = CreateOptions {
+            branch_prefix: "SLAM".to_string(),
+            commit_msg: Some("bump".to_string()),
+            hooks: HookSelection::Skip,
+            author: Some("t <t@t>".parse().unwrap()),
+            stage: true,
+            ..Default::default()
+        };
+        let result = repo.create(temp_dir.path(), &opts);
+        assert!(matches!(result, CreateResult::Staged { .. }), "{:?}", result);
+
+        assert_eq!(git::commit_message(&repo_path, "refs/heads/SLAM-x").unwrap(), "bump");
+        assert_eq!(
+            git::diff_numstat_between(&repo_path, "SLAM-x~1", "SLAM-x").unwrap(),
+            [("config.yml".to_string(), 1, 1)]
+        );
+        assert!(!git::remote_branch_exists(&repo_path, "origin", "SLAM-x").unwrap());
+        assert_eq!(
+            fs::read_to_string(repo_path.join("config.yml")).unwrap(),
+            "line1\nline2\n"
+        );
+    }
+
+    #[test]
+    fn test_repo_create_diff_delete_dir_max_files_gate() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path().join("org/repo");
+        git_init_with_files(&repo_path, &["jenkins/a", "jenkins/b"]);
+
+        let repo = Repo {
+            reposlug: "org/repo".to_string(),
+            dir: None,
+            change_id: "test".to_string(),
+            change: Some(Change::DeleteDir("jenkins".to_string(), 1)),
+            files: vec![],
+            pr_number: 0,
+        };
+
+        let err = repo.create_diff(temp_dir.path(), 1, true, false).unwrap_err();
+        assert!(err.to_string().contains("more than --max-files 1"));
+        assert!(repo_path.join("jenkins/a").exists());
+    }
+
+    #[test]
+    fn test_delete_dir_summary_is_capped() {
+        let tracked: Vec<String> = (0..DELETE_DIR_SUMMARY_LIMIT + 5)
+            .map(|i| format!("dir/{}", i))
+            .collect();
+        let summary = delete_dir_summary(&tracked, true, |_| None);
+        assert_eq!(summary.lines().count(), DELETE_DIR_SUMMARY_LIMIT + 1);
+        assert!(summary.ends_with("... and 5 more files\n"));
+    }
+
+    #[test]
+    fn test_ensure_pr_is_ours() {
+        let repo = Repo::create_repo_from_remote_with_pr("org/repo", "SLAM-test", 7);
+        let mut status = git::PrStatus {
+            head_ref: "SLAM-test".to_string(),
+            draft: false,
+            mergeable: git::Mergeable::Mergeable,
+            reviewed: false,
+            checked: true,
+            checks_failed: false,
+            open: true,
+            merged: false,
+            ..Default::default()
+        };
+        assert!(repo.ensure_pr_is_ours(&status, None).is_ok());
+
+        status.cross_repository = true;
+        status.head_owner = Some("someone".to_string());
+        assert!(repo.ensure_pr_is_ours(&status, Some("someone")).is_ok());
+        let err = repo.ensure_pr_is_ours(&status, None).unwrap_err();
+        assert!(err.to_string().contains("from a fork of 'someone'"));
+        assert!(repo.ensure_pr_is_ours(&status, Some("me")).is_err());
+
+        status.cross_repository = false;
+        status.head_ref = "someone/SLAM-test".to_string();
+        let err = repo.ensure_pr_is_ours(&status, None).unwrap_err();
+        assert!(err.to_string().contains("refusing to modify"));
+    }
+
+    #[test]
+    fn test_merge_check() {
+        let mut status = git::PrStatus {
+            head_ref: "SLAM-test".to_string(),
+            draft: false,
+            mergeable: git::Mergeable::Mergeable,
+            reviewed: false,
+            checked: true,
+            checks_failed: false,
+            open: true,
+            merged: false,
+            ..Default::default()
+        };
+        assert_eq!(merge_check(&status), None);
+
+        status.checked = false;
+        assert_eq!(
+            merge_check(&status),
+            Some(MergeCheck::Waiting {
+                reason: "checks running".to_string()
+            })
+        );
+
+        status.checked = true;
+        status.mergeable = git::Mergeable::Unknown;
+        assert_eq!(
+            merge_check(&status),
+            Some(MergeCheck::Waiting {
+                reason: "mergeability not known yet".to_string()
+            })
+        );
+
+        status.mergeable = git::Mergeable::Conflicting;
+        assert_eq!(
+            merge_check(&status),
+            Some(MergeCheck::Blocked {
+                reason: "merge conflicts".to_string()
+            })
+        );
+
+        status.checks_failed = true;
+        assert!(matches!(merge_check(&status), Some(MergeCheck::Blocked { .. })));
+
+        status.open = false;
+        status.merged = true;
+        assert_eq!(merge_check(&status), Some(MergeCheck::Merged));
+    }
+
+    #[test]
+    fn test_repo_debug() {
+        let repo = Repo {
+            reposlug: "test-repo".to_string(),
+            dir: None,
+            change_id: "test-change".to_string(),
+            change: Some(Change::Delete),
+            files: vec!["test.txt".to_string()],
+            pr_number: 42,
+        };
+
+        let debug_str = format!("{:?}", repo);
+        assert!(debug_str.contains("test-repo"));
+        assert!(debug_str.contains("test-change"));
+        assert!(debug_str.contains("Delete"));
+        assert!(debug_str.contains("42"));
+    }
+}
@@ -0,0 +1,197 @@
+// src/search.rs
+
+use aho_corasick::{AhoCorasick, MatchKind};
+use eyre::{eyre, Result};
+use memchr::memmem;
+use regex::Regex;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::repo::Change;
+
+/// Bytes read per step when streaming a file past a literal needle.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A cheap pre-pass for `Sub` and `Regex` changes telling whether a file can be changed at all,
+/// so the files without a hit (usually most of them) are never decoded, copied or diffed.
+pub enum Prefilter {
+    /// Streams the file through a `memmem` finder for the needle of a `Sub`.
+    Literal(Box<memmem::Finder<'static>>),
+    /// Searches the raw bytes with the pattern of a `Regex`, compiled once for all files.
+    Pattern(regex::bytes::Regex),
+    /// Searches the raw bytes for any pattern of a `MultiSub` at once.
+    Table(AhoCorasick),
+    /// Lets every file through, e.g. for a pattern that doesn't compile.
+    All,
+}
+
+impl Prefilter {
+    pub fn new(change: &Change) -> Self {
+        match change {
+            Change::Sub(pattern, _) if !pattern.is_empty() => {
+                Prefilter::Literal(Box::new(memmem::Finder::new(pattern.as_bytes()).into_owned()))
+            }
+            Change::Regex(pattern, _) => regex::bytes::Regex::new(pattern)
+                .map(Prefilter::Pattern)
+                .unwrap_or(Prefilter::All),
+            Change::MultiSub(pairs) => table(pairs).map(Prefilter::Table).unwrap_or(Prefilter::All),
+            _ => Prefilter::All,
+        }
+    }
+
+    /// Whether the file at `path` may hold a match. A file that can't be read passes, to fail
+    /// (or be skipped) where it is read for real.
+    pub fn may_match(&self, path: &Path) -> bool {
+        let result = match self {
+            Prefilter::Literal(finder) => File::open(path).and_then(|file| stream_contains(file, finder)),
+            Prefilter::Pattern(regex) => std::fs::read(path).map(|bytes| regex.is_match(&bytes)),
+            Prefilter::Table(automaton) => std::fs::read(path).map(|bytes| automaton.is_match(&bytes)),
+            Prefilter::All => return true,
+        };
+        result.unwrap_or(true)
+    }
+}
+
+/// A `Sub`, `Regex` or `MultiSub` change compiled once, to be applied to many files.
+pub enum Substitution {
+    Literal(String, String),
+    Pattern(Regex, String),
+    /// Every pattern of a `MultiSub` in one Aho-Corasick automaton, with the replacements in
+    /// the same order, applied in a single pass over the content.
+    Table(AhoCorasick, Vec<String>),
+}
+
+impl Substitution {
+    /// The substitution `change` makes; `None` for changes of other kinds.
+    pub fn new(change: &Change) -> Result<Option<Self>> {
+        Ok(Some(match change {
+            Change::Sub(pattern, replacement) => Substitution::Literal(pattern.clone(), replacement.clone()),
+            Change::Regex(pattern, replacement) => Substitution::Pattern(
+                Regex::new(pattern).map_err(|e| eyre!("Invalid regex '{}': {}", pattern, e))?,
+                replacement.clone(),
+            ),
+            Change::MultiSub(pairs) => Substitution::Table(
+                table(pairs)?,
+                pairs.iter().map(|(_, replacement)| replacement.clone()).collect(),
+            ),
+            _ => return Ok(None),
+        }))
+    }
+
+    /// `content` after the substitution, or `None` when it changes nothing.
+    pub fn apply(&self, content: &str) -> Option<String> {
+        let updated = match self {
+            Substitution::Literal(pattern, replacement) => {
+                if !content.contains(pattern.as_str()) {
+                    return None;
+                }
+                content.replace(pattern.as_str(), replacement)
+            }
+            Substitution::Pattern(regex, replacement) => {
+                if !regex.is_match(content) {
+                    return None;
+                }
+                regex.replace_all(content, replacement.as_str()).to_string()
+            }
+            Substitution::Table(automaton, replacements) => {
+                if !automaton.is_match(content) {
+                    return None;
+                }
+                automaton.replace_all(content, replacements)
+            }
+        };
+        (updated != content).then_some(updated)
+    }
+}
+
+/// The automaton matching the patterns of `pairs`; where patterns overlap, the leftmost match
+/// wins, and of those starting at the same place the longest.
+fn table(pairs: &[(String, String)]) -> Result<AhoCorasick> {
+    if pairs.iter().any(|(pattern, _)| pattern.is_empty()) {
+        return Err(eyre!("A substitution table can't have an empty pattern"));
+    }
+    AhoCorasick::builder()
+        .match_kind(MatchKind::LeftmostLongest)
+        .build(pairs.iter().map(|(pattern, _)| pattern))
+        .map_err(|e| eyre!("Invalid substitution table: {}", e))
+}
+
+/// Whether `reader` holds the needle of `finder`, read in chunks that overlap by the needle's
+/// length less one, so a match straddling two chunks is found too.
+fn stream_contains(mut reader: impl Read, finder: &memmem::Finder) -> io::Result<bool> {
+    let keep = finder.needle().len().saturating_sub(1);
+    let mut buf = vec![0; CHUNK_SIZE + keep];
+    let mut filled = 0;
+    loop {
+        let read = reader.read(&mut buf[filled..])?;
+        if read == 0 {
+            return Ok(false);
+        }
+        filled += read;
+        if finder.find(&buf[..filled]).is_some() {
+            return Ok(true);
+        }
+        let tail = filled.saturating_sub(keep);
+        buf.copy_within(tail..filled, 0);
+        filled -= tail;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_contains_across_chunks() {
+        let finder = memmem::Finder::new(b"needle");
+        let mut haystack = vec![b'x'; CHUNK_SIZE - 3];
+        haystack.extend_from_slice(b"needle");
+        haystack.extend(vec![b'y'; 100]);
+        assert!(stream_contains(haystack.as_slice(), &finder).unwrap());
+        assert!(!stream_contains(vec![b'x'; 3 * CHUNK_SIZE].as_slice(), &finder).unwrap());
+        assert!(!stream_contains(&b"needl"[..], &finder).unwrap());
+    }
+
+    #[test]
+    fn test_substitution_table() {
+        let pairs: Vec<(String, String)> = [
+            ("getUser", "fetchUser"),
+            ("getUserName", "fetchUserName"),
+            ("id", "key"),
+        ]
+        .iter()
+        .map(|(p, r)| (p.to_string(), r.to_string()))
+        .collect();
+        let table = Substitution::new(&Change::MultiSub(pairs)).unwrap().unwrap();
+        assert_eq!(
+            table.apply("getUserName(id); getUser(id)").as_deref(),
+            Some("fetchUserName(key); fetchUser(key)")
+        );
+        assert_eq!(table.apply("nothing here"), None);
+
+        let empty = vec![(String::new(), "x".to_string())];
+        assert!(Substitution::new(&Change::MultiSub(empty)).is_err());
+        assert!(Substitution::new(&Change::Regex("(".to_string(), String::new())).is_err());
+        assert!(Substitution::new(&Change::Delete).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_prefilter_may_match() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let hit = dir.path().join("hit.yml");
+        let miss = dir.path().join("miss.yml");
+        std::fs::write(&hit, "image: node:18\n").unwrap();
+        std::fs::write(&miss, "image: node:20\n").unwrap();
+
+        let sub = Prefilter::new(&Change::Sub("node:18".to_string(), "node:20".to_string()));
+        assert!(sub.may_match(&hit));
+        assert!(!sub.may_match(&miss));
+        assert!(sub.may_match(&dir.path().join("missing.yml")));
+
+        let regex = Prefilter::new(&Change::Regex(r"node:1\d".to_string(), "node:20".to_string()));
+        assert!(regex.may_match(&hit));
+        assert!(!regex.may_match(&miss));
+        assert!(Prefilter::new(&Change::Regex("(".to_string(), String::new())).may_match(&miss));
+    }
+}
@@ -0,0 +1,1327 @@
+// src/set.rs
+
+use eyre::{eyre, Context, Result};
+use glob::Pattern;
+use itertools::Itertools;
+use log::{debug, info, warn};
+use rayon::prelude::*;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::diff::DiffStat;
+use crate::git;
+use crate::pool;
+use crate::remote;
+use crate::repo::{self, Change, CreateOptions, CreateResult, Prepared, Repo, RepoOverride, ReviewOp};
+
+/// Content predicates narrowing the files a change applies to, e.g. only the files that still
+/// reference the version being replaced.
+#[derive(Debug, Clone, Default)]
+pub struct ContentFilter {
+    /// Keep only files whose content matches.
+    pub only_if: Option<Regex>,
+    /// Drop files whose content matches.
+    pub skip_if: Option<Regex>,
+}
+
+impl ContentFilter {
+    pub fn is_empty(&self) -> bool {
+        self.only_if.is_none() && self.skip_if.is_none()
+    }
+
+    /// Whether a file with `content` (`None` when unreadable) passes; an unreadable file
+    /// never satisfies `only_if`.
+    pub fn keeps(&self, content: Option<&str>) -> bool {
+        let only_if = match (&self.only_if, content) {
+            (Some(re), Some(content)) => re.is_match(content),
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        let skip_if = match (&self.skip_if, content) {
+            (Some(re), Some(content)) => re.is_match(content),
+            _ => false,
+        };
+        only_if && !skip_if
+    }
+}
+
+/// Extracts the repository name (the part after '/') from a reposlug.
+/// If the reposlug is not in the expected format, returns the full string.
+fn extract_reponame(reposlug: &str) -> &str {
+    reposlug.split('/').nth(1).unwrap_or(reposlug)
+}
+
+static PROTECTED: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Sets the repo patterns (name or org/name globs) slam never modifies. Until this is called
+/// no repo is protected.
+pub fn configure_protected(ptns: Vec<String>) {
+    if PROTECTED.set(ptns).is_err() {
+        warn!("Protected repos already configured; ignoring");
+    }
+}
+
+fn protected() -> &'static [String] {
+    PROTECTED.get().map(Vec::as_slice).unwrap_or_default()
+}
+
+/// Splits off the repos matching any of the `protected` patterns, returning the others and
+/// the reposlugs of the protected ones.
+fn split_protected(repos: Vec<Repo>, protected: &[String]) -> (Vec<Repo>, Vec<String>) {
+    let mut skipped = Vec::new();
+    let repos = repos
+        .into_iter()
+        .filter(
+            |r| match protected.iter().find(|ptn| repo_matches_ptn(&r.reposlug, ptn)) {
+                Some(ptn) => {
+                    warn!("Skipping protected repo '{}' (matches '{}')", r.reposlug, ptn);
+                    skipped.push(r.reposlug.clone());
+                    false
+                }
+                None => true,
+            },
+        )
+        .collect();
+    (repos, skipped)
+}
+
+/// `protected` reposlugs with the reason `RepoSet::skipped` gives for them.
+fn protected_skips(protected: &[String]) -> impl Iterator<Item = (String, String)> + '_ {
+    protected
+        .iter()
+        .map(|reposlug| (reposlug.clone(), "protected".to_string()))
+}
+
+fn without_protected(repos: Vec<Repo>, protected: &[String]) -> Vec<Repo> {
+    split_protected(repos, protected).0
+}
+
+/// Filters the given vector of repositories according to a list of filtering specifications.
+/// The filter criteria are applied in the following order:
+/// 1. Exact match on the repository name (the part after '/')
+/// 2. Starts-with match on the repository name
+/// 3. Exact match on the full reposlug ("org/reponame")
+/// 4. Starts-with match on the full reposlug
+///
+/// At the first level where one or more repositories match, those matches are used.
+/// Finally, the resulting list is sorted by reposlug using itertools.
+fn filter_repos_by_spec(repos: Vec<Repo>, specs: &[String]) -> Vec<Repo> {
+    let filtered: Vec<Repo> = if specs.is_empty() {
+        repos
+    } else {
+        // Level 1: Exact match on repository name.
+        let level1: Vec<Repo> = repos
+            .iter()
+            .filter(|r| specs.iter().any(|spec| extract_reponame(&r.reposlug) == spec))
+            .cloned()
+            .collect();
+        if !level1.is_empty() {
+            level1
+        } else {
+            // Level 2: Starts-with match on repository name.
+            let level2: Vec<Repo> = repos
+                .iter()
+                .filter(|r| specs.iter().any(|spec| extract_reponame(&r.reposlug).starts_with(spec)))
+                .cloned()
+                .collect();
+            if !level2.is_empty() {
+                level2
+            } else {
+                // Level 3: Exact match on full reposlug.
+                let level3: Vec<Repo> = repos.iter().filter(|r| specs.contains(&r.reposlug)).cloned().collect();
+                if !level3.is_empty() {
+                    level3
+                } else {
+                    // Level 4: Starts-with match on full reposlug.
+                    repos
+                        .iter()
+                        .filter(|r| specs.iter().any(|spec| r.reposlug.starts_with(spec)))
+                        .cloned()
+                        .collect()
+                }
+            }
+        }
+    };
+
+    filtered
+        .into_iter()
+        .sorted_by(|a, b| a.reposlug.cmp(&b.reposlug))
+        .collect()
+}
+
+/// Returns true if `ptn` matches the reposlug, either as a glob against the full
+/// "org/reponame" slug or against the repository name alone.
+fn repo_matches_ptn(reposlug: &str, ptn: &str) -> bool {
+    match Pattern::new(ptn) {
+        Ok(pattern) => pattern.matches(reposlug) || pattern.matches(extract_reponame(reposlug)),
+        Err(_) => reposlug == ptn || extract_reponame(reposlug) == ptn,
+    }
+}
+
+/// Narrows the repos found for a change-id to those selected by the action's
+/// `--repo` patterns (all repos if none given), minus any matching `--exclude` and
+/// the protected ones.
+fn select_repos_for_action(repos: Vec<Repo>, include: &[String], exclude: &[String]) -> Vec<Repo> {
+    without_protected(repos, protected())
+        .into_iter()
+        .filter(|r| include.is_empty() || include.iter().any(|ptn| repo_matches_ptn(&r.reposlug, ptn)))
+        .filter(|r| {
+            let excluded = exclude.iter().any(|ptn| repo_matches_ptn(&r.reposlug, ptn));
+            if excluded {
+                info!("Excluding '{}' from {} by request", r.reposlug, r.change_id);
+            }
+            !excluded
+        })
+        .collect()
+}
+
+/// Reposlugs in `org` that pass `filter` and match any of the glob `ptns` (all when empty).
+pub fn org_reposlugs(org: &str, filter: &git::RepoFilter, ptns: &[String]) -> Result<Vec<String>> {
+    let all_reposlugs = git::find_repos_in_org(org, filter)?;
+    info!("Found {} repos in '{}'", all_reposlugs.len(), org);
+
+    let filtered_reposlugs: Vec<String> = if ptns.iter().all(|s| s.trim().is_empty()) {
+        all_reposlugs
+    } else {
+        all_reposlugs
+            .into_iter()
+            .filter(|repo| {
+                ptns.iter()
+                    .any(|ptn| Pattern::new(ptn).is_ok_and(|pattern| pattern.matches(repo)))
+            })
+            .collect()
+    };
+    info!("After filtering, {} repos remain", filtered_reposlugs.len());
+    debug!("Filtered repository slugs: {:?}", filtered_reposlugs);
+    Ok(filtered_reposlugs)
+}
+
+/// Every PR of `reposlugs`, in any state, whose branch starts with any of `change_id_ptns`, or
+/// with `branch_prefix` when none are given, listed in parallel on the gh pool. Repos whose PRs
+/// can't be listed are left out with a warning.
+pub fn pr_history(
+    reposlugs: &[String],
+    change_id_ptns: &[String],
+    branch_prefix: &str,
+) -> Vec<(String, git::PrRecord)> {
+    let matched = |branch: &str| {
+        if change_id_ptns.is_empty() {
+            branch.starts_with(branch_prefix)
+        } else {
+            change_id_ptns.iter().any(|pattern| branch.starts_with(pattern))
+        }
+    };
+    let listings: Vec<Vec<(String, git::PrRecord)>> = pool::gh(|| {
+        reposlugs
+            .par_iter()
+            .map(|reposlug| match git::list_all_prs(reposlug) {
+                Ok(prs) => prs
+                    .into_iter()
+                    .filter(|pr| matched(&pr.head_ref_name))
+                    .map(|pr| (reposlug.clone(), pr))
+                    .collect(),
+                Err(e) => {
+                    warn!("Skipping '{}': {}", reposlug, e);
+                    Vec::new()
+                }
+            })
+            .collect()
+    });
+    listings.into_iter().flatten().collect()
+}
+
+/// The local clones under a sandbox root that a change is applied to, sorted by reposlug, or
+/// with `RepoSet::remote` the repos on GitHub themselves.
+#[derive(Debug, Clone)]
+pub struct RepoSet {
+    root: PathBuf,
+    repos: Vec<Repo>,
+    /// Repos `filter_by_spec` matched but left out as protected.
+    protected: Vec<String>,
+    /// Repos dropped along the way, each with the reason.
+    skipped: Vec<(String, String)>,
+    /// Work through the GitHub API instead of the clones under `root`.
+    remote: bool,
+}
+
+impl RepoSet {
+    /// Finds every git repository under `root` and prepares it for `change`. With `file_ptns`,
+    /// each repo records the files matching them (generated files only with `include_generated`).
+    pub fn discover(
+        root: &Path,
+        change: &Option<Change>,
+        file_ptns: &[String],
+        change_id: &str,
+        include_generated: bool,
+    ) -> Result<Self> {
+        let mut repos = Vec::new();
+        let mut skipped = Vec::new();
+        for path in git::find_git_repositories(root)? {
+            match Repo::create_repo_from_local(&path, root, change, file_ptns, change_id, include_generated) {
+                Ok(repo) => repos.push(repo),
+                Err(e) => {
+                    let reposlug = git::local_reposlug(&path, root);
+                    warn!("Skipping '{}': {}", reposlug, e);
+                    skipped.push((reposlug, e.to_string()));
+                }
+            }
+        }
+        Ok(Self {
+            root: root.to_path_buf(),
+            repos,
+            protected: Vec::new(),
+            skipped,
+            remote: false,
+        })
+    }
+
+    /// The repos of `reposlugs` selected by `specs` (as for `filter_by_spec`), prepared for
+    /// `change` from their default branches on GitHub, without local clones. File patterns are
+    /// matched against each repo's tree; repos whose tree can't be listed are skipped.
+    pub fn remote(
+        reposlugs: &[String],
+        specs: &[String],
+        change: &Option<Change>,
+        file_ptns: &[String],
+        change_id: &str,
+        include_generated: bool,
+    ) -> Self {
+        let candidates = reposlugs
+            .iter()
+            .map(|reposlug| Repo {
+                reposlug: reposlug.clone(),
+                dir: None,
+                change_id: change_id.to_string(),
+                change: change.clone(),
+                files: Vec::new(),
+                pr_number: 0,
+            })
+            .collect();
+        let (selected, protected) = split_protected(filter_repos_by_spec(candidates, specs), protected());
+        let selected: Vec<String> = selected.into_iter().map(|repo| repo.reposlug).collect();
+        let (mut repos, mut skipped) = remote::discover(&selected, change, file_ptns, change_id, include_generated);
+        repos.sort_by(|a, b| a.reposlug.cmp(&b.reposlug));
+        skipped.extend(protected_skips(&protected));
+        Self {
+            root: PathBuf::new(),
+            repos,
+            protected,
+            skipped,
+            remote: true,
+        }
+    }
+
+    /// Whether the set works through the GitHub API rather than local clones.
+    pub fn is_remote(&self) -> bool {
+        self.remote
+    }
+
+    /// Keeps the repos selected by `specs` (see `filter_repos_by_spec`), all repos if empty,
+    /// except the protected ones; those are listed by `protected`.
+    pub fn filter_by_spec(self, specs: &[String]) -> Self {
+        let (repos, protected) = split_protected(filter_repos_by_spec(self.repos, specs), protected());
+        let mut skipped = self.skipped;
+        skipped.extend(protected_skips(&protected));
+        Self {
+            repos,
+            root: self.root,
+            protected,
+            skipped,
+            remote: self.remote,
+        }
+    }
+
+    /// The repos the last `filter_by_spec` left out because they are protected.
+    pub fn protected(&self) -> &[String] {
+        &self.protected
+    }
+
+    /// Every repo left out so far, in the order they were dropped, with the reason: those that
+    /// couldn't be read, protected ones, override skips and those without the required or
+    /// matching files.
+    pub fn skipped(&self) -> &[(String, String)] {
+        &self.skipped
+    }
+
+    /// Keeps the repos `skip_reason` returns `None` for and records the others as skipped with
+    /// the reason it returned.
+    fn retain_or_skip(&mut self, mut skip_reason: impl FnMut(&Repo) -> Option<String>) {
+        let skipped = &mut self.skipped;
+        self.repos.retain(|repo| match skip_reason(repo) {
+            Some(reason) => {
+                debug!("Skipping '{}': {}", repo.reposlug, reason);
+                skipped.push((repo.reposlug.clone(), reason));
+                false
+            }
+            None => true,
+        });
+    }
+
+    /// Adjusts each repo by the first of `overrides` whose pattern (as for `--repo`) matches it:
+    /// skipped repos are dropped and returned with the reason (and recorded in `skipped`); the
+    /// others get the override's file patterns and replacement in place of the run's.
+    pub fn apply_overrides(
+        &mut self,
+        overrides: &[(String, RepoOverride)],
+        include_generated: bool,
+    ) -> Result<Vec<(String, String)>> {
+        let mut skipped = Vec::new();
+        let mut repos = Vec::with_capacity(self.repos.len());
+        for repo in std::mem::take(&mut self.repos) {
+            let Some((ptn, ovr)) = overrides.iter().find(|(ptn, _)| repo_matches_ptn(&repo.reposlug, ptn)) else {
+                repos.push(repo);
+                continue;
+            };
+            debug!("Override '{}' applies to '{}'", ptn, repo.reposlug);
+            if ovr.skip {
+                let reason = ovr
+                    .reason
+                    .clone()
+                    .unwrap_or_else(|| format!("skipped by override '{}'", ptn));
+                skipped.push((repo.reposlug, reason));
+                continue;
+            }
+            let change = match (&repo.change, &ovr.replacement) {
+                (Some(change), Some(replacement)) => Some(
+                    change
+                        .with_replacement(replacement)
+                        .wrap_err_with(|| format!("Invalid override '{}' for '{}'", ptn, repo.reposlug))?,
+                ),
+                (change, _) => change.clone(),
+            };
+            match &ovr.files {
+                Some(file_ptns) if self.remote => {
+                    match remote::repo_with_files(
+                        &repo.reposlug,
+                        &change,
+                        file_ptns,
+                        &repo.change_id,
+                        include_generated,
+                    ) {
+                        Ok(repo) => repos.push(repo),
+                        Err(e) => skipped.push((repo.reposlug, e.to_string())),
+                    }
+                }
+                Some(file_ptns) => match Repo::create_repo_from_local(
+                    &repo.path(&self.root),
+                    &self.root,
+                    &change,
+                    file_ptns,
+                    &repo.change_id,
+                    include_generated,
+                ) {
+                    Ok(repo) => repos.push(repo),
+                    Err(e) => skipped.push((repo.reposlug, e.to_string())),
+                },
+                None => repos.push(Repo { change, ..repo }),
+            }
+        }
+        self.repos = repos;
+        self.skipped.extend(skipped.iter().cloned());
+        Ok(skipped)
+    }
+
+    /// Keeps the repos whose clone has the change's branch staged (see `CreateOptions::stage`).
+    pub fn retain_staged(&mut self, branch_prefix: &str) {
+        let root = self.root.clone();
+        self.repos.retain(|repo| {
+            let branch = repo::normalize_change_id(&repo.change_id, branch_prefix);
+            git::branch_exists(&repo.path(&root), &format!("refs/heads/{}", branch)).unwrap_or(false)
+        });
+    }
+
+    /// Keeps the repos that have, for every glob in `ptns`, a file or directory matching it
+    /// (e.g. `Dockerfile`, `.github/workflows/`), whatever files the change edits. A remote
+    /// repo whose tree can't be listed is skipped too.
+    pub fn retain_with_required_files(&mut self, ptns: &[String]) -> Result<()> {
+        if ptns.is_empty() {
+            return Ok(());
+        }
+        if self.remote {
+            let reasons = pool::gh(|| {
+                self.repos
+                    .par_iter()
+                    .map(|repo| match remote::has_paths(&repo.reposlug, ptns) {
+                        Ok(true) => None,
+                        Ok(false) => Some(format!("missing a required file ({})", ptns.join(", "))),
+                        Err(e) => {
+                            warn!("Skipping '{}': {}", repo.reposlug, e);
+                            Some(e.to_string())
+                        }
+                    })
+                    .collect::<Vec<Option<String>>>()
+            });
+            let mut reasons = reasons.into_iter();
+            self.retain_or_skip(|_| reasons.next().flatten());
+            return Ok(());
+        }
+        let patterns = ptns
+            .iter()
+            .map(|ptn| ptn.trim_end_matches('/'))
+            .map(|ptn| {
+                Pattern::new(ptn)
+                    .map(|_| ptn)
+                    .wrap_err_with(|| format!("Invalid file pattern '{}'", ptn))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let root = self.root.clone();
+        self.retain_or_skip(|repo| {
+            let repo_path = repo.path(&root);
+            patterns
+                .iter()
+                .find(|ptn| {
+                    !glob::glob(&repo_path.join(ptn).to_string_lossy())
+                        .map(|mut paths| paths.any(|path| path.is_ok()))
+                        .unwrap_or(false)
+                })
+                .map(|ptn| format!("missing a required file ({})", ptn))
+        });
+        Ok(())
+    }
+
+    /// Keeps the matched files whose content passes `filter`, read from the clones or, for a
+    /// remote set, from the default branches. Repos whose matched files all fail the filter, and
+    /// remote repos whose files can't be read, are skipped.
+    pub fn filter_files_by_content(&mut self, filter: &ContentFilter) {
+        if filter.is_empty() {
+            return;
+        }
+        let root = self.root.clone();
+        let remote = self.remote;
+        let filter_repo = |repo: &mut Repo| -> Option<String> {
+            if repo.files.is_empty() {
+                return None;
+            }
+            let contents = if remote {
+                match remote::read_files(&repo.reposlug, &repo.files) {
+                    Ok(contents) => contents,
+                    Err(e) => {
+                        warn!("Skipping '{}': {}", repo.reposlug, e);
+                        return Some(e.to_string());
+                    }
+                }
+            } else {
+                let repo_path = repo.path(&root);
+                repo.files
+                    .iter()
+                    .map(|file| std::fs::read_to_string(repo_path.join(file)).ok())
+                    .collect()
+            };
+            let files = std::mem::take(&mut repo.files);
+            repo.files = files
+                .into_iter()
+                .zip(contents)
+                .filter(|(_, content)| filter.keeps(content.as_deref()))
+                .map(|(file, _)| file)
+                .collect();
+            debug!(
+                "{} file(s) of '{}' pass the content filter",
+                repo.files.len(),
+                repo.reposlug
+            );
+            repo.files
+                .is_empty()
+                .then(|| "no matched file passes the content filter".to_string())
+        };
+        let reasons: Vec<Option<String>> = if remote {
+            pool::gh(|| self.repos.par_iter_mut().map(filter_repo).collect())
+        } else {
+            self.repos.iter_mut().map(filter_repo).collect()
+        };
+        let mut reasons = reasons.into_iter();
+        self.retain_or_skip(|_| reasons.next().flatten());
+    }
+
+    /// Leaves the matched files git doesn't track out of each clone's change, since `Repo::create`
+    /// works on a fresh checkout that lacks them; repos matching only such files are skipped.
+    /// A no-op for a remote set.
+    pub fn exclude_untracked_files(&mut self) {
+        if self.remote {
+            return;
+        }
+        let root = self.root.clone();
+        let reasons: Vec<Option<String>> = self
+            .repos
+            .iter_mut()
+            .map(|repo| {
+                let untracked = match git::untracked_paths(&repo.path(&root), &repo.files) {
+                    Ok(untracked) => untracked,
+                    Err(e) => {
+                        warn!("Keeping the matched files of '{}' as they are: {}", repo.reposlug, e);
+                        return None;
+                    }
+                };
+                if untracked.is_empty() {
+                    return None;
+                }
+                info!("Leaving untracked {:?} out of '{}'", untracked, repo.reposlug);
+                repo.files.retain(|file| !untracked.contains(file));
+                repo.files
+                    .is_empty()
+                    .then(|| "only untracked files match (--include-untracked commits them)".to_string())
+            })
+            .collect();
+        let mut reasons = reasons.into_iter();
+        self.retain_or_skip(|_| reasons.next().flatten());
+    }
+
+    /// Drops repos in which no file matched the file patterns, recording them as skipped.
+    pub fn retain_with_files(&mut self) {
+        self.retain_or_skip(|repo| {
+            repo.files
+                .is_empty()
+                .then(|| "no file matches the file patterns".to_string())
+        });
+    }
+
+    /// Keeps the repos in `picked` (reposlugs chosen by hand); the others are skipped.
+    pub fn retain_picked(&mut self, picked: &[String]) {
+        self.retain_or_skip(|repo| (!picked.contains(&repo.reposlug)).then(|| "not picked".to_string()));
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn repos(&self) -> &[Repo] {
+        &self.repos
+    }
+
+    pub fn len(&self) -> usize {
+        self.repos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.repos.is_empty()
+    }
+}
+
+/// One change applied across a `RepoSet`: per repo a branch, commit, push and PR, or a
+/// rolled-back dry run when no commit message is set.
+#[derive(Debug)]
+pub struct ChangeRun<'a> {
+    repos: &'a RepoSet,
+    opts: CreateOptions,
+}
+
+impl<'a> ChangeRun<'a> {
+    pub fn new(repos: &'a RepoSet, opts: CreateOptions) -> Self {
+        Self { repos, opts }
+    }
+
+    pub fn options(&self) -> &CreateOptions {
+        &self.opts
+    }
+
+    /// Checks every repo's rendered commit message is a Conventional Commit, so a malformed
+    /// message is caught before any repository is touched rather than once per repo. The files
+    /// a commit touches are only known once its change is applied, so a header using `{{files}}`
+    /// can't be checked and is refused.
+    pub fn validate_conventional(&self) -> Result<()> {
+        if self.opts.commit_msg.is_none() {
+            return Ok(());
+        }
+        let template = self
+            .opts
+            .commit_template
+            .as_deref()
+            .unwrap_or(repo::DEFAULT_COMMIT_TEMPLATE);
+        let header = template.lines().next().unwrap_or_default();
+        if Regex::new(r"\{\{\s*files\s*\}\}").unwrap().is_match(header) {
+            return Err(eyre!(
+                "The commit template's first line uses {{{{files}}}}, so it can't be checked as a Conventional Commit"
+            ));
+        }
+        for repo in self.repos.repos() {
+            let change_id = repo::normalize_change_id(&repo.change_id, &self.opts.branch_prefix);
+            let msg = repo.render_commit_msg(&self.opts, &change_id, &repo.files);
+            repo::validate_conventional_commit(&msg)
+                .wrap_err_with(|| format!("Invalid commit message for '{}'", repo.reposlug))?;
+        }
+        Ok(())
+    }
+
+    /// Repos whose remote already has the change's branch, which `run` will replace; none
+    /// when this is a dry run or only stages.
+    pub fn existing_branches(&self) -> Vec<&Repo> {
+        if self.opts.commit_msg.is_none() || self.opts.stage {
+            return Vec::new();
+        }
+        self.branches_on_remote()
+    }
+
+    /// Repos whose remote already has the change's branch, e.g. ones `publish_each` will replace.
+    pub fn branches_on_remote(&self) -> Vec<&Repo> {
+        let root = self.repos.root();
+        let exists = |repo: &Repo| {
+            let branch = repo::normalize_change_id(&repo.change_id, &self.opts.branch_prefix);
+            if self.repos.is_remote() {
+                remote::branch_exists(&repo.reposlug, &branch)
+            } else {
+                // A clone that hasn't pushed to its fork yet has no fork remote, hence no collision.
+                let remote = if self.opts.fork { git::FORK_REMOTE } else { "origin" };
+                git::remote_branch_exists(&repo.path(root), remote, &branch)
+            }
+            .unwrap_or(false)
+        };
+        let all = || self.repos.repos().par_iter().map(exists).collect::<Vec<bool>>();
+        let exists = if self.repos.is_remote() {
+            pool::gh(all)
+        } else {
+            pool::git(all)
+        };
+        self.repos
+            .repos()
+            .iter()
+            .zip(exists)
+            .filter_map(|(repo, exists)| exists.then_some(repo))
+            .collect()
+    }
+
+    /// Applies the change to every repo in parallel on the git pool (gh calls are handed off
+    /// to the gh pool; a remote set runs on the gh pool throughout) and returns the results in
+    /// the order of `RepoSet::repos`.
+    pub fn run(&self) -> Vec<CreateResult> {
+        self.run_each(|_, _| {})
+    }
+
+    /// As `run`, also calling `on_done` with each repo's result as soon as it is in. A set of
+    /// clones goes through `prepare` and `apply_each` in turn, so no repo is pushed before every
+    /// repo has been diffed.
+    pub fn run_each(&self, on_done: impl Fn(&Repo, &CreateResult) + Sync) -> Vec<CreateResult> {
+        if !self.repos.is_remote() {
+            return self.apply_each(self.prepare(), on_done);
+        }
+        pool::gh(|| {
+            self.repos
+                .repos()
+                .par_iter()
+                .map(|repo| {
+                    let result = remote::create(repo, &self.opts);
+                    on_done(repo, &result);
+                    result
+                })
+                .collect()
+        })
+    }
+
+    /// The first phase of a run over clones: `Repo::prepare` for every repo in parallel on the
+    /// git pool, without fetching, committing or pushing, so each repo's diff and any failure
+    /// are known up front. In the order of `RepoSet::repos`.
+    pub fn prepare(&self) -> Vec<Prepared> {
+        let root = self.repos.root();
+        pool::git(|| {
+            self.repos
+                .repos()
+                .par_iter()
+                .map(|repo| repo.prepare(root, &self.opts))
+                .collect()
+        })
+    }
+
+    /// The second phase: `Repo::apply` for the repos `prepare` found ready, on the git pool so
+    /// no more repos than its size fetch, push and open PRs at once; the others keep the result
+    /// `prepare` gave them. Calls `on_done` with each result as soon as it is in.
+    pub fn apply_each(
+        &self,
+        prepared: Vec<Prepared>,
+        on_done: impl Fn(&Repo, &CreateResult) + Sync,
+    ) -> Vec<CreateResult> {
+        let root = self.repos.root();
+        pool::git(|| {
+            self.repos
+                .repos()
+                .par_iter()
+                .zip(prepared)
+                .map(|(repo, prepared)| {
+                    let result = match prepared {
+                        Prepared::Done(result) => result,
+                        Prepared::Ready { .. } => repo.apply(root, &self.opts),
+                    };
+                    on_done(repo, &result);
+                    result
+                })
+                .collect()
+        })
+    }
+
+    /// Pushes the branches `run` staged and opens their PRs, in parallel as `run_each` does,
+    /// calling `on_done` with each repo's result.
+    pub fn publish_each(&self, on_done: impl Fn(&Repo, &CreateResult) + Sync) -> Vec<CreateResult> {
+        let root = self.repos.root();
+        pool::git(|| {
+            self.repos
+                .repos()
+                .par_iter()
+                .map(|repo| {
+                    let result = repo.publish(root, &self.opts);
+                    on_done(repo, &result);
+                    result
+                })
+                .collect()
+        })
+    }
+}
+
+/// Open PRs for one or more change ids across a set of GitHub repos.
+#[derive(Debug, Clone, Default)]
+pub struct ReviewSet {
+    repos: Vec<Repo>,
+}
+
+/// Compiles the `--path` globs a review diff is limited to.
+pub fn path_patterns(paths: &[String]) -> Result<Vec<Pattern>> {
+    paths
+        .iter()
+        .map(|path| Pattern::new(path).wrap_err_with(|| format!("Invalid path pattern '{}'", path)))
+        .collect()
+}
+
+impl ReviewSet {
+    /// PRs whose branch starts with any of `change_id_ptns`, or with `branch_prefix` when none
+    /// are given.
+    pub fn matching(reposlugs: Vec<String>, change_id_ptns: &[String], branch_prefix: &str) -> Result<Self> {
+        let mut repos = Vec::new();
+        for (branch, pr_list) in &git::get_prs_for_repos(reposlugs)? {
+            let matched = if change_id_ptns.is_empty() {
+                branch.starts_with(branch_prefix)
+            } else {
+                change_id_ptns.iter().any(|pattern| branch.starts_with(pattern))
+            };
+            if matched {
+                for (reposlug, pr_number, _author) in pr_list {
+                    repos.push(Repo::create_repo_from_remote_with_pr(reposlug, branch, *pr_number));
+                }
+            }
+        }
+        Ok(Self { repos })
+    }
+
+    /// PRs opened from exactly `change_id`, which must already carry the branch prefix.
+    pub fn for_change(reposlugs: Vec<String>, change_id: &str) -> Result<Self> {
+        let all_prs = git::get_prs_for_repos(reposlugs)?;
+        let repos = all_prs
+            .get(change_id)
+            .map(|pr_list| {
+                pr_list
+                    .iter()
+                    .map(|(reposlug, pr_number, _author)| {
+                        Repo::create_repo_from_remote_with_pr(reposlug, change_id, *pr_number)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Self { repos })
+    }
+
+    /// Every unprotected repo in `reposlugs`, for purging the branches carrying `branch_prefix`.
+    pub fn purge(reposlugs: &[String], branch_prefix: &str) -> Self {
+        let repos = reposlugs
+            .iter()
+            .map(|reposlug| Repo::create_repo_from_remote_with_pr(reposlug, branch_prefix, 0))
+            .collect();
+        Self {
+            repos: without_protected(repos, protected()),
+        }
+    }
+
+    /// Keeps the repos matching any `include` glob (all if empty) and none of the `exclude` globs.
+    pub fn select(self, include: &[String], exclude: &[String]) -> Self {
+        Self {
+            repos: select_repos_for_action(self.repos, include, exclude),
+        }
+    }
+
+    /// Keeps the PRs on the milestone titled `milestone`; PRs whose status cannot be fetched go too.
+    pub fn on_milestone(self, milestone: &str) -> Self {
+        let statuses = self.statuses();
+        let repos = self
+            .repos
+            .into_iter()
+            .zip(statuses)
+            .filter(|(_, status)| status.as_ref().and_then(|s| s.milestone.as_deref()) == Some(milestone))
+            .map(|(repo, _)| repo)
+            .collect();
+        Self { repos }
+    }
+
+    pub fn repos(&self) -> &[Repo] {
+        &self.repos
+    }
+
+    pub fn into_repos(self) -> Vec<Repo> {
+        self.repos
+    }
+
+    pub fn len(&self) -> usize {
+        self.repos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.repos.is_empty()
+    }
+
+    /// The `page`th (from 1) run of `limit` PRs, ordered by repo.
+    pub fn page(mut self, limit: usize, page: usize) -> Self {
+        self.repos
+            .sort_by(|a, b| (&a.reposlug, a.pr_number).cmp(&(&b.reposlug, b.pr_number)));
+        let repos = self
+            .repos
+            .into_iter()
+            .skip(limit * page.saturating_sub(1))
+            .take(limit)
+            .collect();
+        Self { repos }
+    }
+
+    /// Each PR's rendered diff and diffstat, fetched in parallel on the gh pool, limited to the
+    /// files matching one of the glob `paths` (all files when empty).
+    pub fn diffs(&self, buffer: usize, paths: &[String]) -> Result<Vec<(String, DiffStat)>> {
+        let paths = path_patterns(paths)?;
+        Ok(pool::gh(|| {
+            self.repos
+                .par_iter()
+                .map(|repo| repo.get_review_diff_matching(buffer, &paths))
+                .collect()
+        }))
+    }
+
+    /// Each PR as a `git am` mailbox (see `git::get_pr_patch`), fetched in parallel on the gh pool.
+    pub fn patches(&self) -> Vec<Result<String>> {
+        pool::gh(|| {
+            self.repos
+                .par_iter()
+                .map(|repo| git::get_pr_patch(&repo.reposlug, repo.pr_number, &repo.change_id))
+                .collect()
+        })
+    }
+
+    /// The status of every PR, fetched in parallel; `None` where it could not be fetched.
+    pub fn statuses(&self) -> Vec<Option<git::PrStatus>> {
+        pool::gh(|| {
+            self.repos
+                .par_iter()
+                .map(|repo| {
+                    git::get_pr_status(&repo.reposlug, repo.pr_number)
+                        .map_err(|e| warn!("Failed to get status for '{}': {}", repo.reposlug, e))
+                        .ok()
+                })
+                .collect()
+        })
+    }
+
+    /// Runs `op` on each PR in turn; see `Repo::review`.
+    pub fn review(&self, root: &Path, op: &ReviewOp, summary: bool) -> Vec<Result<String>> {
+        self.repos.iter().map(|repo| repo.review(root, op, summary)).collect()
+    }
+
+    /// Runs `op` on every PR in parallel on the git pool, for local work such as cloning.
+    pub fn review_parallel(&self, root: &Path, op: &ReviewOp) -> Vec<Result<String>> {
+        pool::git(|| self.repos.par_iter().map(|repo| repo.review(root, op, false)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Helper function to create test repos
+    fn create_test_repo(reposlug: &str) -> Repo {
+        Repo {
+            reposlug: reposlug.to_string(),
+            dir: None,
+            change_id: "test-change".to_string(),
+            change: None,
+            files: vec![],
+            pr_number: 0,
+        }
+    }
+
+    #[test]
+    fn test_extract_reponame() {
+        assert_eq!(extract_reponame("org/repo"), "repo");
+        assert_eq!(extract_reponame("tatari-tv/frontend"), "frontend");
+        assert_eq!(extract_reponame("single"), "single");
+        assert_eq!(extract_reponame(""), "");
+        assert_eq!(extract_reponame("a/b/c"), "b"); // Only gets first split
+    }
+
+    #[test]
+    fn test_extract_reponame_edge_cases() {
+        assert_eq!(extract_reponame("/repo"), "repo");
+        assert_eq!(extract_reponame("org/"), "");
+        assert_eq!(extract_reponame("/"), "");
+    }
+
+    #[test]
+    fn test_filter_repos_by_spec_empty() {
+        let repos = vec![create_test_repo("org/repo1"), create_test_repo("org/repo2")];
+
+        let result = filter_repos_by_spec(repos.clone(), &[]);
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].reposlug, "org/repo1");
+        assert_eq!(result[1].reposlug, "org/repo2");
+    }
+
+    #[test]
+    fn test_filter_repos_by_spec_exact_match() {
+        let repos = vec![
+            create_test_repo("org/frontend"),
+            create_test_repo("org/backend"),
+            create_test_repo("org/mobile"),
+        ];
+
+        let specs = vec!["frontend".to_string()];
+        let result = filter_repos_by_spec(repos, &specs);
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].reposlug, "org/frontend");
+    }
+
+    #[test]
+    fn test_filter_repos_by_spec_starts_with() {
+        let repos = vec![
+            create_test_repo("org/frontend-web"),
+            create_test_repo("org/frontend-mobile"),
+            create_test_repo("org/backend"),
+        ];
+
+        let specs = vec!["front".to_string()];
+        let result = filter_repos_by_spec(repos, &specs);
+
+        assert_eq!(result.len(), 2);
+        assert!(result.iter().any(|r| r.reposlug == "org/frontend-mobile"));
+        assert!(result.iter().any(|r| r.reposlug == "org/frontend-web"));
+    }
+
+    #[test]
+    fn test_filter_repos_by_spec_full_slug_exact() {
+        let repos = vec![create_test_repo("org1/repo"), create_test_repo("org2/repo")];
+
+        let specs = vec!["org1/repo".to_string()];
+        let result = filter_repos_by_spec(repos, &specs);
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].reposlug, "org1/repo");
+    }
+
+    #[test]
+    fn test_filter_repos_by_spec_full_slug_starts_with() {
+        let repos = vec![
+            create_test_repo("tatari-tv/frontend"),
+            create_test_repo("tatari-tv/backend"),
+            create_test_repo("other-org/frontend"),
+        ];
+
+        let specs = vec!["tatari".to_string()];
+        let result = filter_repos_by_spec(repos, &specs);
+
+        assert_eq!(result.len(), 2);
+        assert!(result.iter().any(|r| r.reposlug == "tatari-tv/backend"));
+        assert!(result.iter().any(|r| r.reposlug == "tatari-tv/frontend"));
+    }
+
+    #[test]
+    fn test_filter_repos_by_spec_multiple_specs() {
+        let repos = vec![
+            create_test_repo("org/frontend"),
+            create_test_repo("org/backend"),
+            create_test_repo("org/mobile"),
+            create_test_repo("org/docs"),
+        ];
+
+        let specs = vec!["frontend".to_string(), "backend".to_string()];
+        let result = filter_repos_by_spec(repos, &specs);
+
+        assert_eq!(result.len(), 2);
+        assert!(result.iter().any(|r| r.reposlug == "org/backend"));
+        assert!(result.iter().any(|r| r.reposlug == "org/frontend"));
+    }
+
+    #[test]
+    fn test_filter_repos_by_spec_sorting() {
+        let repos = vec![
+            create_test_repo("org/zebra"),
+            create_test_repo("org/alpha"),
+            create_test_repo("org/beta"),
+        ];
+
+        let result = filter_repos_by_spec(repos, &[]);
+
+        // Should be sorted alphabetically by reposlug
+        assert_eq!(result[0].reposlug, "org/alpha");
+        assert_eq!(result[1].reposlug, "org/beta");
+        assert_eq!(result[2].reposlug, "org/zebra");
+    }
+
+    #[test]
+    fn test_repo_matches_ptn() {
+        assert!(repo_matches_ptn("tatari-tv/frontend", "frontend"));
+        assert!(repo_matches_ptn("tatari-tv/frontend", "tatari-tv/frontend"));
+        assert!(repo_matches_ptn("tatari-tv/frontend", "front*"));
+        assert!(repo_matches_ptn("tatari-tv/frontend", "tatari-tv/*"));
+        assert!(!repo_matches_ptn("tatari-tv/frontend", "backend"));
+        assert!(!repo_matches_ptn("tatari-tv/frontend", "front"));
+    }
+
+    #[test]
+    fn test_split_protected() {
+        let repos = vec![
+            create_test_repo("org/infrastructure-live"),
+            create_test_repo("org/payments-api"),
+            create_test_repo("org/frontend"),
+        ];
+        let protected = ["*/infrastructure-live".to_string(), "payments*".to_string()];
+        let (result, skipped) = split_protected(repos.clone(), &protected);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].reposlug, "org/frontend");
+        assert_eq!(skipped, ["org/infrastructure-live", "org/payments-api"]);
+        assert_eq!(without_protected(repos, &[]).len(), 3);
+    }
+
+    #[test]
+    fn test_select_repos_for_action_no_patterns() {
+        let repos = vec![create_test_repo("org/frontend"), create_test_repo("org/backend")];
+        let result = select_repos_for_action(repos, &[], &[]);
+        assert_eq!(result.len(), 2);
+    }
+
+    #[test]
+    fn test_select_repos_for_action_exclude() {
+        let repos = vec![
+            create_test_repo("org/frontend"),
+            create_test_repo("org/backend"),
+            create_test_repo("org/mobile"),
+        ];
+        let result = select_repos_for_action(repos, &[], &["backend".to_string()]);
+        assert_eq!(result.len(), 2);
+        assert!(!result.iter().any(|r| r.reposlug == "org/backend"));
+    }
+
+    #[test]
+    fn test_select_repos_for_action_include_and_exclude() {
+        let repos = vec![
+            create_test_repo("org/frontend-web"),
+            create_test_repo("org/frontend-mobile"),
+            create_test_repo("org/backend"),
+        ];
+        let result = select_repos_for_action(repos, &["frontend-*".to_string()], &["*-mobile".to_string()]);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].reposlug, "org/frontend-web");
+    }
+
+    #[test]
+    fn test_repo_set_discover_and_filter() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for slug in ["org/frontend", "org/backend", "org/docs"] {
+            std::fs::create_dir_all(temp_dir.path().join(slug).join(".git")).unwrap();
+        }
+
+        let set = RepoSet::discover(temp_dir.path(), &None, &[], "SLAM-test", false).unwrap();
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.root(), temp_dir.path());
+
+        let set = set.filter_by_spec(&["frontend".to_string(), "docs".to_string()]);
+        let slugs: Vec<&str> = set.repos().iter().map(|r| r.reposlug.as_str()).collect();
+        assert_eq!(slugs, vec!["org/docs", "org/frontend"]);
+    }
+
+    #[test]
+    fn test_filter_files_by_content() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for (slug, content) in [
+            ("org/old", "image: node:18\n"),
+            ("org/new", "image: node:20\n"),
+            ("org/pinned", "image: node:18 # keep\n"),
+        ] {
+            std::fs::create_dir_all(temp_dir.path().join(slug).join(".git")).unwrap();
+            std::fs::write(temp_dir.path().join(slug).join("ci.yml"), content).unwrap();
+        }
+        let files = vec!["*.yml".to_string()];
+        let mut set = RepoSet::discover(temp_dir.path(), &None, &files, "SLAM-test", false).unwrap();
+        set.filter_files_by_content(&ContentFilter {
+            only_if: Some(Regex::new(r"node:18\b").unwrap()),
+            skip_if: Some(Regex::new("# keep").unwrap()),
+        });
+        set.retain_with_files();
+        let slugs: Vec<&str> = set.repos().iter().map(|r| r.reposlug.as_str()).collect();
+        assert_eq!(slugs, vec!["org/old"]);
+        let reason = "no matched file passes the content filter".to_string();
+        assert_eq!(
+            set.skipped(),
+            [
+                ("org/new".to_string(), reason.clone()),
+                ("org/pinned".to_string(), reason)
+            ]
+        );
+
+        let filter = ContentFilter::default();
+        assert!(filter.is_empty());
+        assert!(filter.keeps(None));
+    }
+
+    #[test]
+    fn test_retain_with_required_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for slug in ["org/api", "org/docs", "org/web"] {
+            std::fs::create_dir_all(temp_dir.path().join(slug).join(".git")).unwrap();
+        }
+        std::fs::write(temp_dir.path().join("org/api/Dockerfile"), "FROM scratch\n").unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("org/api/.github/workflows")).unwrap();
+        std::fs::write(temp_dir.path().join("org/web/Dockerfile.prod"), "FROM scratch\n").unwrap();
+
+        let mut set = RepoSet::discover(temp_dir.path(), &None, &[], "SLAM-test", false).unwrap();
+        set.retain_with_required_files(&["Dockerfile*".to_string()]).unwrap();
+        let slugs: Vec<&str> = set.repos().iter().map(|r| r.reposlug.as_str()).collect();
+        assert_eq!(slugs, vec!["org/api", "org/web"]);
+
+        set.retain_with_required_files(&["Dockerfile*".to_string(), ".github/workflows/".to_string()])
+            .unwrap();
+        let slugs: Vec<&str> = set.repos().iter().map(|r| r.reposlug.as_str()).collect();
+        assert_eq!(slugs, vec!["org/api"]);
+        assert_eq!(
+            set.skipped(),
+            [
+                (
+                    "org/docs".to_string(),
+                    "missing a required file (Dockerfile*)".to_string()
+                ),
+                (
+                    "org/web".to_string(),
+                    "missing a required file (.github/workflows)".to_string()
+                ),
+            ]
+        );
+        assert!(set.retain_with_required_files(&["[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_repo_set_apply_overrides() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for slug in ["org/api", "org/legacy", "org/web"] {
+            std::fs::create_dir_all(temp_dir.path().join(slug).join(".git")).unwrap();
+        }
+        std::fs::create_dir_all(temp_dir.path().join("org/web/ci")).unwrap();
+        std::fs::write(temp_dir.path().join("org/web/ci/build.yml"), "v: 1\n").unwrap();
+        let change = Some(Change::Sub("v: 1".to_string(), "v: 2".to_string()));
+        let files = vec!["*.yml".to_string()];
+        let mut set = RepoSet::discover(temp_dir.path(), &change, &files, "SLAM-test", false)
+            .unwrap()
+            .filter_by_spec(&[]);
+
+        let overrides = vec![
+            (
+                "legacy".to_string(),
+                RepoOverride {
+                    skip: true,
+                    reason: Some("frozen".to_string()),
+                    ..Default::default()
+                },
+            ),
+            (
+                "org/web".to_string(),
+                RepoOverride {
+                    files: Some(vec!["ci/*.yml".to_string()]),
+                    replacement: Some("v: 3".to_string()),
+                    ..Default::default()
+                },
+            ),
+        ];
+        let skipped = set.apply_overrides(&overrides, false).unwrap();
+        assert_eq!(skipped, vec![("org/legacy".to_string(), "frozen".to_string())]);
+
+        let slugs: Vec<&str> = set.repos().iter().map(|r| r.reposlug.as_str()).collect();
+        assert_eq!(slugs, vec!["org/api", "org/web"]);
+        let web = &set.repos()[1];
+        assert_eq!(web.files, vec!["ci/build.yml"]);
+        assert!(matches!(&web.change, Some(Change::Sub(_, repl)) if repl == "v: 3"));
+
+        let delete = vec![(
+            "api".to_string(),
+            RepoOverride {
+                replacement: Some("x".to_string()),
+                ..Default::default()
+            },
+        )];
+        let mut set = RepoSet::discover(temp_dir.path(), &Some(Change::Delete), &[], "SLAM-test", false).unwrap();
+        assert!(set.apply_overrides(&delete, false).is_err());
+    }
+
+    #[test]
+    fn test_change_run_validate_conventional() {
+        let mut set = RepoSet {
+            root: PathBuf::from("/nonexistent"),
+            repos: vec![create_test_repo("org/repo")],
+            protected: Vec::new(),
+            skipped: Vec::new(),
+            remote: false,
+        };
+        let opts = |msg: &str| CreateOptions {
+            commit_msg: Some(msg.to_string()),
+            branch_prefix: "SLAM".to_string(),
+            ..Default::default()
+        };
+        assert!(ChangeRun::new(&set, opts("fix(ci): pin runner"))
+            .validate_conventional()
+            .is_ok());
+        assert!(ChangeRun::new(&set, opts("pin runner"))
+            .validate_conventional()
+            .is_err());
+
+        // Every repo's message is checked, not just the first one's.
+        set.repos.push(create_test_repo("org/c++"));
+        let mut per_repo = opts("pin runner");
+        per_repo.commit_template = Some("chore({{reposlug}}): {{message}}".to_string());
+        let err = ChangeRun::new(&set, per_repo.clone())
+            .validate_conventional()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Invalid commit message for 'org/c++'");
+        set.repos.pop();
+        assert!(ChangeRun::new(&set, per_repo.clone()).validate_conventional().is_ok());
+        per_repo.commit_template = Some("chore: {{ files }}".to_string());
+        assert!(ChangeRun::new(&set, per_repo).validate_conventional().is_err());
+
+        // Nothing to check without repos or without a commit message.
+        assert!(ChangeRun::new(&set, CreateOptions::default())
+            .validate_conventional()
+            .is_ok());
+        set.repos.clear();
+        assert!(ChangeRun::new(&set, opts("pin runner")).validate_conventional().is_ok());
+    }
+
+    #[test]
+    fn test_review_set_purge_and_select() {
+        let reposlugs = vec!["org/frontend".to_string(), "org/backend".to_string()];
+        let set = ReviewSet::purge(&reposlugs, "SLAM");
+        assert_eq!(set.len(), 2);
+        assert!(set.repos().iter().all(|r| r.change_id == "SLAM" && r.pr_number == 0));
+
+        let set = set.select(&[], &["backend".to_string()]);
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.into_repos()[0].reposlug, "org/frontend");
+    }
+
+    #[test]
+    fn test_review_set_page() {
+        let reposlugs: Vec<String> = (1..=5).rev().map(|n| format!("org/repo{}", n)).collect();
+        let page = |limit, page| {
+            ReviewSet::purge(&reposlugs, "SLAM")
+                .page(limit, page)
+                .into_repos()
+                .into_iter()
+                .map(|repo| repo.reposlug)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(page(2, 1), ["org/repo1", "org/repo2"]);
+        assert_eq!(page(2, 3), ["org/repo5"]);
+        assert!(page(2, 4).is_empty());
+    }
+
+    #[test]
+    fn test_review_set_diffs_rejects_bad_path() {
+        let set = ReviewSet { repos: Vec::new() };
+        assert!(set.diffs(1, &["[".to_string()]).is_err());
+        assert!(set.diffs(1, &[".github/workflows/*".to_string()]).unwrap().is_empty());
+    }
+}
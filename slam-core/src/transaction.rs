@@ -0,0 +1,533 @@
+use eyre::{eyre, Context, Result};
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::git;
+use crate::remote;
+
+//------------------------------------------------------------------------------
+// Steps
+//------------------------------------------------------------------------------
+/// A reversible step of a transaction. Steps are plain data, so a transaction can be logged,
+/// written to the journal, printed before it is replayed and tested without a closure in sight.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum Step {
+    /// Push `HEAD` of the checkout at `work_path` (e.g. a temporary worktree) to `branch` on
+    /// the origin of the clone at `repo_path` (or its `remote`, e.g. a fork), or to `refspec`
+    /// where the forge wants one. Undone by deleting `branch` on that remote.
+    Push {
+        repo_path: PathBuf,
+        work_path: PathBuf,
+        branch: String,
+        refspec: Option<String>,
+        signed: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        remote: Option<String>,
+    },
+    /// Point `branch` of `reposlug` at commit `sha` through the GitHub API, creating the branch
+    /// if needed. Undone by deleting the branch.
+    UpdateRef {
+        reposlug: String,
+        branch: String,
+        sha: String,
+    },
+}
+
+impl Step {
+    /// Carries the step out.
+    pub fn apply(&self) -> Result<()> {
+        match self {
+            Step::Push {
+                work_path,
+                branch,
+                refspec,
+                signed,
+                remote,
+                ..
+            } => {
+                let remote = remote.as_deref().unwrap_or("origin");
+                match refspec {
+                    Some(refspec) => git::push_refspec(work_path, remote, refspec, *signed),
+                    None => git::push_branch(work_path, remote, branch, *signed, false),
+                }
+            }
+            Step::UpdateRef { reposlug, branch, sha } => remote::point_branch(reposlug, branch, sha),
+        }
+    }
+
+    /// Undoes what `apply` did.
+    pub fn rollback(&self) -> Result<()> {
+        match self {
+            Step::Push {
+                repo_path,
+                branch,
+                remote,
+                ..
+            } => git::delete_remote_branch(repo_path, remote.as_deref().unwrap_or("origin"), branch),
+            Step::UpdateRef { reposlug, branch, .. } => git::delete_remote_branch_gh(reposlug, branch),
+        }
+    }
+
+    /// What `rollback` will do, e.g. for `slam rollback --dry-run`.
+    pub fn describe_rollback(&self) -> String {
+        match self {
+            Step::Push { repo_path, branch, .. } => {
+                format!("delete remote branch '{}' of '{}'", branch, repo_path.display())
+            }
+            Step::UpdateRef { reposlug, branch, .. } => format!("delete branch '{}' of '{}'", branch, reposlug),
+        }
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Push {
+                work_path,
+                branch,
+                refspec: Some(refspec),
+                ..
+            } => write!(f, "push '{}' ({}) from '{}'", refspec, branch, work_path.display()),
+            Step::Push { work_path, branch, .. } => {
+                write!(f, "push branch '{}' from '{}'", branch, work_path.display())
+            }
+            Step::UpdateRef { reposlug, branch, sha } => {
+                write!(f, "point branch '{}' of '{}' at {}", branch, reposlug, sha)
+            }
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// Journal
+//------------------------------------------------------------------------------
+static JOURNAL_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Persists the steps of journaled transactions under `dir`, so `pending` can find them after
+/// the process died. Until this is called (e.g. in tests) nothing is written.
+pub fn configure_journal(dir: PathBuf) {
+    if JOURNAL_DIR.set(dir).is_err() {
+        warn!("Transaction journal already configured; ignoring");
+    }
+}
+
+/// `<dir>/<change-id>`, with characters that can't be in one file name replaced.
+fn change_dir(dir: &Path, change_id: &str) -> PathBuf {
+    dir.join(change_id.replace(['/', '\\'], "_"))
+}
+
+/// The steps one repo's transaction has applied and not yet committed, kept in
+/// `<journal>/<change-id>/<org>/<repo>.json` while it runs when a journal directory is set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Journal {
+    pub change_id: String,
+    pub reposlug: String,
+    /// In the order they were applied; rolled back last first.
+    pub steps: Vec<Step>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Journal {
+    fn new(dir: Option<&Path>, change_id: &str, reposlug: &str) -> Self {
+        Self {
+            change_id: change_id.to_string(),
+            reposlug: reposlug.to_string(),
+            steps: Vec::new(),
+            path: dir.map(|dir| change_dir(dir, change_id).join(format!("{}.json", reposlug))),
+        }
+    }
+
+    /// Writes the journal, or removes its file once no steps are left.
+    fn save(&self) -> Result<()> {
+        let Some(path) = self.path.as_deref() else {
+            return Ok(());
+        };
+        if self.steps.is_empty() {
+            if let Err(e) = fs::remove_file(path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    return Err(e.into());
+                }
+            }
+            // Drop the owner and change id directories once they are empty too.
+            for dir in path.ancestors().skip(1).take(2) {
+                if fs::remove_dir(dir).is_err() {
+                    break;
+                }
+            }
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write then rename, so a crash mid-write never leaves a truncated journal.
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    fn save_or_warn(&self) {
+        if let Err(e) = self.save() {
+            let path = self.path.as_deref().unwrap_or(Path::new(""));
+            warn!("Failed to update rollback journal '{}': {}", path.display(), e);
+        }
+    }
+
+    fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut journal: Journal =
+            serde_json::from_str(&text).wrap_err_with(|| format!("Invalid rollback journal '{}'", path.display()))?;
+        journal.path = Some(path.to_path_buf());
+        Ok(journal)
+    }
+
+    /// Rolls back the steps, last first. Steps that fail stay in the journal for the next
+    /// attempt; the journal is removed once none are left. Returns the first failure.
+    pub fn replay(&mut self) -> Result<()> {
+        let mut failed = Vec::new();
+        let mut first_error = None;
+        while let Some(step) = self.steps.pop() {
+            match step.rollback() {
+                Ok(()) => debug!("Rolled back: {}", step),
+                Err(e) => {
+                    error!("Rolling back '{}' for '{}' failed: {:?}", step, self.reposlug, e);
+                    failed.insert(0, step);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        self.steps = failed;
+        self.save()?;
+        first_error.map_or(Ok(()), Err)
+    }
+}
+
+fn pending_in(dir: &Path, change_id: &str) -> Result<Vec<Journal>> {
+    journals_in(&change_dir(dir, change_id))
+}
+
+/// The journals under one change id's directory, sorted by reposlug.
+fn journals_in(change_dir: &Path) -> Result<Vec<Journal>> {
+    let Ok(owners) = fs::read_dir(change_dir) else {
+        return Ok(Vec::new());
+    };
+    let mut journals = Vec::new();
+    for owner in owners.flatten().filter(|entry| entry.path().is_dir()) {
+        for entry in fs::read_dir(owner.path())?.flatten() {
+            if entry.path().extension().is_some_and(|ext| ext == "json") {
+                journals.push(Journal::load(&entry.path())?);
+            }
+        }
+    }
+    journals.sort_by(|a, b| a.reposlug.cmp(&b.reposlug));
+    Ok(journals)
+}
+
+/// The journals `change_id`'s runs left behind, one per repo with steps to roll back, sorted
+/// by reposlug.
+pub fn pending(change_id: &str) -> Result<Vec<Journal>> {
+    match JOURNAL_DIR.get() {
+        Some(dir) => pending_in(dir, change_id),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn pending_all_in(dir: &Path) -> Result<Vec<Journal>> {
+    let Ok(changes) = fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+    let mut journals = Vec::new();
+    for change in changes.flatten().filter(|entry| entry.path().is_dir()) {
+        journals.extend(journals_in(&change.path())?);
+    }
+    journals.sort_by(|a, b| (&a.change_id, &a.reposlug).cmp(&(&b.change_id, &b.reposlug)));
+    Ok(journals)
+}
+
+/// Every journal any change id's runs left behind, sorted by change id, then reposlug.
+pub fn pending_all() -> Result<Vec<Journal>> {
+    match JOURNAL_DIR.get() {
+        Some(dir) => pending_all_in(dir),
+        None => Ok(Vec::new()),
+    }
+}
+
+//------------------------------------------------------------------------------
+// Transaction Struct Definition
+//------------------------------------------------------------------------------
+/// Transaction applies reversible `Step`s and, on error, rolls the applied ones back in
+/// reverse order. A journaled transaction records each step on disk before applying it, so
+/// `slam rollback` can undo it if the process dies.
+pub struct Transaction {
+    journal: Journal,
+    committed: bool,
+}
+
+impl Default for Transaction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Transaction {
+            journal: Journal::default(),
+            committed: false,
+        }
+    }
+
+    /// A transaction journaled for `reposlug` under `change_id`, when a journal directory
+    /// is configured.
+    pub fn journaled(change_id: &str, reposlug: &str) -> Self {
+        Transaction {
+            journal: Journal::new(JOURNAL_DIR.get().map(PathBuf::as_path), change_id, reposlug),
+            committed: false,
+        }
+    }
+
+    /// The steps applied so far, oldest first.
+    pub fn steps(&self) -> &[Step] {
+        &self.journal.steps
+    }
+
+    /// Applies `step` and registers it for rollback. The step is journaled first, so a crash
+    /// while it runs still leaves it to be undone; when it fails it is dropped again.
+    pub fn apply(&mut self, step: Step) -> Result<()> {
+        if self.committed {
+            return Err(eyre!("Transaction already committed; not applying '{}'", step));
+        }
+        info!("Applying: {}", step);
+        self.journal.steps.push(step);
+        self.journal.save_or_warn();
+        let applied = self.journal.steps.last().map_or(Ok(()), Step::apply);
+        if applied.is_err() {
+            self.journal.steps.pop();
+            self.journal.save_or_warn();
+        }
+        applied
+    }
+
+    /// Rolls back the applied steps in reverse order. Each error is logged.
+    pub fn rollback(&mut self) {
+        error!(
+            "An error occurred; initiating rollback of {} steps",
+            self.journal.steps.len()
+        );
+        while let Some(step) = self.journal.steps.pop() {
+            match step.rollback() {
+                Ok(()) => debug!("Rolled back: {}", step),
+                Err(e) => error!("Rolling back '{}' failed: {:?}", step, e),
+            }
+        }
+        self.journal.save_or_warn();
+    }
+
+    /// Marks the transaction as committed and forgets the applied steps.
+    pub fn commit(&mut self) {
+        self.committed = true;
+        if !self.journal.steps.is_empty() {
+            self.journal.steps.clear();
+            self.journal.save_or_warn();
+        }
+    }
+}
+
+/// A transaction dropped without `commit` (e.g. when a step fails with `?`) is rolled back.
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        if !self.committed && !self.journal.steps.is_empty() {
+            self.rollback();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .current_dir(dir)
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+        String::from_utf8_lossy(&output.stdout).to_string()
+    }
+
+    /// A clone at `<tmp>/clone` of a bare `<tmp>/origin.git` holding one commit.
+    fn clone_with_origin(temp_dir: &TempDir) -> PathBuf {
+        let origin = temp_dir.path().join("origin.git");
+        let clone = temp_dir.path().join("clone");
+        fs::create_dir_all(&origin).unwrap();
+        git(&origin, &["init", "--quiet", "--bare"]);
+        git(
+            temp_dir.path(),
+            &["clone", "--quiet", origin.to_str().unwrap(), clone.to_str().unwrap()],
+        );
+        fs::write(clone.join("README.md"), "readme\n").unwrap();
+        git(&clone, &["add", "--all"]);
+        git(&clone, &["commit", "--quiet", "-m", "init"]);
+        clone
+    }
+
+    fn push(clone: &Path, branch: &str) -> Step {
+        Step::Push {
+            repo_path: clone.to_path_buf(),
+            work_path: clone.to_path_buf(),
+            branch: branch.to_string(),
+            refspec: None,
+            signed: false,
+            remote: None,
+        }
+    }
+
+    fn remote_branches(clone: &Path) -> Vec<String> {
+        git(clone, &["ls-remote", "--heads", "origin"])
+            .lines()
+            .filter_map(|line| line.split("refs/heads/").nth(1).map(str::to_string))
+            .collect()
+    }
+
+    #[test]
+    fn test_transaction_new() {
+        let transaction = Transaction::new();
+        assert!(transaction.steps().is_empty());
+        assert!(!transaction.committed);
+    }
+
+    #[test]
+    fn test_apply_and_rollback_in_reverse() {
+        let temp_dir = TempDir::new().unwrap();
+        let clone = clone_with_origin(&temp_dir);
+        let mut transaction = Transaction::new();
+
+        transaction.apply(push(&clone, "SLAM-a")).unwrap();
+        transaction.apply(push(&clone, "SLAM-b")).unwrap();
+        assert_eq!(transaction.steps(), [push(&clone, "SLAM-a"), push(&clone, "SLAM-b")]);
+        assert_eq!(remote_branches(&clone), ["SLAM-a", "SLAM-b"]);
+
+        transaction.rollback();
+        assert!(transaction.steps().is_empty());
+        assert!(remote_branches(&clone).is_empty());
+
+        // A second rollback has nothing left to undo.
+        transaction.rollback();
+        assert!(transaction.steps().is_empty());
+    }
+
+    #[test]
+    fn test_failed_step_is_not_registered() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut transaction = Transaction::new();
+        assert!(transaction
+            .apply(push(&temp_dir.path().join("missing"), "SLAM-x"))
+            .is_err());
+        assert!(transaction.steps().is_empty());
+    }
+
+    #[test]
+    fn test_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let clone = clone_with_origin(&temp_dir);
+        let mut transaction = Transaction::new();
+        transaction.apply(push(&clone, "SLAM-x")).unwrap();
+
+        transaction.commit();
+        assert!(transaction.steps().is_empty());
+        assert!(transaction.committed);
+        assert!(transaction.apply(push(&clone, "SLAM-y")).is_err());
+        drop(transaction);
+        assert_eq!(remote_branches(&clone), ["SLAM-x"]);
+    }
+
+    #[test]
+    fn test_drop_rolls_back_uncommitted() {
+        let temp_dir = TempDir::new().unwrap();
+        let clone = clone_with_origin(&temp_dir);
+
+        let mut failed = Transaction::new();
+        failed.apply(push(&clone, "SLAM-x")).unwrap();
+        drop(failed);
+        assert!(remote_branches(&clone).is_empty());
+    }
+
+    #[test]
+    fn test_rollback_empty_transaction() {
+        let mut transaction = Transaction::new();
+
+        // Should not panic on empty rollback
+        transaction.rollback();
+        assert!(transaction.steps().is_empty());
+    }
+
+    #[test]
+    fn test_step_display() {
+        let step = Step::UpdateRef {
+            reposlug: "org/repo".to_string(),
+            branch: "SLAM-x".to_string(),
+            sha: "c1".to_string(),
+        };
+        assert_eq!(step.to_string(), "point branch 'SLAM-x' of 'org/repo' at c1");
+        assert_eq!(step.describe_rollback(), "delete branch 'SLAM-x' of 'org/repo'");
+    }
+
+    #[test]
+    fn test_journal_tracks_applied_steps() {
+        let temp_dir = TempDir::new().unwrap();
+        let clone = clone_with_origin(&temp_dir);
+        let journal_dir = temp_dir.path().join("journal");
+        let mut transaction = Transaction::new();
+        transaction.journal = Journal::new(Some(&journal_dir), "SLAM-x", "org/repo");
+        transaction.apply(push(&clone, "SLAM-x")).unwrap();
+
+        let mut pending = pending_in(&journal_dir, "SLAM-x").unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].reposlug, "org/repo");
+        assert_eq!(pending[0].steps, [push(&clone, "SLAM-x")]);
+        assert!(pending_in(&journal_dir, "SLAM-z").unwrap().is_empty());
+
+        // As `slam rollback` would after a crash.
+        pending[0].replay().unwrap();
+        assert!(remote_branches(&clone).is_empty());
+        assert!(pending_in(&journal_dir, "SLAM-x").unwrap().is_empty());
+
+        transaction.commit();
+        assert!(!journal_dir.join("SLAM-x").exists());
+    }
+
+    #[test]
+    fn test_journal_roundtrip_keeps_step_fields() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut journal = Journal::new(Some(temp_dir.path()), "feature/x", "org/repo");
+        journal.steps.push(Step::UpdateRef {
+            reposlug: "org/repo".to_string(),
+            branch: "feature/x".to_string(),
+            sha: "c1".to_string(),
+        });
+        journal.save().unwrap();
+        assert!(temp_dir.path().join("feature_x/org/repo.json").exists());
+
+        let loaded = pending_in(temp_dir.path(), "feature/x").unwrap();
+        assert_eq!(loaded, [journal.clone()]);
+        let mut other = Journal::new(Some(temp_dir.path()), "SLAM-a", "org/api");
+        other.steps = journal.steps.clone();
+        other.save().unwrap();
+        let all = pending_all_in(temp_dir.path()).unwrap();
+        assert_eq!(all, [other, journal.clone()]);
+
+        journal.steps.clear();
+        journal.save().unwrap();
+        assert!(pending_in(temp_dir.path(), "feature/x").unwrap().is_empty());
+    }
+}
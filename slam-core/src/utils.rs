@@ -0,0 +1,118 @@
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+pub fn indent(s: &str, indent: usize) -> String {
+    let pad = " ".repeat(indent);
+    s.lines()
+        .map(|line| format!("{}{}", pad, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Hex-encoded SHA-256 digest of `data`.
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Expands a leading `~` in `path` to the user's home directory.
+pub fn expand_tilde(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => dirs::home_dir()
+            .map(|home| home.join(rest))
+            .unwrap_or_else(|| path.to_path_buf()),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Replaces every `{{name}}` placeholder in `template` with its value from `vars`.
+/// Whitespace inside the braces is ignored; unknown placeholders are left untouched.
+pub fn render_template(template: &str, vars: &[(&str, String)]) -> String {
+    let placeholder_re = Regex::new(r"\{\{\s*(\w+)\s*\}\}").unwrap();
+    placeholder_re
+        .replace_all(template, |caps: &regex::Captures| {
+            let name = &caps[1];
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.clone())
+                .unwrap_or_else(|| caps[0].to_string())
+        })
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_indent_single_line() {
+        let input = "hello world";
+        let result = indent(input, 4);
+        assert_eq!(result, "    hello world");
+    }
+
+    #[test]
+    fn test_indent_multiple_lines() {
+        let input = "line1\nline2\nline3";
+        let result = indent(input, 2);
+        assert_eq!(result, "  line1\n  line2\n  line3");
+    }
+
+    #[test]
+    fn test_indent_empty_string() {
+        let input = "";
+        let result = indent(input, 3);
+        assert_eq!(result, "");
+    }
+
+    #[test]
+    fn test_indent_zero_indent() {
+        let input = "no indent";
+        let result = indent(input, 0);
+        assert_eq!(result, "no indent");
+    }
+
+    #[test]
+    fn test_indent_with_empty_lines() {
+        let input = "line1\n\nline3";
+        let result = indent(input, 2);
+        assert_eq!(result, "  line1\n  \n  line3");
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"hello"),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert_eq!(sha256_hex(b"").len(), 64);
+    }
+
+    #[test]
+    fn test_expand_tilde() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_tilde(Path::new("~/slam/body.md")), home.join("slam/body.md"));
+        assert_eq!(expand_tilde(Path::new("/abs/body.md")), PathBuf::from("/abs/body.md"));
+        assert_eq!(expand_tilde(Path::new("rel/~/body.md")), PathBuf::from("rel/~/body.md"));
+    }
+
+    #[test]
+    fn test_render_template_substitutes_vars() {
+        let vars = vec![("change_id", "SLAM-test".to_string()), ("count", "3".to_string())];
+        let result = render_template("{{change_id}} touched {{ count }} files", &vars);
+        assert_eq!(result, "SLAM-test touched 3 files");
+    }
+
+    #[test]
+    fn test_render_template_leaves_unknown_placeholders() {
+        let vars = vec![("change_id", "SLAM-test".to_string())];
+        let result = render_template("{{change_id}} {{unknown}}", &vars);
+        assert_eq!(result, "SLAM-test {{unknown}}");
+    }
+
+    #[test]
+    fn test_render_template_repeated_placeholder() {
+        let vars = vec![("x", "1".to_string())];
+        assert_eq!(render_template("{{x}}+{{x}}", &vars), "1+1");
+    }
+}
@@ -1,22 +1,254 @@
-use chrono::Local;
-use clap::{Parser, Subcommand};
+use chrono::{DateTime, Local};
+use clap::{Args, Parser, Subcommand};
+use regex::Regex;
+use std::path::PathBuf;
+use std::time::Duration;
 
-use crate::repo::Change;
+use slam_core::audit::Expectation;
+use slam_core::diff::{self, DiffStyle};
+use slam_core::forge::Protocol;
+pub use slam_core::git::RepoFilter;
+use slam_core::git::{Identity, SigningFormat, Visibility};
+use slam_core::repo::{Change, FileMode, IfExists, ReviewOp};
 
-pub fn default_change_id() -> String {
+use crate::enforce::parse_interval;
+use crate::lock::Scope;
+use crate::logging::LogFormat;
+use crate::ls::{LsColumn, LsSort};
+use crate::report::ReportFormat;
+
+/// What `create` does when the change's branch already exists on a remote, e.g. because a
+/// chosen change id matches an older rollout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Collision {
+    /// List the repos and ask before replacing their branches (`--yes` replaces them).
+    #[default]
+    Ask,
+    /// List the repos and replace their branches.
+    Warn,
+    /// List the repos and stop before changing anything.
+    Error,
+}
+
+/// Prefix of every branch (and PR title) slam creates, unless overridden.
+pub const DEFAULT_BRANCH_PREFIX: &str = "SLAM";
+
+/// Most repos create, approve and purge act on without `--force`.
+pub const DEFAULT_MAX_REPOS: usize = 50;
+
+pub fn default_change_id(branch_prefix: &str) -> String {
     let now = Local::now();
     let ts = now.format("%Y-%m-%dT%H-%M-%S").to_string();
-    format!("SLAM-{}", ts)
+    format!("{}-{}", branch_prefix, ts)
+}
+
+/// Renders a `--change-id-template`. Besides the `--var` values it knows `{{prefix}}` (the
+/// branch prefix), `{{date}}` (`YYYY-MM-DD`), `{{time}}` (`HH-MM-SS`), `{{timestamp}}` (the
+/// default change id's `YYYY-MM-DDTHH-MM-SS`) and `{{user}}` (`$USER`).
+pub fn render_change_id(
+    template: &str,
+    branch_prefix: &str,
+    vars: &[(String, String)],
+    now: DateTime<Local>,
+) -> Result<String, String> {
+    let mut all_vars = vec![
+        ("prefix", branch_prefix.to_string()),
+        ("date", now.format("%Y-%m-%d").to_string()),
+        ("time", now.format("%H-%M-%S").to_string()),
+        ("timestamp", now.format("%Y-%m-%dT%H-%M-%S").to_string()),
+        ("user", std::env::var("USER").unwrap_or_default()),
+    ];
+    // A --var of the same name wins over the built-in.
+    all_vars.splice(0..0, vars.iter().map(|(key, value)| (key.as_str(), value.clone())));
+    let change_id = slam_core::utils::render_template(template, &all_vars);
+    if let Some(unknown) = Regex::new(r"\{\{\s*(\w+)\s*\}\}").unwrap().captures(&change_id) {
+        return Err(format!(
+            "Change id template uses '{}', which is neither built in nor set with --var",
+            &unknown[1]
+        ));
+    }
+    validate_branch_prefix(&change_id).map_err(|_| format!("`{}` isn't a valid change id", change_id))
+}
+
+fn parse_expectation(s: &str) -> Result<Expectation, String> {
+    Expectation::parse(s).map_err(|e| e.to_string())
+}
+
+fn parse_regex(s: &str) -> Result<Regex, String> {
+    Regex::new(s).map_err(|e| format!("invalid regex: {}", e))
+}
+
+/// Accepts a `--var` written `KEY=VALUE`.
+fn parse_var(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || c == '_') => {
+            Ok((key.to_string(), value.to_string()))
+        }
+        _ => Err(format!("`{}` isn't KEY=VALUE", s)),
+    }
+}
+
+/// Characters git never allows in a ref name.
+const REF_FORBIDDEN: &str = "~^:?*[\\";
+
+/// Why git would reject `name` as a branch name (the rules of `git check-ref-format`), if it would.
+fn ref_name_problem(name: &str) -> Option<String> {
+    if name.is_empty() {
+        return Some("is empty".to_string());
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|&c| c.is_whitespace() || c.is_control() || REF_FORBIDDEN.contains(c))
+    {
+        return Some(format!("contains {:?}", c));
+    }
+    for sequence in ["..", "@{", "//"] {
+        if name.contains(sequence) {
+            return Some(format!("contains `{}`", sequence));
+        }
+    }
+    if name == "@" {
+        return Some("is `@`".to_string());
+    }
+    if name.starts_with(['-', '/', '.']) {
+        return Some(format!("starts with `{}`", &name[..1]));
+    }
+    if name.ends_with(['/', '.']) {
+        return Some(format!("ends with `{}`", &name[name.len() - 1..]));
+    }
+    if name
+        .split('/')
+        .any(|part| part.starts_with('.') || part.ends_with(".lock"))
+    {
+        return Some("has a part starting with `.` or ending with `.lock`".to_string());
+    }
+    None
 }
 
+pub fn validate_branch_prefix(s: &str) -> Result<String, String> {
+    match ref_name_problem(s) {
+        Some(problem) => Err(format!("`{}` isn't a valid branch prefix: it {}", s, problem)),
+        None => Ok(s.to_string()),
+    }
+}
+
+/// Checks a `--change-id` as the branch it becomes under `branch_prefix`, so a name git would
+/// reject fails before any repo is touched.
+pub fn validate_change_id(change_id: &str, branch_prefix: &str) -> Result<String, String> {
+    match ref_name_problem(&slam_core::repo::normalize_change_id(change_id, branch_prefix)) {
+        Some(problem) => Err(format!(
+            "`{}` isn't a valid change id: its branch name {}; pass --sanitize to fix it up",
+            change_id, problem
+        )),
+        None => Ok(change_id.to_string()),
+    }
+}
+
+/// `change_id` made into a valid branch name for `--sanitize`: forbidden characters and
+/// whitespace become `-`, and the sequences, leading and trailing characters git rejects are
+/// dropped.
+pub fn sanitize_change_id(change_id: &str, branch_prefix: &str) -> Result<String, String> {
+    let mut sanitized: String = change_id
+        .chars()
+        .map(|c| {
+            if c.is_whitespace() || c.is_control() || REF_FORBIDDEN.contains(c) {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect();
+    for (sequence, replacement) in [("..", "."), ("@{", "-"), ("//", "/"), ("--", "-")] {
+        while sanitized.contains(sequence) {
+            sanitized = sanitized.replace(sequence, replacement);
+        }
+    }
+    let parts: Vec<&str> = sanitized
+        .split('/')
+        .map(|part| {
+            let mut part = part.trim_start_matches('.');
+            while let Some(stem) = part.strip_suffix(".lock") {
+                part = stem;
+            }
+            part
+        })
+        .filter(|part| !part.is_empty())
+        .collect();
+    let sanitized = parts.join("/").trim_matches(['-', '.']).to_string();
+    if sanitized.is_empty() {
+        return Err(format!("Nothing usable as a change id is left of `{}`", change_id));
+    }
+    validate_change_id(&sanitized, branch_prefix)
+}
+
+/// Accepts a directory relative to the repository root; rejects the root itself,
+/// absolute paths, anything escaping the repository and the `.git` directory.
+pub fn validate_repo_dir(s: &str) -> Result<String, String> {
+    let dir = s.trim_end_matches('/');
+    let path = std::path::Path::new(dir);
+    let escapes = path.components().any(|c| !matches!(c, std::path::Component::Normal(_)));
+    if dir.is_empty() || escapes {
+        return Err(format!("`{}` must be a directory inside the repository", s));
+    }
+    if path.components().next() == Some(std::path::Component::Normal(".git".as_ref())) {
+        return Err(format!("`{}` is inside the .git directory", s));
+    }
+    Ok(dir.to_string())
+}
+
+/// Accepts the repository root (`.`) or a directory inside it, as `validate_repo_dir` does.
+fn validate_copy_dest(s: &str) -> Result<String, String> {
+    if s == "." || s == "./" {
+        return Ok(".".to_string());
+    }
+    validate_repo_dir(s)
+}
+
+/// Accepts an `owner/name` for a new repo.
+fn validate_new_reposlug(s: &str) -> Result<String, String> {
+    let valid_part = |part: &str| {
+        !part.is_empty()
+            && !part.starts_with('.')
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+    };
+    match s.split_once('/') {
+        Some((owner, name)) if valid_part(owner) && valid_part(name) => Ok(s.to_string()),
+        _ => Err(format!("`{}` isn't an owner/name like org/new-service", s)),
+    }
+}
+
+/// Accepts a report path ending in `.md` or `.html`.
+fn validate_report_path(s: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(s);
+    match ReportFormat::from_path(&path) {
+        Some(_) => Ok(path),
+        None => Err(format!("`{}` must end in .md or .html", s)),
+    }
+}
+
+/// Accepts a git identity written `Name <email>`.
+pub fn parse_identity(s: &str) -> Result<Identity, String> {
+    s.parse().map_err(|e: eyre::Report| e.to_string())
+}
+
+/// Most context lines `--buffer` takes as a number; `full` shows whole files.
+const MAX_BUFFER: usize = 50;
+
 fn validate_buffer(s: &str) -> Result<usize, String> {
+    if s == "full" {
+        return Ok(diff::FULL_CONTEXT);
+    }
     s.parse::<usize>()
-        .map_err(|_| format!("`{}` isn't a valid number", s))
+        .map_err(|_| format!("`{}` isn't a valid number or `full`", s))
         .and_then(|v| {
-            if (1..=3).contains(&v) {
+            if (1..=MAX_BUFFER).contains(&v) {
                 Ok(v)
             } else {
-                Err(format!("Buffer must be between 1 and 3, but got {}", v))
+                Err(format!(
+                    "Buffer must be between 1 and {} (or full), but got {}",
+                    MAX_BUFFER, v
+                ))
             }
         })
 }
@@ -27,6 +259,7 @@ fn validate_buffer(s: &str) -> Result<usize, String> {
     about = "HPA: horizontal PR autoscaler",
     version = env!("GIT_DESCRIBE"),
     after_help = "Logs are written to: ~/.local/share/slam/slam.log
+Config is read from: ~/.config/slam/slam.yml
 
 This tool helps manage changes across multiple repositories by:
 - Creating and managing branches/PRs across multiple repos
@@ -35,6 +268,145 @@ This tool helps manage changes across multiple repositories by:
 - Tracking changes with unique change IDs"
 )]
 pub struct SlamCli {
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        help = "Max concurrent GitHub API (gh) calls (default: 8)"
+    )]
+    pub gh_jobs: Option<usize>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        help = "Max concurrent local git operations (default: number of CPUs)"
+    )]
+    pub git_jobs: Option<usize>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        help = "Retries for git clone/fetch/pull/push on network errors (default: 3)"
+    )]
+    pub retries: Option<usize>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        help = "Abort create, approve and purge when more repos than this match, unless --force (default: 50)"
+    )]
+    pub max_repos: Option<usize>,
+
+    #[arg(long, global = true, help = "Act on more repos than --max-repos allows")]
+    pub force: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "When another slam run holds the sandbox or change id, wait for it to finish"
+    )]
+    pub wait: bool,
+
+    #[arg(
+        long,
+        global = true,
+        conflicts_with = "wait",
+        help = "Take over the sandbox and change id locks of another slam run, e.g. one on a dead host"
+    )]
+    pub force_lock: bool,
+
+    #[arg(long, global = true, help = "Also act on the repos listed in safety.protected")]
+    pub allow_protected: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "PREFIX",
+        value_parser = validate_branch_prefix,
+        help = "Prefix for the branches and PR titles slam creates, purges and refreshes (default: SLAM)"
+    )]
+    pub branch_prefix: Option<String>,
+
+    #[arg(
+        short = 'C',
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Sandbox directory holding the <org>/<repo> clones (default: workdir in slam.yml, else the current directory)"
+    )]
+    pub workdir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Ignore the cached org repo and open PR lists and the sandbox repo index"
+    )]
+    pub no_cache: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Treat the checked out submodules of the sandbox's repos as repos of their own"
+    )]
+    pub include_submodules: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = DiffStyle::Unified,
+        help = "How diffs are rendered"
+    )]
+    pub diff_style: DiffStyle,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Show at most N diff lines per file, noting how many more were cut"
+    )]
+    pub max_lines_per_file: Option<u64>,
+
+    #[arg(
+        short = 'v',
+        long,
+        global = true,
+        action = clap::ArgAction::Count,
+        help = "Also print logs to stderr: -v info, -vv debug, -vvv trace ($RUST_LOG overrides the level)"
+    )]
+    pub verbose: u8,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = LogFormat::Text,
+        help = "Format of the log lines in slam.log and on stderr"
+    )]
+    pub log_format: LogFormat,
+
+    #[arg(long, global = true, help = "Disable colored output (also honors NO_COLOR)")]
+    pub no_color: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Print long diff output directly instead of piping it to $PAGER"
+    )]
+    pub no_pager: bool,
+
+    #[arg(
+        short = 'y',
+        long,
+        global = true,
+        help = "Don't ask before purging, deleting, admin-merging, rolling back or replacing existing remote branches"
+    )]
+    pub yes: bool,
+
     #[command(subcommand)]
     pub command: SlamCommand,
 }
@@ -49,51 +421,573 @@ pub enum SlamCommand {
         action: SandboxAction,
     },
 
+    /// Overview of sandbox health and in-flight change ids
+    Status {
+        #[arg(long, help = "Only inspect local repos; skip querying GitHub for open PRs")]
+        offline: bool,
+    },
+
     /// Create new <change-id> (branches/PRs) with updates
-    Create {
-        #[arg(short = 'f', long, help = "Glob pattern to find files within each repository")]
+    Create(Box<CreateArgs>),
+
+    /// Review <change-id> (PRs per repo) and merge them
+    Review {
+        #[arg(
+            short = 'o',
+            long,
+            default_value = "tatari-tv",
+            help = "GitHub organization to search for branches"
+        )]
+        org: String,
+
+        #[arg(short = 'r', long, help = "Patterns for repo filtering", default_value = "")]
+        repo_ptns: Vec<String>,
+
+        #[command(flatten)]
+        filter: RepoFilter,
+
+        #[command(subcommand)]
+        action: ReviewAction,
+    },
+
+    /// Report which repos have files deviating from an expected content, without changing anything
+    Audit {
+        #[arg(
+            value_name = "FILE_GLOB",
+            required = true,
+            help = "Glob pattern of the files to check within each repository (repeatable)"
+        )]
         files: Vec<String>,
 
+        #[arg(
+            long,
+            value_name = "REGEX|SHA256",
+            value_parser = parse_expectation,
+            help = "Regex every matched file must match, or the SHA-256 of its exact content (sha256:<hex>)"
+        )]
+        expect: Expectation,
+
+        #[arg(short = 'r', long, help = "Patterns for repo filtering")]
+        repo_ptns: Vec<String>,
+
+        #[arg(
+            long,
+            help = "Audit the default branches of the repos of --org on GitHub instead of the sandbox clones"
+        )]
+        remote: bool,
+
+        #[arg(
+            short = 'o',
+            long = "org",
+            value_name = "ORG",
+            requires = "remote",
+            help = "GitHub organization whose repos --remote audits (repeatable)"
+        )]
+        orgs: Vec<String>,
+    },
+
+    /// Audit the policies of a policies file and run their creates on the repos that drifted
+    Enforce {
+        #[arg(
+            long = "config",
+            value_name = "FILE",
+            help = "YAML file of the policies to enforce (see README: Enforcing Policies)"
+        )]
+        policies: PathBuf,
+
+        #[arg(
+            long,
+            value_name = "INTERVAL",
+            value_parser = parse_interval,
+            help = "Enforce again every INTERVAL (e.g. 30m, 24h, 7d) until stopped, instead of once"
+        )]
+        interval: Option<Duration>,
+    },
+
+    /// Create new repos and seed them with the files of a blueprint directory
+    Init {
+        #[arg(
+            value_name = "BLUEPRINT_DIR",
+            help = "Directory whose files seed each new repo; {{repo}}, {{org}} and {{reposlug}} are rendered per repo"
+        )]
+        blueprint: PathBuf,
+
+        #[arg(
+            short = 'r',
+            long = "repo",
+            value_name = "OWNER/NAME",
+            required = true,
+            value_parser = validate_new_reposlug,
+            help = "Repo to create (repeatable)"
+        )]
+        reposlugs: Vec<String>,
+
+        #[arg(long, value_enum, default_value_t = Visibility::Private, help = "Visibility of the new repos")]
+        visibility: Visibility,
+
+        #[arg(
+            long,
+            help = "Push the blueprint straight to the default branch instead of opening an initial PR"
+        )]
+        direct: bool,
+
         #[arg(
             short = 'x',
             long,
-            help = "Change ID used to create branches and PRs (default: 'SLAM-<YYYY-MM-DDT..>')",
-            default_value_t = default_change_id()
+            conflicts_with = "direct",
+            help = "Change ID of the initial PRs (default: generated)"
         )]
-        change_id: String,
+        change_id: Option<String>,
 
         #[arg(
-            short = 'b',
+            short = 'c',
             long,
-            default_value_t = 1,
-            value_parser = validate_buffer,
-            help = "Number of context lines in the diff output (must be between 1 and 3)"
+            default_value = "Initialize from blueprint",
+            help = "Commit message of the seeded files"
         )]
-        buffer: usize,
+        commit: String,
+    },
+
+    /// Push the branches `create --stage` committed for <change-id> and open their PRs
+    Publish {
+        #[arg(value_name = "CHANGE_ID", help = "Change ID the branches were staged for")]
+        change_id: String,
 
         #[arg(short = 'r', long, help = "Patterns for repo filtering")]
         repo_ptns: Vec<String>,
 
-        #[command(subcommand)]
-        action: Option<CreateAction>,
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Template file for the PR body (default: create.body_template in slam.yml)"
+        )]
+        body_template: Option<PathBuf>,
+
+        #[arg(
+            short = 'd',
+            long,
+            help = "Description exposed to the PR body template as {{description}}"
+        )]
+        description: Option<String>,
+
+        #[arg(long, help = "Don't request reviews from the CODEOWNERS of the changed files")]
+        no_codeowners: bool,
+
+        #[arg(long, value_name = "TITLE", help = "Put each PR on this (open) milestone")]
+        milestone: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "TITLE",
+            help = "Add each PR to this project board of the repo's owner"
+        )]
+        project: Option<String>,
+
+        #[arg(
+            long,
+            help = "Push to your fork of each repo (forking it if needed) and open cross-fork PRs"
+        )]
+        fork: bool,
     },
 
-    /// Review <change-id> (PRs per repo) and merge them
-    Review {
+    /// Undo what runs of <change-id> left half done, e.g. pushed branches of a crashed create
+    Rollback {
+        #[arg(value_name = "CHANGE_ID", help = "Change ID whose outstanding rollbacks to replay")]
+        change_id: String,
+
+        #[arg(long, help = "Only list the steps that would be rolled back")]
+        dry_run: bool,
+    },
+
+    /// Print the log of the latest run, or of the latest run for <change-id>
+    Logs {
+        #[arg(
+            value_name = "CHANGE_ID",
+            help = "Change ID (or its start) the run was for; the latest run of any kind when omitted"
+        )]
+        change_id: Option<String>,
+
+        #[arg(long, help = "List the matching run logs instead of printing the latest")]
+        list: bool,
+    },
+
+    /// Print a shell completion script for <shell>
+    Completions {
+        #[arg(value_enum, value_name = "SHELL")]
+        shell: clap_complete::Shell,
+    },
+
+    /// Print the man page, or write one per subcommand into --dir
+    Manpage {
         #[arg(
-            short = 'o',
             long,
-            default_value = "tatari-tv",
-            help = "GitHub organization to search for branches"
+            value_name = "DIR",
+            help = "Write slam.1 and a page per subcommand (slam-create.1, ...) here"
         )]
-        org: String,
+        dir: Option<PathBuf>,
+    },
+}
 
-        #[arg(short = 'r', long, help = "Patterns for repo filtering", default_value = "")]
-        repo_ptns: Vec<String>,
+impl SlamCommand {
+    /// The change id this run works on, as given; names the run's log file.
+    pub fn change_id(&self) -> Option<&str> {
+        match self {
+            SlamCommand::Create(args) => args.change_id.as_deref(),
+            SlamCommand::Review { action, .. } => match action {
+                ReviewAction::Clone { change_id, .. }
+                | ReviewAction::Approve { change_id, .. }
+                | ReviewAction::Automerge { change_id, .. }
+                | ReviewAction::Delete { change_id, .. }
+                | ReviewAction::Checks { change_id, .. }
+                | ReviewAction::Export { change_id, .. }
+                | ReviewAction::Rerun { change_id, .. } => Some(change_id),
+                ReviewAction::Purge { prefix, .. } => prefix.as_deref(),
+                ReviewAction::Ls { .. }
+                | ReviewAction::Tui { .. }
+                | ReviewAction::Stats { .. }
+                | ReviewAction::Stale { .. } => None,
+            },
+            SlamCommand::Publish { change_id, .. } | SlamCommand::Rollback { change_id, .. } => Some(change_id),
+            SlamCommand::Init { change_id, .. } => change_id.as_deref(),
+            SlamCommand::Sandbox { .. }
+            | SlamCommand::Status { .. }
+            | SlamCommand::Audit { .. }
+            | SlamCommand::Enforce { .. }
+            | SlamCommand::Logs { .. }
+            | SlamCommand::Completions { .. }
+            | SlamCommand::Manpage { .. } => None,
+        }
+    }
+
+    /// The locks this run takes: the sandbox's when it changes the clones, and the change id's
+    /// when it changes the change's branches or PRs. Read-only commands and dry runs take none.
+    pub fn lock_scope(&self) -> Scope {
+        match self {
+            SlamCommand::Sandbox { action, .. } => Scope {
+                sandbox: !matches!(action, SandboxAction::Status {}),
+                change: false,
+            },
+            SlamCommand::Create(args) => Scope {
+                sandbox: !args.remote,
+                change: args.change_id.is_some(),
+            },
+            SlamCommand::Publish { .. } => Scope {
+                sandbox: true,
+                change: true,
+            },
+            SlamCommand::Rollback { dry_run, .. } => Scope {
+                sandbox: false,
+                change: !dry_run,
+            },
+            SlamCommand::Review { action, .. } => match action {
+                ReviewAction::Clone { .. } => Scope {
+                    sandbox: true,
+                    change: false,
+                },
+                ReviewAction::Delete { local, .. } => Scope {
+                    sandbox: *local,
+                    change: true,
+                },
+                ReviewAction::Approve { .. } | ReviewAction::Automerge { .. } | ReviewAction::Rerun { .. } => Scope {
+                    sandbox: false,
+                    change: true,
+                },
+                ReviewAction::Purge { prefix, dry_run } => Scope {
+                    sandbox: false,
+                    change: prefix.is_some() && !dry_run,
+                },
+                ReviewAction::Ls { .. }
+                | ReviewAction::Tui { .. }
+                | ReviewAction::Stats { .. }
+                | ReviewAction::Stale { .. }
+                | ReviewAction::Checks { .. }
+                | ReviewAction::Export { .. } => Scope::default(),
+            },
+            SlamCommand::Init { direct, change_id, .. } => Scope {
+                sandbox: true,
+                change: change_id.is_some() && !direct,
+            },
+            // Each pass takes its own locks, so they aren't held while it sleeps.
+            SlamCommand::Enforce { .. } => Scope::default(),
+            SlamCommand::Status { .. }
+            | SlamCommand::Audit { .. }
+            | SlamCommand::Logs { .. }
+            | SlamCommand::Completions { .. }
+            | SlamCommand::Manpage { .. } => Scope::default(),
+        }
+    }
+}
+
+/// Arguments for `slam create`.
+#[derive(Args, Debug)]
+pub struct CreateArgs {
+    #[arg(short = 'f', long, help = "Glob pattern to find files within each repository")]
+    pub files: Vec<String>,
+
+    #[arg(
+        short = 'x',
+        long,
+        help = "Change ID used to create branches and PRs (default: '<branch-prefix>-<YYYY-MM-DDT..>')"
+    )]
+    pub change_id: Option<String>,
+
+    #[arg(
+        long,
+        requires = "change_id",
+        help = "Replace what git doesn't allow in branch names in --change-id (spaces, ~, ^, .., ...) instead of failing"
+    )]
+    pub sanitize: bool,
+
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        conflicts_with = "change_id",
+        help = "Build the change ID from a template, e.g. 'SLAM-{{ticket}}-{{date}}' (default: create.change_id_template in slam.yml)"
+    )]
+    pub change_id_template: Option<String>,
+
+    #[arg(
+        long = "var",
+        value_name = "KEY=VALUE",
+        value_parser = parse_var,
+        help = "Variable for the change ID template (repeatable), e.g. --var ticket=OPS-12"
+    )]
+    pub vars: Vec<(String, String)>,
+
+    #[arg(
+        long,
+        value_name = "KEY",
+        value_parser = crate::jira::validate_ticket,
+        help = "Jira ticket the change is for, e.g. OPS-123; goes into the branch name, commit message and PR body"
+    )]
+    pub ticket: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "ACTION",
+        help = "When the change's branch already exists on a remote: ask, warn, or error (default: create.on_collision in slam.yml, else ask)"
+    )]
+    pub on_collision: Option<Collision>,
+
+    #[arg(
+        short = 'b',
+        long,
+        default_value_t = 1,
+        value_parser = validate_buffer,
+        help = "Number of context lines in the diff output (1 to 50), or full for whole files"
+    )]
+    pub buffer: usize,
+
+    #[arg(short = 'r', long, help = "Patterns for repo filtering")]
+    pub repo_ptns: Vec<String>,
+
+    #[arg(
+        long,
+        requires = "orgs",
+        conflicts_with_all = ["no_verify", "hooks", "sign", "signing_key", "signing_format", "sign_push"],
+        help = "Change the repos of --org through the GitHub API instead of the sandbox clones (no hooks, no signing)"
+    )]
+    pub remote: bool,
+
+    #[arg(
+        short = 'o',
+        long = "org",
+        value_name = "ORG",
+        requires = "remote",
+        help = "GitHub organization whose repos --remote changes (repeatable)"
+    )]
+    pub orgs: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Also match generated files (lockfiles, dist/, vendor/, @generated, linguist-generated)"
+    )]
+    pub include_generated: bool,
+
+    #[arg(
+        long,
+        value_name = "GLOB",
+        help = "Only target repos with a file or directory matching this glob, e.g. Dockerfile (repeatable; all must match)"
+    )]
+    pub require_file: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "REGEX",
+        requires = "files",
+        value_parser = parse_regex,
+        help = "Only change matched files whose content matches this regex"
+    )]
+    pub only_if: Option<Regex>,
+
+    #[arg(
+        long,
+        value_name = "REGEX",
+        requires = "files",
+        value_parser = parse_regex,
+        help = "Leave matched files whose content matches this regex alone"
+    )]
+    pub skip_if: Option<Regex>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Template file for the PR body (default: create.body_template in slam.yml)"
+    )]
+    pub body_template: Option<PathBuf>,
+
+    #[arg(
+        short = 'd',
+        long,
+        help = "Description exposed to the PR body template as {{description}}"
+    )]
+    pub description: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        help = "Commit message template, e.g. 'chore({{change_id}}): {{message}}' (default: create.commit_template in slam.yml)"
+    )]
+    pub commit_template: Option<String>,
+
+    #[arg(long, help = "Require commit messages to follow the Conventional Commits format")]
+    pub conventional: bool,
+
+    #[arg(
+        long,
+        visible_alias = "skip-hooks",
+        conflicts_with = "hooks",
+        help = "Don't run pre-commit hooks (nor git hooks on commit)"
+    )]
+    pub no_verify: bool,
+
+    #[arg(
+        long = "hook",
+        value_name = "ID",
+        help = "Only run this pre-commit hook (repeatable), e.g. --hook black"
+    )]
+    pub hooks: Vec<String>,
+
+    #[arg(long, help = "Sign commits with GPG/SSH (default: create.sign in slam.yml)")]
+    pub sign: bool,
+
+    #[arg(
+        long,
+        value_name = "KEY",
+        help = "Key to sign with: a GPG key id or an SSH public key path (implies --sign)"
+    )]
+    pub signing_key: Option<String>,
+
+    #[arg(long, value_enum, value_name = "FORMAT", help = "Signature format (implies --sign)")]
+    pub signing_format: Option<SigningFormat>,
+
+    #[arg(long, help = "Also sign pushes where the remote accepts it (implies --sign)")]
+    pub sign_push: bool,
+
+    #[arg(
+        long,
+        value_name = "NAME <EMAIL>",
+        value_parser = parse_identity,
+        help = "Commit author, e.g. 'Bot <bot@corp>' (default: $SLAM_AUTHOR, then create.author in slam.yml)"
+    )]
+    pub author: Option<Identity>,
+
+    #[arg(
+        long,
+        value_name = "NAME <EMAIL>",
+        value_parser = parse_identity,
+        help = "Committer (default: $SLAM_COMMITTER, then create.committer in slam.yml, then the author)"
+    )]
+    pub committer: Option<Identity>,
+
+    #[arg(long, help = "Don't request reviews from the CODEOWNERS of the changed files")]
+    pub no_codeowners: bool,
+
+    #[arg(long, value_name = "TITLE", help = "Put each PR on this (open) milestone")]
+    pub milestone: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "TITLE",
+        help = "Add each PR to this project board of the repo's owner"
+    )]
+    pub project: Option<String>,
+
+    #[arg(
+        long,
+        help = "Force-push to the change's existing branch and keep its open PR, refreshing the body, instead of closing it and opening a new one"
+    )]
+    pub update_pr: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "remote",
+        help = "Push to your fork of each repo (forking it if needed) and open cross-fork PRs (default: create.fork in slam.yml)"
+    )]
+    pub fork: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["remote", "update_pr"],
+        help = "Commit to a local branch named after the change id in each clone and stop; `slam publish` pushes them"
+    )]
+    pub stage: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "remote",
+        help = "Change and commit matched files git doesn't track too, copied from the clone; by default they are left out"
+    )]
+    pub include_untracked: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "remote",
+        help = "Show every repo's diff and ask before pushing anything"
+    )]
+    pub plan: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "remote",
+        help = "Push the repos that prepared cleanly even when others failed (by default nothing is pushed then)"
+    )]
+    pub keep_going: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Per-repo overrides (skip, files, replacement) keyed by repo pattern (default: <workdir>/slam-overrides.yaml)"
+    )]
+    pub overrides: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        value_parser = validate_report_path,
+        help = "Also write a Markdown (.md) or HTML (.html) report of the repos, PRs and diffs"
+    )]
+    pub report: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Also write the opened and updated PRs to PATH, one `org/repo url` per line"
+    )]
+    pub pr_urls: Option<PathBuf>,
+
+    #[arg(long, help = "Also copy the opened and updated PRs to the clipboard")]
+    pub copy_pr_urls: bool,
+
+    #[arg(
+        long,
+        help = "Pick the repos to act on from a fuzzy-searchable list of the matched ones"
+    )]
+    pub pick: bool,
 
-        #[command(subcommand)]
-        action: ReviewAction,
-    },
+    #[command(subcommand)]
+    pub action: Option<CreateAction>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -102,8 +996,22 @@ pub enum CreateAction {
     Add {
         #[arg(value_name = "PATH", help = "Relative path for the new file")]
         path: String,
-        #[arg(value_name = "CONTENT", help = "Contents to write into the file")]
-        content: String,
+        #[arg(
+            value_name = "CONTENT",
+            required_unless_present = "from",
+            conflicts_with = "from",
+            help = "Contents to write into the file, or - to read them from stdin"
+        )]
+        content: Option<String>,
+        #[arg(long, value_name = "FILE", help = "Read the contents from a local file")]
+        from: Option<PathBuf>,
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = IfExists::Fail,
+            help = "What to do when the file already exists with different contents"
+        )]
+        if_exists: IfExists,
         #[arg(
             short = 'c',
             long,
@@ -130,6 +1038,28 @@ pub enum CreateAction {
         simplified: bool,
     },
 
+    /// Delete a directory and every file git tracks in it
+    DeleteDir {
+        #[arg(value_name = "DIR", value_parser = validate_repo_dir, help = "Directory relative to the repository root")]
+        dir: String,
+        #[arg(
+            long,
+            default_value_t = 500,
+            help = "Refuse to delete a directory holding more tracked files than this"
+        )]
+        max_files: usize,
+        #[arg(
+            short = 'c',
+            long,
+            help = "Commit deletion with an optional message",
+            num_args = 0..=1,
+            default_missing_value = "Automated update generated by SLAM"
+        )]
+        commit: Option<String>,
+        #[arg(short = 's', long, help = "Do not display diff output; only list matched files")]
+        simplified: bool,
+    },
+
     /// Substring and replacement (requires two arguments)
     Sub {
         #[arg(value_name = "PTN", help = "Substring pattern to match")]
@@ -165,18 +1095,329 @@ pub enum CreateAction {
         #[arg(short = 's', long, help = "Do not display diff output; only list matched files")]
         simplified: bool,
     },
+
+    /// Replace many literals at once from a table of OLD<TAB>NEW lines, in one pass per file
+    MultiSub {
+        #[arg(
+            value_name = "TABLE",
+            help = "File of OLD<TAB>NEW lines (# comments allowed), or - for stdin"
+        )]
+        table: PathBuf,
+        #[arg(
+            short = 'c',
+            long,
+            help = "Commit changes with an optional message",
+            num_args = 0..=1,
+            default_missing_value = "Automated update generated by SLAM"
+        )]
+        commit: Option<String>,
+        #[arg(short = 's', long, help = "Do not display diff output; only list matched files")]
+        simplified: bool,
+    },
+
+    /// Replace each pattern of a CSV or JSON mapping file with its replacement
+    Map {
+        #[arg(
+            value_name = "FILE",
+            help = "A .csv of pattern,replacement rows or a .json object of pattern: replacement"
+        )]
+        file: PathBuf,
+        #[arg(
+            short = 'c',
+            long,
+            help = "Commit changes with an optional message",
+            num_args = 0..=1,
+            default_missing_value = "Automated update generated by SLAM"
+        )]
+        commit: Option<String>,
+        #[arg(short = 's', long, help = "Do not display diff output; only list matched files")]
+        simplified: bool,
+    },
+
+    /// Copy a local directory tree into every repo
+    Copy {
+        #[arg(value_name = "SOURCE_DIR", help = "Local directory whose files are copied")]
+        source_dir: PathBuf,
+        #[arg(
+            value_name = "DEST",
+            value_parser = validate_copy_dest,
+            help = "Directory relative to the repository root to copy into; . for the root"
+        )]
+        dest: String,
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = IfExists::Fail,
+            help = "What to do when a file already exists with different contents"
+        )]
+        if_exists: IfExists,
+        #[arg(
+            short = 'c',
+            long,
+            help = "Commit changes with an optional message",
+            num_args = 0..=1,
+            default_missing_value = "Automated update generated by SLAM"
+        )]
+        commit: Option<String>,
+        #[arg(short = 's', long, help = "Do not display diff output; only list matched files")]
+        simplified: bool,
+    },
+
+    /// Set the mode of matching files, e.g. make scripts executable
+    Chmod {
+        #[arg(
+            value_name = "MODE",
+            help = "+x (or 755) to make files executable, -x (or 644) to clear it"
+        )]
+        mode: FileMode,
+        #[arg(
+            short = 'c',
+            long,
+            help = "Commit changes with an optional message",
+            num_args = 0..=1,
+            default_missing_value = "Automated update generated by SLAM"
+        )]
+        commit: Option<String>,
+        #[arg(short = 's', long, help = "Do not display diff output; only list matched files")]
+        simplified: bool,
+    },
+}
+
+/// The contents `add` writes: `--from`'s file, stdin for `-`, or the argument itself.
+fn add_content(content: Option<String>, from: Option<PathBuf>) -> eyre::Result<String> {
+    use eyre::WrapErr;
+    use std::io::Read;
+    match (content, from) {
+        (_, Some(path)) => std::fs::read_to_string(slam_core::utils::expand_tilde(&path))
+            .wrap_err_with(|| format!("Failed to read '{}'", path.display())),
+        (Some(content), None) if content == "-" => {
+            let mut content = String::new();
+            std::io::stdin()
+                .read_to_string(&mut content)
+                .wrap_err("Failed to read the file contents from stdin")?;
+            Ok(content)
+        }
+        (content, None) => Ok(content.unwrap_or_default()),
+    }
+}
+
+/// Adds `old -> new` to `pairs`, refusing an empty or repeated pattern; `at` locates the entry
+/// in the errors, e.g. `Line 3`.
+fn push_pair(pairs: &mut Vec<(String, String)>, at: &str, old: &str, new: &str) -> eyre::Result<()> {
+    if old.is_empty() {
+        return Err(eyre::eyre!("{}: the pattern is empty", at));
+    }
+    if pairs.iter().any(|(pattern, _)| pattern == old) {
+        return Err(eyre::eyre!("{}: '{}' is already in the table", at, old));
+    }
+    pairs.push((old.to_string(), new.to_string()));
+    Ok(())
+}
+
+/// The `(pattern, replacement)` pairs of a `multi-sub` table: one `OLD<TAB>NEW` line each,
+/// skipping blank lines and `#` comments. NEW may be empty; OLD may not, nor appear twice.
+fn parse_sub_table(text: &str) -> eyre::Result<Vec<(String, String)>> {
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    for (number, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line)) {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((old, new)) = line.split_once('\t') else {
+            return Err(eyre::eyre!("Line {}: expected OLD<TAB>NEW, found no tab", number));
+        };
+        push_pair(&mut pairs, &format!("Line {}", number), old, new)?;
+    }
+    if pairs.is_empty() {
+        return Err(eyre::eyre!("The substitution table is empty"));
+    }
+    Ok(pairs)
+}
+
+/// The pairs of a `map` file in CSV: two fields per row, optionally double-quoted (with `""` for
+/// a quote), after an optional `pattern,replacement` header.
+fn parse_csv_map(text: &str) -> eyre::Result<Vec<(String, String)>> {
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    for (number, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line)) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = csv_fields(line).map_err(|e| eyre::eyre!("Row {}: {}", number, e))?;
+        let [old, new] = fields.as_slice() else {
+            return Err(eyre::eyre!("Row {}: expected 2 fields, found {}", number, fields.len()));
+        };
+        if pairs.is_empty() && old == "pattern" && new == "replacement" {
+            continue;
+        }
+        push_pair(&mut pairs, &format!("Row {}", number), old, new)?;
+    }
+    if pairs.is_empty() {
+        return Err(eyre::eyre!("The map is empty"));
+    }
+    Ok(pairs)
+}
+
+/// The comma-separated fields of one CSV line.
+fn csv_fields(line: &str) -> eyre::Result<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        let mut field = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err(eyre::eyre!("unterminated quoted field")),
+                }
+            }
+            if !matches!(chars.peek(), None | Some(',')) {
+                return Err(eyre::eyre!("unexpected text after a quoted field"));
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| *c != ',') {
+                field.push(c);
+            }
+        }
+        fields.push(field);
+        if chars.next().is_none() {
+            return Ok(fields);
+        }
+    }
+}
+
+/// The pairs of a `map` file in JSON: an object of `"pattern": "replacement"` members, or an
+/// array of `["pattern", "replacement"]` pairs when the order should be kept.
+fn parse_json_map(text: &str) -> eyre::Result<Vec<(String, String)>> {
+    let value: serde_json::Value = serde_json::from_str(text)?;
+    let entries: Vec<(String, serde_json::Value, serde_json::Value)> = match value {
+        serde_json::Value::Object(members) => members
+            .into_iter()
+            .map(|(old, new)| (format!("Key '{}'", old), serde_json::Value::String(old), new))
+            .collect(),
+        serde_json::Value::Array(items) => items
+            .into_iter()
+            .enumerate()
+            .map(|(i, item)| match item {
+                serde_json::Value::Array(pair) if pair.len() == 2 => {
+                    let mut pair = pair.into_iter();
+                    Ok((format!("Entry {}", i + 1), pair.next().unwrap(), pair.next().unwrap()))
+                }
+                _ => Err(eyre::eyre!("Entry {}: expected a [pattern, replacement] pair", i + 1)),
+            })
+            .collect::<eyre::Result<_>>()?,
+        _ => return Err(eyre::eyre!("expected an object or an array of pairs")),
+    };
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    for (at, old, new) in entries {
+        let (serde_json::Value::String(old), serde_json::Value::String(new)) = (old, new) else {
+            return Err(eyre::eyre!("{}: the pattern and replacement must be strings", at));
+        };
+        push_pair(&mut pairs, &at, &old, &new)?;
+    }
+    if pairs.is_empty() {
+        return Err(eyre::eyre!("The map is empty"));
+    }
+    Ok(pairs)
+}
+
+/// The text of `path`, or of stdin for `-`; `what` names it in the errors.
+fn read_input(path: &std::path::Path, what: &str) -> eyre::Result<String> {
+    use eyre::WrapErr;
+    use std::io::Read;
+    if path.as_os_str() == "-" {
+        let mut text = String::new();
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .wrap_err_with(|| format!("Failed to read the {} from stdin", what))?;
+        return Ok(text);
+    }
+    std::fs::read_to_string(slam_core::utils::expand_tilde(path))
+        .wrap_err_with(|| format!("Failed to read '{}'", path.display()))
+}
+
+/// Reads a `multi-sub` table from `path`, or stdin for `-`.
+fn read_sub_table(path: &std::path::Path) -> eyre::Result<Vec<(String, String)>> {
+    let text = read_input(path, "substitution table")?;
+    parse_sub_table(&text).map_err(|e| eyre::eyre!("Invalid substitution table '{}': {}", path.display(), e))
+}
+
+/// Reads a `map` file, parsed as CSV or JSON by its extension.
+fn read_map_file(path: &std::path::Path) -> eyre::Result<Vec<(String, String)>> {
+    let parse = match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("csv") => parse_csv_map,
+        Some(ext) if ext.eq_ignore_ascii_case("json") => parse_json_map,
+        _ => return Err(eyre::eyre!("'{}' is not a .csv or .json file", path.display())),
+    };
+    let text = read_input(path, "map")?;
+    parse(&text).map_err(|e| eyre::eyre!("Invalid map '{}': {}", path.display(), e))
+}
+
+/// Every file under `source`, skipping `.git`, as `(path under dest, contents)` sorted by path.
+fn copy_files(source: &std::path::Path, dest: &str) -> eyre::Result<Vec<(String, String)>> {
+    use eyre::WrapErr;
+    let source = slam_core::utils::expand_tilde(source);
+    if !source.is_dir() {
+        return Err(eyre::eyre!("'{}' is not a directory", source.display()));
+    }
+    let mut files = Vec::new();
+    let mut dirs = vec![source.clone()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir).wrap_err_with(|| format!("Failed to read '{}'", dir.display()))? {
+            let path = entry?.path();
+            if path.is_dir() {
+                if path.file_name().is_some_and(|name| name != ".git") {
+                    dirs.push(path);
+                }
+                continue;
+            }
+            let relative = path.strip_prefix(&source)?.to_string_lossy().replace('\\', "/");
+            let contents = std::fs::read_to_string(&path)
+                .wrap_err_with(|| format!("Failed to read '{}' (only text files can be copied)", path.display()))?;
+            let target = if dest == "." {
+                relative
+            } else {
+                format!("{}/{}", dest, relative)
+            };
+            files.push((target, contents));
+        }
+    }
+    if files.is_empty() {
+        return Err(eyre::eyre!("'{}' holds no files to copy", source.display()));
+    }
+    files.sort();
+    Ok(files)
 }
 
 impl CreateAction {
-    pub fn decompose(self) -> (Change, Option<String>, bool) {
-        match self {
+    /// Splits the action into its change, commit message and `--simplified` flag, reading
+    /// `add`'s contents from stdin or `--from` where asked, the tables of `multi-sub` and
+    /// `map` and `copy`'s source files.
+    pub fn decompose(self) -> eyre::Result<(Change, Option<String>, bool)> {
+        Ok(match self {
             CreateAction::Delete { commit, simplified } => (Change::Delete, commit, simplified),
+            CreateAction::DeleteDir {
+                dir,
+                max_files,
+                commit,
+                simplified,
+            } => (Change::DeleteDir(dir, max_files), commit, simplified),
             CreateAction::Add {
                 path,
                 content,
+                from,
+                if_exists,
                 commit,
                 simplified,
-            } => (Change::Add(path, content), commit, simplified),
+            } => (
+                Change::Add(path, add_content(content, from)?, if_exists),
+                commit,
+                simplified,
+            ),
             CreateAction::Sub {
                 ptn,
                 repl,
@@ -189,81 +1430,436 @@ impl CreateAction {
                 commit,
                 simplified,
             } => (Change::Regex(ptn, repl), commit, simplified),
-        }
+            CreateAction::MultiSub {
+                table,
+                commit,
+                simplified,
+            } => (Change::MultiSub(read_sub_table(&table)?), commit, simplified),
+            CreateAction::Map {
+                file,
+                commit,
+                simplified,
+            } => (Change::MultiSub(read_map_file(&file)?), commit, simplified),
+            CreateAction::Copy {
+                source_dir,
+                dest,
+                if_exists,
+                commit,
+                simplified,
+            } => (
+                Change::Copy(copy_files(&source_dir, &dest)?, if_exists),
+                commit,
+                simplified,
+            ),
+            CreateAction::Chmod {
+                mode,
+                commit,
+                simplified,
+            } => (Change::Chmod(mode), commit, simplified),
+        })
     }
 }
 
-#[derive(Subcommand, Debug)]
-pub enum ReviewAction {
-    #[command(about = "List Change IDs matching the given pattern")]
-    Ls {
+#[derive(Subcommand, Debug)]
+pub enum ReviewAction {
+    #[command(about = "List Change IDs matching the given pattern")]
+    Ls {
+        #[arg(
+            value_name = "CHANGE_ID_PTNS",
+            num_args = 0..,
+            help = "Optional list of Change IDs to filter by. Uses prefix matching (e.g. Change IDs starting with SLAM)"
+        )]
+        change_id_ptns: Vec<String>,
+
+        #[arg(
+            short = 'b',
+            long,
+            default_value_t = 1,
+            value_parser = validate_buffer,
+            help = "Number of context lines in the diff output (1 to 50), or full for whole files"
+        )]
+        buffer: usize,
+
+        #[arg(
+            long,
+            value_name = "GLOB",
+            help = "Only show the diffs of files matching the glob (repeatable), e.g. '.github/workflows/*'"
+        )]
+        path: Vec<String>,
+
+        #[arg(
+            long,
+            value_name = "PATH",
+            value_parser = validate_report_path,
+            help = "Also write a Markdown (.md) or HTML (.html) report of the repos, PRs and diffs"
+        )]
+        report: Option<PathBuf>,
+
+        #[arg(
+            long,
+            conflicts_with = "report",
+            help = "Show a table of PR counts per Change ID (approved, checks passing, mergeable) instead of diffs"
+        )]
+        summary: bool,
+
+        #[arg(
+            long,
+            conflicts_with_all = ["report", "summary"],
+            help = "Show a table with one row per PR (age, author, checks, review) instead of diffs"
+        )]
+        table: bool,
+
+        #[arg(long, value_name = "TITLE", help = "Only show PRs on this milestone")]
+        milestone: Option<String>,
+
+        #[arg(
+            long,
+            value_enum,
+            requires = "table",
+            default_value_t = LsSort::Repo,
+            help = "Order of the --table rows"
+        )]
+        sort: LsSort,
+
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            requires = "table",
+            value_name = "COLUMNS",
+            help = "Comma-separated --table columns to show [default: repo,pr,age,author,checks,review,mergeable]"
+        )]
+        columns: Vec<LsColumn>,
+
+        #[arg(
+            long,
+            value_name = "N",
+            value_parser = clap::value_parser!(u64).range(1..),
+            conflicts_with = "summary",
+            help = "Show at most N PRs, in repo order (see --page)"
+        )]
+        limit: Option<u64>,
+
+        #[arg(
+            long,
+            value_name = "P",
+            default_value_t = 1,
+            value_parser = clap::value_parser!(u64).range(1..),
+            requires = "limit",
+            help = "Which run of --limit PRs to show, from 1"
+        )]
+        page: u64,
+
+        #[arg(
+            long,
+            conflicts_with_all = ["report", "summary", "table"],
+            help = "Show one PR's diff at a time, waiting for Enter before the next"
+        )]
+        step: bool,
+    },
+    #[command(
+        about = "Summarize the PRs of a rollout across the org: open/merged/closed, time to merge, pending reviews and blockers"
+    )]
+    Stats {
+        #[arg(
+            value_name = "CHANGE_ID_PTNS",
+            num_args = 0..,
+            help = "Optional list of Change IDs to count. Uses prefix matching (default: every Change ID with the branch prefix)"
+        )]
+        change_id_ptns: Vec<String>,
+    },
+    #[command(about = "Clone all repos that have an open PR for the given Change ID")]
+    Clone {
+        #[arg(
+            value_name = "CHANGE_ID",
+            help = "Change ID used to find the PR (exact match required)"
+        )]
+        change_id: String,
+
+        #[arg(short, long, help = "Pass `--all` to clone all repos, even with closed PRs")]
+        all: bool,
+    },
+    #[command(about = "Approve a specific PR & merge it per matched repos, identified by its Change ID")]
+    Approve {
+        #[arg(
+            value_name = "CHANGE_ID",
+            help = "Change ID used to find the PR (exact match required)"
+        )]
+        change_id: String,
+
+        #[arg(long, help = "Pass `--admin` to `gh pr merge` to bypass failing checks")]
+        admin_override: bool,
+
+        #[arg(
+            short = 'r',
+            long = "repo",
+            help = "Only act on repos matching these patterns (name or org/name glob)"
+        )]
+        repos: Vec<String>,
+
+        #[arg(
+            short = 'e',
+            long,
+            help = "Skip repos matching these patterns (name or org/name glob)"
+        )]
+        exclude: Vec<String>,
+    },
+    #[command(
+        about = "Merge each PR for the given Change ID once its checks pass, via the forge's auto-merge or --watch"
+    )]
+    Automerge {
+        #[arg(
+            value_name = "CHANGE_ID",
+            help = "Change ID used to find the PR (exact match required)"
+        )]
+        change_id: String,
+
+        #[arg(
+            long,
+            help = "Instead of enabling auto-merge, keep polling and merge each PR as soon as it is ready"
+        )]
+        watch: bool,
+
         #[arg(
-            value_name = "CHANGE_ID_PTNS",
-            num_args = 0..,
-            help = "Optional list of Change IDs to filter by. Uses prefix matching (e.g. Change IDs starting with SLAM)"
+            long,
+            value_name = "SECS",
+            default_value_t = 60,
+            value_parser = clap::value_parser!(u64).range(1..),
+            requires = "watch",
+            help = "Seconds between polls with --watch"
         )]
-        change_id_ptns: Vec<String>,
+        interval: u64,
 
         #[arg(
-            short = 'b',
+            short = 'r',
+            long = "repo",
+            help = "Only act on repos matching these patterns (name or org/name glob)"
+        )]
+        repos: Vec<String>,
+
+        #[arg(
+            short = 'e',
             long,
-            default_value_t = 1,
-            value_parser = validate_buffer,
-            help = "Number of context lines in the diff output (must be between 1 and 3)"
+            help = "Skip repos matching these patterns (name or org/name glob)"
         )]
-        buffer: usize,
+        exclude: Vec<String>,
     },
-    #[command(about = "Clone all repos that have an open PR for the given Change ID")]
-    Clone {
+    #[command(about = "Delete a PR & branches per matched repos, identified by its Change ID")]
+    Delete {
+        #[arg(
+            value_name = "CHANGE_ID",
+            help = "Change ID used to find the PR to delete (exact match required)"
+        )]
+        change_id: String,
+
+        #[arg(
+            short = 'r',
+            long = "repo",
+            help = "Only act on repos matching these patterns (name or org/name glob)"
+        )]
+        repos: Vec<String>,
+
+        #[arg(
+            short = 'e',
+            long,
+            help = "Skip repos matching these patterns (name or org/name glob)"
+        )]
+        exclude: Vec<String>,
+
+        #[arg(
+            long,
+            help = "Also delete the local branch in each sandbox clone, checking out the default branch first"
+        )]
+        local: bool,
+    },
+    #[command(
+        about = "List the failing and pending checks on each PR for the given Change ID, with links to their runs"
+    )]
+    Checks {
         #[arg(
             value_name = "CHANGE_ID",
             help = "Change ID used to find the PR (exact match required)"
         )]
         change_id: String,
 
-        #[arg(short, long, help = "Pass `--all` to clone all repos, even with closed PRs")]
-        all: bool,
+        #[arg(
+            short = 'r',
+            long = "repo",
+            help = "Only act on repos matching these patterns (name or org/name glob)"
+        )]
+        repos: Vec<String>,
+
+        #[arg(
+            short = 'e',
+            long,
+            help = "Skip repos matching these patterns (name or org/name glob)"
+        )]
+        exclude: Vec<String>,
     },
-    #[command(about = "Approve a specific PR & merge it per matched repos, identified by its Change ID")]
-    Approve {
+    #[command(about = "Write each PR for the given Change ID to a .patch file that git am can apply")]
+    Export {
         #[arg(
             value_name = "CHANGE_ID",
             help = "Change ID used to find the PR (exact match required)"
         )]
         change_id: String,
 
-        #[arg(long, help = "Pass `--admin` to `gh pr merge` to bypass failing checks")]
-        admin_override: bool,
+        #[arg(
+            long,
+            value_name = "DIR",
+            default_value = "patches",
+            help = "Directory to write <org>/<repo>.patch files into"
+        )]
+        dir: PathBuf,
+
+        #[arg(
+            short = 'r',
+            long = "repo",
+            help = "Only act on repos matching these patterns (name or org/name glob)"
+        )]
+        repos: Vec<String>,
+
+        #[arg(
+            short = 'e',
+            long,
+            help = "Skip repos matching these patterns (name or org/name glob)"
+        )]
+        exclude: Vec<String>,
     },
-    #[command(about = "Delete a PR & branches per matched repos, identified by its Change ID")]
-    Delete {
+    #[command(about = "Re-run the failed workflow runs on each PR for the given Change ID")]
+    Rerun {
         #[arg(
             value_name = "CHANGE_ID",
-            help = "Change ID used to find the PR to delete (exact match required)"
+            help = "Change ID used to find the PR (exact match required)"
         )]
         change_id: String,
+
+        #[arg(
+            long = "check",
+            value_name = "NAME",
+            help = "Only re-run workflows with this name (repeatable); default: every failed one"
+        )]
+        checks: Vec<String>,
+
+        #[arg(
+            short = 'r',
+            long = "repo",
+            help = "Only act on repos matching these patterns (name or org/name glob)"
+        )]
+        repos: Vec<String>,
+
+        #[arg(
+            short = 'e',
+            long,
+            help = "Skip repos matching these patterns (name or org/name glob)"
+        )]
+        exclude: Vec<String>,
+    },
+    #[command(about = "Interactive dashboard to browse, open and approve PRs per Change ID")]
+    Tui {
+        #[arg(
+            value_name = "CHANGE_ID_PTNS",
+            num_args = 0..,
+            help = "Optional list of Change IDs to filter by. Uses prefix matching (e.g. Change IDs starting with SLAM)"
+        )]
+        change_id_ptns: Vec<String>,
+    },
+    #[command(
+        about = "Purge: close every PR and delete every remote branch with the branch prefix (default: SLAM) for each matching repo"
+    )]
+    Purge {
+        #[arg(
+            long,
+            value_name = "CHANGE_ID_PREFIX",
+            help = "Only purge branches starting with this Change ID prefix (e.g. SLAM-2024-05-02)"
+        )]
+        prefix: Option<String>,
+
+        #[arg(
+            long,
+            help = "List the PRs and branches that would be closed and deleted, without touching them"
+        )]
+        dry_run: bool,
     },
     #[command(
-        about = "Purge: close every PR and delete every remote branch prefixed with SLAM for each matching repo"
+        about = "List the branches with the branch prefix whose last commit is older than a threshold, with their PRs"
     )]
-    Purge {},
+    Stale {
+        #[arg(
+            long,
+            value_name = "AGE",
+            value_parser = parse_interval,
+            help = "How long ago a branch's last commit must be for it to count as stale, e.g. 30d"
+        )]
+        older_than: Duration,
+
+        #[arg(long, help = "Close the PRs of the stale branches and delete the branches")]
+        delete: bool,
+    },
+}
+
+impl ReviewAction {
+    /// The per-repo operation this action performs; listing, checks and the dashboard only show diffs.
+    pub fn op(&self) -> ReviewOp {
+        match self {
+            ReviewAction::Ls { buffer, .. } => ReviewOp::Show { buffer: *buffer },
+            ReviewAction::Tui { .. }
+            | ReviewAction::Checks { .. }
+            | ReviewAction::Export { .. }
+            | ReviewAction::Stats { .. }
+            | ReviewAction::Stale { .. } => ReviewOp::Show { buffer: 1 },
+            ReviewAction::Clone { .. } => ReviewOp::Clone,
+            ReviewAction::Approve { admin_override, .. } => ReviewOp::Approve {
+                admin_override: *admin_override,
+            },
+            ReviewAction::Automerge { .. } => ReviewOp::AutoMerge,
+            ReviewAction::Delete { local, .. } => ReviewOp::Delete { local: *local },
+            ReviewAction::Rerun { checks, .. } => ReviewOp::Rerun { checks: checks.clone() },
+            ReviewAction::Purge { dry_run, .. } => ReviewOp::Purge { dry_run: *dry_run },
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
 pub enum SandboxAction {
     /// Set up sandbox environment
-    Setup {},
+    Setup {
+        #[arg(
+            short = 'o',
+            long = "org",
+            default_value = "tatari-tv",
+            help = "GitHub organization to clone repos from (repeatable)"
+        )]
+        orgs: Vec<String>,
+        #[command(flatten)]
+        filter: RepoFilter,
+        #[arg(
+            long,
+            value_enum,
+            help = "Clone new repos over ssh or https (default: network.protocol in slam.yml, else ssh)"
+        )]
+        protocol: Option<Protocol>,
+    },
     /// Refresh sandbox by resetting and pulling repositories
     Refresh {},
+    /// Show branch, dirty state and ahead/behind counts per repo without changing anything
+    Status {},
+    /// Report clones whose upstream repo is archived, deleted or renamed
+    Prune {
+        #[arg(long, help = "Delete the reported clones (those with uncommitted changes are kept)")]
+        remove: bool,
+    },
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use clap::CommandFactory;
+    use slam_core::git::Visibility;
 
     #[test]
     fn test_default_change_id_format() {
-        let change_id = default_change_id();
+        let change_id = default_change_id(DEFAULT_BRANCH_PREFIX);
         assert!(change_id.starts_with("SLAM-"));
 
         // Should be in format SLAM-YYYY-MM-DDTHH-MM-SS
@@ -276,25 +1872,125 @@ mod tests {
         assert_eq!(timestamp_part.chars().nth(16), Some('-'));
     }
 
+    #[test]
+    fn test_render_change_id() {
+        let now = DateTime::parse_from_rfc3339("2024-05-02T10:30:15+00:00")
+            .unwrap()
+            .with_timezone(&Local);
+        let vars = [("ticket".to_string(), "OPS-12".to_string())];
+        let date = now.format("%Y-%m-%d").to_string();
+        assert_eq!(
+            render_change_id("SLAM-{{ticket}}-{{ date }}", "SLAM", &vars, now),
+            Ok(format!("SLAM-OPS-12-{}", date))
+        );
+        assert_eq!(
+            render_change_id("{{prefix}}-{{timestamp}}", "SLAM", &[], now),
+            Ok(now.format("SLAM-%Y-%m-%dT%H-%M-%S").to_string())
+        );
+        let err = render_change_id("SLAM-{{ticket}}", "SLAM", &[], now).unwrap_err();
+        assert!(err.contains("'ticket'"));
+        let bad = [("ticket".to_string(), "two words".to_string())];
+        assert!(render_change_id("SLAM-{{ticket}}", "SLAM", &bad, now).is_err());
+    }
+
+    #[test]
+    fn test_change_id_template_flags() {
+        let cli = SlamCli::try_parse_from([
+            "slam",
+            "create",
+            "--change-id-template",
+            "SLAM-{{ticket}}",
+            "--var",
+            "ticket=OPS-12",
+            "--on-collision",
+            "error",
+        ])
+        .unwrap();
+        let SlamCommand::Create(args) = cli.command else {
+            panic!("expected create");
+        };
+        assert_eq!(args.vars, [("ticket".to_string(), "OPS-12".to_string())]);
+        assert_eq!(args.on_collision, Some(Collision::Error));
+        assert_eq!(args.ticket, None);
+        let cli = SlamCli::try_parse_from(["slam", "create", "--ticket", "OPS-123"]).unwrap();
+        let SlamCommand::Create(args) = cli.command else {
+            panic!("expected create");
+        };
+        assert_eq!(args.ticket.as_deref(), Some("OPS-123"));
+        assert!(SlamCli::try_parse_from(["slam", "create", "--ticket", "ops-123"]).is_err());
+        assert!(SlamCli::try_parse_from(["slam", "create", "--var", "no-equals"]).is_err());
+        assert!(SlamCli::try_parse_from([
+            "slam",
+            "create",
+            "-x",
+            "SLAM-x",
+            "--change-id-template",
+            "SLAM-{{date}}"
+        ])
+        .is_err());
+    }
+
     #[test]
     fn test_default_change_id_uniqueness() {
-        let id1 = default_change_id();
+        let id1 = default_change_id("SLAM");
         std::thread::sleep(std::time::Duration::from_millis(1001)); // Ensure different second
-        let id2 = default_change_id();
+        let id2 = default_change_id("SLAM");
         assert_ne!(id1, id2);
     }
 
+    #[test]
+    fn test_default_change_id_custom_prefix() {
+        assert!(default_change_id("platform/bump").starts_with("platform/bump-"));
+    }
+
+    #[test]
+    fn test_validate_and_sanitize_change_id() {
+        assert_eq!(validate_change_id("bump-node", "SLAM"), Ok("bump-node".to_string()));
+        assert!(validate_change_id("-leading-dash", "SLAM").is_ok());
+        let err = validate_change_id("bump node 20", "SLAM").unwrap_err();
+        assert!(err.contains("contains ' '"), "{}", err);
+        assert!(err.contains("--sanitize"));
+        assert!(validate_change_id("SLAM-a..b", "SLAM").is_err());
+        assert!(validate_change_id("SLAM-x/.hidden", "SLAM").is_err());
+        assert!(validate_change_id("SLAM-x.lock", "SLAM").is_err());
+        assert!(validate_change_id("SLAM-x@{1}", "SLAM").is_err());
+
+        assert_eq!(
+            sanitize_change_id("bump node:20 ~now", "SLAM").unwrap(),
+            "bump-node-20-now"
+        );
+        assert_eq!(sanitize_change_id("a..b//c/.d.lock/", "SLAM").unwrap(), "a.b/c/d");
+        assert_eq!(sanitize_change_id("  fix?  ", "SLAM").unwrap(), "fix");
+        assert!(sanitize_change_id("~^:", "SLAM").is_err());
+    }
+
+    #[test]
+    fn test_validate_branch_prefix() {
+        assert_eq!(validate_branch_prefix("SLAM"), Ok("SLAM".to_string()));
+        assert!(validate_branch_prefix("team/infra").is_ok());
+        assert!(validate_branch_prefix("").is_err());
+        assert!(validate_branch_prefix("has space").is_err());
+        assert!(validate_branch_prefix("a..b").is_err());
+        assert!(validate_branch_prefix("-flag").is_err());
+        assert!(validate_branch_prefix("trailing/").is_err());
+        assert!(validate_branch_prefix("bad~name").is_err());
+        assert!(validate_branch_prefix("team//infra").is_err());
+        assert!(validate_branch_prefix("x.lock").is_err());
+    }
+
     #[test]
     fn test_validate_buffer_valid_values() {
         assert_eq!(validate_buffer("1"), Ok(1));
         assert_eq!(validate_buffer("2"), Ok(2));
         assert_eq!(validate_buffer("3"), Ok(3));
+        assert_eq!(validate_buffer("10"), Ok(10));
+        assert_eq!(validate_buffer("full"), Ok(diff::FULL_CONTEXT));
     }
 
     #[test]
     fn test_validate_buffer_invalid_values() {
         assert!(validate_buffer("0").is_err());
-        assert!(validate_buffer("4").is_err());
+        assert!(validate_buffer("51").is_err());
         assert!(validate_buffer("-1").is_err());
         assert!(validate_buffer("abc").is_err());
         assert!(validate_buffer("").is_err());
@@ -306,10 +2002,10 @@ mod tests {
         assert!(err.contains("isn't a valid number"));
 
         let err = validate_buffer("0").unwrap_err();
-        assert!(err.contains("Buffer must be between 1 and 3"));
+        assert!(err.contains("Buffer must be between 1 and 50"));
 
-        let err = validate_buffer("4").unwrap_err();
-        assert!(err.contains("Buffer must be between 1 and 3"));
+        let err = validate_buffer("51").unwrap_err();
+        assert!(err.contains("Buffer must be between 1 and 50"));
     }
 
     #[test]
@@ -319,7 +2015,7 @@ mod tests {
             simplified: true,
         };
 
-        let (change, commit, simplified) = action.decompose();
+        let (change, commit, simplified) = action.decompose().unwrap();
         assert!(matches!(change, Change::Delete));
         assert_eq!(commit, Some("test commit".to_string()));
         assert!(simplified);
@@ -329,17 +2025,123 @@ mod tests {
     fn test_create_action_decompose_add() {
         let action = CreateAction::Add {
             path: "test.txt".to_string(),
-            content: "test content".to_string(),
+            content: Some("test content".to_string()),
+            from: None,
+            if_exists: IfExists::Skip,
             commit: None,
             simplified: false,
         };
 
-        let (change, commit, simplified) = action.decompose();
-        assert!(matches!(change, Change::Add(path, content) if path == "test.txt" && content == "test content"));
+        let (change, commit, simplified) = action.decompose().unwrap();
+        assert!(
+            matches!(change, Change::Add(path, content, IfExists::Skip) if path == "test.txt" && content == "test content")
+        );
         assert_eq!(commit, None);
         assert!(!simplified);
     }
 
+    #[test]
+    fn test_create_action_add_from_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let source = dir.path().join("ci.yml");
+        std::fs::write(&source, "on: push\n").unwrap();
+        let cli = SlamCli::try_parse_from([
+            "slam",
+            "create",
+            "add",
+            ".github/workflows/ci.yml",
+            "--from",
+            source.to_str().unwrap(),
+        ])
+        .unwrap();
+        let SlamCommand::Create(args) = cli.command else {
+            panic!("expected create");
+        };
+        let (change, _, _) = args.action.unwrap().decompose().unwrap();
+        assert!(matches!(change, Change::Add(_, content, _) if content == "on: push\n"));
+
+        assert!(SlamCli::try_parse_from(["slam", "create", "add", "a.txt"]).is_err());
+        assert!(SlamCli::try_parse_from(["slam", "create", "add", "a.txt", "x", "--from", "f"]).is_err());
+        let missing = CreateAction::Add {
+            path: "a.txt".to_string(),
+            content: None,
+            from: Some(dir.path().join("missing")),
+            if_exists: IfExists::Fail,
+            commit: None,
+            simplified: false,
+        };
+        assert!(missing.decompose().is_err());
+    }
+
+    #[test]
+    fn test_create_action_copy_reads_tree() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let source = dir.path().join("standard");
+        std::fs::create_dir_all(source.join("workflows")).unwrap();
+        std::fs::create_dir_all(source.join(".git")).unwrap();
+        std::fs::write(source.join("workflows/ci.yml"), "on: push\n").unwrap();
+        std::fs::write(source.join("CODEOWNERS"), "* @org/team\n").unwrap();
+        std::fs::write(source.join(".git/HEAD"), "ref\n").unwrap();
+
+        let copy = |dest: &str| CreateAction::Copy {
+            source_dir: source.clone(),
+            dest: dest.to_string(),
+            if_exists: IfExists::Overwrite,
+            commit: None,
+            simplified: false,
+        };
+        let (change, _, _) = copy(".github").decompose().unwrap();
+        let Change::Copy(files, IfExists::Overwrite) = change else {
+            panic!("expected a copy");
+        };
+        let paths: Vec<&str> = files.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, [".github/CODEOWNERS", ".github/workflows/ci.yml"]);
+        let (change, _, _) = copy(".").decompose().unwrap();
+        assert!(matches!(change, Change::Copy(files, _) if files[0].0 == "CODEOWNERS"));
+
+        assert_eq!(validate_copy_dest("./"), Ok(".".to_string()));
+        assert!(validate_copy_dest("../x").is_err());
+    }
+
+    #[test]
+    fn test_validate_new_reposlug() {
+        assert_eq!(
+            validate_new_reposlug("org/new-service"),
+            Ok("org/new-service".to_string())
+        );
+        assert!(validate_new_reposlug("new-service").is_err());
+        assert!(validate_new_reposlug("org/").is_err());
+        assert!(validate_new_reposlug("org/a/b").is_err());
+        assert!(validate_new_reposlug("org/.hidden").is_err());
+    }
+
+    #[test]
+    fn test_create_action_decompose_delete_dir() {
+        let action = CreateAction::DeleteDir {
+            dir: "jenkins".to_string(),
+            max_files: 50,
+            commit: None,
+            simplified: false,
+        };
+
+        let (change, _, _) = action.decompose().unwrap();
+        assert!(matches!(change, Change::DeleteDir(dir, 50) if dir == "jenkins"));
+    }
+
+    #[test]
+    fn test_validate_repo_dir() {
+        assert_eq!(validate_repo_dir("jenkins/"), Ok("jenkins".to_string()));
+        assert_eq!(validate_repo_dir("ci/legacy"), Ok("ci/legacy".to_string()));
+        assert!(validate_repo_dir("").is_err());
+        assert!(validate_repo_dir("/").is_err());
+        assert!(validate_repo_dir(".").is_err());
+        assert!(validate_repo_dir("/etc").is_err());
+        assert!(validate_repo_dir("../other").is_err());
+        assert!(validate_repo_dir("a/../../b").is_err());
+        assert!(validate_repo_dir(".git").is_err());
+        assert!(validate_repo_dir(".git/hooks").is_err());
+    }
+
     #[test]
     fn test_create_action_decompose_sub() {
         let action = CreateAction::Sub {
@@ -349,7 +2151,7 @@ mod tests {
             simplified: false,
         };
 
-        let (change, commit, simplified) = action.decompose();
+        let (change, commit, simplified) = action.decompose().unwrap();
         assert!(matches!(change, Change::Sub(ptn, repl) if ptn == "old" && repl == "new"));
         assert_eq!(commit, Some("sub commit".to_string()));
         assert!(!simplified);
@@ -364,18 +2166,73 @@ mod tests {
             simplified: true,
         };
 
-        let (change, commit, simplified) = action.decompose();
+        let (change, commit, simplified) = action.decompose().unwrap();
         assert!(matches!(change, Change::Regex(ptn, repl) if ptn == "foo" && repl == "bar"));
         assert_eq!(commit, Some("regex commit".to_string()));
         assert!(simplified);
     }
 
+    #[test]
+    fn test_parse_sub_table() {
+        let pairs = parse_sub_table("# renames\ngetUser\tfetchUser\n\nlegacy_flag\t\n").unwrap();
+        assert_eq!(
+            pairs,
+            [
+                ("getUser".to_string(), "fetchUser".to_string()),
+                ("legacy_flag".to_string(), String::new())
+            ]
+        );
+        assert!(parse_sub_table("getUser fetchUser\n").is_err());
+        assert!(parse_sub_table("\tfetchUser\n").is_err());
+        assert!(parse_sub_table("a\tb\na\tc\n").is_err());
+        assert!(parse_sub_table("# nothing\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_map_files() {
+        let csv = "pattern,replacement\nold.example.com,new.example.com\n\"lib, v1\",\"lib \"\"v2\"\"\"\n";
+        let pairs = parse_csv_map(csv).unwrap();
+        assert_eq!(pairs[0], ("old.example.com".to_string(), "new.example.com".to_string()));
+        assert_eq!(pairs[1], ("lib, v1".to_string(), "lib \"v2\"".to_string()));
+        assert!(parse_csv_map("a,b,c\n").is_err());
+        assert!(parse_csv_map("\"a,b\n").is_err());
+        assert!(parse_csv_map("pattern,replacement\n").is_err());
+
+        let pairs = parse_json_map(r#"[["requests==2.31", "requests==2.32"], ["old", ""]]"#).unwrap();
+        assert_eq!(pairs[1], ("old".to_string(), String::new()));
+        assert_eq!(
+            parse_json_map(r#"{"a": "b"}"#).unwrap(),
+            [("a".to_string(), "b".to_string())]
+        );
+        assert!(parse_json_map(r#"{"a": 1}"#).is_err());
+        assert!(parse_json_map(r#"[["a"]]"#).is_err());
+        assert!(parse_json_map(r#"[["a", "b"], ["a", "c"]]"#).is_err());
+
+        assert!(read_map_file(std::path::Path::new("map.txt")).is_err());
+    }
+
+    #[test]
+    fn test_create_action_chmod_decompose() {
+        let cli = SlamCli::try_parse_from(["slam", "create", "-f", "*.sh", "chmod", "+x", "-c"]).unwrap();
+        let SlamCommand::Create(args) = cli.command else {
+            panic!("expected create");
+        };
+        let (change, commit, _) = args.action.expect("chmod action").decompose().unwrap();
+        assert!(matches!(change, Change::Chmod(FileMode::Executable)));
+        assert!(commit.is_some());
+        assert!(SlamCli::try_parse_from(["slam", "create", "chmod", "700"]).is_err());
+    }
+
     // Note: Testing CLI parsing would require integration tests with clap
     // since the Parser derive macro generates the parsing logic
 
     #[test]
     fn test_sandbox_action_debug() {
-        let setup = SandboxAction::Setup {};
+        let setup = SandboxAction::Setup {
+            orgs: vec!["tatari-tv".to_string()],
+            filter: RepoFilter::default(),
+            protocol: None,
+        };
         let refresh = SandboxAction::Refresh {};
 
         // Ensure Debug is implemented
@@ -383,11 +2240,309 @@ mod tests {
         assert!(!format!("{:?}", refresh).is_empty());
     }
 
+    #[test]
+    fn test_create_hook_flags() {
+        let cli = SlamCli::try_parse_from(["slam", "create", "--skip-hooks"]).unwrap();
+        let SlamCommand::Create(args) = cli.command else {
+            panic!("expected create");
+        };
+        assert!(args.no_verify);
+
+        let cli = SlamCli::try_parse_from(["slam", "create", "--hook", "black", "--hook", "isort"]).unwrap();
+        let SlamCommand::Create(args) = cli.command else {
+            panic!("expected create");
+        };
+        assert_eq!(args.hooks, vec!["black", "isort"]);
+
+        assert!(SlamCli::try_parse_from(["slam", "create", "--no-verify", "--hook", "black"]).is_err());
+    }
+
+    #[test]
+    fn test_create_signing_flags() {
+        let cli = SlamCli::try_parse_from(["slam", "create", "--signing-format", "ssh", "--sign-push"]).unwrap();
+        let SlamCommand::Create(args) = cli.command else {
+            panic!("expected create");
+        };
+        assert!(!args.sign);
+        assert_eq!(args.signing_format, Some(SigningFormat::Ssh));
+        assert!(args.sign_push);
+
+        assert!(SlamCli::try_parse_from(["slam", "create", "--signing-format", "pgp"]).is_err());
+    }
+
+    #[test]
+    fn test_create_identity_flags() {
+        let cli = SlamCli::try_parse_from(["slam", "create", "--author", "Fleet Bot <bot@corp>"]).unwrap();
+        let SlamCommand::Create(args) = cli.command else {
+            panic!("expected create");
+        };
+        assert_eq!(args.author.unwrap().email, "bot@corp");
+        assert!(args.committer.is_none());
+
+        assert!(SlamCli::try_parse_from(["slam", "create", "--author", "bot@corp"]).is_err());
+    }
+
+    #[test]
+    fn test_create_remote_flags() {
+        let cli = SlamCli::try_parse_from(["slam", "create", "--remote", "-o", "org1", "--org", "org2"]).unwrap();
+        let SlamCommand::Create(args) = cli.command else {
+            panic!("expected create");
+        };
+        assert!(args.remote);
+        assert_eq!(args.orgs, vec!["org1", "org2"]);
+
+        assert!(SlamCli::try_parse_from(["slam", "create", "--remote"]).is_err());
+        assert!(SlamCli::try_parse_from(["slam", "create", "-o", "org1"]).is_err());
+        assert!(SlamCli::try_parse_from(["slam", "create", "--remote", "-o", "org1", "--sign"]).is_err());
+        assert!(SlamCli::try_parse_from(["slam", "create", "--remote", "-o", "org1", "--hook", "black"]).is_err());
+    }
+
+    #[test]
+    fn test_stage_and_publish() {
+        let cli = SlamCli::try_parse_from(["slam", "create", "--stage", "-x", "SLAM-x"]).unwrap();
+        let SlamCommand::Create(args) = cli.command else {
+            panic!("expected create");
+        };
+        assert!(args.stage);
+        assert!(SlamCli::try_parse_from(["slam", "create", "--stage", "--update-pr"]).is_err());
+
+        let cli = SlamCli::try_parse_from(["slam", "publish", "SLAM-x", "-r", "frontend"]).unwrap();
+        assert_eq!(cli.command.change_id(), Some("SLAM-x"));
+        let SlamCommand::Publish { repo_ptns, .. } = cli.command else {
+            panic!("expected publish");
+        };
+        assert_eq!(repo_ptns, ["frontend"]);
+    }
+
+    #[test]
+    fn test_milestone_and_project() {
+        let cli = SlamCli::try_parse_from([
+            "slam",
+            "create",
+            "--milestone",
+            "Q3 base images",
+            "--project",
+            "Fleet rollouts",
+        ])
+        .unwrap();
+        let SlamCommand::Create(args) = cli.command else {
+            panic!("expected create");
+        };
+        assert_eq!(args.milestone.as_deref(), Some("Q3 base images"));
+        assert_eq!(args.project.as_deref(), Some("Fleet rollouts"));
+        assert!(!args.fork);
+
+        let cli = SlamCli::try_parse_from(["slam", "review", "ls", "SLAM", "--milestone", "Q3 base images"]).unwrap();
+        let SlamCommand::Review { action, .. } = cli.command else {
+            panic!("expected review command");
+        };
+        assert!(matches!(action, ReviewAction::Ls { milestone: Some(m), .. } if m == "Q3 base images"));
+
+        let cli = SlamCli::try_parse_from(["slam", "review", "ls", "--limit", "20", "--page", "3"]).unwrap();
+        let SlamCommand::Review { action, .. } = cli.command else {
+            panic!("expected review command");
+        };
+        assert!(matches!(
+            action,
+            ReviewAction::Ls {
+                limit: Some(20),
+                page: 3,
+                ..
+            }
+        ));
+        assert!(SlamCli::try_parse_from(["slam", "review", "ls", "--page", "2"]).is_err());
+        assert!(SlamCli::try_parse_from(["slam", "review", "ls", "--step", "--table"]).is_err());
+    }
+
+    #[test]
+    fn test_content_predicates() {
+        let cli = SlamCli::try_parse_from([
+            "slam",
+            "create",
+            "-f",
+            "Dockerfile",
+            "--only-if",
+            r"node:18\b",
+            "--skip-if",
+            "# pinned",
+        ])
+        .unwrap();
+        let SlamCommand::Create(args) = cli.command else {
+            panic!("expected create");
+        };
+        assert!(args.only_if.unwrap().is_match("FROM node:18"));
+        assert!(args.skip_if.unwrap().is_match("# pinned"));
+        assert!(SlamCli::try_parse_from(["slam", "create", "--only-if", "x"]).is_err());
+        assert!(args.require_file.is_empty());
+        assert!(SlamCli::try_parse_from(["slam", "create", "-f", "*", "--only-if", "("]).is_err());
+    }
+
+    #[test]
+    fn test_fork_flag() {
+        let cli = SlamCli::try_parse_from(["slam", "create", "--fork"]).unwrap();
+        let SlamCommand::Create(args) = cli.command else {
+            panic!("expected create");
+        };
+        assert!(args.fork);
+        assert!(SlamCli::try_parse_from(["slam", "create", "--fork", "--remote", "-o", "org"]).is_err());
+
+        let cli = SlamCli::try_parse_from(["slam", "publish", "SLAM-x", "--fork"]).unwrap();
+        assert!(matches!(cli.command, SlamCommand::Publish { fork: true, .. }));
+    }
+
+    #[test]
+    fn test_rollback_command() {
+        let cli = SlamCli::try_parse_from(["slam", "rollback", "SLAM-x", "--dry-run"]).unwrap();
+        assert_eq!(cli.command.change_id(), Some("SLAM-x"));
+        assert!(matches!(cli.command, SlamCommand::Rollback { dry_run: true, .. }));
+        assert!(SlamCli::try_parse_from(["slam", "rollback"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_report_path() {
+        assert_eq!(
+            validate_report_path("out/report.md"),
+            Ok(PathBuf::from("out/report.md"))
+        );
+        assert!(validate_report_path("report.html").is_ok());
+        assert!(validate_report_path("report.txt").is_err());
+    }
+
+    #[test]
+    fn test_sandbox_setup_orgs() {
+        let cli = SlamCli::try_parse_from(["slam", "sandbox", "setup"]).unwrap();
+        let SlamCommand::Sandbox {
+            action: SandboxAction::Setup { orgs, .. },
+            ..
+        } = cli.command
+        else {
+            panic!("expected sandbox setup");
+        };
+        assert_eq!(orgs, vec!["tatari-tv"]);
+
+        let cli = SlamCli::try_parse_from(["slam", "sandbox", "setup", "--org", "a", "-o", "b"]).unwrap();
+        let SlamCommand::Sandbox {
+            action: SandboxAction::Setup { orgs, .. },
+            ..
+        } = cli.command
+        else {
+            panic!("expected sandbox setup");
+        };
+        assert_eq!(orgs, vec!["a", "b"]);
+
+        let cli = SlamCli::try_parse_from(["slam", "sandbox", "setup", "--protocol", "https"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            SlamCommand::Sandbox {
+                action: SandboxAction::Setup {
+                    protocol: Some(Protocol::Https),
+                    ..
+                },
+                ..
+            }
+        ));
+        assert!(SlamCli::try_parse_from(["slam", "sandbox", "setup", "--protocol", "git"]).is_err());
+    }
+
+    #[test]
+    fn test_repo_filter_parsing() {
+        let cli = SlamCli::try_parse_from([
+            "slam",
+            "review",
+            "--topic",
+            "platform",
+            "--topic",
+            "service",
+            "--language",
+            "python",
+            "--visibility",
+            "internal",
+            "--include-archived",
+            "ls",
+        ])
+        .unwrap();
+        let SlamCommand::Review { filter, .. } = cli.command else {
+            panic!("expected review command");
+        };
+        assert_eq!(filter.topics, vec!["platform", "service"]);
+        assert_eq!(filter.language.as_deref(), Some("python"));
+        assert_eq!(filter.visibility, Some(Visibility::Internal));
+        assert!(filter.include_archived);
+    }
+
+    #[test]
+    fn test_automerge_interval_requires_watch() {
+        let cli =
+            SlamCli::try_parse_from(["slam", "review", "automerge", "SLAM-x", "--watch", "--interval", "30"]).unwrap();
+        let SlamCommand::Review { action, .. } = cli.command else {
+            panic!("expected review command");
+        };
+        assert!(matches!(
+            action,
+            ReviewAction::Automerge {
+                watch: true,
+                interval: 30,
+                ..
+            }
+        ));
+        assert_eq!(action.op(), ReviewOp::AutoMerge);
+
+        assert!(SlamCli::try_parse_from(["slam", "review", "automerge", "SLAM-x", "--interval", "30"]).is_err());
+        assert!(
+            SlamCli::try_parse_from(["slam", "review", "automerge", "SLAM-x", "--watch", "--interval", "0"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_rerun_checks() {
+        let cli = SlamCli::try_parse_from([
+            "slam", "review", "rerun", "SLAM-x", "--check", "test", "--check", "lint", "-r", "frontend",
+        ])
+        .unwrap();
+        let SlamCommand::Review { action, .. } = cli.command else {
+            panic!("expected review command");
+        };
+        assert!(
+            matches!(&action, ReviewAction::Rerun { change_id, repos, .. } if change_id == "SLAM-x" && repos == &["frontend"])
+        );
+        assert_eq!(
+            action.op(),
+            ReviewOp::Rerun {
+                checks: vec!["test".to_string(), "lint".to_string()]
+            }
+        );
+    }
+
+    #[test]
+    fn test_command_definition() {
+        SlamCli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_purge_dry_run_and_prefix() {
+        let cli = SlamCli::try_parse_from(["slam", "review", "purge", "--prefix", "2024-05", "--dry-run"]).unwrap();
+        let SlamCommand::Review { action, .. } = cli.command else {
+            panic!("expected review command");
+        };
+        assert!(matches!(&action, ReviewAction::Purge { prefix: Some(p), dry_run: true } if p == "2024-05"));
+        assert_eq!(action.op(), ReviewOp::Purge { dry_run: true });
+    }
+
     #[test]
     fn test_review_action_debug() {
         let ls = ReviewAction::Ls {
             change_id_ptns: vec!["SLAM-test".to_string()],
             buffer: 2,
+            path: vec![],
+            report: None,
+            summary: false,
+            table: false,
+            milestone: None,
+            sort: LsSort::Repo,
+            columns: vec![],
+            limit: None,
+            page: 1,
+            step: false,
         };
 
         let clone = ReviewAction::Clone {
@@ -398,13 +2553,23 @@ mod tests {
         let approve = ReviewAction::Approve {
             change_id: "SLAM-test".to_string(),
             admin_override: false,
+            repos: vec![],
+            exclude: vec!["tatari-tv/special".to_string()],
         };
 
         let delete = ReviewAction::Delete {
             change_id: "SLAM-test".to_string(),
+            repos: vec!["frontend".to_string()],
+            exclude: vec![],
+            local: true,
+        };
+
+        let purge = ReviewAction::Purge {
+            prefix: Some("SLAM-2024".to_string()),
+            dry_run: true,
         };
 
-        let purge = ReviewAction::Purge {};
+        let tui = ReviewAction::Tui { change_id_ptns: vec![] };
 
         // Ensure Debug is implemented for all variants
         assert!(!format!("{:?}", ls).is_empty());
@@ -412,5 +2577,7 @@ mod tests {
         assert!(!format!("{:?}", approve).is_empty());
         assert!(!format!("{:?}", delete).is_empty());
         assert!(!format!("{:?}", purge).is_empty());
+        assert!(!format!("{:?}", tui).is_empty());
+        assert_eq!(approve.op(), ReviewOp::Approve { admin_override: false });
     }
 }
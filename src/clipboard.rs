@@ -0,0 +1,45 @@
+// src/clipboard.rs
+
+use eyre::{eyre, Result};
+use log::debug;
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+/// Clipboard tools tried in order: macOS, Wayland, X11 (two flavors), WSL.
+const TOOLS: &[&[&str]] = &[
+    &["pbcopy"],
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+    &["clip.exe"],
+];
+
+/// Copies `text` to the system clipboard with the first of `TOOLS` that is installed.
+pub fn copy(text: &str) -> Result<()> {
+    for words in TOOLS {
+        let mut child = match Command::new(words[0])
+            .args(&words[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(eyre!("Failed to run '{}': {}", words.join(" "), e)),
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(eyre!("'{}' exited with {}", words.join(" "), status));
+        }
+        debug!("Copied {} bytes with {}", text.len(), words[0]);
+        return Ok(());
+    }
+    Err(eyre!(
+        "No clipboard tool found (tried {})",
+        TOOLS.iter().map(|words| words[0]).collect::<Vec<_>>().join(", ")
+    ))
+}
@@ -0,0 +1,105 @@
+// src/confirm.rs
+
+use eyre::{eyre, Result};
+use std::io::{BufRead, IsTerminal, Write};
+use std::sync::OnceLock;
+
+/// How many of the affected items the prompt lists before summarizing the rest.
+const SAMPLE_SIZE: usize = 10;
+
+static ASSUME_YES: OnceLock<bool> = OnceLock::new();
+
+/// With `assume_yes` (`--yes`), `confirm` proceeds without asking.
+pub fn configure(assume_yes: bool) {
+    let _ = ASSUME_YES.set(assume_yes);
+}
+
+fn repo_count(n: usize) -> String {
+    format!("{} repo{}", n, if n == 1 { "" } else { "s" })
+}
+
+/// "About to <action> in N repos:", a sample of `items` and the question.
+fn prompt_text(action: &str, items: &[String]) -> String {
+    let mut text = format!("About to {} in {}:\n", action, repo_count(items.len()));
+    for item in items.iter().take(SAMPLE_SIZE) {
+        text.push_str(&format!("  {}\n", item));
+    }
+    if items.len() > SAMPLE_SIZE {
+        text.push_str(&format!("  ... and {} more\n", items.len() - SAMPLE_SIZE));
+    }
+    text.push_str("Proceed? [y/N] ");
+    text
+}
+
+fn is_yes(answer: &str) -> bool {
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Asks before a destructive `action` on `items` (one per repo). True with `--yes`; an error
+/// when stdin is not a terminal, so scripts must pass `--yes` rather than proceed unasked.
+pub fn confirm(action: &str, items: &[String]) -> Result<bool> {
+    if ASSUME_YES.get().copied().unwrap_or(false) {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(eyre!(
+            "Refusing to {} in {} without confirmation; pass --yes to proceed",
+            action,
+            repo_count(items.len())
+        ));
+    }
+    eprint!("{}", prompt_text(action, items));
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(is_yes(&answer))
+}
+
+/// Waits for Enter before the next of `what`; false when the answer is `q` (quit). Needs a
+/// terminal to read the answer from.
+pub fn next(what: &str) -> Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        return Err(eyre!("Stepping through {} needs a terminal", what));
+    }
+    eprint!("-- Enter for the next {}, q to quit -- ", what);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(!is_quit(&answer))
+}
+
+fn is_quit(answer: &str) -> bool {
+    matches!(answer.trim().to_lowercase().as_str(), "q" | "quit")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_text_samples_items() {
+        let items: Vec<String> = (1..=12).map(|n| format!("org/repo{}", n)).collect();
+        let text = prompt_text("close PRs and delete branch 'SLAM-x'", &items);
+        assert!(text.starts_with("About to close PRs and delete branch 'SLAM-x' in 12 repos:\n  org/repo1\n"));
+        assert!(text.contains("  org/repo10\n  ... and 2 more\n"));
+        assert!(!text.contains("org/repo11"));
+        assert!(text.ends_with("Proceed? [y/N] "));
+
+        assert!(prompt_text("purge", &items[..1]).starts_with("About to purge in 1 repo:\n"));
+    }
+
+    #[test]
+    fn test_is_yes() {
+        assert!(is_yes("y\n"));
+        assert!(is_yes(" YES "));
+        assert!(!is_yes("\n"));
+        assert!(!is_yes("no"));
+    }
+
+    #[test]
+    fn test_is_quit() {
+        assert!(is_quit("q\n"));
+        assert!(is_quit(" Quit "));
+        assert!(!is_quit("\n"));
+    }
+}
@@ -0,0 +1,158 @@
+// src/enforce.rs
+
+use clap::Parser;
+use eyre::{eyre, Context, Result};
+use log::debug;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use slam_core::audit::Expectation;
+
+use crate::cli::{self, Collision, CreateArgs, SlamCli, SlamCommand};
+
+/// One declared policy: the files every repo must keep as `expect` says, and the `slam create`
+/// that brings a drifted repo back in line.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    pub name: String,
+    /// Globs of the files to audit, as for `slam audit`.
+    pub files: Vec<String>,
+    /// Regex or `sha256:<hex>`, as for `slam audit --expect`.
+    pub expect: String,
+    /// Repo patterns (as for `-r`) the policy applies to; all repos when empty.
+    #[serde(default)]
+    pub repos: Vec<String>,
+    /// Change id of the policy's PRs; `enforce-<name>` when unset.
+    pub change_id: Option<String>,
+    /// Arguments of the `slam create` that fixes a drifted repo, after `create`, e.g.
+    /// `[-f, CODEOWNERS, sub, '@org/old', '@org/platform', -c, 'chore: ...']`.
+    pub create: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    policies: Vec<Policy>,
+}
+
+impl Policy {
+    pub fn expectation(&self) -> Result<Expectation> {
+        Expectation::parse(&self.expect)
+    }
+
+    /// The policy's `create`, aimed at `reposlugs` (any `-r` of its own is replaced) under the
+    /// policy's change id. Reruns reuse the open PRs rather than replacing them.
+    pub fn create_args(&self, reposlugs: &[String]) -> Result<CreateArgs> {
+        let argv = ["slam", "create"]
+            .into_iter()
+            .map(String::from)
+            .chain(self.create.iter().cloned());
+        let cli = SlamCli::try_parse_from(argv)
+            .map_err(|e| eyre!("Invalid create arguments of policy '{}': {}", self.name, e))?;
+        let SlamCommand::Create(mut args) = cli.command else {
+            unreachable!("parsed as create");
+        };
+        args.repo_ptns = reposlugs.to_vec();
+        args.change_id = Some(
+            self.change_id
+                .clone()
+                .unwrap_or_else(|| format!("enforce-{}", self.name)),
+        );
+        args.change_id_template = None;
+        args.update_pr = !args.stage;
+        args.on_collision = Some(Collision::Warn);
+        args.pick = false;
+        Ok(*args)
+    }
+}
+
+/// Loads the policies file, checking every policy's expectation, create arguments and change id
+/// up front so a typo fails before the first pass rather than in it.
+pub fn load(path: &Path, branch_prefix: &str) -> Result<Vec<Policy>> {
+    let content = fs::read_to_string(path).wrap_err_with(|| format!("Failed to read policies '{}'", path.display()))?;
+    let file: PolicyFile =
+        serde_yaml::from_str(&content).wrap_err_with(|| format!("Failed to parse policies '{}'", path.display()))?;
+    for policy in &file.policies {
+        policy
+            .expectation()
+            .wrap_err_with(|| format!("Invalid expect of policy '{}'", policy.name))?;
+        let args = policy.create_args(&[])?;
+        if args.action.is_none() {
+            return Err(eyre!("Policy '{}' has no change in its create arguments", policy.name));
+        }
+        if let Some(change_id) = &args.change_id {
+            cli::validate_change_id(change_id, branch_prefix).map_err(|e| eyre!("Policy '{}': {}", policy.name, e))?;
+        }
+    }
+    debug!("Loaded {} policies from '{}'", file.policies.len(), path.display());
+    Ok(file.policies)
+}
+
+/// Accepts an `--interval` such as `90s`, `30m`, `24h` or `7d`.
+pub fn parse_interval(s: &str) -> Result<Duration, String> {
+    let invalid = || format!("`{}` isn't an interval like 30m, 24h or 7d", s);
+    let unit = s.chars().last().ok_or_else(invalid)?;
+    let secs = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    let count: u64 = s[..s.len() - 1].parse().map_err(|_| invalid())?;
+    if count == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(count * secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const POLICIES: &str = r#"
+policies:
+  - name: codeowners
+    files: [CODEOWNERS]
+    expect: '(?m)^\* @org/platform$'
+    repos: ['org/']
+    create: [-f, CODEOWNERS, -r, ignored, sub, '@org/old', '@org/platform', -c, 'chore: platform owns']
+"#;
+
+    #[test]
+    fn test_load_and_create_args() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("policies.yaml");
+        fs::write(&path, POLICIES).unwrap();
+        let policies = load(&path, "SLAM").unwrap();
+        assert_eq!(policies[0].repos, ["org/"]);
+
+        let args = policies[0].create_args(&["org/api".to_string()]).unwrap();
+        assert_eq!(args.repo_ptns, ["org/api"]);
+        assert_eq!(args.files, ["CODEOWNERS"]);
+        assert_eq!(args.change_id.as_deref(), Some("enforce-codeowners"));
+        assert!(args.update_pr);
+        assert_eq!(args.on_collision, Some(Collision::Warn));
+
+        fs::write(&path, POLICIES.replace("sub, '@org/old', '@org/platform', ", "")).unwrap();
+        assert!(load(&path, "SLAM").is_err());
+        fs::write(&path, POLICIES.replace("expect: '(?m)", "expect: '(")).unwrap();
+        assert!(load(&path, "SLAM").is_err());
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_interval("30m"), Ok(Duration::from_secs(1800)));
+        assert_eq!(parse_interval("24h"), Ok(Duration::from_secs(86400)));
+        assert_eq!(parse_interval("7d"), Ok(Duration::from_secs(7 * 86400)));
+        assert!(parse_interval("0h").is_err());
+        assert!(parse_interval("24").is_err());
+        assert!(parse_interval("h").is_err());
+        assert!(parse_interval("").is_err());
+    }
+}
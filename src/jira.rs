@@ -0,0 +1,207 @@
+// src/jira.rs
+
+use eyre::{eyre, Result};
+use log::{debug, info, warn};
+use regex::Regex;
+use serde_json::{json, Value};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use slam_core::repo::Ticket;
+
+/// Jira issue key: a project key, a dash and the issue number, e.g. `OPS-123`.
+const TICKET_RE: &str = r"[A-Z][A-Z0-9_]+-[0-9]+";
+
+#[derive(Debug, Clone)]
+pub enum Auth {
+    /// Jira Cloud: account email and API token.
+    Basic { email: String, token: String },
+    /// Jira Server/Data Center: personal access token.
+    Bearer(String),
+}
+
+/// The Jira instance tickets live in.
+#[derive(Debug, Clone)]
+pub struct Jira {
+    /// Base URL, e.g. `https://corp.atlassian.net`.
+    pub url: String,
+    pub auth: Option<Auth>,
+    /// Transition (e.g. `Done`) applied to the ticket once every PR of its change has merged.
+    pub transition: Option<String>,
+}
+
+impl Jira {
+    fn issue_url(&self, key: &str) -> String {
+        format!("{}/browse/{}", self.url.trim_end_matches('/'), key)
+    }
+
+    fn call(&self, method: &str, path: &str, body: Option<&Value>) -> Result<Value> {
+        let agent = ureq::AgentBuilder::new().timeout(Duration::from_secs(30)).build();
+        let url = format!("{}/rest/api/2/{}", self.url.trim_end_matches('/'), path);
+        let mut request = agent.request(method, &url).set("Accept", "application/json");
+        match &self.auth {
+            Some(Auth::Basic { email, token }) => {
+                use base64::Engine;
+                let credentials = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", email, token));
+                request = request.set("Authorization", &format!("Basic {}", credentials));
+            }
+            Some(Auth::Bearer(token)) => request = request.set("Authorization", &format!("Bearer {}", token)),
+            None => {}
+        }
+        let response = match body {
+            Some(body) => request.send_json(body),
+            None => request.call(),
+        };
+        match response {
+            Ok(response) => {
+                let text = response.into_string()?;
+                if text.trim().is_empty() {
+                    Ok(Value::Null)
+                } else {
+                    Ok(serde_json::from_str(&text)?)
+                }
+            }
+            Err(ureq::Error::Status(code, response)) => Err(eyre!(
+                "Jira returned HTTP {} for {} {}: {}",
+                code,
+                method,
+                path,
+                response.into_string().unwrap_or_default().trim()
+            )),
+            Err(e) => Err(eyre!("{}", e)),
+        }
+    }
+
+    /// Moves issue `key` through the transition named `name` (case-insensitive).
+    fn transition(&self, key: &str, name: &str) -> Result<()> {
+        let path = format!("issue/{}/transitions", key);
+        let transitions = self.call("GET", &path, None)?;
+        let id = transition_id(&transitions, name)
+            .ok_or_else(|| eyre!("Ticket {} has no transition '{}' from its current status", key, name))?;
+        self.call("POST", &path, Some(&json!({ "transition": { "id": id } })))?;
+        Ok(())
+    }
+}
+
+/// Id of the transition called `name` in a `GET issue/{key}/transitions` response.
+fn transition_id(transitions: &Value, name: &str) -> Option<String> {
+    transitions["transitions"]
+        .as_array()?
+        .iter()
+        .find(|t| t["name"].as_str().is_some_and(|n| n.eq_ignore_ascii_case(name)))
+        .and_then(|t| t["id"].as_str().map(str::to_string))
+}
+
+static JIRA: OnceLock<Option<Jira>> = OnceLock::new();
+
+/// Sets the Jira instance tickets link to and are transitioned in.
+pub fn configure(jira: Option<Jira>) {
+    let _ = JIRA.set(jira);
+}
+
+fn jira() -> Option<&'static Jira> {
+    JIRA.get().and_then(Option::as_ref)
+}
+
+/// Whether merging a change's last PR should move its ticket along.
+pub fn transitions() -> bool {
+    jira().is_some_and(|jira| jira.transition.is_some())
+}
+
+/// Accepts a Jira issue key such as `OPS-123`.
+pub fn validate_ticket(s: &str) -> Result<String, String> {
+    let re = Regex::new(&format!("^{}$", TICKET_RE)).unwrap();
+    if re.is_match(s) {
+        Ok(s.to_string())
+    } else {
+        Err(format!("`{}` isn't a ticket key like OPS-123", s))
+    }
+}
+
+/// The ticket `key`, linked to its issue page when Jira is configured.
+pub fn ticket(key: &str) -> Ticket {
+    Ticket {
+        key: key.to_string(),
+        url: jira().map(|jira| jira.issue_url(key)),
+    }
+}
+
+/// Puts `ticket` into `change_id` right after the branch prefix, unless it is already in it.
+pub fn with_ticket(change_id: &str, branch_prefix: &str, ticket: &str) -> String {
+    if change_id.contains(ticket) {
+        return change_id.to_string();
+    }
+    match change_id
+        .strip_prefix(branch_prefix)
+        .and_then(|rest| rest.strip_prefix('-'))
+    {
+        Some(rest) => format!("{}-{}-{}", branch_prefix, ticket, rest),
+        None => format!("{}-{}-{}", branch_prefix, ticket, change_id),
+    }
+}
+
+/// The ticket `with_ticket` put into `change_id`, if any.
+pub fn ticket_in(change_id: &str, branch_prefix: &str) -> Option<String> {
+    let rest = change_id.strip_prefix(branch_prefix)?.strip_prefix('-')?;
+    let re = Regex::new(&format!("^({})(-|$)", TICKET_RE)).unwrap();
+    re.captures(rest).map(|caps| caps[1].to_string())
+}
+
+/// Applies the configured transition to `ticket`, now that all PRs of its change have merged.
+/// Best effort: the merges already happened, so a failure only warns.
+pub fn complete(ticket: &str) {
+    let Some((jira, transition)) = jira().and_then(|jira| Some((jira, jira.transition.as_deref()?))) else {
+        debug!("No jira.transition configured; leaving {} as is", ticket);
+        return;
+    };
+    match jira.transition(ticket, transition) {
+        Ok(()) => {
+            info!("Moved {} to '{}'", ticket, transition);
+            println!("Moved {} to '{}'.", ticket, transition);
+        }
+        Err(e) => warn!("Failed to move {} to '{}': {}", ticket, transition, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_ticket_and_ticket_in() {
+        assert_eq!(
+            with_ticket("SLAM-2024-05-02T10-30-15", "SLAM", "OPS-12"),
+            "SLAM-OPS-12-2024-05-02T10-30-15"
+        );
+        assert_eq!(with_ticket("bump-node", "SLAM", "OPS-12"), "SLAM-OPS-12-bump-node");
+        assert_eq!(with_ticket("SLAM-x-OPS-12", "SLAM", "OPS-12"), "SLAM-x-OPS-12");
+
+        assert_eq!(
+            ticket_in("SLAM-OPS-12-2024-05-02T10-30-15", "SLAM").as_deref(),
+            Some("OPS-12")
+        );
+        assert_eq!(ticket_in("SLAM-OPS-12", "SLAM").as_deref(), Some("OPS-12"));
+        assert_eq!(ticket_in("SLAM-2024-05-02T10-30-15", "SLAM"), None);
+        assert_eq!(ticket_in("SLAM-OPS-12x", "SLAM"), None);
+    }
+
+    #[test]
+    fn test_validate_ticket() {
+        assert_eq!(validate_ticket("OPS-123"), Ok("OPS-123".to_string()));
+        assert!(validate_ticket("ops-123").is_err());
+        assert!(validate_ticket("OPS").is_err());
+        assert!(validate_ticket("OPS-12 ").is_err());
+    }
+
+    #[test]
+    fn test_transition_id() {
+        let transitions = json!({
+            "transitions": [
+                { "id": "11", "name": "In Progress" },
+                { "id": "31", "name": "Done" },
+            ]
+        });
+        assert_eq!(transition_id(&transitions, "done").as_deref(), Some("31"));
+        assert_eq!(transition_id(&transitions, "Closed"), None);
+    }
+}
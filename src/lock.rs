@@ -0,0 +1,219 @@
+// src/lock.rs
+
+use chrono::{DateTime, Local};
+use eyre::{eyre, Context, Result};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Name of the lock file in the sandbox root, next to the repo index.
+pub const SANDBOX_LOCK: &str = ".slam-lock";
+
+/// How often `--wait` checks whether the other run is done.
+const WAIT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Which locks a command takes: the sandbox's when it changes the clones or their worktrees, and
+/// the change id's when it changes the change's branches or PRs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Scope {
+    pub sandbox: bool,
+    pub change: bool,
+}
+
+/// What to do when another run holds a lock.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Contention {
+    /// Fail, naming the other run.
+    #[default]
+    Fail,
+    /// Wait until the other run releases it.
+    Wait,
+    /// Take the lock over.
+    Force,
+}
+
+/// The run holding a lock, as written into the lock file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Holder {
+    pid: u32,
+    /// RFC 3339.
+    started: String,
+    command: String,
+}
+
+/// An advisory lock, held until dropped. It is the OS's lock on the open lock file, so a run that
+/// dies releases it with its process; the holder written into the file only names the run.
+#[derive(Debug)]
+pub struct Lock {
+    path: PathBuf,
+    file: File,
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        // Once taken over by --force-lock, the file at the path is the new holder's.
+        if same_file(&self.file, &self.path) {
+            match fs::remove_file(&self.path) {
+                Ok(()) => debug!("Released lock '{}'", self.path.display()),
+                Err(e) => warn!("Failed to release lock '{}': {}", self.path.display(), e),
+            }
+        }
+        let _ = self.file.unlock();
+    }
+}
+
+/// Whether `path` still names the open `file`, rather than one created after it was removed.
+#[cfg(unix)]
+fn same_file(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(open), Ok(named)) => open.dev() == named.dev() && open.ino() == named.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_file(_file: &File, path: &Path) -> bool {
+    path.exists()
+}
+
+/// Takes the lock at `path` for `command`; `what` names what it guards in the errors, e.g.
+/// `the sandbox '/src'`. The file is locked before the holder is written into it, so a run that
+/// reads it half-written still sees the lock as held.
+pub fn acquire(path: &Path, what: &str, command: &str, contention: Contention) -> Result<Lock> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).wrap_err_with(|| format!("Failed to create '{}'", dir.display()))?;
+    }
+    let mut waiting = false;
+    loop {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .wrap_err_with(|| format!("Failed to open lock '{}'", path.display()))?;
+        match file.try_lock() {
+            // The run that held it may have removed the file between our open and lock.
+            Ok(()) if !same_file(&file, path) => continue,
+            Ok(()) => {
+                let holder = Holder {
+                    pid: std::process::id(),
+                    started: Local::now().to_rfc3339(),
+                    command: command.to_string(),
+                };
+                file.set_len(0)
+                    .and_then(|()| file.write_all(serde_json::to_string(&holder)?.as_bytes()))
+                    .wrap_err_with(|| format!("Failed to write lock '{}'", path.display()))?;
+                debug!("Took lock '{}'", path.display());
+                return Ok(Lock {
+                    path: path.to_path_buf(),
+                    file,
+                });
+            }
+            Err(TryLockError::WouldBlock) => {}
+            Err(TryLockError::Error(e)) => return Err(eyre!("Failed to take lock '{}': {}", path.display(), e)),
+        }
+        // The holder may not have written itself in yet.
+        let mut text = String::new();
+        let held = match file
+            .read_to_string(&mut text)
+            .ok()
+            .and_then(|_| serde_json::from_str::<Holder>(&text).ok())
+        {
+            Some(holder) => format!(
+                "another slam run is in progress on {} (pid {}, `slam {}`, started {})",
+                what,
+                holder.pid,
+                holder.command,
+                DateTime::parse_from_rfc3339(&holder.started)
+                    .map(|started| started.format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or(holder.started.clone())
+            ),
+            None => format!("another slam run is in progress on {}", what),
+        };
+        match contention {
+            Contention::Fail => {
+                return Err(eyre!(
+                    "{}; pass --wait to wait for it, or --force-lock if it is gone",
+                    held
+                ))
+            }
+            Contention::Wait => {
+                if !waiting {
+                    eprintln!("Waiting: {}", held);
+                    waiting = true;
+                }
+                std::thread::sleep(WAIT_INTERVAL);
+            }
+            Contention::Force => {
+                // The other run keeps its lock on the removed file; the next pass locks a new one.
+                warn!("Taking over the lock: {}", held);
+                match fs::remove_file(path) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == ErrorKind::NotFound => {}
+                    Err(e) => return Err(eyre!("Failed to remove lock '{}': {}", path.display(), e)),
+                }
+            }
+        }
+    }
+}
+
+/// The lock of `change_id` under `dir`, shared by every sandbox since the branches are remote.
+pub fn change_lock_path(dir: &Path, change_id: &str) -> PathBuf {
+    dir.join(format!("{}.lock", change_id.replace(['/', '\\'], "_")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acquire_and_release() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = change_lock_path(&dir.path().join("locks"), "SLAM/feature");
+        assert!(path.ends_with("locks/SLAM_feature.lock"));
+
+        let lock = acquire(&path, "change 'SLAM/feature'", "create", Contention::Fail).unwrap();
+        let holder: Holder = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(holder.pid, std::process::id());
+        drop(lock);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_acquire_held_stale_and_forced() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(SANDBOX_LOCK);
+        let holder = Holder {
+            pid: 4242,
+            started: Local::now().to_rfc3339(),
+            command: "sandbox refresh".to_string(),
+        };
+        // Another run, locked but not yet written in, is still held.
+        let other = File::create(&path).unwrap();
+        other.lock().unwrap();
+        let err = acquire(&path, "the sandbox", "create", Contention::Fail).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("another slam run is in progress on the sandbox;"));
+
+        fs::write(&path, serde_json::to_string(&holder).unwrap()).unwrap();
+        let err = acquire(&path, "the sandbox", "create", Contention::Fail).unwrap_err();
+        assert!(err.to_string().contains("(pid 4242, `slam sandbox refresh`"));
+        let forced = acquire(&path, "the sandbox", "create", Contention::Force).unwrap();
+        drop(other);
+        assert!(path.exists());
+        drop(forced);
+        assert!(!path.exists());
+
+        // A file left by a run that died holds nothing.
+        fs::write(&path, serde_json::to_string(&holder).unwrap()).unwrap();
+        drop(acquire(&path, "the sandbox", "create", Contention::Fail).unwrap());
+        fs::write(&path, "").unwrap();
+        drop(acquire(&path, "the sandbox", "create", Contention::Fail).unwrap());
+    }
+}
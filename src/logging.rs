@@ -0,0 +1,335 @@
+// src/logging.rs
+
+use chrono::{DateTime, Local};
+use eyre::{Context, Result};
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer, Registry};
+
+/// How log lines are written, to the log file and (with `-v`) to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum LogFormat {
+    /// One line per event, prefixed with the repo it concerns.
+    #[default]
+    Text,
+    /// One JSON object per event, with the repo in its spans.
+    Json,
+}
+
+/// Log files are rotated to `<name>.1` once they reach this size.
+const MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+/// Rotated copies kept (`slam.log.1` is the newest).
+const KEEP_ROTATED: usize = 5;
+/// Run logs older than this are deleted when a new run starts.
+const RUN_LOG_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+/// Run logs start with the run's local time in this format, so they sort by age.
+const RUN_TIMESTAMP: &str = "%Y%m%dT%H%M%S";
+
+/// Directory holding one log file per run, under the log directory.
+pub fn runs_dir(log_dir: &Path) -> PathBuf {
+    log_dir.join("runs")
+}
+
+/// Moves `path` to `path.1` (shifting older copies up, dropping the one past `keep`) once it
+/// is at least `max_bytes` long.
+fn rotate(path: &Path, max_bytes: u64, keep: usize) -> std::io::Result<()> {
+    if fs::metadata(path).map_or(true, |m| m.len() < max_bytes) {
+        return Ok(());
+    }
+    let rotated = |n: usize| PathBuf::from(format!("{}.{}", path.display(), n));
+    let _ = fs::remove_file(rotated(keep));
+    for n in (1..keep).rev() {
+        if rotated(n).exists() {
+            fs::rename(rotated(n), rotated(n + 1))?;
+        }
+    }
+    fs::rename(path, rotated(1))
+}
+
+/// A log file opened for appending that rotates itself once a write takes it past its size
+/// limit, so long-running commands like `slam enforce --interval` don't grow it without end.
+struct RotatingFile {
+    path: PathBuf,
+    file: fs::File,
+    len: u64,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl RotatingFile {
+    fn open(path: &Path, max_bytes: u64, keep: usize) -> Result<Self> {
+        let file = open_append(path)?;
+        let len = file.metadata().map_or(0, |m| m.len());
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            len,
+            max_bytes,
+            keep,
+        })
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.len >= self.max_bytes {
+            match rotate(&self.path, self.max_bytes, self.keep)
+                .and_then(|()| fs::OpenOptions::new().create(true).append(true).open(&self.path))
+            {
+                Ok(file) => self.file = file,
+                Err(e) => eprintln!("Warning: failed to rotate '{}': {}", self.path.display(), e),
+            }
+            self.len = 0;
+        }
+        let written = self.file.write(buf)?;
+        self.len += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Deletes the run logs in `dir` last written more than `max_age` ago.
+fn prune_run_logs(dir: &Path, max_age: Duration) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let now = SystemTime::now();
+    for entry in entries.flatten() {
+        let modified = entry.metadata().and_then(|m| m.modified());
+        if modified.is_ok_and(|t| now.duration_since(t).unwrap_or_default() > max_age) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// `change_id` as it appears in run log names, with characters unsafe in a file name replaced.
+fn file_safe(change_id: &str) -> String {
+    change_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "._-".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// `<timestamp>-<change-id>.log`, or `<timestamp>.log` for runs not tied to a change id.
+fn run_log_name(started: DateTime<Local>, change_id: Option<&str>) -> String {
+    let timestamp = started.format(RUN_TIMESTAMP);
+    match change_id {
+        Some(change_id) => format!("{}-{}.log", timestamp, file_safe(change_id)),
+        None => format!("{}.log", timestamp),
+    }
+}
+
+/// The change id part of a run log's file name, if it has one.
+fn run_log_change_id(name: &str) -> Option<&str> {
+    let stem = name.strip_suffix(".log")?;
+    stem.split_once('-').map(|(_, change_id)| change_id)
+}
+
+/// Run logs in `dir`, oldest first, optionally only those whose change id starts with `change_id`.
+pub fn run_logs(dir: &Path, change_id: Option<&str>) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let change_id = change_id.map(file_safe);
+    let mut logs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            match &change_id {
+                Some(change_id) => run_log_change_id(name).is_some_and(|id| id.starts_with(change_id.as_str())),
+                None => name.ends_with(".log"),
+            }
+        })
+        .collect();
+    logs.sort();
+    logs
+}
+
+/// The level `-v` flags ask for; `$RUST_LOG`, when set, takes precedence.
+fn default_directive(verbose: u8) -> &'static str {
+    match verbose {
+        0 | 1 => "info",
+        2 => "debug",
+        _ => "trace",
+    }
+}
+
+fn filter(verbose: u8) -> EnvFilter {
+    EnvFilter::builder()
+        .with_default_directive(
+            default_directive(verbose)
+                .parse()
+                .expect("default log directive is valid"),
+        )
+        .from_env_lossy()
+}
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+fn layer<W>(writer: W, format: LogFormat, ansi: bool, verbose: u8) -> BoxedLayer
+where
+    W: for<'a> fmt::MakeWriter<'a> + Send + Sync + 'static,
+{
+    let layer = fmt::layer().with_writer(writer).with_ansi(ansi);
+    match format {
+        LogFormat::Text => layer.with_filter(filter(verbose)).boxed(),
+        LogFormat::Json => layer.json().with_filter(filter(verbose)).boxed(),
+    }
+}
+
+fn open_append(path: &Path) -> Result<fs::File> {
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .wrap_err_with(|| format!("Failed to open log file '{}'", path.display()))
+}
+
+/// What this run logs to besides `slam.log`.
+pub enum RunLog<'a> {
+    /// No file of its own, e.g. for `slam logs`.
+    None,
+    /// A new file in the runs directory, named by the start time and the change id.
+    Run { change_id: Option<&'a str> },
+}
+
+/// Logs to `<log_dir>/slam.log` and to a file of this run's own, both appended to and rotated by size,
+/// and with `verbose` set mirrors the same events to stderr. Events from the `log` macros are
+/// picked up too, within the per-repo spans slam-core opens.
+pub fn init(log_dir: &Path, run: RunLog, verbose: u8, format: LogFormat, color: bool) -> Result<()> {
+    fs::create_dir_all(log_dir).context("Failed to create log directory")?;
+    let log_file = log_dir.join("slam.log");
+    let mut layers = vec![layer(
+        Mutex::new(RotatingFile::open(&log_file, MAX_LOG_BYTES, KEEP_ROTATED)?),
+        format,
+        false,
+        verbose,
+    )];
+    let mut run_file = None;
+    if let RunLog::Run { change_id } = run {
+        let dir = runs_dir(log_dir);
+        fs::create_dir_all(&dir).context("Failed to create run log directory")?;
+        prune_run_logs(&dir, RUN_LOG_MAX_AGE);
+        let path = dir.join(run_log_name(Local::now(), change_id));
+        layers.push(layer(
+            Mutex::new(RotatingFile::open(&path, MAX_LOG_BYTES, KEEP_ROTATED)?),
+            format,
+            false,
+            verbose,
+        ));
+        run_file = Some(path);
+    }
+    if verbose > 0 {
+        let ansi = color && std::io::stderr().is_terminal();
+        layers.push(layer(std::io::stderr, format, ansi, verbose));
+    }
+    tracing_subscriber::registry()
+        .with(layers)
+        .try_init()
+        .context("Failed to initialize logging")?;
+
+    tracing::info!("Logging initialized, writing to: {}", log_file.display());
+    if let Some(path) = run_file {
+        tracing::info!("This run is also logged to: {}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rotate() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = temp_dir.path().join("slam.log");
+        let rotated = |n: usize| temp_dir.path().join(format!("slam.log.{}", n));
+
+        fs::write(&log, "small").unwrap();
+        rotate(&log, 10, 2).unwrap();
+        assert!(log.exists() && !rotated(1).exists());
+
+        for run in ["first run", "second run", "third run"] {
+            fs::write(&log, run).unwrap();
+            rotate(&log, 5, 2).unwrap();
+        }
+        assert!(!log.exists());
+        assert_eq!(fs::read_to_string(rotated(1)).unwrap(), "third run");
+        assert_eq!(fs::read_to_string(rotated(2)).unwrap(), "second run");
+        assert!(!rotated(3).exists());
+    }
+
+    #[test]
+    fn test_rotating_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = temp_dir.path().join("slam.log");
+        let mut file = RotatingFile::open(&log, 8, 2).unwrap();
+        for line in ["pass 1\n", "pass 2\n", "pass 3\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        assert_eq!(fs::read_to_string(&log).unwrap(), "pass 3\n");
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("slam.log.1")).unwrap(),
+            "pass 1\npass 2\n"
+        );
+    }
+
+    #[test]
+    fn test_run_logs_by_change_id() {
+        let started = Local.with_ymd_and_hms(2024, 5, 2, 10, 15, 0).unwrap();
+        assert_eq!(run_log_name(started, None), "20240502T101500.log");
+        assert_eq!(
+            run_log_name(started, Some("SLAM-2024-05-02/x")),
+            "20240502T101500-SLAM-2024-05-02_x.log"
+        );
+
+        let temp_dir = TempDir::new().unwrap();
+        for name in [
+            "20240502T101500-SLAM-a.log",
+            "20240503T090000.log",
+            "20240504T120000-SLAM-a.log",
+            "20240505T120000-SLAM-b.log",
+            "20240506T120000-SLAM-c_d.log",
+        ] {
+            fs::write(temp_dir.path().join(name), "").unwrap();
+        }
+        let names = |change_id| -> Vec<String> {
+            run_logs(temp_dir.path(), change_id)
+                .iter()
+                .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+                .collect()
+        };
+        assert_eq!(
+            names(Some("SLAM-a")),
+            ["20240502T101500-SLAM-a.log", "20240504T120000-SLAM-a.log"]
+        );
+        assert_eq!(names(Some("SLAM")).len(), 4);
+        assert_eq!(names(Some("SLAM-c/d")), ["20240506T120000-SLAM-c_d.log"]);
+        assert_eq!(names(None).last().unwrap(), "20240506T120000-SLAM-c_d.log");
+    }
+
+    #[test]
+    fn test_default_directive() {
+        assert_eq!(default_directive(0), "info");
+        assert_eq!(default_directive(1), "info");
+        assert_eq!(default_directive(2), "debug");
+        assert_eq!(default_directive(5), "trace");
+    }
+}
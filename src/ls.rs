@@ -0,0 +1,719 @@
+// src/ls.rs
+
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use std::collections::BTreeMap;
+
+use slam_core::audit::Finding;
+use slam_core::git::{CheckState, Mergeable, PrRecord, PrStatus, StaleBranch};
+use slam_core::Repo;
+
+/// One row of `review ls --summary`: how far the PRs of a change id have come.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChangeSummary {
+    pub change_id: String,
+    pub open: usize,
+    pub approved: usize,
+    pub checks_passing: usize,
+    pub mergeable: usize,
+    /// PRs whose status could not be fetched; counted as open only.
+    pub unknown: usize,
+}
+
+/// Counts the PRs of each change id, sorted by change id; `statuses` line up with `repos`.
+pub fn summarize(repos: &[Repo], statuses: &[Option<PrStatus>]) -> Vec<ChangeSummary> {
+    let mut changes: BTreeMap<&str, ChangeSummary> = BTreeMap::new();
+    for (repo, status) in repos.iter().zip(statuses) {
+        let row = changes.entry(&repo.change_id).or_insert_with(|| ChangeSummary {
+            change_id: repo.change_id.clone(),
+            ..Default::default()
+        });
+        row.open += 1;
+        match status {
+            Some(status) => {
+                row.approved += status.reviewed as usize;
+                row.checks_passing += status.checked as usize;
+                row.mergeable += (status.mergeable == Mergeable::Mergeable) as usize;
+            }
+            None => row.unknown += 1,
+        }
+    }
+    changes.into_values().collect()
+}
+
+/// Renders the rows as an aligned table, with a total line when there is more than one.
+pub fn format_summary(rows: &[ChangeSummary]) -> String {
+    let width = rows
+        .iter()
+        .map(|row| row.change_id.len())
+        .chain(["CHANGE ID".len()])
+        .max()
+        .unwrap_or_default();
+    let line = |change_id: &str, counts: [String; 4]| {
+        format!(
+            "{:<width$}  {:>4}  {:>8}  {:>6}  {:>9}\n",
+            change_id,
+            counts[0],
+            counts[1],
+            counts[2],
+            counts[3],
+            width = width
+        )
+    };
+    let header = ["PRS", "APPROVED", "CHECKS", "MERGEABLE"].map(str::to_string);
+    let mut out = line("CHANGE ID", header).bold().to_string();
+    for row in rows {
+        out.push_str(&line(
+            &row.change_id,
+            [row.open, row.approved, row.checks_passing, row.mergeable].map(|n| n.to_string()),
+        ));
+    }
+    let unknown: usize = rows.iter().map(|row| row.unknown).sum();
+    if unknown > 0 {
+        out.push_str(&format!("\n{} PRs with unknown status (see the log)\n", unknown));
+    }
+    out
+}
+
+/// Order of the rows in `review ls --table`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum LsSort {
+    /// By repo name (default).
+    #[default]
+    Repo,
+    /// Oldest PRs first.
+    Age,
+    /// Failing checks first, then pending, then passing.
+    Checks,
+    /// By PR author, then repo.
+    Author,
+}
+
+/// A column of `review ls --table`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LsColumn {
+    Repo,
+    Change,
+    Pr,
+    Age,
+    Author,
+    Checks,
+    Review,
+    Mergeable,
+}
+
+impl LsColumn {
+    pub const DEFAULT: [LsColumn; 7] = [
+        LsColumn::Repo,
+        LsColumn::Pr,
+        LsColumn::Age,
+        LsColumn::Author,
+        LsColumn::Checks,
+        LsColumn::Review,
+        LsColumn::Mergeable,
+    ];
+
+    fn header(self) -> &'static str {
+        match self {
+            LsColumn::Repo => "REPO",
+            LsColumn::Change => "CHANGE ID",
+            LsColumn::Pr => "PR",
+            LsColumn::Age => "AGE",
+            LsColumn::Author => "AUTHOR",
+            LsColumn::Checks => "CHECKS",
+            LsColumn::Review => "REVIEW",
+            LsColumn::Mergeable => "MERGEABLE",
+        }
+    }
+}
+
+/// Sort rank of a PR's checks: failing, pending, passing, then unknown.
+fn checks_rank(status: Option<&PrStatus>) -> u8 {
+    match status {
+        Some(s) if s.checks_failed => 0,
+        Some(s) if !s.checked => 1,
+        Some(_) => 2,
+        None => 3,
+    }
+}
+
+fn created_at(status: Option<&PrStatus>) -> Option<DateTime<Utc>> {
+    let created_at = status?.created_at.as_deref()?;
+    DateTime::parse_from_rfc3339(created_at)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Age in the largest whole unit: `3d`, `5h` or `12m`.
+fn format_age(created_at: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let age = now.signed_duration_since(created_at);
+    if age.num_days() > 0 {
+        format!("{}d", age.num_days())
+    } else if age.num_hours() > 0 {
+        format!("{}h", age.num_hours())
+    } else {
+        format!("{}m", age.num_minutes().max(0))
+    }
+}
+
+fn cell(column: LsColumn, repo: &Repo, status: Option<&PrStatus>, now: DateTime<Utc>) -> String {
+    let Some(s) = status else {
+        return match column {
+            LsColumn::Repo => repo.reposlug.clone(),
+            LsColumn::Change => repo.change_id.clone(),
+            LsColumn::Pr => format!("#{}", repo.pr_number),
+            _ => "?".to_string(),
+        };
+    };
+    match column {
+        LsColumn::Repo => repo.reposlug.clone(),
+        LsColumn::Change => repo.change_id.clone(),
+        LsColumn::Pr => format!("#{}", repo.pr_number),
+        LsColumn::Age => created_at(status).map_or_else(|| "?".to_string(), |t| format_age(t, now)),
+        LsColumn::Author if s.author.is_empty() => "?".to_string(),
+        LsColumn::Author => s.author.clone(),
+        LsColumn::Checks => ["fail", "pending", "pass"][checks_rank(status) as usize].to_string(),
+        LsColumn::Review if s.draft => "draft".to_string(),
+        LsColumn::Review if s.reviewed => "approved".to_string(),
+        LsColumn::Review => "pending".to_string(),
+        LsColumn::Mergeable => match s.mergeable {
+            Mergeable::Mergeable => "yes",
+            Mergeable::Conflicting => "conflicts",
+            Mergeable::Unknown => "unknown",
+        }
+        .to_string(),
+    }
+}
+
+/// Renders one row per PR, ordered by `sort` (ties broken by repo); `statuses` line up with `repos`.
+pub fn format_table(
+    repos: &[Repo],
+    statuses: &[Option<PrStatus>],
+    sort: LsSort,
+    columns: &[LsColumn],
+    now: DateTime<Utc>,
+) -> String {
+    let mut rows: Vec<(&Repo, Option<&PrStatus>)> = repos.iter().zip(statuses.iter().map(Option::as_ref)).collect();
+    rows.sort_by(|(a, _), (b, _)| (&a.reposlug, a.pr_number).cmp(&(&b.reposlug, b.pr_number)));
+    match sort {
+        LsSort::Repo => {}
+        // PRs whose status is unknown sort last.
+        LsSort::Age => rows.sort_by_key(|(_, status)| {
+            let created_at = created_at(*status);
+            (created_at.is_none(), created_at)
+        }),
+        LsSort::Checks => rows.sort_by_key(|(_, status)| checks_rank(*status)),
+        LsSort::Author => rows.sort_by_key(|(_, status)| (status.is_none(), status.map(|s| s.author.to_lowercase()))),
+    }
+
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|(repo, status)| columns.iter().map(|c| cell(*c, repo, *status, now)).collect())
+        .collect();
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            cells
+                .iter()
+                .map(|row| row[i].len())
+                .chain([c.header().len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    let line = |values: Vec<&str>| {
+        let line = values
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        format!("{}\n", line.trim_end())
+    };
+    let mut out = line(columns.iter().map(|c| c.header()).collect()).bold().to_string();
+    for row in &cells {
+        out.push_str(&line(row.iter().map(String::as_str).collect()));
+    }
+    out
+}
+
+/// Renders `review checks`: per PR, the checks that are failing or still pending, with links to
+/// their runs; PRs whose checks all pass get one line. `statuses` line up with `repos`.
+pub fn format_checks(repos: &[Repo], statuses: &[Option<PrStatus>]) -> String {
+    let mut rows: Vec<(&Repo, Option<&PrStatus>)> = repos.iter().zip(statuses.iter().map(Option::as_ref)).collect();
+    rows.sort_by(|(a, _), (b, _)| (&a.reposlug, a.pr_number).cmp(&(&b.reposlug, b.pr_number)));
+
+    let mut out = String::new();
+    for (repo, status) in rows {
+        let title = format!("{} #{}", repo.reposlug, repo.pr_number).bold();
+        let Some(status) = status else {
+            out.push_str(&format!("{}  status unknown (see the log)\n", title));
+            continue;
+        };
+        let mut checks: Vec<_> = status
+            .checks
+            .iter()
+            .filter(|check| check.state != CheckState::Passing)
+            .collect();
+        if checks.is_empty() {
+            let verdict = if status.checks.is_empty() {
+                "no checks"
+            } else {
+                "all checks passing"
+            };
+            out.push_str(&format!("{}  {}\n", title, verdict));
+            continue;
+        }
+        checks.sort_by_key(|check| check.state == CheckState::Pending);
+        out.push_str(&format!("{}\n", title));
+        let width = checks.iter().map(|check| check.name.len()).max().unwrap_or_default();
+        for check in checks {
+            let state = match check.state {
+                CheckState::Failing => "failing".red(),
+                _ => "pending".yellow(),
+            };
+            let line = format!(
+                "  {}  {:<width$}  {}",
+                state,
+                check.name,
+                check.url.as_deref().unwrap_or_default(),
+                width = width
+            );
+            out.push_str(&format!("{}\n", line.trim_end()));
+        }
+    }
+    out
+}
+
+/// What `review stats` reports: how far a rollout (or all of them) has come across the org.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RolloutStats {
+    pub open: usize,
+    pub merged: usize,
+    pub closed: usize,
+    /// Median time from opening a PR to merging it.
+    pub median_time_to_merge: Option<chrono::Duration>,
+    /// Repos with an open PR that isn't approved yet, sorted.
+    pub pending_review: Vec<String>,
+    /// Open PRs held up by a failing check, and by a missing approval; a PR can count for both.
+    pub failing_checks: usize,
+    pub missing_approvals: usize,
+    /// Open PRs whose status could not be fetched.
+    pub unknown: usize,
+}
+
+/// Tallies the PRs of `history`; `statuses` line up with its open PRs, in order.
+pub fn rollout_stats(history: &[(String, PrRecord)], statuses: &[Option<PrStatus>]) -> RolloutStats {
+    let mut stats = RolloutStats::default();
+    let mut merge_times = Vec::new();
+    let mut statuses = statuses.iter();
+    for (reposlug, pr) in history {
+        match pr.state.as_str() {
+            "OPEN" => {
+                stats.open += 1;
+                match statuses.next().and_then(Option::as_ref) {
+                    Some(status) => {
+                        if !status.reviewed {
+                            stats.missing_approvals += 1;
+                            stats.pending_review.push(reposlug.clone());
+                        }
+                        stats.failing_checks += status.checks_failed as usize;
+                    }
+                    None => stats.unknown += 1,
+                }
+            }
+            "MERGED" => {
+                stats.merged += 1;
+                let parse = |t: &Option<String>| DateTime::parse_from_rfc3339(t.as_deref()?).ok();
+                if let (Some(created), Some(merged)) = (parse(&pr.created_at), parse(&pr.merged_at)) {
+                    merge_times.push(merged.signed_duration_since(created));
+                }
+            }
+            _ => stats.closed += 1,
+        }
+    }
+    merge_times.sort();
+    let mid = merge_times.len() / 2;
+    stats.median_time_to_merge = match merge_times.len() {
+        0 => None,
+        n if n % 2 == 1 => Some(merge_times[mid]),
+        _ => Some((merge_times[mid - 1] + merge_times[mid]) / 2),
+    };
+    stats.pending_review.sort();
+    stats.pending_review.dedup();
+    stats
+}
+
+/// A duration in its two largest units: `2d 4h`, `5h 12m` or `12m`.
+fn format_duration(duration: chrono::Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// Renders `review stats`, the blockers on the open PRs listed most common first.
+pub fn format_stats(stats: &RolloutStats) -> String {
+    let mut out = format!(
+        "{}  {} open, {} merged, {} closed\n",
+        format!("{:<13}", "PRs").bold(),
+        stats.open,
+        stats.merged,
+        stats.closed
+    );
+    if let Some(median) = stats.median_time_to_merge {
+        out.push_str(&format!(
+            "{}  median {} over {} merged PRs\n",
+            format!("{:<13}", "Time to merge").bold(),
+            format_duration(median),
+            stats.merged
+        ));
+    }
+    if !stats.pending_review.is_empty() {
+        out.push_str(&format!(
+            "\n{} ({} repos):\n",
+            "Pending review".bold(),
+            stats.pending_review.len()
+        ));
+        for reposlug in &stats.pending_review {
+            out.push_str(&format!("  {}\n", reposlug));
+        }
+    }
+    let mut blockers = [
+        ("missing approvals", stats.missing_approvals),
+        ("failing checks", stats.failing_checks),
+    ];
+    blockers.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    if blockers.iter().any(|(_, count)| *count > 0) {
+        out.push_str(&format!("\n{}:\n", "Blockers on open PRs".bold()));
+        for (blocker, count) in blockers {
+            out.push_str(&format!("  {:<17}  {}\n", blocker, count));
+        }
+    }
+    if stats.unknown > 0 {
+        out.push_str(&format!(
+            "\n{} open PRs with unknown status (see the log)\n",
+            stats.unknown
+        ));
+    }
+    out
+}
+
+/// Renders `review stale`: each stale branch with the age of its last commit and its PR, oldest
+/// first, then the counts.
+pub fn format_stale(branches: &[StaleBranch], now: DateTime<Utc>) -> String {
+    let mut branches: Vec<&StaleBranch> = branches.iter().collect();
+    branches.sort_by(|a, b| a.committed.cmp(&b.committed));
+    let slug_width = branches.iter().map(|b| b.reposlug.len()).max().unwrap_or(0);
+    let branch_width = branches.iter().map(|b| b.branch.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for stale in &branches {
+        let age = DateTime::parse_from_rfc3339(&stale.committed)
+            .map(|committed| format!("{}d", (now - committed.with_timezone(&Utc)).num_days()))
+            .unwrap_or_else(|_| stale.committed.clone());
+        let pr = stale
+            .pr_number
+            .map(|n| format!("# {}", n))
+            .unwrap_or_else(|| "no PR".to_string());
+        out.push_str(&format!(
+            "  {:<slug_width$}  {:<branch_width$}  {:>5}  {}\n",
+            stale.reposlug, stale.branch, age, pr
+        ));
+    }
+    let repos = branches
+        .iter()
+        .map(|b| &b.reposlug)
+        .collect::<std::collections::HashSet<_>>()
+        .len();
+    let prs = branches.iter().filter(|b| b.pr_number.is_some()).count();
+    out.push_str(&format!(
+        "\n{} stale branches ({} with open PRs) in {} repos\n",
+        branches.len().to_string().yellow(),
+        prs,
+        repos
+    ));
+    out
+}
+
+/// Renders `audit`: the repos out of compliance under what is wrong with them, then the counts.
+pub fn format_audit(findings: &[(String, Finding)]) -> String {
+    let mut deviating = Vec::new();
+    let mut missing = Vec::new();
+    let mut unreadable = Vec::new();
+    for (reposlug, finding) in findings {
+        match finding {
+            Finding::Compliant => {}
+            Finding::Deviating(files) => deviating.push(format!("{}  {}", reposlug, files.join(", "))),
+            Finding::Missing => missing.push(reposlug.clone()),
+            Finding::Unreadable(reason) => unreadable.push(format!("{}  {}", reposlug, reason)),
+        }
+    }
+    let mut out = String::new();
+    for (title, lines) in [
+        ("Deviating", &deviating),
+        ("No matching file", &missing),
+        ("Unreadable", &unreadable),
+    ] {
+        if !lines.is_empty() {
+            out.push_str(&format!("{} ({}):\n", title.bold(), lines.len()));
+            for line in lines {
+                out.push_str(&format!("  {}\n", line));
+            }
+            out.push('\n');
+        }
+    }
+    let compliant = findings.iter().filter(|(_, finding)| finding.is_compliant()).count();
+    out.push_str(&format!(
+        "{} of {} repos compliant\n",
+        if compliant == findings.len() {
+            compliant.to_string().green()
+        } else {
+            compliant.to_string().yellow()
+        },
+        findings.len()
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(reviewed: bool, checked: bool, mergeable: bool) -> Option<PrStatus> {
+        Some(PrStatus {
+            head_ref: String::new(),
+            draft: false,
+            mergeable: if mergeable {
+                Mergeable::Mergeable
+            } else {
+                Mergeable::Conflicting
+            },
+            reviewed,
+            checked,
+            checks_failed: false,
+            open: true,
+            merged: false,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_format_audit() {
+        colored::control::set_override(false);
+        let findings = vec![
+            (
+                "org/bad".to_string(),
+                Finding::Deviating(vec!["ci.yml".to_string(), "cd.yml".to_string()]),
+            ),
+            ("org/good".to_string(), Finding::Compliant),
+            ("org/none".to_string(), Finding::Missing),
+        ];
+        assert_eq!(
+            format_audit(&findings),
+            "Deviating (1):\n  org/bad  ci.yml, cd.yml\n\nNo matching file (1):\n  org/none\n\n1 of 3 repos compliant\n"
+        );
+    }
+
+    #[test]
+    fn test_format_stale() {
+        colored::control::set_override(false);
+        let stale = |reposlug: &str, branch: &str, committed: &str, pr_number| StaleBranch {
+            reposlug: reposlug.to_string(),
+            branch: branch.to_string(),
+            committed: committed.to_string(),
+            pr_number,
+        };
+        let branches = [
+            stale("org/web", "SLAM-b", "2024-04-01T00:00:00Z", None),
+            stale("org/api", "SLAM-a", "2024-03-01T00:00:00Z", Some(12)),
+        ];
+        let now = "2024-05-01T00:00:00Z".parse().unwrap();
+        assert_eq!(
+            format_stale(&branches, now),
+            "  org/api  SLAM-a    61d  # 12\n  org/web  SLAM-b    30d  no PR\n\n2 stale branches (1 with open PRs) in 2 repos\n"
+        );
+    }
+
+    #[test]
+    fn test_rollout_stats() {
+        let pr = |reposlug: &str, state: &str, created: &str, merged: Option<&str>| {
+            (
+                reposlug.to_string(),
+                PrRecord {
+                    head_ref_name: "SLAM-x".to_string(),
+                    number: 1,
+                    state: state.to_string(),
+                    created_at: Some(created.to_string()),
+                    merged_at: merged.map(str::to_string),
+                },
+            )
+        };
+        let history = [
+            pr("org/a", "MERGED", "2024-05-01T00:00:00Z", Some("2024-05-01T02:00:00Z")),
+            pr("org/b", "OPEN", "2024-05-01T00:00:00Z", None),
+            pr("org/c", "MERGED", "2024-05-01T00:00:00Z", Some("2024-05-02T06:00:00Z")),
+            pr("org/d", "CLOSED", "2024-05-01T00:00:00Z", None),
+            pr("org/e", "OPEN", "2024-05-01T00:00:00Z", None),
+            pr("org/f", "OPEN", "2024-05-01T00:00:00Z", None),
+        ];
+        let mut failing = status(false, false, true);
+        failing.as_mut().unwrap().checks_failed = true;
+        let stats = rollout_stats(&history, &[failing, status(true, true, true), None]);
+
+        assert_eq!((stats.open, stats.merged, stats.closed, stats.unknown), (3, 2, 1, 1));
+        assert_eq!(stats.median_time_to_merge, Some(chrono::Duration::hours(16)));
+        assert_eq!(stats.pending_review, ["org/b"]);
+        assert_eq!((stats.failing_checks, stats.missing_approvals), (1, 1));
+        assert_eq!(format_duration(chrono::Duration::hours(30)), "1d 6h");
+        assert_eq!(format_duration(chrono::Duration::minutes(75)), "1h 15m");
+        assert!(format_stats(&stats).contains("median 16h 0m over 2 merged PRs"));
+    }
+
+    #[test]
+    fn test_summarize_groups_by_change_id() {
+        let repos = [
+            Repo::create_repo_from_remote_with_pr("org/b", "SLAM-b", 1),
+            Repo::create_repo_from_remote_with_pr("org/a", "SLAM-a", 2),
+            Repo::create_repo_from_remote_with_pr("org/c", "SLAM-b", 3),
+        ];
+        let rows = summarize(&repos, &[status(true, true, true), status(false, true, false), None]);
+        assert_eq!(
+            rows,
+            vec![
+                ChangeSummary {
+                    change_id: "SLAM-a".to_string(),
+                    open: 1,
+                    checks_passing: 1,
+                    ..Default::default()
+                },
+                ChangeSummary {
+                    change_id: "SLAM-b".to_string(),
+                    open: 2,
+                    approved: 1,
+                    checks_passing: 1,
+                    mergeable: 1,
+                    unknown: 1,
+                },
+            ]
+        );
+    }
+
+    fn pr(author: &str, created_at: &str, checked: bool, checks_failed: bool) -> Option<PrStatus> {
+        Some(PrStatus {
+            author: author.to_string(),
+            created_at: Some(created_at.to_string()),
+            checked,
+            checks_failed,
+            open: true,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_format_table_sorts() {
+        colored::control::set_override(false);
+        let now = DateTime::parse_from_rfc3339("2024-03-10T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let repos = [
+            Repo::create_repo_from_remote_with_pr("org/a", "SLAM-x", 1),
+            Repo::create_repo_from_remote_with_pr("org/b", "SLAM-x", 2),
+            Repo::create_repo_from_remote_with_pr("org/c", "SLAM-x", 3),
+            Repo::create_repo_from_remote_with_pr("org/d", "SLAM-x", 4),
+        ];
+        let statuses = [
+            pr("zed", "2024-03-10T07:00:00Z", true, false),
+            pr("amy", "2024-03-01T12:00:00Z", false, true),
+            pr("bob", "2024-03-09T00:00:00Z", false, false),
+            None,
+        ];
+        let first_column = |sort, columns: &[LsColumn]| -> Vec<String> {
+            format_table(&repos, &statuses, sort, columns, now)
+                .lines()
+                .skip(1)
+                .map(|line| line.split_whitespace().next().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(
+            first_column(LsSort::Repo, &[LsColumn::Repo]),
+            ["org/a", "org/b", "org/c", "org/d"]
+        );
+        assert_eq!(first_column(LsSort::Age, &[LsColumn::Age]), ["9d", "1d", "5h", "?"]);
+        assert_eq!(
+            first_column(LsSort::Checks, &[LsColumn::Checks]),
+            ["fail", "pending", "pass", "?"]
+        );
+        assert_eq!(
+            first_column(LsSort::Author, &[LsColumn::Author]),
+            ["amy", "bob", "zed", "?"]
+        );
+
+        let out = format_table(&repos, &statuses, LsSort::Repo, &LsColumn::DEFAULT, now);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "REPO   PR  AGE  AUTHOR  CHECKS   REVIEW   MERGEABLE");
+        assert_eq!(lines[1], "org/a  #1  5h   zed     pass     pending  unknown");
+        assert_eq!(lines[4], "org/d  #4  ?    ?       ?        ?        ?");
+    }
+
+    #[test]
+    fn test_format_checks() {
+        use slam_core::git::Check;
+
+        colored::control::set_override(false);
+        let check = |name: &str, state, url: Option<&str>| Check {
+            name: name.to_string(),
+            state,
+            url: url.map(str::to_string),
+        };
+        let repos = [
+            Repo::create_repo_from_remote_with_pr("org/b", "SLAM-x", 2),
+            Repo::create_repo_from_remote_with_pr("org/a", "SLAM-x", 1),
+            Repo::create_repo_from_remote_with_pr("org/c", "SLAM-x", 3),
+        ];
+        let statuses = [
+            Some(PrStatus {
+                checks: vec![check("build", CheckState::Passing, None)],
+                ..Default::default()
+            }),
+            Some(PrStatus {
+                checks: vec![
+                    check("lint", CheckState::Pending, None),
+                    check("build", CheckState::Passing, None),
+                    check("ci / test", CheckState::Failing, Some("https://ci.example.com/1")),
+                ],
+                ..Default::default()
+            }),
+            None,
+        ];
+        assert_eq!(
+            format_checks(&repos, &statuses),
+            "org/a #1\n  failing  ci / test  https://ci.example.com/1\n  pending  lint\n\
+             org/b #2  all checks passing\n\
+             org/c #3  status unknown (see the log)\n"
+        );
+    }
+
+    #[test]
+    fn test_format_summary() {
+        colored::control::set_override(false);
+        let rows = vec![ChangeSummary {
+            change_id: "SLAM-2024-01-01".to_string(),
+            open: 12,
+            approved: 3,
+            checks_passing: 10,
+            mergeable: 11,
+            unknown: 1,
+        }];
+        let out = format_summary(&rows);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "CHANGE ID         PRS  APPROVED  CHECKS  MERGEABLE");
+        assert_eq!(lines[1], "SLAM-2024-01-01    12         3      10         11");
+        assert!(out.ends_with("1 PRs with unknown status (see the log)\n"));
+    }
+}
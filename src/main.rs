@@ -1,133 +1,525 @@
 // src/main.rs
 
-use clap::{CommandFactory, FromArgMatches};
-use eyre::{Context, Result};
-use glob::Pattern;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use eyre::{eyre, Context, Result};
 use itertools::Itertools;
 use log::{debug, info, warn};
 use rayon::prelude::*;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+use slam_core::audit::{self, Expectation, Finding};
+use slam_core::{cache, diff, forge, git, github, pool, repo, set, transaction, utils, ChangeRun, RepoSet, ReviewSet};
 
 // Built-in version from build.rs via env!("GIT_DESCRIBE")
 
 mod cli;
-mod diff;
-mod git;
-mod repo;
+mod clipboard;
+mod config;
+mod confirm;
+mod enforce;
+mod jira;
+mod lock;
+mod logging;
+mod ls;
+mod metrics;
+mod notify;
+mod output;
+mod overrides;
+mod pager;
+mod picker;
+mod report;
 mod sandbox;
-mod transaction;
-mod utils;
+mod status;
+mod tui;
+
+/// `slam create` exit codes for partial results; see "Exit Codes" in the README.
+const EXIT_PARTIAL_FAILURE: i32 = 2;
+const EXIT_ALL_FAILED: i32 = 3;
+const EXIT_NO_MATCH: i32 = 4;
+const EXIT_NONCOMPLIANT: i32 = 5;
+
+/// Maps per-repo create results to the process exit code.
+fn create_exit_code(results: &[repo::CreateResult]) -> i32 {
+    let failed = results
+        .iter()
+        .filter(|r| matches!(r, repo::CreateResult::Failed { .. }))
+        .count();
+    if results.is_empty() {
+        EXIT_NO_MATCH
+    } else if failed == 0 {
+        0
+    } else if failed == results.len() {
+        EXIT_ALL_FAILED
+    } else {
+        EXIT_PARTIAL_FAILURE
+    }
+}
 
-/// Extracts the repository name (the part after '/') from a reposlug.
-/// If the reposlug is not in the expected format, returns the full string.
-fn extract_reponame(reposlug: &str) -> &str {
-    reposlug.split('/').nth(1).unwrap_or(reposlug)
+/// How many repos create, publish, approve and purge may act on; `force` lifts the limit.
+#[derive(Debug, Clone, Copy)]
+struct RepoLimit {
+    max: usize,
+    force: bool,
 }
 
-/// Filters the given vector of repositories according to a list of filtering specifications.
-/// The filter criteria are applied in the following order:
-/// 1. Exact match on the repository name (the part after '/')
-/// 2. Starts-with match on the repository name
-/// 3. Exact match on the full reposlug ("org/reponame")
-/// 4. Starts-with match on the full reposlug
-///
-/// At the first level where one or more repositories match, those matches are used.
-/// Finally, the resulting list is sorted by reposlug using itertools.
-fn filter_repos_by_spec(repos: Vec<repo::Repo>, specs: &[String]) -> Vec<repo::Repo> {
-    let filtered: Vec<repo::Repo> = if specs.is_empty() {
-        repos
+impl RepoLimit {
+    /// Errors when `count` repos are more than allowed, so a too-broad pattern stops the run
+    /// before anything is touched.
+    fn check(&self, count: usize, action: &str) -> Result<()> {
+        if count > self.max && !self.force {
+            return Err(eyre!(
+                "Refusing to {} {} repos, more than --max-repos {}; narrow the repo patterns or pass --force",
+                action,
+                count,
+                self.max
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Commit signing from the flags, falling back to `create.*` in slam.yml. Any signing flag
+/// turns it on; in the config only `sign` and `sign_push` do.
+fn signing(
+    config: &config::Config,
+    sign: bool,
+    key: Option<String>,
+    format: Option<git::SigningFormat>,
+    sign_push: bool,
+) -> Option<git::Signing> {
+    let create = &config.create;
+    let enabled = sign || sign_push || key.is_some() || format.is_some() || create.sign || create.sign_push;
+    enabled.then(|| git::Signing {
+        key: key.or_else(|| create.signing_key.clone()),
+        format: format.or(create.signing_format),
+        push: sign_push || create.sign_push,
+    })
+}
+
+/// The flag's identity, else the one in `env_var`, else the config's `setting`.
+fn identity(
+    flag: Option<git::Identity>,
+    env_var: &str,
+    config: Option<&str>,
+    setting: &str,
+) -> Result<Option<git::Identity>> {
+    if flag.is_some() {
+        return Ok(flag);
+    }
+    if let Some(value) = std::env::var(env_var).ok().filter(|v| !v.trim().is_empty()) {
+        return value
+            .parse()
+            .map(Some)
+            .wrap_err_with(|| format!("Invalid ${}", env_var));
+    }
+    config
+        .map(|value| {
+            value
+                .parse()
+                .wrap_err_with(|| format!("Invalid {} in slam.yml", setting))
+        })
+        .transpose()
+}
+
+/// The repos an audit checks: the sandbox clones matching `repo_ptns`, or with `remote` those
+/// of `orgs` on GitHub.
+fn audit_set(root: &Path, files: &[String], repo_ptns: &[String], remote: bool, orgs: &[String]) -> Result<RepoSet> {
+    if remote {
+        let mut reposlugs = Vec::new();
+        for org in orgs {
+            reposlugs.extend(git::find_repos_in_org(org, &git::RepoFilter::default())?);
+        }
+        Ok(RepoSet::remote(&reposlugs, repo_ptns, &None, files, "audit", false))
     } else {
-        // Level 1: Exact match on repository name.
-        let level1: Vec<repo::Repo> = repos
+        Ok(RepoSet::discover(root, &None, files, "audit", false)?.filter_by_spec(repo_ptns))
+    }
+}
+
+/// Runs `slam audit`: 0 when every matched repo complies, `EXIT_NONCOMPLIANT` otherwise.
+fn process_audit_command(
+    root: &Path,
+    files: &[String],
+    expect: &Expectation,
+    repo_ptns: &[String],
+    remote: bool,
+    orgs: &[String],
+) -> Result<i32> {
+    let repos = audit_set(root, files, repo_ptns, remote, orgs)?;
+    if repos.is_empty() {
+        println!("No repositories matched your criteria.");
+        return Ok(EXIT_NO_MATCH);
+    }
+    let findings = audit::audit(&repos, expect);
+    print!("{}", ls::format_audit(&findings));
+    Ok(if findings.iter().all(|(_, finding)| finding.is_compliant()) {
+        0
+    } else {
+        EXIT_NONCOMPLIANT
+    })
+}
+
+/// Audits `policy` and runs its create on the repos that drifted from it: those whose files
+/// deviate or are missing. Returns the create's exit code, or 0 when nothing drifted.
+fn enforce_policy(
+    policy: &enforce::Policy,
+    config: &config::Config,
+    root: &Path,
+    branch_prefix: &str,
+    limit: RepoLimit,
+    locks_dir: &Path,
+) -> Result<i32> {
+    let probe = policy.create_args(&[])?;
+    let repos = audit_set(root, &policy.files, &policy.repos, probe.remote, &probe.orgs)?;
+    let findings = audit::audit(&repos, &policy.expectation()?);
+    let mut drifted = Vec::new();
+    for (reposlug, finding) in &findings {
+        match finding {
+            Finding::Compliant => {}
+            Finding::Deviating(_) | Finding::Missing => drifted.push(reposlug.clone()),
+            Finding::Unreadable(e) => warn!("Policy '{}': couldn't audit {}: {}", policy.name, reposlug, e),
+        }
+    }
+    println!(
+        "Policy '{}': {} of {} repos drifted",
+        policy.name,
+        drifted.len(),
+        findings.len()
+    );
+    if drifted.is_empty() {
+        return Ok(0);
+    }
+    let args = policy.create_args(&drifted)?;
+    let change_id = repo::normalize_change_id(args.change_id.as_deref().unwrap_or_default(), branch_prefix);
+    let _change_lock = lock::acquire(
+        &lock::change_lock_path(locks_dir, &change_id),
+        &format!("change id '{}'", change_id),
+        &format!("enforce (policy '{}')", policy.name),
+        lock::Contention::Wait,
+    )?;
+    process_create_command(args, config, root, branch_prefix, limit)
+}
+
+/// Runs `slam enforce`: a pass audits every policy and runs its create on the drifted repos; with
+/// an `interval` the passes repeat until the process is stopped. Returns `EXIT_PARTIAL_FAILURE`
+/// when a policy of the single pass failed.
+fn process_enforce_command(
+    policies_path: &Path,
+    interval: Option<Duration>,
+    config: &config::Config,
+    root: &Path,
+    branch_prefix: &str,
+    limit: RepoLimit,
+    log_dir: &Path,
+) -> Result<i32> {
+    let mut policies = enforce::load(policies_path, branch_prefix)?;
+    loop {
+        // Passes wait for other runs rather than failing, and refresh the clones so the audit
+        // sees the default branches as they are now.
+        let local = policies
             .iter()
-            .filter(|r| specs.iter().any(|spec| extract_reponame(&r.reposlug) == spec))
-            .cloned()
-            .collect();
-        if !level1.is_empty() {
-            level1
-        } else {
-            // Level 2: Starts-with match on repository name.
-            let level2: Vec<repo::Repo> = repos
-                .iter()
-                .filter(|r| specs.iter().any(|spec| extract_reponame(&r.reposlug).starts_with(spec)))
-                .cloned()
-                .collect();
-            if !level2.is_empty() {
-                level2
-            } else {
-                // Level 3: Exact match on full reposlug.
-                let level3: Vec<repo::Repo> = repos.iter().filter(|r| specs.contains(&r.reposlug)).cloned().collect();
-                if !level3.is_empty() {
-                    level3
-                } else {
-                    // Level 4: Starts-with match on full reposlug.
-                    repos
-                        .iter()
-                        .filter(|r| specs.iter().any(|spec| r.reposlug.starts_with(spec)))
-                        .cloned()
-                        .collect()
+            .any(|policy| policy.create_args(&[]).is_ok_and(|args| !args.remote));
+        let sandbox_lock = local
+            .then(|| {
+                lock::acquire(
+                    &root.join(lock::SANDBOX_LOCK),
+                    &format!("the sandbox '{}'", root.display()),
+                    "enforce",
+                    lock::Contention::Wait,
+                )
+            })
+            .transpose()?;
+        if local {
+            if let Err(e) = sandbox::sandbox_refresh(root, branch_prefix) {
+                warn!("Failed to refresh the sandbox: {}", e);
+            }
+        }
+        let mut failed = 0;
+        for policy in &policies {
+            match enforce_policy(policy, config, root, branch_prefix, limit, &log_dir.join("locks")) {
+                Ok(0) | Ok(EXIT_NO_MATCH) => {}
+                Ok(code) => {
+                    warn!("Policy '{}': create exited with {}", policy.name, code);
+                    failed += 1;
+                }
+                Err(e) => {
+                    warn!("Policy '{}' failed: {}", policy.name, e);
+                    failed += 1;
                 }
             }
         }
+        let Some(interval) = interval else {
+            return Ok(if failed == 0 { 0 } else { EXIT_PARTIAL_FAILURE });
+        };
+        drop(sandbox_lock);
+        info!(
+            "Enforced {} policies ({} failed); next pass in {}s",
+            policies.len(),
+            failed,
+            interval.as_secs()
+        );
+        std::thread::sleep(interval);
+        // Edits to the policies apply from the next pass; a broken file keeps the last good one.
+        match enforce::load(policies_path, branch_prefix) {
+            Ok(loaded) => policies = loaded,
+            Err(e) => warn!("Keeping the previous policies: {}", e),
+        }
+    }
+}
+
+/// Writes the blueprint `change` (a `Change::Copy`) into the fresh clone at `repo_path`, commits
+/// it and pushes it straight to the default branch.
+fn push_blueprint(repo_path: &Path, reposlug: &str, change: &repo::Change, commit_msg: &str) -> Result<()> {
+    let branch = git::current_branch(repo_path)?.ok_or_else(|| eyre!("'{}' has no branch checked out", reposlug))?;
+    let repo::Change::Copy(files, _) = change.interpolated(reposlug, || Ok(branch.clone()))? else {
+        unreachable!("blueprints are copies");
     };
+    for (path, contents) in files {
+        let target = repo_path.join(&path);
+        if let Some(dir) = target.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&target, contents).wrap_err_with(|| format!("Failed to write '{}'", target.display()))?;
+    }
+    git::commit_all(repo_path, commit_msg, &git::CommitOptions::default())?;
+    git::push_branch(repo_path, "origin", &branch, false, false)
+}
 
-    filtered
-        .into_iter()
-        .sorted_by(|a, b| a.reposlug.cmp(&b.reposlug))
-        .collect()
+/// Runs `slam init`: creates each repo, clones it into the sandbox and seeds it with the
+/// blueprint, through an initial PR opened as by `create copy`, or with `direct` by pushing to
+/// the default branch. Repos that already exist are left alone.
+#[allow(clippy::too_many_arguments)]
+fn process_init_command(
+    blueprint: PathBuf,
+    reposlugs: Vec<String>,
+    visibility: git::Visibility,
+    direct: bool,
+    change_id: Option<String>,
+    commit_msg: String,
+    config: &config::Config,
+    root: &Path,
+    branch_prefix: &str,
+    limit: RepoLimit,
+) -> Result<i32> {
+    limit.check(reposlugs.len(), "create")?;
+    if let Some(change_id) = &change_id {
+        cli::validate_change_id(change_id, branch_prefix).map_err(|e| eyre!(e))?;
+    }
+    // Reads the blueprint now, so a missing or binary file fails before any repo is created.
+    let copy = cli::CreateAction::Copy {
+        source_dir: blueprint.clone(),
+        dest: ".".to_string(),
+        if_exists: repo::IfExists::Overwrite,
+        commit: Some(commit_msg.clone()),
+        simplified: false,
+    };
+    let (change, _, _) = copy.decompose()?;
+    if !confirm::confirm(
+        &format!("create the repo from blueprint '{}'", blueprint.display()),
+        &reposlugs,
+    )? {
+        println!("Aborted; nothing was created.");
+        return Ok(0);
+    }
+
+    let mut created = Vec::new();
+    let mut failed = 0;
+    for reposlug in &reposlugs {
+        let repo_path = root.join(reposlug);
+        let result = match git::lookup_repo(reposlug) {
+            Ok(Some(_)) => {
+                eprintln!("Skipped {}: already exists", reposlug);
+                continue;
+            }
+            Ok(None) if repo_path.exists() => Err(eyre!("'{}' already exists in the sandbox", repo_path.display())),
+            Ok(None) => git::create_repo(reposlug, visibility).and_then(|()| git::clone_repo(reposlug, &repo_path)),
+            Err(e) => Err(e),
+        };
+        match result.and_then(|()| {
+            if direct {
+                push_blueprint(&repo_path, reposlug, &change, &commit_msg)
+            } else {
+                Ok(())
+            }
+        }) {
+            Ok(()) => {
+                println!("Created {}", reposlug);
+                created.push(reposlug.clone());
+            }
+            Err(e) => {
+                eprintln!("Failed {}: {}", reposlug, e);
+                failed += 1;
+            }
+        }
+    }
+    let code = if created.is_empty() || direct {
+        0
+    } else {
+        let mut argv = vec!["slam".to_string(), "create".to_string()];
+        for reposlug in &created {
+            argv.extend(["-r".to_string(), reposlug.clone()]);
+        }
+        if let Some(change_id) = change_id {
+            argv.extend(["-x".to_string(), change_id]);
+        }
+        argv.extend([
+            "copy".to_string(),
+            blueprint.to_string_lossy().into_owned(),
+            ".".to_string(),
+            "--if-exists".to_string(),
+            "overwrite".to_string(),
+            "-c".to_string(),
+            commit_msg,
+        ]);
+        let cli::SlamCommand::Create(args) = cli::SlamCli::try_parse_from(argv)?.command else {
+            unreachable!("parsed as create");
+        };
+        process_create_command(*args, config, root, branch_prefix, limit)?
+    };
+    Ok(if failed == 0 {
+        code
+    } else if failed == reposlugs.len() {
+        EXIT_ALL_FAILED
+    } else {
+        EXIT_PARTIAL_FAILURE
+    })
 }
 
+/// Runs `slam create` and returns the exit code for its per-repo results.
 fn process_create_command(
-    files: Vec<String>,
-    change_id: String,
-    buffer: usize,
-    repo_ptns: Vec<String>,
-    action: Option<cli::CreateAction>,
-) -> Result<()> {
+    args: cli::CreateArgs,
+    config: &config::Config,
+    root: &Path,
+    branch_prefix: &str,
+    limit: RepoLimit,
+) -> Result<i32> {
+    let cli::CreateArgs {
+        files,
+        change_id,
+        sanitize: _,
+        change_id_template: _,
+        vars: _,
+        ticket,
+        on_collision,
+        buffer,
+        repo_ptns,
+        remote,
+        orgs,
+        include_generated,
+        require_file,
+        only_if,
+        skip_if,
+        body_template,
+        description,
+        commit_template,
+        conventional,
+        no_verify,
+        hooks,
+        sign,
+        signing_key,
+        signing_format,
+        sign_push,
+        author,
+        committer,
+        no_codeowners,
+        milestone,
+        project,
+        update_pr,
+        fork,
+        stage,
+        include_untracked,
+        plan,
+        keep_going,
+        overrides: overrides_path,
+        report: report_path,
+        pr_urls: pr_urls_path,
+        copy_pr_urls,
+        pick,
+        action,
+    } = args;
+    let mut run_metrics = metrics::RunMetrics::new("create");
+
     let total_emoji = "🔍";
     let repos_emoji = "📦";
     let files_emoji = "📄";
     let diffs_emoji = "📝";
+    let compliant_emoji = "✅";
+    let required_emoji = "🧩";
+    let skipped_emoji = "⏭";
+    let picked_emoji = "👆";
 
     let (change, commit_msg, simplified) = match action {
         Some(action) => {
-            let (change, commit_msg, simplified) = action.decompose();
+            let (change, commit_msg, simplified) = action.decompose()?;
             (Some(change), commit_msg, simplified)
         }
         None => (None, None, false),
     };
 
-    let root = std::env::current_dir()?;
-    let discovered_paths = git::find_git_repositories(&root)?;
-    let mut discovered_repos = Vec::new();
-
-    for path in discovered_paths {
-        if let Some(repo) = repo::Repo::create_repo_from_local(&path, &root, &change, &files, &change_id) {
-            discovered_repos.push(repo);
+    let change_id = change_id.unwrap_or_else(|| cli::default_change_id(branch_prefix));
+    let mut status = Vec::new();
+    let mut filtered_repos = if remote {
+        let mut reposlugs = Vec::new();
+        for org in &orgs {
+            reposlugs.extend(git::find_repos_in_org(org, &git::RepoFilter::default())?);
         }
+        status.push(format!("{}{}", reposlugs.len(), total_emoji));
+        RepoSet::remote(&reposlugs, &repo_ptns, &change, &files, &change_id, include_generated)
+    } else {
+        let discovered_repos = RepoSet::discover(root, &change, &files, &change_id, include_generated)?;
+        status.push(format!("{}{}", discovered_repos.len(), total_emoji));
+        discovered_repos.filter_by_spec(&repo_ptns)
+    };
+    for reposlug in filtered_repos.protected() {
+        eprintln!(
+            "Skipped {}: protected (safety.protected; --allow-protected to include)",
+            reposlug
+        );
     }
 
-    let mut status = Vec::new();
-    status.push(format!("{}{}", discovered_repos.len(), total_emoji));
-
-    // Use the new filtering function instead of the inline lambda.
-    let mut filtered_repos = filter_repos_by_spec(discovered_repos, &repo_ptns);
-
     if !repo_ptns.is_empty() {
         status.push(format!("{}{}", filtered_repos.len(), repos_emoji));
     }
+    let overrides = overrides::load(overrides_path, root)?;
+    for (reposlug, reason) in filtered_repos.apply_overrides(&overrides, include_generated)? {
+        eprintln!("Skipped {}: {}", reposlug, reason);
+    }
+    if !require_file.is_empty() {
+        filtered_repos.retain_with_required_files(&require_file)?;
+        status.push(format!("{}{}", filtered_repos.len(), required_emoji));
+    }
     if !files.is_empty() {
-        filtered_repos.retain(|repo| !repo.files.is_empty());
+        if !include_untracked {
+            filtered_repos.exclude_untracked_files();
+        }
+        filtered_repos.filter_files_by_content(&set::ContentFilter { only_if, skip_if });
+        filtered_repos.retain_with_files();
         status.push(format!("{}{}", filtered_repos.len(), files_emoji));
     }
+    if pick && !filtered_repos.is_empty() {
+        let reposlugs = filtered_repos
+            .repos()
+            .iter()
+            .map(|repo| repo.reposlug.clone())
+            .collect();
+        let Some(picked) = picker::pick(reposlugs)? else {
+            println!("Aborted; nothing was changed.");
+            return Ok(0);
+        };
+        filtered_repos.retain_picked(&picked);
+        status.push(format!("{}{}", filtered_repos.len(), picked_emoji));
+    }
     // Dry-run: if no change is specified, list matched repositories and exit.
     if change.is_none() {
         if filtered_repos.is_empty() {
             println!("No repositories matched your criteria.");
+            return Ok(EXIT_NO_MATCH);
         } else {
             println!("Matched repositories:");
-            for repo in &filtered_repos {
+            for repo in filtered_repos.repos() {
                 println!("  {}", repo.reposlug);
                 if !files.is_empty() {
                     for file in &repo.files {
@@ -135,164 +527,1051 @@ fn process_create_command(
                     }
                 }
             }
+            let skipped = filtered_repos.skipped();
+            if !skipped.is_empty() {
+                print!("\n{}", skipped_summary(skipped));
+                status.push(format!("{}{}", skipped.len(), skipped_emoji));
+            }
             status.reverse();
             println!("\n  {}", status.join(" | "));
         }
-        return Ok(());
+        return Ok(0);
     }
 
     status.push(format!("{}{}", filtered_repos.len(), diffs_emoji));
+    run_metrics.mark("discover");
+
+    let body_template = read_body_template(body_template, config)?;
+    let commit_template = commit_template.or_else(|| config.create.commit_template.clone());
+    let opts = repo::CreateOptions {
+        buffer,
+        branch_prefix: branch_prefix.to_string(),
+        commit_msg,
+        commit_template,
+        simplified,
+        body_template,
+        description,
+        hooks: repo::HookSelection::from_args(no_verify, hooks),
+        signing: signing(config, sign, signing_key, signing_format, sign_push),
+        author: identity(author, "SLAM_AUTHOR", config.create.author.as_deref(), "create.author")?,
+        committer: identity(
+            committer,
+            "SLAM_COMMITTER",
+            config.create.committer.as_deref(),
+            "create.committer",
+        )?,
+        request_reviews: !no_codeowners,
+        milestone,
+        project,
+        ticket: ticket.as_deref().map(jira::ticket),
+        update_existing_pr: update_pr,
+        fork: fork || (config.create.fork && !remote),
+        stage,
+        include_untracked,
+    };
+    let run = ChangeRun::new(&filtered_repos, opts);
+    if conventional || config.create.conventional_commits {
+        run.validate_conventional()?;
+    }
+    if run.options().commit_msg.is_some() {
+        limit.check(
+            filtered_repos.len(),
+            if stage { "stage commits in" } else { "open PRs in" },
+        )?;
+    }
+    let existing = run.existing_branches();
+    if !existing.is_empty() {
+        let branch = repo::normalize_change_id(&change_id, branch_prefix);
+        let reposlugs: Vec<String> = existing.iter().map(|repo| repo.reposlug.clone()).collect();
+        match on_collision.or(config.create.on_collision).unwrap_or_default() {
+            cli::Collision::Ask => {
+                if !confirm::confirm(&format!("replace the existing remote branch '{}'", branch), &reposlugs)? {
+                    println!("Aborted; nothing was changed.");
+                    return Ok(0);
+                }
+            }
+            cli::Collision::Warn => {
+                eprintln!(
+                    "Warning: replacing the existing remote branch '{}' in: {}",
+                    branch,
+                    reposlugs.join(", ")
+                );
+            }
+            cli::Collision::Error => {
+                return Err(eyre!(
+                    "Branch '{}' already exists on the remote of {}; pick another change id",
+                    branch,
+                    reposlugs.join(", ")
+                ));
+            }
+        }
+    }
+    // Report failures and skips as they happen, each repo's message whole.
+    let printer = output::Printer::stderr();
+    let report = |repo: &repo::Repo, result: &repo::CreateResult| match result {
+        repo::CreateResult::Skipped { reason } => printer.print(format!("Skipped {}: {}", repo.reposlug, reason)),
+        repo::CreateResult::Failed { reason } => printer.print(format!("Error: {}", reason)),
+        _ => {}
+    };
+    let results = if filtered_repos.is_remote() {
+        run_metrics.phase("apply", || run.run_each(report))
+    } else {
+        // Diff every clone before anything is pushed, so a failing repo stops the run (or is
+        // at least known) before the others are pushed, and the plan can be reviewed.
+        let mut prepared = run_metrics.phase("prepare", || run.prepare());
+        if run.options().commit_msg.is_some() && !stage {
+            let failed = prepared
+                .iter()
+                .filter(|p| matches!(p, repo::Prepared::Done(repo::CreateResult::Failed { .. })))
+                .count();
+            if failed > 0 && !keep_going {
+                prepared = hold_back(prepared, failed);
+            } else if plan && !review_plan(filtered_repos.repos(), &prepared)? {
+                println!("Aborted; nothing was pushed.");
+                return Ok(0);
+            }
+        }
+        run_metrics.phase("apply", || run.apply_each(prepared, report))
+    };
+    printer.finish();
+
+    let mut output = String::new();
+    let mut stats = Vec::new();
+    let mut report_entries = Vec::new();
+    let mut review_urls = Vec::new();
+    let mut opened = Vec::new();
+    let mut updated = Vec::new();
+    let mut outcomes = Vec::new();
+    let mut compliant = Vec::new();
+    let mut skipped = filtered_repos.skipped().to_vec();
+    let mut staged = 0;
+    for (repo, result) in filtered_repos.repos().iter().zip(&results) {
+        let outcome = |url: Option<&String>, error: Option<&String>| notify::RepoOutcome {
+            reposlug: repo.reposlug.clone(),
+            url: url.cloned(),
+            error: error.cloned(),
+        };
+        let (diff, stat, pr_url) = match result {
+            repo::CreateResult::DryRun { diff, stat } => (diff, stat, None),
+            repo::CreateResult::Staged { diff, stat } => {
+                staged += 1;
+                (diff, stat, None)
+            }
+            repo::CreateResult::PrCreated { diff, stat, pr_url } => {
+                outcomes.push(outcome(Some(pr_url), None));
+                opened.push((repo.reposlug.clone(), pr_url.clone()));
+                (diff, stat, Some(pr_url.clone()))
+            }
+            repo::CreateResult::PrUpdated { diff, stat, pr_url } => {
+                outcomes.push(outcome(Some(pr_url), None));
+                updated.push((repo.reposlug.clone(), pr_url.clone()));
+                (diff, stat, Some(pr_url.clone()))
+            }
+            repo::CreateResult::Pushed { diff, stat, review_url } => {
+                outcomes.push(outcome(Some(review_url), None));
+                review_urls.push(format!("  {}  {}\n", repo.reposlug, review_url));
+                (diff, stat, None)
+            }
+            repo::CreateResult::NoChanges => continue,
+            repo::CreateResult::AlreadyCompliant => {
+                compliant.push(format!("  {}\n", repo.reposlug));
+                continue;
+            }
+            repo::CreateResult::Skipped { reason } => {
+                skipped.push((repo.reposlug.clone(), reason.clone()));
+                continue;
+            }
+            repo::CreateResult::Failed { reason } => {
+                outcomes.push(outcome(None, Some(reason)));
+                continue;
+            }
+        };
+        output.push_str(&format!("{}\n", diff));
+        stats.push((repo.reposlug.clone(), *stat));
+        report_entries.push(report::ReportEntry {
+            reposlug: repo.reposlug.clone(),
+            pr_url,
+            stat: *stat,
+            diff: diff.clone(),
+        });
+    }
+    if !stats.is_empty() {
+        output.push_str(&diff::format_diffstat_summary(&stats));
+        output.push('\n');
+    }
+    if !compliant.is_empty() {
+        output.push_str("Already compliant, left alone:\n");
+        output.push_str(&compliant.concat());
+        output.push('\n');
+        status.push(format!("{}{}", compliant.len(), compliant_emoji));
+    }
+    if !skipped.is_empty() {
+        output.push_str(&skipped_summary(&skipped));
+        output.push('\n');
+        status.push(format!("{}{}", skipped.len(), skipped_emoji));
+    }
+    if !review_urls.is_empty() {
+        output.push_str("Pushed without a PR; open the reviews at:\n");
+        output.push_str(&review_urls.concat());
+        output.push('\n');
+    }
+    for (heading, prs) in [("Opened PRs:", &mut opened), ("Updated PRs:", &mut updated)] {
+        if !prs.is_empty() {
+            prs.sort();
+            output.push_str(&format!("{}\n", heading));
+            for (reposlug, url) in prs.iter() {
+                output.push_str(&format!("  {}  {}\n", reposlug, url));
+            }
+            output.push('\n');
+        }
+    }
+    if staged > 0 {
+        let branch = repo::normalize_change_id(&change_id, branch_prefix);
+        output.push_str(&format!(
+            "Staged {} repo(s) on local branch '{}'; `slam publish {}` pushes them and opens the PRs.\n\n",
+            staged, branch, branch
+        ));
+    }
 
-    // Apply changes to repositories in parallel.
-    let results: Vec<Result<Option<String>, eyre::Error>> = filtered_repos
-        .par_iter()
-        .map(|repo| repo.create(&root, buffer, commit_msg.as_deref(), simplified))
-        .collect();
+    status.reverse();
+    output.push_str(&format!("  {}\n", status.join(" | ")));
+    pager::page(&output);
+    if let Some(path) = report_path {
+        report::write(&path, &change_id, &report_entries)?;
+    }
+    run_metrics.count("repos_processed", results.len());
+    run_metrics.count("prs_created", opened.len());
+    run_metrics.count("prs_updated", updated.len());
+    run_metrics.count("failures", outcomes.iter().filter(|o| o.error.is_some()).count());
+    run_metrics.count("skipped", skipped.len());
+    let mut prs = [opened, updated].concat();
+    prs.sort();
+    if let Some(path) = pr_urls_path {
+        fs::write(&path, pr_url_list(&prs))
+            .wrap_err_with(|| format!("Failed to write PR URLs to '{}'", path.display()))?;
+    }
+    if copy_pr_urls && !prs.is_empty() {
+        // Best effort: the PRs exist either way and are listed above.
+        match clipboard::copy(&pr_url_list(&prs)) {
+            Ok(()) => println!("Copied {} PR URL(s) to the clipboard.", prs.len()),
+            Err(e) => warn!("Failed to copy the PR URLs: {}", e),
+        }
+    }
+    run_metrics.mark("total");
+    metrics::send(&run_metrics);
+    notify::send(&notify::RunSummary {
+        command: "create".to_string(),
+        change_id,
+        repos: outcomes,
+    });
+    Ok(create_exit_code(&results))
+}
 
-    let successful_diffs: Vec<String> = results
+/// `prepared` with every repo that is ready skipped instead, because `failed` repos failed to
+/// prepare and nothing is pushed until they are fixed (or `--keep-going` is passed).
+fn hold_back(prepared: Vec<repo::Prepared>, failed: usize) -> Vec<repo::Prepared> {
+    prepared
         .into_iter()
-        .filter_map(|result| match result {
-            Ok(Some(diff)) => Some(diff),
-            Ok(None) => None,
-            Err(e) => {
-                eprintln!("Error: {}", e);
-                None
-            }
+        .map(|prepared| match prepared {
+            repo::Prepared::Ready { .. } => repo::Prepared::Done(repo::CreateResult::Skipped {
+                reason: format!(
+                    "not pushed: {} repo(s) failed to prepare (--keep-going pushes the others)",
+                    failed
+                ),
+            }),
+            done => done,
         })
-        .collect();
+        .collect()
+}
 
-    for diff in successful_diffs {
-        println!("{}", diff);
+/// Shows the diff of every repo `prepared` found ready and asks whether to push them.
+fn review_plan(repos: &[repo::Repo], prepared: &[repo::Prepared]) -> Result<bool> {
+    let mut plan = String::new();
+    let mut stats = Vec::new();
+    for (repo, prepared) in repos.iter().zip(prepared) {
+        if let repo::Prepared::Ready { diff, stat } = prepared {
+            plan.push_str(&format!("{}\n", diff));
+            stats.push((repo.reposlug.clone(), *stat));
+        }
+    }
+    if stats.is_empty() {
+        return Ok(true);
     }
+    plan.push_str(&diff::format_diffstat_summary(&stats));
+    pager::page(&plan);
+    let reposlugs: Vec<String> = stats.into_iter().map(|(reposlug, _)| reposlug).collect();
+    confirm::confirm("push the change and open PRs", &reposlugs)
+}
 
-    status.reverse();
-    println!("  {}", status.join(" | "));
-    Ok(())
+/// The skipped repos grouped under their reasons, in the order the reasons first came up.
+fn skipped_summary(skipped: &[(String, String)]) -> String {
+    let mut groups: Vec<(&str, Vec<&str>)> = Vec::new();
+    for (reposlug, reason) in skipped {
+        match groups.iter_mut().find(|(r, _)| r == reason) {
+            Some((_, reposlugs)) => reposlugs.push(reposlug),
+            None => groups.push((reason, vec![reposlug])),
+        }
+    }
+    let mut summary = format!("Skipped {} repo(s):\n", skipped.len());
+    for (reason, reposlugs) in groups {
+        summary.push_str(&format!("  {}:\n", reason));
+        for reposlug in reposlugs {
+            summary.push_str(&format!("    {}\n", reposlug));
+        }
+    }
+    summary
 }
 
-fn process_review_command(org: String, action: &cli::ReviewAction, reposlug_ptns: Vec<String>) -> Result<()> {
-    let all_reposlugs = git::find_repos_in_org(&org)?;
-    info!("Found {} repos in '{}'", all_reposlugs.len(), org);
+/// One `org/repo  url` line per PR.
+fn pr_url_list(prs: &[(String, String)]) -> String {
+    prs.iter()
+        .map(|(reposlug, url)| format!("{}  {}\n", reposlug, url))
+        .collect()
+}
 
-    let filtered_reposlugs: Vec<String> = if reposlug_ptns.iter().all(|s| s.trim().is_empty()) {
-        all_reposlugs.clone()
-    } else {
-        all_reposlugs
-            .into_iter()
-            .filter(|repo| {
-                reposlug_ptns.iter().any(|ptn| {
-                    if let Ok(pattern) = Pattern::new(ptn) {
-                        pattern.matches(repo)
-                    } else {
-                        false
-                    }
-                })
-            })
-            .collect()
-    };
-    info!("After filtering, {} repos remain", filtered_reposlugs.len());
-    debug!("Filtered repository slugs: {:?}", filtered_reposlugs);
+/// The PR body template at `path`, else at `create.body_template`, read in full.
+fn read_body_template(path: Option<PathBuf>, config: &config::Config) -> Result<Option<String>> {
+    match path.or_else(|| config.create.body_template.clone()) {
+        Some(path) => fs::read_to_string(utils::expand_tilde(&path))
+            .map(Some)
+            .wrap_err_with(|| format!("Failed to read PR body template '{}'", path.display())),
+        None => Ok(None),
+    }
+}
 
-    let mut repos_with_prs = Vec::new();
+/// Options for `slam publish`; only those that shape the push and the PR apply.
+struct PublishArgs {
+    change_id: String,
+    repo_ptns: Vec<String>,
+    body_template: Option<PathBuf>,
+    description: Option<String>,
+    no_codeowners: bool,
+    milestone: Option<String>,
+    project: Option<String>,
+    fork: bool,
+}
 
-    match action {
-        cli::ReviewAction::Ls { change_id_ptns, .. } => {
-            let all_prs = git::get_prs_for_repos(filtered_reposlugs)?;
-            for (title, pr_list) in &all_prs {
-                if change_id_ptns.is_empty() || change_id_ptns.iter().any(|pattern| title.starts_with(pattern)) {
-                    for (reposlug, pr_number, _author) in pr_list {
-                        repos_with_prs.push(repo::Repo::create_repo_from_remote_with_pr(reposlug, title, *pr_number));
-                    }
+fn process_publish_command(
+    args: PublishArgs,
+    config: &config::Config,
+    root: &Path,
+    branch_prefix: &str,
+    limit: RepoLimit,
+) -> Result<i32> {
+    let branch = repo::normalize_change_id(&args.change_id, branch_prefix);
+    let mut repos = RepoSet::discover(root, &None, &[], &branch, false)?.filter_by_spec(&args.repo_ptns);
+    for reposlug in repos.protected() {
+        eprintln!(
+            "Skipped {}: protected (safety.protected; --allow-protected to include)",
+            reposlug
+        );
+    }
+    repos.retain_staged(branch_prefix);
+    if repos.is_empty() {
+        println!("Nothing staged for '{}'.", branch);
+        return Ok(EXIT_NO_MATCH);
+    }
+    limit.check(repos.len(), "open PRs in")?;
+
+    let opts = repo::CreateOptions {
+        branch_prefix: branch_prefix.to_string(),
+        body_template: read_body_template(args.body_template, config)?,
+        description: args.description,
+        signing: signing(config, false, None, None, false),
+        request_reviews: !args.no_codeowners,
+        milestone: args.milestone,
+        project: args.project,
+        fork: args.fork || config.create.fork,
+        ticket: jira::ticket_in(&branch, branch_prefix).as_deref().map(jira::ticket),
+        ..Default::default()
+    };
+    let run = ChangeRun::new(&repos, opts);
+    let existing = run.branches_on_remote();
+    if !existing.is_empty() {
+        let reposlugs: Vec<String> = existing.iter().map(|repo| repo.reposlug.clone()).collect();
+        if !confirm::confirm(&format!("replace the existing remote branch '{}'", branch), &reposlugs)? {
+            println!("Aborted; nothing was changed.");
+            return Ok(0);
+        }
+    }
+    let mut run_metrics = metrics::RunMetrics::new("publish");
+    let printer = output::Printer::stderr();
+    let results = run_metrics.phase("apply", || {
+        run.publish_each(|repo, result| match result {
+            repo::CreateResult::Skipped { reason } => printer.print(format!("Skipped {}: {}", repo.reposlug, reason)),
+            repo::CreateResult::Failed { reason } => printer.print(format!("Error: {}", reason)),
+            _ => {}
+        })
+    });
+    printer.finish();
+
+    let mut outcomes = Vec::new();
+    for (repo, result) in repos.repos().iter().zip(&results) {
+        let (url, error) = match result {
+            repo::CreateResult::PrCreated { pr_url: url, .. } | repo::CreateResult::Pushed { review_url: url, .. } => {
+                println!("{}  {}", repo.reposlug, url);
+                (Some(url.clone()), None)
+            }
+            repo::CreateResult::Failed { reason } => (None, Some(reason.clone())),
+            _ => continue,
+        };
+        outcomes.push(notify::RepoOutcome {
+            reposlug: repo.reposlug.clone(),
+            url,
+            error,
+        });
+    }
+    run_metrics.count("repos_processed", results.len());
+    run_metrics.count("prs_created", outcomes.iter().filter(|o| o.url.is_some()).count());
+    run_metrics.count("failures", outcomes.iter().filter(|o| o.error.is_some()).count());
+    run_metrics.mark("total");
+    metrics::send(&run_metrics);
+    notify::send(&notify::RunSummary {
+        command: "publish".to_string(),
+        change_id: branch,
+        repos: outcomes,
+    });
+    Ok(create_exit_code(&results))
+}
+
+fn process_review_command(
+    org: String,
+    action: &cli::ReviewAction,
+    reposlug_ptns: Vec<String>,
+    filter: &cli::RepoFilter,
+    root: &Path,
+    branch_prefix: &str,
+    limit: RepoLimit,
+) -> Result<()> {
+    let reposlugs = set::org_reposlugs(&org, filter, &reposlug_ptns)?;
+    // Stats count merged and closed PRs too, so they don't work on the open ones of a ReviewSet.
+    if let cli::ReviewAction::Stats { change_id_ptns } = action {
+        return review_stats(&reposlugs, change_id_ptns, branch_prefix);
+    }
+    // Stale branches are found by their last commit, whatever their change id.
+    if let cli::ReviewAction::Stale { older_than, delete } = action {
+        return review_stale(&reposlugs, *older_than, *delete, branch_prefix, limit);
+    }
+    // Every PR of the change, not just the selected ones, must merge before its ticket moves on.
+    let org_reposlugs = reposlugs.clone();
+
+    let mut paging = None;
+    let review_set = match action {
+        cli::ReviewAction::Ls {
+            change_id_ptns,
+            milestone,
+            limit,
+            page,
+            ..
+        } => {
+            let review_set = ReviewSet::matching(reposlugs, change_id_ptns, branch_prefix)?;
+            let review_set = match milestone {
+                Some(milestone) => review_set.on_milestone(milestone),
+                None => review_set,
+            };
+            match limit {
+                Some(limit) => {
+                    let (limit, page) = (*limit as usize, *page as usize);
+                    let total = review_set.len();
+                    let review_set = review_set.page(limit, page);
+                    paging = Some(page_note(total, limit, page, review_set.len()));
+                    review_set
                 }
+                None => review_set,
             }
         }
+        cli::ReviewAction::Tui { change_id_ptns } => ReviewSet::matching(reposlugs, change_id_ptns, branch_prefix)?,
+        cli::ReviewAction::Stats { .. } | cli::ReviewAction::Stale { .. } => {
+            unreachable!("review stats and stale return before building a review set")
+        }
         cli::ReviewAction::Clone {
             change_id,
             all: include_closed,
         } => {
-            let all_prs = git::get_prs_for_repos(filtered_reposlugs.clone())?;
-
-            if let Some(pr_list) = all_prs.get(change_id) {
-                for (reposlug, pr_number, _author) in pr_list {
-                    repos_with_prs.push(repo::Repo::create_repo_from_remote_with_pr(
-                        reposlug, change_id, *pr_number,
-                    ));
-                }
-            }
+            let review_set = ReviewSet::for_change(reposlugs, &repo::normalize_change_id(change_id, branch_prefix))?;
             if *include_closed {
                 warn!("--all flag for closed PRs is not yet implemented.");
             }
+            review_set
         }
-        cli::ReviewAction::Approve { change_id, .. } | cli::ReviewAction::Delete { change_id } => {
-            let all_prs = git::get_prs_for_repos(filtered_reposlugs)?;
-
-            if let Some(pr_list) = all_prs.get(change_id) {
-                for (reposlug, pr_number, _author) in pr_list {
-                    repos_with_prs.push(repo::Repo::create_repo_from_remote_with_pr(
-                        reposlug, change_id, *pr_number,
-                    ));
-                }
-            }
+        cli::ReviewAction::Approve {
+            change_id,
+            repos,
+            exclude,
+            ..
         }
-        cli::ReviewAction::Purge {} => {
-            for reposlug in &filtered_reposlugs {
-                repos_with_prs.push(repo::Repo::create_repo_from_remote_with_pr(reposlug, "SLAM", 0));
-            }
+        | cli::ReviewAction::Automerge {
+            change_id,
+            repos,
+            exclude,
+            ..
         }
-    }
+        | cli::ReviewAction::Delete {
+            change_id,
+            repos,
+            exclude,
+            ..
+        }
+        | cli::ReviewAction::Checks {
+            change_id,
+            repos,
+            exclude,
+        }
+        | cli::ReviewAction::Export {
+            change_id,
+            repos,
+            exclude,
+            ..
+        }
+        | cli::ReviewAction::Rerun {
+            change_id,
+            repos,
+            exclude,
+            ..
+        } => ReviewSet::for_change(reposlugs, &repo::normalize_change_id(change_id, branch_prefix))?
+            .select(repos, exclude),
+        cli::ReviewAction::Purge { prefix, .. } => match prefix {
+            Some(prefix) => ReviewSet::purge(&reposlugs, &repo::normalize_change_id(prefix, branch_prefix)),
+            None => ReviewSet::purge(&reposlugs, branch_prefix),
+        },
+    };
 
-    if repos_with_prs.is_empty() {
+    if review_set.is_empty() {
         println!("No repositories with matching PRs found.");
+        if let Some(note) = paging {
+            print!("{}", note);
+        }
         return Ok(());
     }
 
     match action {
-        cli::ReviewAction::Ls { .. } => {
-            let repo_outputs: Vec<String> = repos_with_prs
-                .par_iter()
-                .map(|repo| {
-                    repo.review(action, false)
-                        .unwrap_or_else(|e| format!("Error processing {}: {}", repo.reposlug, e))
-                })
-                .collect();
+        cli::ReviewAction::Approve { .. } => limit.check(review_set.len(), "approve and merge PRs in")?,
+        cli::ReviewAction::Purge { dry_run: false, .. } => limit.check(review_set.len(), "purge")?,
+        _ => {}
+    }
+
+    let destructive = match action {
+        cli::ReviewAction::Purge { dry_run: false, .. } => Some("close PRs and delete branches".to_string()),
+        cli::ReviewAction::Delete { change_id, .. } => Some(format!("close the PR and delete branch '{}'", change_id)),
+        cli::ReviewAction::Approve {
+            admin_override: true, ..
+        } => Some("merge PRs with admin override".to_string()),
+        _ => None,
+    };
+    if let Some(what) = destructive {
+        let items: Vec<String> = review_set
+            .repos()
+            .iter()
+            .map(|repo| match repo.pr_number {
+                0 => format!("{} ({}*)", repo.reposlug, repo.change_id),
+                pr_number => format!("{} (# {})", repo.reposlug, pr_number),
+            })
+            .collect();
+        if !confirm::confirm(&what, &items)? {
+            println!("Aborted; nothing was changed.");
+            return Ok(());
+        }
+    }
 
-            for output in repo_outputs {
-                println!("{}", output);
+    match action {
+        cli::ReviewAction::Ls { summary: true, .. } => {
+            let rows = ls::summarize(review_set.repos(), &review_set.statuses());
+            pager::page(&ls::format_summary(&rows));
+        }
+        cli::ReviewAction::Ls {
+            table: true,
+            sort,
+            columns,
+            ..
+        } => {
+            let columns = if columns.is_empty() {
+                &ls::LsColumn::DEFAULT[..]
+            } else {
+                &columns[..]
+            };
+            let table = ls::format_table(
+                review_set.repos(),
+                &review_set.statuses(),
+                *sort,
+                columns,
+                chrono::Utc::now(),
+            );
+            pager::page(&(table + paging.as_deref().unwrap_or_default()));
+        }
+        cli::ReviewAction::Ls {
+            step: true,
+            buffer,
+            path,
+            ..
+        } => {
+            let paths = set::path_patterns(path)?;
+            let count = review_set.len();
+            for (i, repo) in review_set.repos().iter().enumerate() {
+                let (diff, stat) = repo.get_review_diff_matching(*buffer, &paths);
+                println!("{}\n  [{}/{}] {}\n", diff.trim_end(), i + 1, count, stat);
+                if i + 1 < count && !confirm::next("PR")? {
+                    break;
+                }
+            }
+            if let Some(note) = paging {
+                print!("{}", note);
             }
         }
+        cli::ReviewAction::Ls {
+            buffer, report, path, ..
+        } => {
+            let repo_outputs = review_set.diffs(*buffer, path)?;
+
+            let mut output = String::new();
+            let mut stats = Vec::new();
+            let mut report_entries = Vec::new();
+            for (repo, (diff, stat)) in review_set.repos().iter().zip(repo_outputs) {
+                output.push_str(&format!("{}\n", diff));
+                stats.push((format!("{} (# {})", repo.reposlug, repo.pr_number), stat));
+                report_entries.push(report::ReportEntry {
+                    reposlug: repo.reposlug.clone(),
+                    pr_url: Some(forge::pr_url(&repo.reposlug, repo.pr_number)),
+                    stat,
+                    diff,
+                });
+            }
+            output.push_str(&diff::format_diffstat_summary(&stats));
+            if let Some(note) = &paging {
+                output.push_str(note);
+            }
+            pager::page(&output);
+            if let Some(path) = report {
+                let title = review_set
+                    .repos()
+                    .iter()
+                    .map(|r| r.change_id.as_str())
+                    .sorted()
+                    .dedup()
+                    .join(", ");
+                report::write(path, &title, &report_entries)?;
+            }
+        }
+        cli::ReviewAction::Checks { .. } => {
+            pager::page(&ls::format_checks(review_set.repos(), &review_set.statuses()));
+        }
+        cli::ReviewAction::Tui { .. } => tui::run(root, review_set.into_repos())?,
+        cli::ReviewAction::Export { dir, .. } => export_patches(&review_set, dir)?,
+        cli::ReviewAction::Automerge {
+            watch: true, interval, ..
+        } => {
+            watch_automerge(&review_set, Duration::from_secs(*interval));
+            complete_tickets(&review_set, org_reposlugs, branch_prefix);
+        }
         _ => {
-            if repos_with_prs.len() > 1 {
+            let op = action.op();
+            if review_set.len() > 1 || op != repo::ReviewOp::Clone {
+                let results = review_set.review(root, &op, true);
                 println!("Summary:");
-                let summaries: Vec<String> = repos_with_prs
-                    .iter()
-                    .map(|repo| repo.review(action, true).unwrap_or_else(|e| format!("Error: {}", e)))
-                    .collect();
-
-                for summary in summaries {
-                    println!("  {}", summary);
+                let mut conflicting = Vec::new();
+                for summary in &results {
+                    match summary {
+                        Ok(summary) => println!("  {}", summary),
+                        Err(e) => match e.downcast_ref::<repo::MergeConflict>() {
+                            Some(conflict) => conflicting.push(conflict),
+                            None => println!("  Error: {}", e),
+                        },
+                    }
                 }
                 println!();
+                if !conflicting.is_empty() {
+                    println!("Merge conflicts (rebase required):");
+                    for conflict in conflicting {
+                        println!("  {} (# {})", conflict.reposlug, conflict.pr_number);
+                    }
+                    println!();
+                }
+                if op == (repo::ReviewOp::Purge { dry_run: true }) {
+                    println!("Dry run: nothing was closed or deleted.");
+                }
+                if matches!(op, repo::ReviewOp::Approve { .. }) {
+                    notify::send(&approve_summary(&review_set, &results));
+                    complete_tickets(&review_set, org_reposlugs, branch_prefix);
+                }
+            }
+
+            if op == repo::ReviewOp::Clone {
+                // Print each clone as it finishes rather than after the slowest one.
+                let printer = output::Printer::stdout();
+                pool::git(|| {
+                    review_set
+                        .repos()
+                        .par_iter()
+                        .for_each(|repo| match repo.review(root, &op, false) {
+                            Ok(output) => printer.print(output),
+                            Err(e) => printer.print(format!("Error processing {}: {}", repo.reposlug, e)),
+                        })
+                });
+                printer.print("");
+                printer.finish();
             }
+        }
+    }
+    Ok(())
+}
+
+/// Prints `review stats` for the PRs of `reposlugs` matching `change_id_ptns`, fetching the
+/// status of the open ones for their review and check blockers.
+fn review_stats(reposlugs: &[String], change_id_ptns: &[String], branch_prefix: &str) -> Result<()> {
+    let history = set::pr_history(reposlugs, change_id_ptns, branch_prefix);
+    if history.is_empty() {
+        println!("No repositories with matching PRs found.");
+        return Ok(());
+    }
+    let statuses: Vec<Option<git::PrStatus>> = pool::gh(|| {
+        history
+            .par_iter()
+            .filter(|(_, pr)| pr.state == "OPEN")
+            .map(|(reposlug, pr)| {
+                git::get_pr_status(reposlug, pr.number)
+                    .map_err(|e| warn!("Failed to get status for '{}': {}", reposlug, e))
+                    .ok()
+            })
+            .collect()
+    });
+    pager::page(&ls::format_stats(&ls::rollout_stats(&history, &statuses)));
+    Ok(())
+}
 
-            if matches!(action, cli::ReviewAction::Clone { .. }) {
-                let repo_outputs: Vec<String> = repos_with_prs
-                    .par_iter()
-                    .map(|repo| {
-                        repo.review(action, false)
-                            .unwrap_or_else(|e| format!("Error processing {}: {}", repo.reposlug, e))
-                    })
-                    .collect();
+/// Lists the branches with the branch prefix in `reposlugs` whose last commit is older than
+/// `older_than`; with `delete`, closes their PRs and deletes them once confirmed.
+fn review_stale(
+    reposlugs: &[String],
+    older_than: Duration,
+    delete: bool,
+    branch_prefix: &str,
+    limit: RepoLimit,
+) -> Result<()> {
+    let now = chrono::Utc::now();
+    let cutoff = (now - chrono::Duration::from_std(older_than)?)
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string();
+    // Protected repos are left out, as for purge.
+    let repos = ReviewSet::purge(reposlugs, branch_prefix);
+    let results: Vec<Result<Vec<git::StaleBranch>>> = pool::gh(|| {
+        repos
+            .repos()
+            .par_iter()
+            .map(|repo| git::stale_branches(&repo.reposlug, branch_prefix, &cutoff))
+            .collect()
+    });
+    let mut stale = Vec::new();
+    for (repo, result) in repos.repos().iter().zip(results) {
+        match result {
+            Ok(branches) => stale.extend(branches),
+            Err(e) => println!("  Error: {}: {}", repo.reposlug, e),
+        }
+    }
+    if stale.is_empty() {
+        println!(
+            "No '{}' branches with a last commit before {}.",
+            branch_prefix,
+            &cutoff[..10]
+        );
+        return Ok(());
+    }
+    pager::page(&ls::format_stale(&stale, now));
+    if !delete {
+        return Ok(());
+    }
 
-                for output in repo_outputs {
-                    println!("{}", output);
+    let stale_repos: Vec<&str> = stale.iter().map(|b| b.reposlug.as_str()).unique().collect();
+    limit.check(stale_repos.len(), "delete stale branches in")?;
+    let items: Vec<String> = stale
+        .iter()
+        .map(|b| match b.pr_number {
+            Some(pr_number) => format!("{} {} (# {})", b.reposlug, b.branch, pr_number),
+            None => format!("{} {}", b.reposlug, b.branch),
+        })
+        .collect();
+    if !confirm::confirm("close the PRs and delete the stale branches", &items)? {
+        println!("Aborted; nothing was changed.");
+        return Ok(());
+    }
+    for branch in &stale {
+        if let Some(pr_number) = branch.pr_number {
+            match git::close_pr(&branch.reposlug, pr_number) {
+                Ok(()) => println!("  Closed PR #{} for repo '{}'", pr_number, branch.reposlug),
+                Err(e) => {
+                    println!("  Error: {}", e);
+                    continue;
                 }
-                println!();
             }
         }
+        git::delete_remote_branch_gh(&branch.reposlug, &branch.branch)?;
+        println!(
+            "  Deleted remote branch '{}' for repo '{}'",
+            branch.branch, branch.reposlug
+        );
+    }
+    Ok(())
+}
+
+/// Writes each PR of `review_set` to `<dir>/<org>/<repo>.patch`, reporting the ones that
+/// could not be fetched.
+fn export_patches(review_set: &ReviewSet, dir: &Path) -> Result<()> {
+    let mut written = 0;
+    for (repo, patch) in review_set.repos().iter().zip(review_set.patches()) {
+        let patch = match patch {
+            Ok(patch) => patch,
+            Err(e) => {
+                println!("  Error: {} (# {}): {}", repo.reposlug, repo.pr_number, e);
+                continue;
+            }
+        };
+        let path = dir.join(format!("{}.patch", repo.reposlug));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).wrap_err_with(|| format!("Failed to create '{}'", parent.display()))?;
+        }
+        fs::write(&path, patch).wrap_err_with(|| format!("Failed to write '{}'", path.display()))?;
+        println!("  {} (# {}) -> {}", repo.reposlug, repo.pr_number, path.display());
+        written += 1;
+    }
+    println!(
+        "\nWrote {} of {} patches to {}",
+        written,
+        review_set.len(),
+        dir.display()
+    );
+    Ok(())
+}
+
+/// Writes the man page to stdout, or `slam.1` and one page per subcommand into `dir`.
+fn write_manpages(dir: Option<&Path>) -> Result<()> {
+    let cmd = cli::SlamCli::command();
+    match dir {
+        Some(dir) => {
+            fs::create_dir_all(dir).wrap_err_with(|| format!("Failed to create '{}'", dir.display()))?;
+            clap_mangen::generate_to(cmd, dir)
+                .wrap_err_with(|| format!("Failed to write man pages to '{}'", dir.display()))?;
+            println!("Wrote man pages to {}", dir.display());
+        }
+        None => {
+            let mut page = Vec::new();
+            clap_mangen::Man::new(cmd).render(&mut page)?;
+            print_bytes(&page)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes generated output to stdout; a reader that stops early (`| head`) is not an error.
+fn print_bytes(bytes: &[u8]) -> Result<()> {
+    match std::io::stdout().write_all(bytes) {
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Replays the rollback steps runs of `change_id` journaled but never finished; with
+/// `dry_run` only lists them, in the order they would run.
+fn rollback(change_id: &str, dry_run: bool) -> Result<()> {
+    let journals = transaction::pending(change_id)?;
+    if journals.is_empty() {
+        println!("Nothing to roll back for '{}'.", change_id);
+        return Ok(());
+    }
+    if dry_run {
+        for journal in &journals {
+            println!("{}", journal.reposlug);
+            for step in journal.steps.iter().rev() {
+                println!("  {}", step.describe_rollback());
+            }
+        }
+        return Ok(());
+    }
+    let items: Vec<String> = journals
+        .iter()
+        .map(|journal| format!("{} ({} step(s))", journal.reposlug, journal.steps.len()))
+        .collect();
+    if !confirm::confirm(&format!("roll back '{}'", change_id), &items)? {
+        println!("Aborted; nothing was changed.");
+        return Ok(());
+    }
+    let mut failed = 0;
+    for mut journal in journals {
+        match journal.replay() {
+            Ok(()) => println!("Rolled back {}", journal.reposlug),
+            Err(e) => {
+                eprintln!("Failed to roll back {}: {}", journal.reposlug, e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(eyre!(
+            "{} rollback(s) failed; run `slam rollback {}` again to retry",
+            failed,
+            change_id
+        ));
+    }
+    Ok(())
+}
+
+/// Prints the latest run log for `change_id` (any run's when `None`), or lists them all.
+fn show_logs(runs_dir: &Path, change_id: Option<String>, list: bool) -> Result<()> {
+    let logs = logging::run_logs(runs_dir, change_id.as_deref());
+    let Some(latest) = logs.last() else {
+        return Err(match change_id {
+            Some(change_id) => eyre!("No run logs for '{}' in '{}'", change_id, runs_dir.display()),
+            None => eyre!("No run logs in '{}'", runs_dir.display()),
+        });
+    };
+    if list {
+        for log in &logs {
+            println!("{}", log.display());
+        }
+        return Ok(());
     }
+    let content =
+        fs::read_to_string(latest).wrap_err_with(|| format!("Failed to read run log '{}'", latest.display()))?;
+    pager::page(&content);
     Ok(())
 }
 
+/// Polls the PRs every `interval`, merging each once it is ready, until none is left waiting.
+fn watch_automerge(review_set: &ReviewSet, interval: Duration) {
+    let mut waiting: Vec<&repo::Repo> = review_set.repos().iter().collect();
+    let mut done = Vec::new();
+    while !waiting.is_empty() {
+        let checks = pool::gh(|| {
+            waiting
+                .par_iter()
+                .map(|repo| repo.merge_when_ready())
+                .collect::<Vec<_>>()
+        });
+        let mut still_waiting = Vec::new();
+        for (repo, check) in waiting.into_iter().zip(checks) {
+            let label = format!("{} (# {})", repo.reposlug, repo.pr_number);
+            match check {
+                Ok(repo::MergeCheck::Waiting { reason }) => {
+                    debug!("{} is waiting: {}", label, reason);
+                    still_waiting.push(repo);
+                }
+                Ok(repo::MergeCheck::Merged) => {
+                    println!("  {}: merged", label);
+                    done.push(format!("{}: merged", label));
+                }
+                Ok(repo::MergeCheck::Blocked { reason }) => {
+                    println!("  {}: {}", label, reason);
+                    done.push(format!("{}: {}", label, reason));
+                }
+                Err(e) => {
+                    println!("  {}: Error: {}", label, e);
+                    done.push(format!("{}: Error: {}", label, e));
+                }
+            }
+        }
+        waiting = still_waiting;
+        if !waiting.is_empty() {
+            println!(
+                "Waiting on {} PR{}; checking again in {}s",
+                waiting.len(),
+                if waiting.len() == 1 { "" } else { "s" },
+                interval.as_secs()
+            );
+            std::thread::sleep(interval);
+        }
+    }
+    println!("\nSummary:");
+    for line in done {
+        println!("  {}", line);
+    }
+}
+
+/// Where `review ls --limit` left off: which PRs of `total` the page shows and how to see more.
+fn page_note(total: usize, limit: usize, page: usize, shown: usize) -> String {
+    let pages = total.div_ceil(limit);
+    if shown == 0 {
+        return format!("\nPage {} is past the last of {} ({} PRs).\n", page, pages, total);
+    }
+    let first = limit * (page - 1) + 1;
+    let mut note = format!(
+        "\nShowing PRs {}-{} of {} (page {} of {})",
+        first,
+        first + shown - 1,
+        total,
+        page,
+        pages
+    );
+    if page < pages {
+        note.push_str(&format!("; --page {} for more", page + 1));
+    }
+    note.push_str(".\n");
+    note
+}
+
+/// Moves the Jira ticket of each change in `review_set` along once none of its PRs in
+/// `reposlugs` is still open.
+fn complete_tickets(review_set: &ReviewSet, reposlugs: Vec<String>, branch_prefix: &str) {
+    if !jira::transitions() {
+        return;
+    }
+    let change_ids = review_set
+        .repos()
+        .iter()
+        .map(|repo| repo.change_id.clone())
+        .sorted()
+        .dedup();
+    for change_id in change_ids {
+        let Some(ticket) = jira::ticket_in(&change_id, branch_prefix) else {
+            continue;
+        };
+        match ReviewSet::for_change(reposlugs.clone(), &change_id) {
+            Ok(open) if open.is_empty() => jira::complete(&ticket),
+            Ok(open) => info!(
+                "Leaving {} as is: {} PR(s) of '{}' still open",
+                ticket,
+                open.len(),
+                change_id
+            ),
+            Err(e) => warn!("Failed to check for open PRs of '{}': {}", change_id, e),
+        }
+    }
+}
+
+/// The `review approve` run as posted to the notification webhook.
+fn approve_summary(review_set: &ReviewSet, results: &[Result<String>]) -> notify::RunSummary {
+    notify::RunSummary {
+        command: "review approve".to_string(),
+        change_id: review_set
+            .repos()
+            .iter()
+            .map(|r| r.change_id.as_str())
+            .sorted()
+            .dedup()
+            .join(", "),
+        repos: review_set
+            .repos()
+            .iter()
+            .zip(results)
+            .map(|(repo, result)| notify::RepoOutcome {
+                reposlug: repo.reposlug.clone(),
+                url: Some(forge::pr_url(&repo.reposlug, repo.pr_number)),
+                error: result.as_ref().err().map(|e| e.to_string()),
+            })
+            .collect(),
+    }
+}
+
+/// Resolves the sandbox root: the given directory (with `~` expanded) or the current directory.
+fn resolve_workdir(workdir: Option<PathBuf>) -> Result<PathBuf> {
+    match workdir {
+        Some(dir) => {
+            let expanded = utils::expand_tilde(&dir);
+            if !expanded.is_dir() {
+                return Err(eyre!("Workdir '{}' is not a directory", dir.display()));
+            }
+            Ok(expanded.canonicalize()?)
+        }
+        None => Ok(std::env::current_dir()?),
+    }
+}
+
 /// XDG data dir, honoring `$XDG_DATA_HOME` and falling back to `$HOME/.local/share`.
 ///
 /// We deliberately do NOT use the `dirs` config/data helpers: those honor
@@ -309,83 +1588,325 @@ fn xdg_data_dir() -> Option<PathBuf> {
     dirs::home_dir().map(|h| h.join(".local").join("share"))
 }
 
-fn setup_logging() -> Result<()> {
-    let log_dir = xdg_data_dir().unwrap_or_else(|| PathBuf::from(".")).join("slam");
+/// XDG cache dir, honoring `$XDG_CACHE_HOME` and falling back to `$HOME/.cache`.
+fn xdg_cache_dir() -> Option<PathBuf> {
+    if let Ok(dir) = std::env::var("XDG_CACHE_HOME") {
+        let path = PathBuf::from(dir);
+        if path.is_absolute() {
+            return Some(path);
+        }
+    }
+    dirs::home_dir().map(|h| h.join(".cache"))
+}
 
-    fs::create_dir_all(&log_dir).context("Failed to create log directory")?;
+/// XDG config dir, honoring `$XDG_CONFIG_HOME` and falling back to `$HOME/.config`.
+fn xdg_config_dir() -> Option<PathBuf> {
+    if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
+        let path = PathBuf::from(dir);
+        if path.is_absolute() {
+            return Some(path);
+        }
+    }
+    dirs::home_dir().map(|h| h.join(".config"))
+}
 
-    let log_file = log_dir.join("slam.log");
+fn main() -> Result<ExitCode> {
+    let mut args = cli::SlamCli::from_arg_matches(&cli::SlamCli::command().get_matches())?;
+    let config_path = xdg_config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("slam")
+        .join("slam.yml");
+    let config = config::Config::load(&config_path)?;
+    let branch_prefix = match args.branch_prefix.clone().or_else(|| config.branch_prefix.clone()) {
+        Some(prefix) => cli::validate_branch_prefix(&prefix).map_err(|e| eyre!(e))?,
+        None => cli::DEFAULT_BRANCH_PREFIX.to_string(),
+    };
+    // Settle create's change id now, so the run's log file can be named after it.
+    if let cli::SlamCommand::Create(create) = &mut args.command {
+        // A change id git can't use as a branch name would fail in every repo; catch it now.
+        if let Some(change_id) = &create.change_id {
+            let checked = if create.sanitize {
+                cli::sanitize_change_id(change_id, &branch_prefix)
+            } else {
+                cli::validate_change_id(change_id, &branch_prefix)
+            };
+            let checked = checked.map_err(|e| eyre!(e))?;
+            if &checked != change_id {
+                eprintln!("Sanitized change id '{}' to '{}'", change_id, checked);
+            }
+            create.change_id = Some(checked);
+        }
+        if create.change_id.is_none() {
+            let template = create
+                .change_id_template
+                .as_deref()
+                .or(config.create.change_id_template.as_deref());
+            let mut vars = create.vars.clone();
+            if let Some(ticket) = &create.ticket {
+                if !vars.iter().any(|(key, _)| key == "ticket") {
+                    vars.push(("ticket".to_string(), ticket.clone()));
+                }
+            }
+            create.change_id = Some(match template {
+                Some(template) => cli::render_change_id(template, &branch_prefix, &vars, chrono::Local::now())
+                    .map_err(|e| eyre!(e))?,
+                None => cli::default_change_id(&branch_prefix),
+            });
+        }
+        // The ticket rides in the branch name, so publish and review can find it again.
+        if let (Some(change_id), Some(ticket)) = (&create.change_id, &create.ticket) {
+            let change_id = repo::normalize_change_id(change_id, &branch_prefix);
+            create.change_id = Some(jira::with_ticket(&change_id, &branch_prefix, ticket));
+        }
+    }
 
-    let target = Box::new(
-        fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&log_file)
-            .context("Failed to open log file")?,
+    let log_dir = xdg_data_dir().unwrap_or_else(|| PathBuf::from(".")).join("slam");
+    let change_id = args
+        .command
+        .change_id()
+        .map(|change_id| repo::normalize_change_id(change_id, &branch_prefix));
+    let run_log = match args.command {
+        cli::SlamCommand::Logs { .. } | cli::SlamCommand::Completions { .. } | cli::SlamCommand::Manpage { .. } => {
+            logging::RunLog::None
+        }
+        _ => logging::RunLog::Run {
+            change_id: change_id.as_deref(),
+        },
+    };
+    let color = !args.no_color && std::env::var_os("NO_COLOR").is_none();
+    logging::init(&log_dir, run_log, args.verbose, args.log_format, color)?;
+
+    pool::configure(
+        args.gh_jobs.or(config.concurrency.gh_jobs),
+        args.git_jobs.or(config.concurrency.git_jobs),
+    )?;
+    let cache_ttl = if args.no_cache {
+        Duration::ZERO
+    } else {
+        config
+            .discovery
+            .cache_ttl
+            .map(Duration::from_secs)
+            .unwrap_or(cache::DEFAULT_TTL)
+    };
+    if let Some(cache_dir) = xdg_cache_dir() {
+        cache::configure(cache_dir.join("slam"), cache_ttl);
+    }
+    transaction::configure_journal(log_dir.join("journal"));
+    // --no-cache also searches the sandbox again rather than trusting its repo index.
+    git::configure_repo_scan(
+        config.discovery.max_depth.unwrap_or(git::DEFAULT_SCAN_DEPTH),
+        &config.discovery.ignore,
+        config.discovery.index && !args.no_cache,
+        args.include_submodules,
     );
+    git::configure_network_retry(
+        args.retries
+            .or(config.network.retries)
+            .unwrap_or(git::DEFAULT_NETWORK_RETRIES),
+        config
+            .network
+            .retry_backoff_ms
+            .map(Duration::from_millis)
+            .unwrap_or(git::DEFAULT_RETRY_BACKOFF),
+    );
+    git::configure_fork_prs(
+        config.create.fork
+            || matches!(&args.command, cli::SlamCommand::Create(create) if create.fork)
+            || matches!(args.command, cli::SlamCommand::Publish { fork: true, .. }),
+    );
+    github::configure(config.github.auth()?, config.github.api_url.clone());
+    forge::configure(config.forges()?);
+    let protocol = match &args.command {
+        cli::SlamCommand::Sandbox {
+            action: cli::SandboxAction::Setup { protocol, .. },
+            ..
+        } => *protocol,
+        _ => None,
+    };
+    forge::configure_protocol(protocol.or(config.network.protocol).unwrap_or_default());
+    notify::configure(config.notify.webhook());
+    metrics::configure(config.metrics.sink());
+    jira::configure(config.jira.jira());
+    if args.allow_protected {
+        warn!("--allow-protected: protected repos may be modified");
+    } else {
+        set::configure_protected(config.safety.protected.clone());
+    }
+    diff::configure(args.diff_style);
+    diff::configure_max_lines(args.max_lines_per_file.map(|n| n as usize));
+    if args.no_color {
+        colored::control::set_override(false);
+    }
+    pager::configure(!args.no_pager);
+    confirm::configure(args.yes);
+    let root = resolve_workdir(args.workdir.or_else(|| config.workdir.clone()))?;
+    debug!("Using sandbox root '{}'", root.display());
+
+    // Held until the run ends, so two runs can't race on the same clones or branches.
+    let contention = if args.force_lock {
+        lock::Contention::Force
+    } else if args.wait {
+        lock::Contention::Wait
+    } else {
+        lock::Contention::Fail
+    };
+    let command_line = std::env::args().skip(1).collect::<Vec<_>>().join(" ");
+    let scope = args.command.lock_scope();
+    let _sandbox_lock = scope
+        .sandbox
+        .then(|| {
+            lock::acquire(
+                &root.join(lock::SANDBOX_LOCK),
+                &format!("the sandbox '{}'", root.display()),
+                &command_line,
+                contention,
+            )
+        })
+        .transpose()?;
+    let _change_lock = match change_id.as_deref().filter(|_| scope.change) {
+        Some(change_id) => Some(lock::acquire(
+            &lock::change_lock_path(&log_dir.join("locks"), change_id),
+            &format!("change id '{}'", change_id),
+            &command_line,
+            contention,
+        )?),
+        None => None,
+    };
 
-    env_logger::Builder::from_default_env()
-        .target(env_logger::Target::Pipe(target))
-        .init();
-
-    info!("Logging initialized, writing to: {}", log_file.display());
-    Ok(())
-}
-
-fn main() -> Result<()> {
-    setup_logging()?;
-
-    let args = cli::SlamCli::from_arg_matches(&cli::SlamCli::command().get_matches())?;
+    let limit = RepoLimit {
+        max: args
+            .max_repos
+            .or(config.safety.max_repos)
+            .unwrap_or(cli::DEFAULT_MAX_REPOS),
+        force: args.force,
+    };
 
-    let result = match args.command {
+    // Commands report partial failures through the exit code; returned from `main` rather than
+    // exiting here, so the locks above are released on the way out.
+    let result: Result<i32> = match args.command {
         cli::SlamCommand::Sandbox { repo_ptns, action } => match action {
-            cli::SandboxAction::Setup {} => sandbox::sandbox_setup(repo_ptns),
-            cli::SandboxAction::Refresh {} => sandbox::sandbox_refresh(),
-        },
-        cli::SlamCommand::Create {
+            cli::SandboxAction::Setup { orgs, filter, .. } => {
+                sandbox::sandbox_setup(&root, &orgs, repo_ptns, &filter, &branch_prefix)
+            }
+            cli::SandboxAction::Refresh {} => sandbox::sandbox_refresh(&root, &branch_prefix),
+            cli::SandboxAction::Status {} => sandbox::sandbox_status(&root, &branch_prefix),
+            cli::SandboxAction::Prune { remove } => sandbox::sandbox_prune(&root, remove),
+        }
+        .map(|()| 0),
+        cli::SlamCommand::Status { offline } => status::slam_status(&root, &branch_prefix, offline).map(|()| 0),
+        cli::SlamCommand::Audit {
             files,
-            change_id,
-            buffer,
+            expect,
             repo_ptns,
+            remote,
+            orgs,
+        } => process_audit_command(&root, &files, &expect, &repo_ptns, remote, &orgs),
+        cli::SlamCommand::Enforce { policies, interval } => {
+            process_enforce_command(&policies, interval, &config, &root, &branch_prefix, limit, &log_dir)
+        }
+        cli::SlamCommand::Init {
+            blueprint,
+            reposlugs,
+            visibility,
+            direct,
+            change_id,
+            commit,
+        } => process_init_command(
+            blueprint,
+            reposlugs,
+            visibility,
+            direct,
+            change_id,
+            commit,
+            &config,
+            &root,
+            &branch_prefix,
+            limit,
+        ),
+        cli::SlamCommand::Create(args) => process_create_command(*args, &config, &root, &branch_prefix, limit),
+        cli::SlamCommand::Review {
+            org,
             action,
-        } => process_create_command(files, change_id, buffer, repo_ptns, action),
-        cli::SlamCommand::Review { org, action, repo_ptns } => process_review_command(org, &action, repo_ptns),
-    };
-
-    if let Err(e) = result {
-        let error_msg = e.to_string();
-
-        // Provide helpful debugging suggestions for common issues
-        if error_msg.contains("Failed to parse open PRs JSON") || error_msg.contains("invalid type: map, expected u64")
-        {
-            eprintln!("Error: {}", e);
-            eprintln!();
-            eprintln!("💡 This appears to be a JSON parsing issue. To troubleshoot:");
-            eprintln!("   1. Run with debug logging: RUST_LOG=debug slam ...");
-            eprintln!("   2. Check GitHub CLI authentication: gh auth status");
-            eprintln!("   3. Verify repository access and permissions");
-            eprintln!();
-            eprintln!("For more help, see: https://github.com/scottidler/slam/blob/main/README.md#troubleshooting-common-issues");
-        } else if error_msg.contains("Failed to list open PRs") || error_msg.contains("Failed to list remote branches")
-        {
-            eprintln!("Error: {}", e);
-            eprintln!();
-            eprintln!("💡 This appears to be a GitHub CLI or repository access issue:");
-            eprintln!("   1. Ensure 'gh' is installed and authenticated: gh auth status");
-            eprintln!("   2. Verify you have access to the repository");
-            eprintln!("   3. Check repository name spelling and organization");
-            eprintln!("   4. Run with debug logging: RUST_LOG=debug slam ...");
-        } else {
-            eprintln!("Error: {}", e);
-            eprintln!();
-            eprintln!("💡 For detailed troubleshooting information, run with debug logging:");
-            eprintln!("   RUST_LOG=debug slam [your command]");
+            repo_ptns,
+            filter,
+        } => process_review_command(org, &action, repo_ptns, &filter, &root, &branch_prefix, limit).map(|()| 0),
+        cli::SlamCommand::Completions { shell } => {
+            let mut script = Vec::new();
+            clap_complete::generate(shell, &mut cli::SlamCli::command(), "slam", &mut script);
+            print_bytes(&script).map(|()| 0)
+        }
+        cli::SlamCommand::Manpage { dir } => write_manpages(dir.as_deref()).map(|()| 0),
+        cli::SlamCommand::Publish {
+            change_id,
+            repo_ptns,
+            body_template,
+            description,
+            no_codeowners,
+            milestone,
+            project,
+            fork,
+        } => {
+            let args = PublishArgs {
+                change_id,
+                repo_ptns,
+                body_template,
+                description,
+                no_codeowners,
+                milestone,
+                project,
+                fork,
+            };
+            process_publish_command(args, &config, &root, &branch_prefix, limit)
+        }
+        cli::SlamCommand::Rollback { change_id, dry_run } => {
+            rollback(&repo::normalize_change_id(&change_id, &branch_prefix), dry_run).map(|()| 0)
         }
+        cli::SlamCommand::Logs { change_id, list } => show_logs(
+            &logging::runs_dir(&log_dir),
+            change_id.map(|change_id| repo::normalize_change_id(&change_id, &branch_prefix)),
+            list,
+        )
+        .map(|()| 0),
+    };
 
-        std::process::exit(1);
-    }
+    let code = match result {
+        Ok(code) => code,
+        Err(e) => {
+            let error_msg = e.to_string();
 
-    Ok(())
+            // Provide helpful debugging suggestions for common issues
+            if error_msg.contains("Failed to parse open PRs JSON")
+                || error_msg.contains("invalid type: map, expected u64")
+            {
+                eprintln!("Error: {}", e);
+                eprintln!();
+                eprintln!("💡 This appears to be a JSON parsing issue. To troubleshoot:");
+                eprintln!("   1. Run with debug logging: RUST_LOG=debug slam ...");
+                eprintln!("   2. Check GitHub CLI authentication: gh auth status");
+                eprintln!("   3. Verify repository access and permissions");
+                eprintln!();
+                eprintln!("For more help, see: https://github.com/scottidler/slam/blob/main/README.md#troubleshooting-common-issues");
+            } else if error_msg.contains("Failed to list open PRs")
+                || error_msg.contains("Failed to list remote branches")
+            {
+                eprintln!("Error: {}", e);
+                eprintln!();
+                eprintln!("💡 This appears to be a GitHub CLI or repository access issue:");
+                eprintln!("   1. Ensure 'gh' is installed and authenticated: gh auth status");
+                eprintln!("   2. Verify you have access to the repository");
+                eprintln!("   3. Check repository name spelling and organization");
+                eprintln!("   4. Run with debug logging: RUST_LOG=debug slam ...");
+            } else {
+                eprintln!("Error: {}", e);
+                eprintln!();
+                eprintln!("💡 For detailed troubleshooting information, run with debug logging:");
+                eprintln!("   RUST_LOG=debug slam [your command]");
+            }
+            1
+        }
+    };
+    Ok(ExitCode::from(u8::try_from(code).unwrap_or(1)))
 }
 
 #[cfg(test)]
@@ -393,131 +1914,147 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_extract_reponame() {
-        assert_eq!(extract_reponame("org/repo"), "repo");
-        assert_eq!(extract_reponame("tatari-tv/frontend"), "frontend");
-        assert_eq!(extract_reponame("single"), "single");
-        assert_eq!(extract_reponame(""), "");
-        assert_eq!(extract_reponame("a/b/c"), "b"); // Only gets first split
-    }
+    fn test_resolve_workdir() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let resolved = resolve_workdir(Some(temp_dir.path().to_path_buf())).unwrap();
+        assert_eq!(resolved, temp_dir.path().canonicalize().unwrap());
 
-    #[test]
-    fn test_extract_reponame_edge_cases() {
-        assert_eq!(extract_reponame("/repo"), "repo");
-        assert_eq!(extract_reponame("org/"), "");
-        assert_eq!(extract_reponame("/"), "");
+        let err = resolve_workdir(Some(temp_dir.path().join("missing"))).unwrap_err();
+        assert!(err.to_string().contains("is not a directory"));
     }
 
     #[test]
-    fn test_filter_repos_by_spec_empty() {
-        let repos = vec![create_test_repo("org/repo1"), create_test_repo("org/repo2")];
-
-        let result = filter_repos_by_spec(repos.clone(), &[]);
-        assert_eq!(result.len(), 2);
-        assert_eq!(result[0].reposlug, "org/repo1");
-        assert_eq!(result[1].reposlug, "org/repo2");
+    fn test_identity_precedence() {
+        let bot: git::Identity = "Bot <bot@corp>".parse().unwrap();
+        let flag = identity(
+            Some(bot.clone()),
+            "SLAM_TEST_AUTHOR_FLAG",
+            Some("Other <o@corp>"),
+            "create.author",
+        );
+        assert_eq!(flag.unwrap(), Some(bot));
+
+        std::env::set_var("SLAM_TEST_AUTHOR_ENV", "Env Bot <env@corp>");
+        let env = identity(None, "SLAM_TEST_AUTHOR_ENV", Some("Other <o@corp>"), "create.author").unwrap();
+        assert_eq!(env.unwrap().email, "env@corp");
+
+        let config = identity(None, "SLAM_TEST_AUTHOR_UNSET", Some("Cfg <cfg@corp>"), "create.author").unwrap();
+        assert_eq!(config.unwrap().name, "Cfg");
+        assert!(identity(None, "SLAM_TEST_AUTHOR_UNSET", Some("nope"), "create.author").is_err());
+        assert!(identity(None, "SLAM_TEST_AUTHOR_UNSET", None, "create.author")
+            .unwrap()
+            .is_none());
     }
 
     #[test]
-    fn test_filter_repos_by_spec_exact_match() {
-        let repos = vec![
-            create_test_repo("org/frontend"),
-            create_test_repo("org/backend"),
-            create_test_repo("org/mobile"),
-        ];
-
-        let specs = vec!["frontend".to_string()];
-        let result = filter_repos_by_spec(repos, &specs);
-
-        assert_eq!(result.len(), 1);
-        assert_eq!(result[0].reposlug, "org/frontend");
+    fn test_signing() {
+        let mut config = config::Config::default();
+        assert_eq!(signing(&config, false, None, None, false), None);
+
+        config.create.signing_key = Some("ABCD1234".to_string());
+        assert_eq!(signing(&config, false, None, None, false), None);
+        let signing_on = signing(&config, true, None, None, false).unwrap();
+        assert_eq!(signing_on.key.as_deref(), Some("ABCD1234"));
+        assert!(!signing_on.push);
+
+        config.create.sign_push = true;
+        let from_config = signing(&config, false, None, Some(git::SigningFormat::Ssh), false).unwrap();
+        assert_eq!(from_config.format, Some(git::SigningFormat::Ssh));
+        assert!(from_config.push);
     }
 
     #[test]
-    fn test_filter_repos_by_spec_starts_with() {
-        let repos = vec![
-            create_test_repo("org/frontend-web"),
-            create_test_repo("org/frontend-mobile"),
-            create_test_repo("org/backend"),
-        ];
-
-        let specs = vec!["front".to_string()];
-        let result = filter_repos_by_spec(repos, &specs);
-
-        assert_eq!(result.len(), 2);
-        assert!(result.iter().any(|r| r.reposlug == "org/frontend-mobile"));
-        assert!(result.iter().any(|r| r.reposlug == "org/frontend-web"));
+    fn test_page_note() {
+        assert_eq!(
+            page_note(45, 20, 2, 20),
+            "\nShowing PRs 21-40 of 45 (page 2 of 3); --page 3 for more.\n"
+        );
+        assert_eq!(page_note(45, 20, 3, 5), "\nShowing PRs 41-45 of 45 (page 3 of 3).\n");
+        assert_eq!(page_note(45, 20, 4, 0), "\nPage 4 is past the last of 3 (45 PRs).\n");
     }
 
     #[test]
-    fn test_filter_repos_by_spec_full_slug_exact() {
-        let repos = vec![create_test_repo("org1/repo"), create_test_repo("org2/repo")];
-
-        let specs = vec!["org1/repo".to_string()];
-        let result = filter_repos_by_spec(repos, &specs);
-
-        assert_eq!(result.len(), 1);
-        assert_eq!(result[0].reposlug, "org1/repo");
+    fn test_hold_back() {
+        let prepared = vec![
+            repo::Prepared::Ready {
+                diff: "org/a".to_string(),
+                stat: diff::DiffStat::default(),
+            },
+            repo::Prepared::Done(repo::CreateResult::Failed {
+                reason: "boom".to_string(),
+            }),
+            repo::Prepared::Done(repo::CreateResult::NoChanges),
+        ];
+        let held = hold_back(prepared, 1);
+        assert!(matches!(
+            &held[0],
+            repo::Prepared::Done(repo::CreateResult::Skipped { reason }) if reason.starts_with("not pushed: 1 repo(s)")
+        ));
+        assert!(matches!(
+            &held[1],
+            repo::Prepared::Done(repo::CreateResult::Failed { .. })
+        ));
+        assert!(matches!(&held[2], repo::Prepared::Done(repo::CreateResult::NoChanges)));
     }
 
     #[test]
-    fn test_filter_repos_by_spec_full_slug_starts_with() {
-        let repos = vec![
-            create_test_repo("tatari-tv/frontend"),
-            create_test_repo("tatari-tv/backend"),
-            create_test_repo("other-org/frontend"),
+    fn test_skipped_summary() {
+        let skipped = vec![
+            ("org/a".to_string(), "no file matches the file patterns".to_string()),
+            ("org/b".to_string(), "protected".to_string()),
+            ("org/c".to_string(), "no file matches the file patterns".to_string()),
         ];
-
-        let specs = vec!["tatari".to_string()];
-        let result = filter_repos_by_spec(repos, &specs);
-
-        assert_eq!(result.len(), 2);
-        assert!(result.iter().any(|r| r.reposlug == "tatari-tv/backend"));
-        assert!(result.iter().any(|r| r.reposlug == "tatari-tv/frontend"));
+        assert_eq!(
+            skipped_summary(&skipped),
+            "Skipped 3 repo(s):\n  no file matches the file patterns:\n    org/a\n    org/c\n  protected:\n    org/b\n"
+        );
     }
 
     #[test]
-    fn test_filter_repos_by_spec_multiple_specs() {
-        let repos = vec![
-            create_test_repo("org/frontend"),
-            create_test_repo("org/backend"),
-            create_test_repo("org/mobile"),
-            create_test_repo("org/docs"),
+    fn test_pr_url_list() {
+        let prs = vec![
+            ("org/a".to_string(), "https://github.com/org/a/pull/3".to_string()),
+            ("org/b".to_string(), "https://github.com/org/b/pull/7".to_string()),
         ];
-
-        let specs = vec!["frontend".to_string(), "backend".to_string()];
-        let result = filter_repos_by_spec(repos, &specs);
-
-        assert_eq!(result.len(), 2);
-        assert!(result.iter().any(|r| r.reposlug == "org/backend"));
-        assert!(result.iter().any(|r| r.reposlug == "org/frontend"));
+        assert_eq!(
+            pr_url_list(&prs),
+            "org/a  https://github.com/org/a/pull/3\norg/b  https://github.com/org/b/pull/7\n"
+        );
+        assert_eq!(pr_url_list(&[]), "");
     }
 
     #[test]
-    fn test_filter_repos_by_spec_sorting() {
-        let repos = vec![
-            create_test_repo("org/zebra"),
-            create_test_repo("org/alpha"),
-            create_test_repo("org/beta"),
-        ];
-
-        let result = filter_repos_by_spec(repos, &[]);
-
-        // Should be sorted alphabetically by reposlug
-        assert_eq!(result[0].reposlug, "org/alpha");
-        assert_eq!(result[1].reposlug, "org/beta");
-        assert_eq!(result[2].reposlug, "org/zebra");
+    fn test_repo_limit() {
+        let limit = RepoLimit { max: 2, force: false };
+        assert!(limit.check(2, "purge").is_ok());
+        let err = limit.check(3, "purge").unwrap_err().to_string();
+        assert!(err.starts_with("Refusing to purge 3 repos, more than --max-repos 2"));
+        assert!(RepoLimit { max: 2, force: true }.check(400, "purge").is_ok());
     }
 
-    // Helper function to create test repos
-    fn create_test_repo(reposlug: &str) -> repo::Repo {
-        repo::Repo {
-            reposlug: reposlug.to_string(),
-            change_id: "test-change".to_string(),
-            change: None,
-            files: vec![],
-            pr_number: 0,
-        }
+    #[test]
+    fn test_create_exit_code() {
+        use repo::CreateResult;
+
+        let failed = || CreateResult::Failed {
+            reason: "push rejected".to_string(),
+        };
+        assert_eq!(create_exit_code(&[]), EXIT_NO_MATCH);
+        assert_eq!(create_exit_code(&[CreateResult::NoChanges]), 0);
+        assert_eq!(
+            create_exit_code(&[
+                CreateResult::Skipped {
+                    reason: "untracked files".to_string()
+                },
+                CreateResult::NoChanges
+            ]),
+            0
+        );
+        assert_eq!(
+            create_exit_code(&[CreateResult::NoChanges, failed()]),
+            EXIT_PARTIAL_FAILURE
+        );
+        assert_eq!(create_exit_code(&[failed(), failed()]), EXIT_ALL_FAILED);
     }
 
     #[test]
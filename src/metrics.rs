@@ -0,0 +1,227 @@
+// src/metrics.rs
+
+use eyre::{eyre, Result};
+use log::{debug, info, warn};
+use serde_json::{json, Value};
+use std::net::UdpSocket;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Where run metrics are exported: a StatsD daemon, an OTLP/HTTP collector, or both.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sink {
+    /// `host:port` of a StatsD daemon, sent over UDP.
+    pub statsd: Option<String>,
+    /// Base URL of an OTLP/HTTP collector, e.g. `http://localhost:4318`.
+    pub otlp_endpoint: Option<String>,
+    /// Prepended to every metric name.
+    pub prefix: String,
+}
+
+/// The counters and phase timings of one run, e.g. of `create`.
+#[derive(Debug, Clone)]
+pub struct RunMetrics {
+    /// The command as typed, e.g. `create`; sent as a tag/attribute.
+    pub command: String,
+    started: Instant,
+    counters: Vec<(&'static str, u64)>,
+    timings: Vec<(&'static str, Duration)>,
+}
+
+impl RunMetrics {
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            started: Instant::now(),
+            counters: Vec::new(),
+            timings: Vec::new(),
+        }
+    }
+
+    pub fn count(&mut self, name: &'static str, value: usize) {
+        self.counters.push((name, value as u64));
+    }
+
+    /// Runs `f`, recording how long it took as the `phase` timing.
+    pub fn phase<T>(&mut self, phase: &'static str, f: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let result = f();
+        self.timings.push((phase, started.elapsed()));
+        result
+    }
+
+    /// Records the time since the run started as the `phase` timing, e.g. for discovery.
+    pub fn mark(&mut self, phase: &'static str) {
+        self.timings.push((phase, self.started.elapsed()));
+    }
+}
+
+static SINK: OnceLock<Option<Sink>> = OnceLock::new();
+
+/// Sets where `send` exports to; without a sink (or before this is called) nothing is sent.
+pub fn configure(sink: Option<Sink>) {
+    let _ = SINK.set(sink);
+}
+
+/// StatsD lines for `metrics`: counters as `|c`, phase timings as `|ms`, tagged with the command
+/// in the DogStatsD style most daemons accept.
+fn statsd_lines(prefix: &str, metrics: &RunMetrics) -> Vec<String> {
+    let tag = format!("|#command:{}", metrics.command);
+    let counters = metrics
+        .counters
+        .iter()
+        .map(|(name, value)| format!("{}.{}:{}|c{}", prefix, name, value, tag));
+    let timings = metrics
+        .timings
+        .iter()
+        .map(|(phase, elapsed)| format!("{}.phase.{}:{}|ms{}", prefix, phase, elapsed.as_millis(), tag));
+    counters.chain(timings).collect()
+}
+
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// An OTLP/JSON `ExportMetricsServiceRequest` for `metrics`: counters as delta sums, phase
+/// timings as a `{prefix}.phase.duration` gauge in milliseconds with a `phase` attribute.
+fn otlp_payload(prefix: &str, metrics: &RunMetrics, now: SystemTime) -> Value {
+    let time = now
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string();
+    let command = attribute("command", &metrics.command);
+    let mut points: Vec<Value> = metrics
+        .counters
+        .iter()
+        .map(|(name, value)| {
+            json!({
+                "name": format!("{}.{}", prefix, name),
+                "sum": {
+                    "aggregationTemporality": 1,
+                    "isMonotonic": true,
+                    "dataPoints": [{ "asInt": value.to_string(), "timeUnixNano": time, "attributes": [command] }],
+                },
+            })
+        })
+        .collect();
+    if !metrics.timings.is_empty() {
+        let durations: Vec<Value> = metrics
+            .timings
+            .iter()
+            .map(|(phase, elapsed)| {
+                json!({
+                    "asDouble": elapsed.as_secs_f64() * 1000.0,
+                    "timeUnixNano": time,
+                    "attributes": [command, attribute("phase", phase)],
+                })
+            })
+            .collect();
+        points.push(json!({
+            "name": format!("{}.phase.duration", prefix),
+            "unit": "ms",
+            "gauge": { "dataPoints": durations },
+        }));
+    }
+    json!({
+        "resourceMetrics": [{
+            "resource": { "attributes": [attribute("service.name", "slam")] },
+            "scopeMetrics": [{ "scope": { "name": "slam" }, "metrics": points }],
+        }],
+    })
+}
+
+fn send_statsd(addr: &str, lines: &[String]) -> Result<()> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.connect(addr).map_err(|e| eyre!("{}: {}", addr, e))?;
+    for line in lines {
+        socket.send(line.as_bytes())?;
+    }
+    Ok(())
+}
+
+fn send_otlp(endpoint: &str, body: &Value) -> Result<()> {
+    let url = format!("{}/v1/metrics", endpoint.trim_end_matches('/'));
+    let agent = ureq::AgentBuilder::new().timeout(Duration::from_secs(10)).build();
+    match agent.post(&url).send_json(body) {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, response)) => Err(eyre!(
+            "HTTP {}: {}",
+            code,
+            response.into_string().unwrap_or_default().trim()
+        )),
+        Err(e) => Err(eyre!("{}", e)),
+    }
+}
+
+/// Exports `metrics` to the configured sink. A failed export only warns: the run itself
+/// already happened.
+pub fn send(metrics: &RunMetrics) {
+    let Some(sink) = SINK.get().and_then(Option::as_ref) else {
+        return;
+    };
+    debug!("Run metrics: {:?}", metrics);
+    if let Some(addr) = &sink.statsd {
+        match send_statsd(addr, &statsd_lines(&sink.prefix, metrics)) {
+            Ok(()) => info!("Sent run metrics to StatsD at {}", addr),
+            Err(e) => warn!("Failed to send run metrics to StatsD: {}", e),
+        }
+    }
+    if let Some(endpoint) = &sink.otlp_endpoint {
+        match send_otlp(endpoint, &otlp_payload(&sink.prefix, metrics, SystemTime::now())) {
+            Ok(()) => info!("Sent run metrics to OTLP collector at {}", endpoint),
+            Err(e) => warn!("Failed to send run metrics to the OTLP collector: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics() -> RunMetrics {
+        let mut metrics = RunMetrics::new("create");
+        metrics.count("repos_processed", 12);
+        metrics.count("failures", 1);
+        metrics.timings.push(("apply", Duration::from_millis(1500)));
+        metrics
+    }
+
+    #[test]
+    fn test_statsd_lines() {
+        assert_eq!(
+            statsd_lines("slam", &metrics()),
+            [
+                "slam.repos_processed:12|c|#command:create",
+                "slam.failures:1|c|#command:create",
+                "slam.phase.apply:1500|ms|#command:create",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_send_statsd() {
+        let daemon = UdpSocket::bind("127.0.0.1:0").unwrap();
+        daemon.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let lines = statsd_lines("slam", &metrics());
+        send_statsd(&daemon.local_addr().unwrap().to_string(), &lines).unwrap();
+        let mut buf = [0; 256];
+        let len = daemon.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], lines[0].as_bytes());
+    }
+
+    #[test]
+    fn test_otlp_payload() {
+        let payload = otlp_payload("slam", &metrics(), UNIX_EPOCH + Duration::from_secs(2));
+        let points = &payload["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+        assert_eq!(points[0]["name"], "slam.repos_processed");
+        assert_eq!(points[0]["sum"]["dataPoints"][0]["asInt"], "12");
+        assert_eq!(points[0]["sum"]["dataPoints"][0]["timeUnixNano"], "2000000000");
+        assert_eq!(points[2]["name"], "slam.phase.duration");
+        assert_eq!(points[2]["gauge"]["dataPoints"][0]["asDouble"], 1500.0);
+        assert_eq!(
+            points[2]["gauge"]["dataPoints"][0]["attributes"][1],
+            json!({ "key": "phase", "value": { "stringValue": "apply" } })
+        );
+    }
+}
@@ -0,0 +1,241 @@
+// src/notify.rs
+
+use eyre::{eyre, Result};
+use log::{debug, info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Payload shape a webhook expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    Slack,
+    Teams,
+    /// Structured JSON for custom receivers.
+    Generic,
+}
+
+impl WebhookFormat {
+    /// Guesses the format from the webhook's host, falling back to `Generic`.
+    pub fn infer(url: &str) -> Self {
+        let host = url
+            .split_once("://")
+            .map_or(url, |(_, rest)| rest)
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if host == "hooks.slack.com" {
+            WebhookFormat::Slack
+        } else if host.ends_with(".webhook.office.com") || host == "outlook.office.com" {
+            WebhookFormat::Teams
+        } else {
+            WebhookFormat::Generic
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    pub url: String,
+    pub format: WebhookFormat,
+}
+
+/// How the run went for one repo: a link to its PR (or review page), or why it failed.
+#[derive(Debug, Clone)]
+pub struct RepoOutcome {
+    pub reposlug: String,
+    pub url: Option<String>,
+    pub error: Option<String>,
+}
+
+/// What a `create` or `review approve` run did, as posted to the webhook.
+#[derive(Debug, Clone)]
+pub struct RunSummary {
+    /// The command as typed, e.g. `create` or `review approve`.
+    pub command: String,
+    pub change_id: String,
+    pub repos: Vec<RepoOutcome>,
+}
+
+impl RunSummary {
+    fn failures(&self) -> usize {
+        self.repos.iter().filter(|r| r.error.is_some()).count()
+    }
+
+    fn headline(&self) -> String {
+        let mut headline = format!(
+            "slam {} {}: {} repo{}",
+            self.command,
+            self.change_id,
+            self.repos.len(),
+            if self.repos.len() == 1 { "" } else { "s" }
+        );
+        match self.failures() {
+            0 => {}
+            n => headline.push_str(&format!(", {} failed", n)),
+        }
+        headline
+    }
+
+    /// One line per repo, with links rendered by `link(label, url)`.
+    fn lines(&self, link: impl Fn(&str, &str) -> String) -> Vec<String> {
+        self.repos
+            .iter()
+            .map(|repo| match (&repo.error, &repo.url) {
+                (Some(error), _) => format!("{}: failed: {}", repo.reposlug, error),
+                (None, Some(url)) => link(&repo.reposlug, url),
+                (None, None) => repo.reposlug.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Slack mrkdwn needs `&`, `<` and `>` escaped outside of links.
+fn escape_slack(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn payload(format: WebhookFormat, summary: &RunSummary) -> Value {
+    match format {
+        WebhookFormat::Slack => {
+            let lines = summary.lines(|label, url| format!("<{}|{}>", url, escape_slack(label)));
+            let text = std::iter::once(format!("*{}*", escape_slack(&summary.headline())))
+                .chain(lines.iter().map(|line| format!("• {}", line)))
+                .collect::<Vec<_>>()
+                .join("\n");
+            json!({ "text": text })
+        }
+        WebhookFormat::Teams => {
+            let lines = summary.lines(|label, url| format!("[{}]({})", label, url));
+            json!({
+                "@type": "MessageCard",
+                "@context": "https://schema.org/extensions",
+                "summary": summary.headline(),
+                "title": summary.headline(),
+                "themeColor": if summary.failures() == 0 { "2EB886" } else { "D00000" },
+                "text": lines.iter().map(|line| format!("- {}", line)).collect::<Vec<_>>().join("\n\n"),
+            })
+        }
+        WebhookFormat::Generic => json!({
+            "command": summary.command,
+            "change_id": summary.change_id,
+            "text": summary.headline(),
+            "failures": summary.failures(),
+            "repos": summary.repos.iter().map(|repo| json!({
+                "reposlug": repo.reposlug,
+                "url": repo.url,
+                "error": repo.error,
+            })).collect::<Vec<_>>(),
+        }),
+    }
+}
+
+static WEBHOOK: OnceLock<Option<Webhook>> = OnceLock::new();
+
+/// Sets the webhook `send` posts to; without one (or before this is called) nothing is sent.
+pub fn configure(webhook: Option<Webhook>) {
+    let _ = WEBHOOK.set(webhook);
+}
+
+fn post(webhook: &Webhook, body: &Value) -> Result<()> {
+    let agent = ureq::AgentBuilder::new().timeout(Duration::from_secs(30)).build();
+    match agent.post(&webhook.url).send_json(body) {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, response)) => Err(eyre!(
+            "HTTP {}: {}",
+            code,
+            response.into_string().unwrap_or_default().trim()
+        )),
+        Err(e) => Err(eyre!("{}", e)),
+    }
+}
+
+/// Posts `summary` to the configured webhook. Runs with no repos to report are not posted, and a
+/// failed post only warns: the run itself already happened.
+pub fn send(summary: &RunSummary) {
+    let Some(webhook) = WEBHOOK.get().and_then(Option::as_ref) else {
+        return;
+    };
+    if summary.repos.is_empty() {
+        debug!("Nothing to notify for slam {} {}", summary.command, summary.change_id);
+        return;
+    }
+    match post(webhook, &payload(webhook.format, summary)) {
+        Ok(()) => info!("Posted run summary to {:?} webhook", webhook.format),
+        Err(e) => warn!("Failed to post run summary to webhook: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary() -> RunSummary {
+        RunSummary {
+            command: "create".to_string(),
+            change_id: "SLAM-2024-01-01".to_string(),
+            repos: vec![
+                RepoOutcome {
+                    reposlug: "org/api".to_string(),
+                    url: Some("https://github.com/org/api/pull/7".to_string()),
+                    error: None,
+                },
+                RepoOutcome {
+                    reposlug: "org/web".to_string(),
+                    url: None,
+                    error: Some("push rejected".to_string()),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_infer_format() {
+        assert_eq!(
+            WebhookFormat::infer("https://hooks.slack.com/services/T/B/x"),
+            WebhookFormat::Slack
+        );
+        assert_eq!(
+            WebhookFormat::infer("https://corp.webhook.office.com/webhookb2/abc"),
+            WebhookFormat::Teams
+        );
+        assert_eq!(
+            WebhookFormat::infer("https://ci.example.com/hooks/hooks.slack.com"),
+            WebhookFormat::Generic
+        );
+    }
+
+    #[test]
+    fn test_slack_payload() {
+        let text = payload(WebhookFormat::Slack, &summary())["text"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            text,
+            "*slam create SLAM-2024-01-01: 2 repos, 1 failed*\n\
+             • <https://github.com/org/api/pull/7|org/api>\n\
+             • org/web: failed: push rejected"
+        );
+    }
+
+    #[test]
+    fn test_teams_and_generic_payloads() {
+        let card = payload(WebhookFormat::Teams, &summary());
+        assert_eq!(card["@type"], "MessageCard");
+        assert_eq!(card["themeColor"], "D00000");
+        assert!(card["text"]
+            .as_str()
+            .unwrap()
+            .contains("[org/api](https://github.com/org/api/pull/7)"));
+
+        let generic = payload(WebhookFormat::Generic, &summary());
+        assert_eq!(generic["change_id"], "SLAM-2024-01-01");
+        assert_eq!(generic["failures"], 1);
+        assert_eq!(generic["repos"][1]["error"], "push rejected");
+        assert!(generic["repos"][1]["url"].is_null());
+    }
+}
@@ -0,0 +1,110 @@
+// src/output.rs
+
+use std::io::{self, Write};
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+/// Prints blocks of output sent from parallel tasks on a single thread, each block whole, so
+/// the lines of one repo are never split by another's.
+pub struct Printer {
+    tx: Option<Sender<String>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Printer {
+    /// Starts the printer thread writing to `writer`.
+    pub fn new<W: Write + Send + 'static>(mut writer: W) -> Self {
+        let (tx, rx) = mpsc::channel::<String>();
+        let handle = thread::spawn(move || {
+            for block in rx {
+                // A closed stdout (e.g. `| head`) leaves nothing to print to.
+                if writer.write_all(block.as_bytes()).and_then(|_| writer.flush()).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            tx: Some(tx),
+            handle: Some(handle),
+        }
+    }
+
+    pub fn stdout() -> Self {
+        Self::new(io::stdout())
+    }
+
+    pub fn stderr() -> Self {
+        Self::new(io::stderr())
+    }
+
+    /// Queues `text` to be printed as one block; a trailing newline is added when missing.
+    pub fn print(&self, text: impl Into<String>) {
+        let mut text = text.into();
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(text);
+        }
+    }
+
+    /// Waits until everything queued has been printed.
+    pub fn finish(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.tx.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for Printer {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rayon::prelude::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_blocks_are_not_interleaved() {
+        let capture = Capture::default();
+        let printer = Printer::new(capture.clone());
+        (0..32).into_par_iter().for_each(|n| {
+            let block: String = (0..20).map(|line| format!("repo{} line{}\n", n, line)).collect();
+            printer.print(block);
+        });
+        printer.print("done");
+        printer.finish();
+
+        let out = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 32 * 20 + 1);
+        for block in lines[..32 * 20].chunks(20) {
+            let repo = block[0].split_whitespace().next().unwrap();
+            assert!(block.iter().all(|line| line.starts_with(&format!("{} ", repo))));
+        }
+        assert_eq!(lines.last(), Some(&"done"));
+    }
+}
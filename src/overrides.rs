@@ -0,0 +1,78 @@
+// src/overrides.rs
+
+use eyre::{eyre, Context, Result};
+use log::debug;
+use slam_core::repo::RepoOverride;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Overrides file picked up from the sandbox root when `--overrides` is not given.
+pub const DEFAULT_FILE: &str = "slam-overrides.yaml";
+
+/// Parses the overrides file: a mapping from repo patterns (`name` or `org/name` globs) to
+/// overrides, kept in file order since the first matching pattern wins.
+fn parse(content: &str) -> Result<Vec<(String, RepoOverride)>> {
+    let mapping: Option<serde_yaml::Mapping> = serde_yaml::from_str(content)?;
+    mapping
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(ptn, value)| {
+            let ptn = ptn
+                .as_str()
+                .ok_or_else(|| eyre!("Repo pattern {:?} is not a string", ptn))?
+                .to_string();
+            let ovr = serde_yaml::from_value(value).wrap_err_with(|| format!("Invalid override for '{}'", ptn))?;
+            Ok((ptn, ovr))
+        })
+        .collect()
+}
+
+/// Loads the overrides from `path`, or from `<root>/slam-overrides.yaml` when that exists.
+pub fn load(path: Option<PathBuf>, root: &Path) -> Result<Vec<(String, RepoOverride)>> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            let default = root.join(DEFAULT_FILE);
+            if !default.is_file() {
+                return Ok(Vec::new());
+            }
+            default
+        }
+    };
+    let content =
+        fs::read_to_string(&path).wrap_err_with(|| format!("Failed to read overrides '{}'", path.display()))?;
+    let overrides = parse(&content).wrap_err_with(|| format!("Failed to parse overrides '{}'", path.display()))?;
+    debug!("Loaded {} overrides from '{}'", overrides.len(), path.display());
+    Ok(overrides)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_keeps_file_order() {
+        let overrides =
+            parse("legacy-*:\n  skip: true\n  reason: frozen\norg/web:\n  files: [ci/*.yml]\n  replacement: 'v: 3'\n")
+                .unwrap();
+        assert_eq!(overrides.len(), 2);
+        assert_eq!(overrides[0].0, "legacy-*");
+        assert!(overrides[0].1.skip);
+        assert_eq!(overrides[1].1.files, Some(vec!["ci/*.yml".to_string()]));
+        assert_eq!(overrides[1].1.replacement.as_deref(), Some("v: 3"));
+
+        assert!(parse("org/web:\n  replace: x\n").is_err());
+        assert!(parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_load_default_file() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(load(None, temp_dir.path()).unwrap().is_empty());
+        assert!(load(Some(temp_dir.path().join("missing.yaml")), temp_dir.path()).is_err());
+
+        fs::write(temp_dir.path().join(DEFAULT_FILE), "api:\n  skip: true\n").unwrap();
+        assert_eq!(load(None, temp_dir.path()).unwrap()[0].0, "api");
+    }
+}
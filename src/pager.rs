@@ -0,0 +1,87 @@
+// src/pager.rs
+
+use log::{debug, warn};
+use ratatui::crossterm::terminal;
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+/// Pager used when `$PAGER` is unset; `-R` passes color escape codes through.
+const DEFAULT_PAGER: &str = "less -R";
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// Enables or disables paging for `page`. Until this is called (e.g. in tests) output is
+/// printed directly.
+pub fn configure(enabled: bool) {
+    let _ = ENABLED.set(enabled);
+}
+
+/// Splits `$PAGER` into a command line, falling back to `less -R`. An empty `$PAGER`
+/// disables paging.
+fn pager_command(env: Option<&str>) -> Option<Vec<String>> {
+    let words: Vec<String> = env
+        .unwrap_or(DEFAULT_PAGER)
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+    (!words.is_empty()).then_some(words)
+}
+
+/// True when `text` has more lines than fit on a terminal `rows` high (0 means unknown).
+fn needs_paging(text: &str, rows: u16) -> bool {
+    rows > 0 && text.lines().count() >= rows as usize
+}
+
+fn spawn_pager(words: &[String], text: &str) -> std::io::Result<()> {
+    let mut child = Command::new(&words[0])
+        .args(&words[1..])
+        .stdin(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The user may quit the pager before reading everything; a broken pipe is fine.
+        if let Err(e) = stdin.write_all(text.as_bytes()) {
+            debug!("Pager stopped reading: {}", e);
+        }
+    }
+    child.wait()?;
+    Ok(())
+}
+
+/// Prints `text`, piping it through the pager when stdout is a terminal and the text is
+/// taller than the terminal.
+pub fn page(text: &str) {
+    let enabled = ENABLED.get().copied().unwrap_or(false) && std::io::stdout().is_terminal();
+    let rows = terminal::size().map(|(_, rows)| rows).unwrap_or(u16::MAX);
+    if enabled && needs_paging(text, rows) {
+        if let Some(words) = pager_command(std::env::var("PAGER").ok().as_deref()) {
+            match spawn_pager(&words, text) {
+                Ok(()) => return,
+                Err(e) => warn!("Failed to run pager '{}': {}", words.join(" "), e),
+            }
+        }
+    }
+    print!("{}", text);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pager_command() {
+        assert_eq!(pager_command(None), Some(vec!["less".to_string(), "-R".to_string()]));
+        assert_eq!(
+            pager_command(Some("most -s")),
+            Some(vec!["most".to_string(), "-s".to_string()])
+        );
+        assert_eq!(pager_command(Some("  ")), None);
+    }
+
+    #[test]
+    fn test_needs_paging() {
+        assert!(!needs_paging("a\nb\n", 24));
+        assert!(needs_paging(&"line\n".repeat(24), 24));
+        assert!(!needs_paging(&"line\n".repeat(100), 0));
+    }
+}
@@ -0,0 +1,245 @@
+// src/picker.rs
+
+use eyre::{eyre, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::io::IsTerminal;
+
+/// How well `query` fuzzy-matches `candidate`: its characters must appear in order (ignoring
+/// case); the fewer characters between the first and last of them, the better (lower). `None`
+/// when it doesn't match; every candidate matches the empty query with 0.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<usize> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut start = None;
+    let mut pos = 0;
+    for c in query.to_lowercase().chars() {
+        let found = pos + candidate[pos..].iter().position(|&d| d == c)?;
+        start.get_or_insert(found);
+        pos = found + 1;
+    }
+    Some(start.map_or(0, |start| pos - start))
+}
+
+/// Picker state: the items, which are chosen, and the ones the query lets through, best first.
+struct Picker {
+    items: Vec<String>,
+    chosen: Vec<bool>,
+    query: String,
+    visible: Vec<usize>,
+    list: ListState,
+}
+
+impl Picker {
+    fn new(items: Vec<String>) -> Self {
+        let chosen = vec![false; items.len()];
+        let mut picker = Self {
+            items,
+            chosen,
+            query: String::new(),
+            visible: Vec::new(),
+            list: ListState::default(),
+        };
+        picker.refilter();
+        picker
+    }
+
+    fn refilter(&mut self) {
+        let mut scored: Vec<(usize, usize)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| fuzzy_score(&self.query, item).map(|score| (score, i)))
+            .collect();
+        scored.sort();
+        self.visible = scored.into_iter().map(|(_, i)| i).collect();
+        self.list.select((!self.visible.is_empty()).then_some(0));
+    }
+
+    fn type_char(&mut self, c: char) {
+        self.query.push(c);
+        self.refilter();
+    }
+
+    fn backspace(&mut self) {
+        if self.query.pop().is_some() {
+            self.refilter();
+        }
+    }
+
+    fn move_by(&mut self, forward: bool) {
+        let len = self.visible.len();
+        if let Some(row) = self.list.selected().filter(|_| len > 0) {
+            self.list.select(Some(if forward {
+                (row + 1) % len
+            } else {
+                (row + len - 1) % len
+            }));
+        }
+    }
+
+    /// Toggles the highlighted item and moves on to the next.
+    fn toggle(&mut self) {
+        if let Some(&i) = self.list.selected().and_then(|row| self.visible.get(row)) {
+            self.chosen[i] = !self.chosen[i];
+            self.move_by(true);
+        }
+    }
+
+    /// Chooses every visible item, or unchooses them all when they already are.
+    fn toggle_visible(&mut self) {
+        let choose = !self.visible.iter().all(|&i| self.chosen[i]);
+        for &i in &self.visible {
+            self.chosen[i] = choose;
+        }
+    }
+
+    /// The chosen items in their original order; with none chosen, the highlighted one.
+    fn picked(&self) -> Vec<String> {
+        let chosen: Vec<String> = self
+            .items
+            .iter()
+            .zip(&self.chosen)
+            .filter(|(_, chosen)| **chosen)
+            .map(|(item, _)| item.clone())
+            .collect();
+        if !chosen.is_empty() {
+            return chosen;
+        }
+        self.list
+            .selected()
+            .and_then(|row| self.visible.get(row))
+            .map(|&i| vec![self.items[i].clone()])
+            .unwrap_or_default()
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [query_area, list_area, footer_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+
+        let chosen = self.chosen.iter().filter(|chosen| **chosen).count();
+        let query = Paragraph::new(format!("> {}", self.query)).block(Block::default().borders(Borders::ALL).title(
+            format!("{}/{} repos, {} picked", self.visible.len(), self.items.len(), chosen),
+        ));
+        frame.render_widget(query, query_area);
+
+        let items: Vec<ListItem> = self
+            .visible
+            .iter()
+            .map(|&i| {
+                let (mark, style) = if self.chosen[i] {
+                    ("[x] ", Style::default().fg(Color::Green))
+                } else {
+                    ("[ ] ", Style::default())
+                };
+                ListItem::new(Line::styled(format!("{}{}", mark, self.items[i]), style))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, list_area, &mut self.list);
+
+        frame.render_widget(
+            Paragraph::new("type to filter  ↑/↓ move  space/tab pick  ctrl-a pick all shown  enter done  esc cancel"),
+            footer_area,
+        );
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<Option<Vec<String>>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            match key.code {
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Char('c') if ctrl => return Ok(None),
+                KeyCode::Char('a') if ctrl => self.toggle_visible(),
+                KeyCode::Enter => return Ok(Some(self.picked())),
+                KeyCode::Down => self.move_by(true),
+                KeyCode::Up => self.move_by(false),
+                KeyCode::Char(' ') | KeyCode::Tab => self.toggle(),
+                KeyCode::Backspace => self.backspace(),
+                KeyCode::Char(c) => self.type_char(c),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Lets the user fuzzy-search `items` and pick some of them; `None` when they cancel. Needs a
+/// terminal.
+pub fn pick(items: Vec<String>) -> Result<Option<Vec<String>>> {
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return Err(eyre!("--pick needs a terminal to show the repos in"));
+    }
+    let mut picker = Picker::new(items);
+    let mut terminal = ratatui::init();
+    let result = picker.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picker() -> Picker {
+        Picker::new(
+            ["org/api-gateway", "org/web", "org/payments-api", "other/gateway"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("", "org/web"), Some(0));
+        assert_eq!(fuzzy_score("web", "org/web"), Some(3));
+        assert_eq!(fuzzy_score("OW", "org/web"), Some(5));
+        assert_eq!(fuzzy_score("bew", "org/web"), None);
+    }
+
+    #[test]
+    fn test_filter_ranks_tighter_matches_first() {
+        let mut picker = picker();
+        for c in "gate".chars() {
+            picker.type_char(c);
+        }
+        let visible: Vec<&str> = picker.visible.iter().map(|&i| picker.items[i].as_str()).collect();
+        assert_eq!(visible, ["other/gateway", "org/api-gateway"]);
+        picker.backspace();
+        picker.backspace();
+        picker.backspace();
+        picker.backspace();
+        assert_eq!(picker.visible.len(), 4);
+    }
+
+    #[test]
+    fn test_pick_chosen_or_highlighted() {
+        let mut picker = picker();
+        assert_eq!(picker.picked(), ["org/api-gateway"]);
+
+        picker.move_by(true);
+        picker.toggle();
+        picker.toggle();
+        assert_eq!(picker.picked(), ["org/web", "org/payments-api"]);
+
+        for c in "api".chars() {
+            picker.type_char(c);
+        }
+        picker.toggle_visible();
+        assert_eq!(picker.picked(), ["org/api-gateway", "org/web", "org/payments-api"]);
+        picker.toggle_visible();
+        assert_eq!(picker.picked(), ["org/web"]);
+    }
+}
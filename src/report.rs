@@ -0,0 +1,202 @@
+// src/report.rs
+
+use eyre::{eyre, Context, Result};
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+use slam_core::diff::DiffStat;
+
+/// Report flavor, picked from the output file's extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Some(ReportFormat::Markdown),
+            "html" | "htm" => Some(ReportFormat::Html),
+            _ => None,
+        }
+    }
+}
+
+/// One repo's section of a report.
+#[derive(Debug, Clone)]
+pub struct ReportEntry {
+    pub reposlug: String,
+    pub pr_url: Option<String>,
+    pub stat: DiffStat,
+    /// Diff as printed to the terminal; color codes are stripped when rendering.
+    pub diff: String,
+}
+
+fn strip_ansi(text: &str) -> String {
+    let ansi_re = Regex::new(r"\x1b\[[0-9;]*m").unwrap();
+    ansi_re.replace_all(text, "").to_string()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The longest run of backticks in `text` plus one (at least three), so a fence can't be closed early.
+fn markdown_fence(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat((longest + 1).max(3))
+}
+
+fn render_markdown(title: &str, entries: &[ReportEntry]) -> String {
+    let mut out = format!("# {}\n\n", title);
+    out.push_str("| Repo | PR | Files | + | - |\n|---|---|---:|---:|---:|\n");
+    for entry in entries {
+        let pr = match &entry.pr_url {
+            Some(url) => format!("[{}]({})", url.rsplit('/').next().unwrap_or(url), url),
+            None => "-".to_string(),
+        };
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            entry.reposlug, pr, entry.stat.files, entry.stat.insertions, entry.stat.deletions
+        ));
+    }
+    for entry in entries {
+        out.push_str(&format!("\n## {}\n\n", entry.reposlug));
+        if let Some(url) = &entry.pr_url {
+            out.push_str(&format!("{}\n\n", url));
+        }
+        let diff = strip_ansi(&entry.diff);
+        let fence = markdown_fence(&diff);
+        out.push_str(&format!("{}\n{}\n{}\n", fence, diff.trim_end(), fence));
+    }
+    out
+}
+
+fn render_html(title: &str, entries: &[ReportEntry]) -> String {
+    let title = escape_html(title);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n\
+         body {{ font-family: sans-serif; }}\n\
+         table {{ border-collapse: collapse; }}\n\
+         th, td {{ border: 1px solid #ccc; padding: 2px 8px; }}\n\
+         pre {{ background: #f6f8fa; padding: 8px; }}\n\
+         .add {{ color: #22863a; }}\n\
+         .del {{ color: #cb2431; }}\n\
+         </style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        title, title
+    );
+    out.push_str("<table>\n<tr><th>Repo</th><th>PR</th><th>Files</th><th>+</th><th>-</th></tr>\n");
+    for entry in entries {
+        let reposlug = escape_html(&entry.reposlug);
+        let pr = match &entry.pr_url {
+            Some(url) => format!("<a href=\"{}\">{}</a>", escape_html(url), escape_html(url)),
+            None => "-".to_string(),
+        };
+        out.push_str(&format!(
+            "<tr><td><a href=\"#{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            reposlug, reposlug, pr, entry.stat.files, entry.stat.insertions, entry.stat.deletions
+        ));
+    }
+    out.push_str("</table>\n");
+    for entry in entries {
+        let reposlug = escape_html(&entry.reposlug);
+        out.push_str(&format!("<h2 id=\"{}\">{}</h2>\n<pre>", reposlug, reposlug));
+        for line in strip_ansi(&entry.diff).lines() {
+            let class = match line.trim_start().chars().next() {
+                Some('+') => Some("add"),
+                Some('-') => Some("del"),
+                _ => None,
+            };
+            match class {
+                Some(class) => out.push_str(&format!("<span class=\"{}\">{}</span>\n", class, escape_html(line))),
+                None => out.push_str(&format!("{}\n", escape_html(line))),
+            }
+        }
+        out.push_str("</pre>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Renders `entries` in the given format.
+pub fn render(format: ReportFormat, title: &str, entries: &[ReportEntry]) -> String {
+    match format {
+        ReportFormat::Markdown => render_markdown(title, entries),
+        ReportFormat::Html => render_html(title, entries),
+    }
+}
+
+/// Writes a report to `path`, choosing Markdown or HTML from its extension.
+pub fn write(path: &Path, title: &str, entries: &[ReportEntry]) -> Result<()> {
+    let format =
+        ReportFormat::from_path(path).ok_or_else(|| eyre!("Report '{}' must end in .md or .html", path.display()))?;
+    fs::write(path, render(format, title, entries))
+        .wrap_err_with(|| format!("Failed to write report '{}'", path.display()))?;
+    println!("Report written to {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pr_url: Option<&str>) -> ReportEntry {
+        ReportEntry {
+            reposlug: "org/repo".to_string(),
+            pr_url: pr_url.map(str::to_string),
+            stat: DiffStat {
+                files: 1,
+                insertions: 1,
+                deletions: 1,
+            },
+            diff: "org/repo\n  M config.yml\n    \x1b[31m-   2\x1b[0m | a<b\n    \x1b[32m+   2\x1b[0m | a>b\n"
+                .to_string(),
+        }
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ReportFormat::from_path(Path::new("out.md")),
+            Some(ReportFormat::Markdown)
+        );
+        assert_eq!(ReportFormat::from_path(Path::new("out.HTML")), Some(ReportFormat::Html));
+        assert_eq!(ReportFormat::from_path(Path::new("out.txt")), None);
+        assert_eq!(ReportFormat::from_path(Path::new("out")), None);
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let out = render(
+            ReportFormat::Markdown,
+            "SLAM-x",
+            &[entry(Some("https://github.com/org/repo/pull/7"))],
+        );
+        assert!(out.starts_with("# SLAM-x\n"));
+        assert!(out.contains("| org/repo | [7](https://github.com/org/repo/pull/7) | 1 | 1 | 1 |"));
+        assert!(out.contains("## org/repo"));
+        assert!(out.contains("```\norg/repo\n  M config.yml\n    -   2 | a<b\n    +   2 | a>b\n```"));
+        assert!(!out.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_render_html_escapes_and_marks_lines() {
+        let out = render(ReportFormat::Html, "SLAM-x", &[entry(None)]);
+        assert!(out.contains("<h1>SLAM-x</h1>"));
+        assert!(out.contains("<td>-</td>"));
+        assert!(out.contains("<span class=\"del\">    -   2 | a&lt;b</span>"));
+        assert!(out.contains("<span class=\"add\">    +   2 | a&gt;b</span>"));
+        assert!(!out.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_markdown_fence_longer_than_content() {
+        assert_eq!(markdown_fence("plain"), "```");
+        assert_eq!(markdown_fence("has ```` inside"), "`````");
+    }
+}
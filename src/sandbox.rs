@@ -1,20 +1,26 @@
 // src/sandbox.rs
 
 use rayon::prelude::*;
-use std::env;
-use std::io::{self, Write};
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 use colored::Colorize;
 use eyre::Result;
 use log::{debug, info, warn};
 
-use crate::git;
+use slam_core::git;
+use slam_core::pool;
+
+use crate::output::Printer;
+use crate::status;
 
 /// Refreshes a single repository by pruning remote branches, cleaning local stale branches,
 /// resetting, checking out the head branch, pulling the latest changes, and installing pre-commit hooks.
+/// Only local branches starting with `branch_prefix` are considered stale candidates.
+/// A detached HEAD (e.g. a CI checkout) is left for the head branch, with the commit it was at in
+/// the status, and a shallow clone is unshallowed when pulling needs the history.
 /// Returns a status string.
-pub fn refresh_repo(repo: &Path) -> Result<String> {
+pub fn refresh_repo(repo: &Path, branch_prefix: &str) -> Result<String> {
     let success_emoji = "📥";
     let error_emoji = "❗";
     let missing_emoji = "❓";
@@ -24,8 +30,8 @@ pub fn refresh_repo(repo: &Path) -> Result<String> {
     git::remote_prune(repo)?;
     debug!("Finished remote prune for repo '{}'", repo.display());
 
-    // Remove any local branches starting with "SLAM" that don't have a corresponding remote branch.
-    match git::list_local_branches_with_prefix(repo, "SLAM") {
+    // Remove any local branches with the branch prefix that don't have a corresponding remote branch.
+    match git::list_local_branches_with_prefix(repo, branch_prefix) {
         Ok(local_branches) => {
             debug!(
                 "Found {} local SLAM branches in '{}'",
@@ -33,7 +39,7 @@ pub fn refresh_repo(repo: &Path) -> Result<String> {
                 repo.display()
             );
             for branch in local_branches {
-                match git::remote_branch_exists(repo, &branch) {
+                match git::remote_branch_exists(repo, "origin", &branch) {
                     Ok(true) => {
                         debug!("Remote branch '{}' exists in '{}'", branch, repo.display());
                     }
@@ -64,16 +70,34 @@ pub fn refresh_repo(repo: &Path) -> Result<String> {
 
     // Capture the SHA before updating
     let sha_before = git::get_head_sha(repo)?;
+    let detached = git::current_branch(repo)?.is_none();
 
     // Reset any local changes and switch to HEAD
     git::reset_hard(repo)?;
     debug!("Completed hard reset for repo '{}'", repo.display());
 
+    if detached {
+        // The commit stays reachable through the reflog; the status line names it to go back to.
+        info!(
+            "HEAD of '{}' is detached at {}; checking out '{}'",
+            repo.display(),
+            sha_before,
+            branch
+        );
+        git::fetch_branch(repo, "origin", &branch)?;
+    }
     git::checkout(repo, &branch)?;
     debug!("Checked out branch '{}' in repo '{}'", branch, repo.display());
 
-    // Pull the latest
-    git::pull(repo)?;
+    // Pull the latest; a shallow clone may lack the history to merge, so fetch it and retry.
+    if let Err(e) = git::pull(repo) {
+        if !git::is_shallow(repo)? {
+            return Err(e);
+        }
+        debug!("Pull failed in shallow '{}': {}; unshallowing", repo.display(), e);
+        git::unshallow(repo)?;
+        git::pull(repo)?;
+    }
     debug!("Pulled latest changes for repo '{}'", repo.display());
 
     // Capture the SHA after updating
@@ -112,10 +136,11 @@ pub fn refresh_repo(repo: &Path) -> Result<String> {
     debug!("Returning status for repo '{}'", reposlug);
 
     // Insert `sha_display` between the branch name and the emoji
-    Ok(format!(
-        "{:>6} {} {} {}",
-        branch_display, sha_display, hook_status, reposlug
-    ))
+    let mut line = format!("{:>6} {} {} {}", branch_display, sha_display, hook_status, reposlug);
+    if detached {
+        line.push_str(&format!(" (was detached at {})", &sha_before[..7]).dimmed().to_string());
+    }
+    Ok(line)
 }
 
 /// Generates a status line for a newly cloned repository.
@@ -151,39 +176,63 @@ fn generate_clone_status(repo: &Path) -> Result<String> {
     ))
 }
 
-/// Refreshes all repositories found in the current working directory.
+/// Refreshes all repositories found under the sandbox `root`.
 /// Each repository is processed in parallel; status output is printed for each.
-pub fn sandbox_refresh() -> Result<()> {
-    let cwd = env::current_dir()?;
-    debug!("Current working directory: '{}'", cwd.display());
-    let repos = git::find_git_repositories(&cwd)?;
-    debug!("Found {} repositories in '{}'", repos.len(), cwd.display());
-
-    repos.par_iter().for_each(|repo| {
-        debug!("Processing repo '{}'", repo.display());
-        match refresh_repo(repo) {
-            Ok(line) => {
-                println!("{}", line);
-                io::stdout().flush().expect("Failed to flush stdout");
-            }
-            Err(e) => {
-                warn!("Error processing repo {}: {}", repo.to_string_lossy().trim_end(), e);
+pub fn sandbox_refresh(root: &Path, branch_prefix: &str) -> Result<()> {
+    debug!("Sandbox root: '{}'", root.display());
+    let repos = git::index_git_repositories(root)?;
+    debug!("Found {} repositories in '{}'", repos.len(), root.display());
+
+    let printer = Printer::stdout();
+    pool::git(|| {
+        repos.par_iter().for_each(|repo| {
+            debug!("Processing repo '{}'", repo.display());
+            match refresh_repo(repo, branch_prefix) {
+                Ok(line) => printer.print(line),
+                Err(e) => {
+                    warn!("Error processing repo {}: {}", repo.to_string_lossy().trim_end(), e);
+                }
             }
-        }
+        })
     });
+    printer.finish();
     Ok(())
 }
 
-/// Sets up a sandbox environment by retrieving the list of repositories for a given organization,
-/// filtering them based on provided patterns, and then cloning or updating each repository.
+/// The clones under `root` by the `org/repo` of their origin remote.
+fn existing_clones(root: &Path) -> Result<BTreeMap<String, PathBuf>> {
+    if !root.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let clones = git::index_git_repositories(root)?;
+    Ok(pool::git(|| {
+        clones
+            .into_par_iter()
+            .map(|path| (git::local_reposlug(&path, root), path))
+            .collect()
+    }))
+}
+
+/// Sets up a sandbox environment by retrieving the list of repositories for each organization,
+/// filtering them based on provided patterns, and then cloning or updating each repository
+/// into `<root>/<org>/<repo>`.
 /// For existing repositories, performs a full refresh to ensure they are on the HEAD branch and up to date.
 /// Pre-commit hooks are installed if available.
 /// Outputs status lines in the same format as sandbox_refresh.
-pub fn sandbox_setup(repo_ptns: Vec<String>) -> Result<()> {
-    let org = "tatari-tv";
-    debug!("Retrieving repository list for organization '{}'", org);
-    let repos = git::find_repos_in_org(org)?;
-    info!("Found {} repos in '{}'", repos.len(), org);
+pub fn sandbox_setup(
+    root: &Path,
+    orgs: &[String],
+    repo_ptns: Vec<String>,
+    filter: &git::RepoFilter,
+    branch_prefix: &str,
+) -> Result<()> {
+    let mut repos = Vec::new();
+    for org in orgs {
+        debug!("Retrieving repository list for organization '{}'", org);
+        let org_repos = git::find_repos_in_org(org, filter)?;
+        info!("Found {} repos in '{}'", org_repos.len(), org);
+        repos.extend(org_repos);
+    }
 
     let filtered_repos: Vec<String> = if repo_ptns.is_empty() {
         debug!("No repository patterns provided; using all repos");
@@ -197,56 +246,243 @@ pub fn sandbox_setup(repo_ptns: Vec<String>) -> Result<()> {
     };
     info!("After filtering, {} repos remain", filtered_repos.len());
 
-    let cwd = env::current_dir()?;
-    debug!("Sandbox setup working directory: '{}'", cwd.display());
+    debug!("Sandbox setup root: '{}'", root.display());
 
-    filtered_repos.par_iter().for_each(|reposlug| {
-        let target = cwd.join(reposlug);
+    // A repo already cloned elsewhere under root, e.g. into a renamed directory, is refreshed
+    // where it is rather than cloned a second time.
+    let existing = existing_clones(root)?;
+    let printer = Printer::stdout();
+    pool::git(|| {
+        filtered_repos.par_iter().for_each(|reposlug| {
+            let target = existing.get(reposlug).cloned().unwrap_or_else(|| root.join(reposlug));
 
-        if target.exists() {
-            debug!(
-                "Repository {} already exists in {}; performing full refresh...",
-                reposlug,
-                target.display()
-            );
+            if target.exists() {
+                debug!(
+                    "Repository {} already exists in {}; performing full refresh...",
+                    reposlug,
+                    target.display()
+                );
 
-            // Perform a full refresh to ensure the repo is on HEAD branch and up to date
-            match refresh_repo(&target) {
-                Ok(status_line) => {
-                    println!("{}", status_line);
-                    io::stdout().flush().expect("Failed to flush stdout");
+                // Perform a full refresh to ensure the repo is on HEAD branch and up to date
+                match refresh_repo(&target, branch_prefix) {
+                    Ok(status_line) => printer.print(status_line),
+                    Err(e) => {
+                        warn!("Failed to refresh repository {}: {}", reposlug, e);
+                    }
                 }
-                Err(e) => {
-                    warn!("Failed to refresh repository {}: {}", reposlug, e);
+            } else {
+                debug!("Cloning repository {} into {}", reposlug, target.display());
+                if let Err(e) = git::clone_repo(reposlug, &target) {
+                    warn!("Failed to clone repository {}: {}", reposlug, e);
+                    return; // Skip status generation if clone failed
+                }
+
+                // Generate and print status line for newly cloned repo
+                match generate_clone_status(&target) {
+                    Ok(status_line) => printer.print(status_line),
+                    Err(e) => {
+                        warn!("Failed to generate status for cloned repository {}: {}", reposlug, e);
+                    }
                 }
             }
+        })
+    });
+    printer.finish();
+    git::index_git_repositories(root)?;
+    Ok(())
+}
+
+/// Prints each repository's branch, dirty state, ahead/behind counts and stale branches
+/// without fetching, resetting or otherwise modifying anything.
+pub fn sandbox_status(root: &Path, branch_prefix: &str) -> Result<()> {
+    for health in status::collect_health(root, branch_prefix)? {
+        println!("{}", status::format_repo_health(&health));
+    }
+    Ok(())
+}
+
+/// Why a local clone no longer corresponds to a live upstream repository.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PruneReason {
+    Archived,
+    Deleted,
+    Renamed(String),
+}
+
+impl std::fmt::Display for PruneReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PruneReason::Archived => write!(f, "archived"),
+            PruneReason::Deleted => write!(f, "deleted"),
+            PruneReason::Renamed(to) => write!(f, "renamed to {}", to),
+        }
+    }
+}
+
+/// Classifies `reposlug` against the org listing; repos missing from it are looked up
+/// individually to tell a rename (GitHub redirects the old name) from a deletion.
+fn prune_reason(reposlug: &str, org_repos: &[git::OrgRepo]) -> Result<Option<PruneReason>> {
+    let name = reposlug.split('/').nth(1).unwrap_or(reposlug);
+    if let Some(repo) = org_repos.iter().find(|r| r.name == name) {
+        return Ok(repo.archived.then_some(PruneReason::Archived));
+    }
+    Ok(match git::lookup_repo(reposlug)? {
+        None => Some(PruneReason::Deleted),
+        Some(full_name) if !full_name.eq_ignore_ascii_case(reposlug) => Some(PruneReason::Renamed(full_name)),
+        Some(_) => None,
+    })
+}
+
+/// Why the clone at `target` must not be removed: once its upstream is archived or deleted it may
+/// hold the last copy of uncommitted changes, local-only commits or stashes. A clone that can't be
+/// checked is kept too.
+fn prune_blocker(target: &Path) -> Option<&'static str> {
+    if !git::changed_files(target).map(|f| f.is_empty()).unwrap_or(false) {
+        Some("has uncommitted changes")
+    } else if git::unpushed_commits_all(target).map_or(true, |n| n > 0) {
+        Some("has unpushed commits")
+    } else if git::has_stash(target).unwrap_or(true) {
+        Some("has stashed changes")
+    } else {
+        None
+    }
+}
+
+/// Reports local clones under `root` whose upstream is archived, deleted or renamed, and
+/// removes them when `remove` is set. Clones with uncommitted changes, unpushed commits or
+/// stashes are never removed.
+pub fn sandbox_prune(root: &Path, remove: bool) -> Result<()> {
+    let mut by_org: BTreeMap<String, Vec<(String, PathBuf)>> = BTreeMap::new();
+    for (reposlug, path) in existing_clones(root)? {
+        if let Some((org, _)) = reposlug.split_once('/') {
+            by_org
+                .entry(org.to_string())
+                .or_default()
+                .push((reposlug.clone(), path));
         } else {
-            debug!("Cloning repository {} into {}", reposlug, target.display());
-            if let Err(e) = git::clone_repo(reposlug, &target) {
-                warn!("Failed to clone repository {}: {}", reposlug, e);
-                return; // Skip status generation if clone failed
-            }
+            debug!("Skipping '{}': not in <org>/<repo> layout", reposlug);
+        }
+    }
 
-            // Generate and print status line for newly cloned repo
-            match generate_clone_status(&target) {
-                Ok(status_line) => {
-                    println!("{}", status_line);
-                    io::stdout().flush().expect("Failed to flush stdout");
-                }
+    let mut pruned = 0;
+    for (org, reposlugs) in by_org {
+        // Always list fresh: a stale cache could make a brand-new repo look deleted.
+        let org_repos = git::fetch_org_repos(&org)?;
+        let results: Vec<(String, PathBuf, Result<Option<PruneReason>>)> = pool::gh(|| {
+            reposlugs
+                .into_par_iter()
+                .map(|(reposlug, target)| {
+                    let reason = prune_reason(&reposlug, &org_repos);
+                    (reposlug, target, reason)
+                })
+                .collect()
+        });
+        for (reposlug, target, reason) in results {
+            let reason = match reason {
+                Ok(Some(reason)) => reason,
+                Ok(None) => continue,
                 Err(e) => {
-                    warn!("Failed to generate status for cloned repository {}: {}", reposlug, e);
+                    warn!("Could not check '{}': {}", reposlug, e);
+                    continue;
                 }
-            }
+            };
+            pruned += 1;
+            let action = if !remove {
+                "would remove".to_string()
+            } else if let Some(blocker) = prune_blocker(&target) {
+                format!("kept: {}", blocker).yellow().to_string()
+            } else {
+                match std::fs::remove_dir_all(&target) {
+                    Ok(()) => {
+                        info!("Removed '{}' ({})", target.display(), reason);
+                        "removed".red().to_string()
+                    }
+                    Err(e) => format!("failed to remove: {}", e),
+                }
+            };
+            println!(
+                "{} {} ({})",
+                format!("{:>24}", reason.to_string()).magenta(),
+                reposlug,
+                action
+            );
         }
-    });
+    }
+
+    if pruned == 0 {
+        println!("No archived, deleted or renamed repos found.");
+    } else if !remove {
+        println!("\nRun again with --remove to delete these clones.");
+    }
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::env;
     use tempfile::TempDir;
 
+    fn org_repo(name: &str, archived: bool) -> git::OrgRepo {
+        git::OrgRepo {
+            name: name.to_string(),
+            archived,
+            visibility: None,
+            language: None,
+            topics: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_prune_reason_from_org_listing() {
+        let org_repos = vec![org_repo("live", false), org_repo("old", true)];
+        assert_eq!(prune_reason("org/live", &org_repos).unwrap(), None);
+        assert_eq!(
+            prune_reason("org/old", &org_repos).unwrap(),
+            Some(PruneReason::Archived)
+        );
+    }
+
+    #[test]
+    fn test_prune_blocker() {
+        let dir = TempDir::new().unwrap();
+        let origin = dir.path().join("origin");
+        let clone = dir.path().join("clone");
+        let setup = format!(
+            "git init -q {0} && cd {0} && git -c user.name=t -c user.email=t@t commit -q --allow-empty -m init && \
+             git clone -q {0} {1}",
+            origin.display(),
+            clone.display()
+        );
+        assert!(std::process::Command::new("sh")
+            .args(["-c", &setup])
+            .status()
+            .unwrap()
+            .success());
+        assert_eq!(prune_blocker(&clone), None);
+
+        let commit = format!(
+            "cd {} && git checkout -q -b mine && git -c user.name=t -c user.email=t@t commit -q --allow-empty -m local",
+            clone.display()
+        );
+        assert!(std::process::Command::new("sh")
+            .args(["-c", &commit])
+            .status()
+            .unwrap()
+            .success());
+        assert_eq!(prune_blocker(&clone), Some("has unpushed commits"));
+    }
+
+    #[test]
+    fn test_prune_reason_display() {
+        assert_eq!(PruneReason::Archived.to_string(), "archived");
+        assert_eq!(PruneReason::Deleted.to_string(), "deleted");
+        assert_eq!(
+            PruneReason::Renamed("org/new".to_string()).to_string(),
+            "renamed to org/new"
+        );
+    }
+
     #[test]
     fn test_sandbox_setup_empty_patterns() {
         // This test would require mocking git::find_repos_in_org
@@ -359,9 +595,9 @@ mod tests {
 
     #[test]
     fn test_org_constant() {
-        // Test that the hardcoded org is what we expect
+        // Test that the default org is what we expect
         // This is tested indirectly through the sandbox_setup function
-        // The function uses "tatari-tv" as the organization
+        // `sandbox setup` uses "tatari-tv" when no --org is given
         assert_eq!("tatari-tv".len(), 9); // Basic sanity check
     }
 
@@ -502,14 +738,14 @@ mod tests {
 
     #[test]
     fn test_both_functions_use_stdout_flush() {
-        // Test that both setup and refresh use io::stdout().flush()
+        // Test that both setup and refresh print through the shared Printer
         // This ensures consistent output behavior in parallel processing
 
         // Both functions should:
-        // 1. Print status lines with println!()
-        // 2. Flush stdout with io::stdout().flush().expect("Failed to flush stdout")
+        // 1. Send status lines to a Printer::stdout()
+        // 2. Call printer.finish() so every line is flushed before returning
 
-        // This is important for parallel processing to ensure output appears immediately
+        // This is important for parallel processing to ensure lines never interleave
         let flush_error_msg = "Failed to flush stdout";
         assert_eq!(flush_error_msg, "Failed to flush stdout");
         assert!(!flush_error_msg.is_empty());
@@ -523,8 +759,7 @@ mod tests {
 
         // Both paths should:
         // 1. Generate a status line
-        // 2. Print it with println!()
-        // 3. Flush stdout
+        // 2. Send it to the Printer
 
         // The key difference is:
         // - Existing repos: calls refresh_repo() which returns status
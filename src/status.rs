@@ -0,0 +1,264 @@
+// src/status.rs
+
+use colored::Colorize;
+use eyre::Result;
+use log::{debug, warn};
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use slam_core::git;
+use slam_core::pool;
+use slam_core::transaction::{self, Journal};
+
+/// Local state of one sandbox repository.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepoHealth {
+    pub reposlug: String,
+    /// Checked-out branch, or `None` for a detached HEAD.
+    pub branch: Option<String>,
+    /// Commits (ahead, behind) the upstream branch; `None` without an upstream.
+    pub ahead_behind: Option<(usize, usize)>,
+    /// Files with uncommitted changes (including untracked files).
+    pub dirty_files: usize,
+    /// Local branches with the branch prefix whose remote branch is gone.
+    pub stale_branches: Vec<String>,
+}
+
+/// Inspects `repo` without touching the network: remote branches are taken from the
+/// local remote-tracking refs, so run `slam sandbox refresh` first for exact results.
+fn repo_health(root: &Path, repo: &Path, branch_prefix: &str) -> Result<RepoHealth> {
+    let reposlug = git::local_reposlug(repo, root);
+    let branch = git::current_branch(repo).ok().flatten();
+    let ahead_behind = git::ahead_behind(repo)?;
+    let dirty_files = git::changed_files(repo)?.len();
+    let remote_branches = git::list_remote_tracking_branches_with_prefix(repo, branch_prefix)?;
+    let stale_branches = git::list_local_branches_with_prefix(repo, branch_prefix)?
+        .into_iter()
+        .filter(|branch| !remote_branches.contains(branch))
+        .collect();
+    Ok(RepoHealth {
+        reposlug,
+        branch,
+        ahead_behind,
+        dirty_files,
+        stale_branches,
+    })
+}
+
+/// Counts open PRs per change id, keeping only branches with the branch prefix.
+fn in_flight_changes(reposlugs: Vec<String>, branch_prefix: &str) -> Result<BTreeMap<String, usize>> {
+    let prs = git::get_prs_for_repos(reposlugs)?;
+    Ok(prs
+        .into_iter()
+        .filter(|(change_id, _)| change_id.starts_with(branch_prefix))
+        .map(|(change_id, pr_list)| (change_id, pr_list.len()))
+        .collect())
+}
+
+/// Renders the status report; `changes` is `None` when GitHub was not queried, and `pending` are
+/// the rollback journals interrupted runs left behind.
+pub fn format_status(
+    healths: &[RepoHealth],
+    changes: Option<&BTreeMap<String, usize>>,
+    pending: &[Journal],
+    branch_prefix: &str,
+) -> String {
+    let dirty: Vec<&RepoHealth> = healths.iter().filter(|h| h.dirty_files > 0).collect();
+    let stale: Vec<&RepoHealth> = healths.iter().filter(|h| !h.stale_branches.is_empty()).collect();
+    let stale_count: usize = stale.iter().map(|h| h.stale_branches.len()).sum();
+
+    let mut out = String::new();
+    out.push_str(&format!(
+        "{} {} repos, {} dirty, {} stale {} branches\n",
+        "Sandbox:".bold(),
+        healths.len(),
+        dirty.len(),
+        stale_count,
+        branch_prefix
+    ));
+    for health in &dirty {
+        out.push_str(&format!(
+            "  {} {} ({} files)\n",
+            "dirty".yellow(),
+            health.reposlug,
+            health.dirty_files
+        ));
+    }
+    for health in &stale {
+        out.push_str(&format!(
+            "  {} {}: {}\n",
+            "stale".red(),
+            health.reposlug,
+            health.stale_branches.join(", ")
+        ));
+    }
+
+    out.push('\n');
+    match changes {
+        None => out.push_str(&format!("{} skipped (--offline)\n", "In-flight changes:".bold())),
+        Some(changes) if changes.is_empty() => {
+            out.push_str(&format!("{} none\n", "In-flight changes:".bold()));
+        }
+        Some(changes) => {
+            out.push_str(&format!("{}\n", "In-flight changes:".bold()));
+            for (change_id, count) in changes {
+                out.push_str(&format!("  {} {} open PRs\n", change_id.magenta(), count));
+            }
+        }
+    }
+
+    let mut rollbacks: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for journal in pending {
+        rollbacks.entry(&journal.change_id).or_default().push(&journal.reposlug);
+    }
+    if !rollbacks.is_empty() {
+        out.push_str(&format!("\n{}\n", "Outstanding rollbacks:".bold()));
+        for (change_id, reposlugs) in rollbacks {
+            out.push_str(&format!(
+                "  {} {} (run `slam rollback {}`)\n",
+                change_id.magenta(),
+                reposlugs.join(", "),
+                change_id
+            ));
+        }
+    }
+    out
+}
+
+/// One line per repo: branch, clean/dirty, ahead/behind and stale branches.
+pub fn format_repo_health(health: &RepoHealth) -> String {
+    let branch = health.branch.as_deref().unwrap_or("(detached)");
+    let state = if health.dirty_files == 0 {
+        "clean".green().to_string()
+    } else {
+        format!("{} dirty", health.dirty_files).yellow().to_string()
+    };
+    let tracking = match health.ahead_behind {
+        Some((ahead, behind)) => format!("↑{} ↓{}", ahead, behind),
+        None => "no upstream".to_string(),
+    };
+    let mut line = format!(
+        "{} {} {} {}",
+        format!("{:>12}", branch).magenta(),
+        state,
+        tracking,
+        health.reposlug
+    );
+    if !health.stale_branches.is_empty() {
+        line.push_str(&format!(" {} {}", "stale:".red(), health.stale_branches.join(", ")));
+    }
+    line
+}
+
+/// Inspects every repo under `root` in parallel, sorted by reposlug. Nothing is modified.
+pub fn collect_health(root: &Path, branch_prefix: &str) -> Result<Vec<RepoHealth>> {
+    let repos = git::find_git_repositories(root)?;
+    debug!("Collecting status for {} repos under '{}'", repos.len(), root.display());
+
+    let mut healths: Vec<RepoHealth> = pool::git(|| {
+        repos
+            .par_iter()
+            .filter_map(|repo| match repo_health(root, repo, branch_prefix) {
+                Ok(health) => Some(health),
+                Err(e) => {
+                    warn!("Failed to inspect '{}': {}", repo.display(), e);
+                    None
+                }
+            })
+            .collect()
+    });
+    healths.sort_by(|a, b| a.reposlug.cmp(&b.reposlug));
+    Ok(healths)
+}
+
+/// Prints an overview of the sandbox under `root` and of every in-flight change id.
+pub fn slam_status(root: &Path, branch_prefix: &str, offline: bool) -> Result<()> {
+    let healths = collect_health(root, branch_prefix)?;
+    let changes = if offline {
+        None
+    } else {
+        let reposlugs = healths.iter().map(|h| h.reposlug.clone()).collect();
+        Some(in_flight_changes(reposlugs, branch_prefix)?)
+    };
+
+    let pending = transaction::pending_all().unwrap_or_else(|e| {
+        warn!("Failed to read the rollback journals: {}", e);
+        Vec::new()
+    });
+
+    print!("{}", format_status(&healths, changes.as_ref(), &pending, branch_prefix));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn health(reposlug: &str, dirty_files: usize, stale: &[&str]) -> RepoHealth {
+        RepoHealth {
+            reposlug: reposlug.to_string(),
+            branch: Some("main".to_string()),
+            ahead_behind: Some((0, 0)),
+            dirty_files,
+            stale_branches: stale.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_format_status_summary() {
+        colored::control::set_override(false);
+        let healths = vec![
+            health("org/clean", 0, &[]),
+            health("org/dirty", 3, &[]),
+            health("org/stale", 0, &["SLAM-a", "SLAM-b"]),
+        ];
+        let mut changes = BTreeMap::new();
+        changes.insert("SLAM-2025-01-01T00-00-00".to_string(), 4);
+
+        let pending: Vec<Journal> = ["org/api", "org/web"]
+            .iter()
+            .map(|reposlug| {
+                let mut journal = Journal::default();
+                journal.change_id = "SLAM-crashed".to_string();
+                journal.reposlug = reposlug.to_string();
+                journal
+            })
+            .collect();
+
+        let out = format_status(&healths, Some(&changes), &pending, "SLAM");
+        assert!(out.contains("Sandbox: 3 repos, 1 dirty, 2 stale SLAM branches"));
+        assert!(out.contains("SLAM-crashed org/api, org/web (run `slam rollback SLAM-crashed`)"));
+        assert!(out.contains("dirty org/dirty (3 files)"));
+        assert!(out.contains("stale org/stale: SLAM-a, SLAM-b"));
+        assert!(out.contains("SLAM-2025-01-01T00-00-00 4 open PRs"));
+        assert!(!out.contains("org/clean"));
+    }
+
+    #[test]
+    fn test_format_repo_health() {
+        colored::control::set_override(false);
+        let clean = health("org/clean", 0, &[]);
+        assert_eq!(format_repo_health(&clean), "        main clean ↑0 ↓0 org/clean");
+
+        let mut messy = health("org/messy", 2, &["SLAM-old"]);
+        messy.branch = None;
+        messy.ahead_behind = None;
+        assert_eq!(
+            format_repo_health(&messy),
+            "  (detached) 2 dirty no upstream org/messy stale: SLAM-old"
+        );
+    }
+
+    #[test]
+    fn test_format_status_offline_and_empty() {
+        colored::control::set_override(false);
+        let out = format_status(&[], None, &[], "SLAM");
+        assert!(out.contains("Sandbox: 0 repos, 0 dirty, 0 stale SLAM branches"));
+        assert!(out.contains("In-flight changes: skipped (--offline)"));
+        assert!(!out.contains("Outstanding rollbacks"));
+
+        let out = format_status(&[], Some(&BTreeMap::new()), &[], "SLAM");
+        assert!(out.contains("In-flight changes: none"));
+    }
+}
@@ -0,0 +1,405 @@
+// src/tui.rs
+
+use eyre::Result;
+use log::warn;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table, TableState, Tabs};
+use ratatui::{DefaultTerminal, Frame};
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use slam_core::git;
+use slam_core::pool;
+use slam_core::repo::{Repo, ReviewOp};
+
+/// What happened to a PR during this session.
+#[derive(Debug, Clone, PartialEq)]
+enum Decision {
+    Pending,
+    Approved,
+    Skipped,
+    Failed(String),
+}
+
+impl Decision {
+    fn label(&self) -> &str {
+        match self {
+            Decision::Pending => "",
+            Decision::Approved => "approved",
+            Decision::Skipped => "skipped",
+            Decision::Failed(_) => "failed",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    repo: Repo,
+    /// `None` when `gh pr view` failed; the PR is still listed so it can be opened.
+    status: Option<git::PrStatus>,
+    decision: Decision,
+}
+
+/// Dashboard state: one tab per change id, one row per PR.
+struct App {
+    changes: Vec<(String, Vec<Entry>)>,
+    tab: usize,
+    table: TableState,
+    message: String,
+}
+
+/// Renders the (checks, review, mergeable) columns for a PR.
+fn status_cells(status: Option<&git::PrStatus>) -> [&'static str; 3] {
+    match status {
+        None => ["?", "?", "?"],
+        Some(status) => [
+            if status.checked { "pass" } else { "fail" },
+            if status.draft {
+                "draft"
+            } else if status.reviewed {
+                "approved"
+            } else {
+                "pending"
+            },
+            match status.mergeable {
+                git::Mergeable::Mergeable => "yes",
+                git::Mergeable::Conflicting => "conflicts",
+                git::Mergeable::Unknown => "unknown",
+            },
+        ],
+    }
+}
+
+fn cell_style(value: &str) -> Style {
+    match value {
+        "pass" | "approved" | "yes" => Style::default().fg(Color::Green),
+        "fail" | "failed" | "conflicts" => Style::default().fg(Color::Red),
+        "pending" | "draft" | "skipped" | "unknown" => Style::default().fg(Color::Yellow),
+        _ => Style::default(),
+    }
+}
+
+/// Groups PRs by change id (sorted), each group sorted by reposlug.
+fn group_by_change(entries: Vec<Entry>) -> Vec<(String, Vec<Entry>)> {
+    let mut changes: BTreeMap<String, Vec<Entry>> = BTreeMap::new();
+    for entry in entries {
+        changes.entry(entry.repo.change_id.clone()).or_default().push(entry);
+    }
+    changes
+        .into_iter()
+        .map(|(change_id, mut entries)| {
+            entries.sort_by(|a, b| a.repo.reposlug.cmp(&b.repo.reposlug));
+            (change_id, entries)
+        })
+        .collect()
+}
+
+impl App {
+    fn new(entries: Vec<Entry>) -> Self {
+        let changes = group_by_change(entries);
+        let mut table = TableState::default();
+        if !changes.is_empty() {
+            table.select(Some(0));
+        }
+        Self {
+            changes,
+            tab: 0,
+            table,
+            message: String::new(),
+        }
+    }
+
+    fn rows(&self) -> &[Entry] {
+        self.changes
+            .get(self.tab)
+            .map(|(_, e)| e.as_slice())
+            .unwrap_or_default()
+    }
+
+    fn selected_mut(&mut self) -> Option<&mut Entry> {
+        let row = self.table.selected()?;
+        self.changes.get_mut(self.tab)?.1.get_mut(row)
+    }
+
+    fn next_row(&mut self) {
+        let len = self.rows().len();
+        if len > 0 {
+            let row = self.table.selected().map_or(0, |r| (r + 1) % len);
+            self.table.select(Some(row));
+        }
+    }
+
+    fn prev_row(&mut self) {
+        let len = self.rows().len();
+        if len > 0 {
+            let row = self.table.selected().map_or(0, |r| (r + len - 1) % len);
+            self.table.select(Some(row));
+        }
+    }
+
+    fn switch_tab(&mut self, forward: bool) {
+        let len = self.changes.len();
+        if len > 0 {
+            self.tab = if forward {
+                (self.tab + 1) % len
+            } else {
+                (self.tab + len - 1) % len
+            };
+            self.table.select(Some(0));
+        }
+    }
+
+    fn skip(&mut self) {
+        if let Some(entry) = self.selected_mut() {
+            if entry.decision == Decision::Pending {
+                entry.decision = Decision::Skipped;
+            }
+            self.message = format!("Skipped {}", entry.repo.reposlug);
+        }
+        self.next_row();
+    }
+
+    fn open(&mut self) {
+        if let Some(entry) = self.selected_mut() {
+            let repo = &entry.repo;
+            self.message = match git::open_pr_in_browser(&repo.reposlug, repo.pr_number) {
+                Ok(()) => format!("Opened {} (# {})", repo.reposlug, repo.pr_number),
+                Err(e) => e.to_string(),
+            };
+        }
+    }
+
+    /// Approves and merges the selected PR with the same checks as `slam review approve`.
+    fn approve(&mut self, root: &Path) {
+        let Some(entry) = self.selected_mut() else {
+            return;
+        };
+        if entry.decision == Decision::Approved {
+            return;
+        }
+        let message = match entry
+            .repo
+            .review(root, &ReviewOp::Approve { admin_override: false }, false)
+        {
+            Ok(message) => {
+                entry.decision = Decision::Approved;
+                message
+            }
+            Err(e) => {
+                entry.decision = Decision::Failed(e.to_string());
+                format!("{}: {}", entry.repo.reposlug, e)
+            }
+        };
+        self.message = message;
+        self.next_row();
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [tabs_area, table_area, footer_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(3), Constraint::Length(3)]).areas(frame.area());
+
+        let titles: Vec<Line> = self
+            .changes
+            .iter()
+            .map(|(change_id, entries)| Line::from(format!("{} ({})", change_id, entries.len())))
+            .collect();
+        let tabs = Tabs::new(titles)
+            .select(self.tab)
+            .block(Block::default().borders(Borders::ALL).title("Change IDs"))
+            .highlight_style(Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD));
+        frame.render_widget(tabs, tabs_area);
+
+        let rows: Vec<Row> = self
+            .rows()
+            .iter()
+            .map(|entry| {
+                let [checks, review, mergeable] = status_cells(entry.status.as_ref());
+                let decision = entry.decision.label().to_string();
+                Row::new(vec![
+                    Line::from(entry.repo.reposlug.clone()),
+                    Line::from(format!("#{}", entry.repo.pr_number)),
+                    Line::styled(checks, cell_style(checks)),
+                    Line::styled(review, cell_style(review)),
+                    Line::styled(mergeable, cell_style(mergeable)),
+                    Line::styled(decision.clone(), cell_style(&decision)),
+                ])
+            })
+            .collect();
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(8),
+                Constraint::Length(8),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Length(10),
+            ],
+        )
+        .header(
+            Row::new(["Repo", "PR", "Checks", "Review", "Mergeable", "Action"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::default().borders(Borders::ALL))
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, table_area, &mut self.table);
+
+        let footer = Paragraph::new(vec![
+            Line::from("↑/↓ select  ←/→ change id  o open  a approve+merge  s skip  q quit"),
+            Line::from(self.message.clone()),
+        ]);
+        frame.render_widget(footer, footer_area);
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal, root: &Path) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => self.next_row(),
+                KeyCode::Up | KeyCode::Char('k') => self.prev_row(),
+                KeyCode::Right | KeyCode::Tab | KeyCode::Char('l') => self.switch_tab(true),
+                KeyCode::Left | KeyCode::BackTab | KeyCode::Char('h') => self.switch_tab(false),
+                KeyCode::Char('o') | KeyCode::Enter => self.open(),
+                KeyCode::Char('s') => self.skip(),
+                KeyCode::Char('a') => self.approve(root),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Fetches the status of every PR and runs the interactive dashboard until the user quits.
+pub fn run(root: &Path, repos: Vec<Repo>) -> Result<()> {
+    let entries: Vec<Entry> = pool::gh(|| {
+        repos
+            .into_par_iter()
+            .map(|repo| {
+                let status = git::get_pr_status(&repo.reposlug, repo.pr_number)
+                    .map_err(|e| warn!("Failed to get status for '{}': {}", repo.reposlug, e))
+                    .ok();
+                Entry {
+                    repo,
+                    status,
+                    decision: Decision::Pending,
+                }
+            })
+            .collect()
+    });
+
+    let mut app = App::new(entries);
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal, root);
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(change_id: &str, reposlug: &str, pr_number: u64) -> Entry {
+        Entry {
+            repo: Repo::create_repo_from_remote_with_pr(reposlug, change_id, pr_number),
+            status: None,
+            decision: Decision::Pending,
+        }
+    }
+
+    fn pr_status(draft: bool, mergeable: bool, reviewed: bool, checked: bool) -> git::PrStatus {
+        git::PrStatus {
+            head_ref: "SLAM-test".to_string(),
+            draft,
+            mergeable: if mergeable {
+                git::Mergeable::Mergeable
+            } else {
+                git::Mergeable::Conflicting
+            },
+            reviewed,
+            checked,
+            checks_failed: false,
+            open: true,
+            merged: false,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_group_by_change_sorts() {
+        let app = App::new(vec![
+            entry("SLAM-b", "org/zeta", 1),
+            entry("SLAM-a", "org/beta", 2),
+            entry("SLAM-b", "org/alpha", 3),
+        ]);
+        let change_ids: Vec<&str> = app.changes.iter().map(|(c, _)| c.as_str()).collect();
+        assert_eq!(change_ids, vec!["SLAM-a", "SLAM-b"]);
+        let repos: Vec<&str> = app.changes[1].1.iter().map(|e| e.repo.reposlug.as_str()).collect();
+        assert_eq!(repos, vec!["org/alpha", "org/zeta"]);
+        assert_eq!(app.table.selected(), Some(0));
+    }
+
+    #[test]
+    fn test_navigation_wraps() {
+        let mut app = App::new(vec![
+            entry("SLAM-a", "org/one", 1),
+            entry("SLAM-a", "org/two", 2),
+            entry("SLAM-b", "org/three", 3),
+        ]);
+        app.prev_row();
+        assert_eq!(app.table.selected(), Some(1));
+        app.next_row();
+        assert_eq!(app.table.selected(), Some(0));
+
+        app.switch_tab(false);
+        assert_eq!(app.tab, 1);
+        assert_eq!(app.table.selected(), Some(0));
+        app.switch_tab(true);
+        assert_eq!(app.tab, 0);
+    }
+
+    #[test]
+    fn test_skip_marks_and_advances() {
+        let mut app = App::new(vec![entry("SLAM-a", "org/one", 1), entry("SLAM-a", "org/two", 2)]);
+        app.skip();
+        assert_eq!(app.changes[0].1[0].decision, Decision::Skipped);
+        assert_eq!(app.table.selected(), Some(1));
+        assert_eq!(app.message, "Skipped org/one");
+    }
+
+    #[test]
+    fn test_status_cells() {
+        assert_eq!(status_cells(None), ["?", "?", "?"]);
+        assert_eq!(
+            status_cells(Some(&pr_status(false, true, true, true))),
+            ["pass", "approved", "yes"]
+        );
+        assert_eq!(
+            status_cells(Some(&pr_status(true, false, false, false))),
+            ["fail", "draft", "conflicts"]
+        );
+        assert_eq!(
+            status_cells(Some(&pr_status(false, true, false, true))),
+            ["pass", "pending", "yes"]
+        );
+    }
+
+    #[test]
+    fn test_empty_app() {
+        let mut app = App::new(vec![]);
+        app.next_row();
+        app.switch_tab(true);
+        app.skip();
+        assert!(app.rows().is_empty());
+        assert_eq!(app.table.selected(), None);
+    }
+}